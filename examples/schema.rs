@@ -1,10 +1,18 @@
 use std::env::current_dir;
 use std::fs::create_dir_all;
 
-use cosmwasm_schema::{export_schema, remove_schemas, schema_for};
+use cosmwasm_schema::{export_schema, export_schema_with_title, remove_schemas, schema_for};
 
-use marketpalace_subscription_contract::msg::{HandleMsg, InstantiateMsg, QueryMsg};
-use marketpalace_subscription_contract::state::State;
+use marketpalace_subscription_contract::msg::{
+    AssetExchange, AuthorizationView, ExchangeDate, ExecuteMsg, InstantiateMsg, MigrateMsg,
+    QueryMsg, QueryResponses, SignedAuthorization, StateView, SudoMsg,
+};
+use marketpalace_subscription_contract::raise_msg::{
+    RaiseExecuteMsg, RaiseQueryMsg, SubscriptionHoldings,
+};
+use marketpalace_subscription_contract::state::{
+    AssetExchangeAuthorization, MigrationRecord, State,
+};
 
 fn main() {
     let mut out_dir = current_dir().unwrap();
@@ -13,7 +21,28 @@ fn main() {
     remove_schemas(&out_dir).unwrap();
 
     export_schema(&schema_for!(InstantiateMsg), &out_dir);
-    export_schema(&schema_for!(HandleMsg), &out_dir);
+    export_schema(&schema_for!(ExecuteMsg), &out_dir);
     export_schema(&schema_for!(QueryMsg), &out_dir);
+    export_schema(&schema_for!(MigrateMsg), &out_dir);
+    export_schema(&schema_for!(SudoMsg), &out_dir);
     export_schema(&schema_for!(State), &out_dir);
+    export_schema(&schema_for!(StateView), &out_dir);
+    export_schema(&schema_for!(AssetExchange), &out_dir);
+    export_schema(&schema_for!(ExchangeDate), &out_dir);
+    export_schema(&schema_for!(AssetExchangeAuthorization), &out_dir);
+    export_schema(&schema_for!(AuthorizationView), &out_dir);
+    export_schema(&schema_for!(MigrationRecord), &out_dir);
+    export_schema(&schema_for!(RaiseExecuteMsg), &out_dir);
+    export_schema(&schema_for!(RaiseQueryMsg), &out_dir);
+    export_schema(&schema_for!(SubscriptionHoldings), &out_dir);
+    export_schema(&schema_for!(SignedAuthorization), &out_dir);
+
+    // one schema per query, named after it, e.g. get_state_response.json
+    for (query, schema) in QueryMsg::response_schemas() {
+        let title: String = query
+            .split('_')
+            .map(|word| word[..1].to_uppercase() + &word[1..])
+            .collect();
+        export_schema_with_title(&schema, &out_dir, &format!("{}Response", title));
+    }
 }
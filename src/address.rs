@@ -0,0 +1,247 @@
+//! Derivation of bech32 account addresses from secp256k1 public keys, as done by the cosmos sdk:
+//! `bech32(hrp, ripemd160(sha256(compressed_pubkey)))`. The ripemd160 and bech32 primitives are
+//! implemented here because neither is available from cosmwasm-std or the crates this contract
+//! builds against; the tests pin them to the reference vectors and to published cosmos sdk and
+//! cosmjs pubkey to address vectors.
+
+use sha2::{Digest, Sha256};
+
+use crate::error::ContractError;
+
+const BECH32_CHARSET: &[u8; 32] = b"qpzry9x8gf2tvdw0s3jn54khce6mua7l";
+const BECH32_GENERATOR: [u32; 5] = [0x3b6a57b2, 0x26508e6d, 0x1ea119fa, 0x3d4233dd, 0x2a1462b3];
+
+/// The human readable part of a bech32 address, e.g. `tp` for `tp1...`.
+pub fn address_prefix(address: &str) -> Option<&str> {
+    address
+        .rfind('1')
+        .map(|separator| &address[..separator])
+        .filter(|prefix| !prefix.is_empty())
+}
+
+pub fn pubkey_address(prefix: &str, pubkey: &[u8]) -> Result<String, ContractError> {
+    if pubkey.len() != 33 {
+        return Err(ContractError::from(
+            "pubkey must be a 33 byte compressed secp256k1 key",
+        ));
+    }
+    Ok(bech32_encode(prefix, &ripemd160(&Sha256::digest(pubkey))))
+}
+
+pub fn bech32_encode(hrp: &str, data: &[u8]) -> String {
+    let mut values = Vec::with_capacity(data.len() * 8 / 5 + 7);
+    let mut acc = 0u32;
+    let mut bits = 0;
+    for byte in data {
+        acc = (acc << 8) | *byte as u32;
+        bits += 8;
+        while bits >= 5 {
+            bits -= 5;
+            values.push(((acc >> bits) & 31) as u8);
+        }
+    }
+    if bits > 0 {
+        values.push(((acc << (5 - bits)) & 31) as u8);
+    }
+
+    let mut checked: Vec<u8> = hrp.bytes().map(|c| c >> 5).collect();
+    checked.push(0);
+    checked.extend(hrp.bytes().map(|c| c & 31));
+    checked.extend(&values);
+    checked.extend([0; 6]);
+    let polymod = bech32_polymod(&checked) ^ 1;
+    values.extend((0..6).map(|i| ((polymod >> (5 * (5 - i))) & 31) as u8));
+
+    let mut address = String::from(hrp);
+    address.push('1');
+    address.extend(values.iter().map(|v| BECH32_CHARSET[*v as usize] as char));
+    address
+}
+
+fn bech32_polymod(values: &[u8]) -> u32 {
+    values.iter().fold(1u32, |chk, value| {
+        let top = chk >> 25;
+        BECH32_GENERATOR
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| (top >> i) & 1 == 1)
+            .fold(((chk & 0x1ffffff) << 5) ^ *value as u32, |chk, (_, g)| {
+                chk ^ g
+            })
+    })
+}
+
+const R_LEFT: [usize; 80] = [
+    0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 7, 4, 13, 1, 10, 6, 15, 3, 12, 0, 9, 5,
+    2, 14, 11, 8, 3, 10, 14, 4, 9, 15, 8, 1, 2, 7, 0, 6, 13, 11, 5, 12, 1, 9, 11, 10, 0, 8, 12, 4,
+    13, 3, 7, 15, 14, 5, 6, 2, 4, 0, 5, 9, 7, 12, 2, 10, 14, 1, 3, 8, 11, 6, 15, 13,
+];
+const R_RIGHT: [usize; 80] = [
+    5, 14, 7, 0, 9, 2, 11, 4, 13, 6, 15, 8, 1, 10, 3, 12, 6, 11, 3, 7, 0, 13, 5, 10, 14, 15, 8, 12,
+    4, 9, 1, 2, 15, 5, 1, 3, 7, 14, 6, 9, 11, 8, 12, 2, 10, 0, 4, 13, 8, 6, 4, 1, 3, 11, 15, 0, 5,
+    12, 2, 13, 9, 7, 10, 14, 12, 15, 10, 4, 1, 5, 8, 7, 6, 2, 13, 14, 0, 3, 9, 11,
+];
+const S_LEFT: [u32; 80] = [
+    11, 14, 15, 12, 5, 8, 7, 9, 11, 13, 14, 15, 6, 7, 9, 8, 7, 6, 8, 13, 11, 9, 7, 15, 7, 12, 15,
+    9, 11, 7, 13, 12, 11, 13, 6, 7, 14, 9, 13, 15, 14, 8, 13, 6, 5, 12, 7, 5, 11, 12, 14, 15, 14,
+    15, 9, 8, 9, 14, 5, 6, 8, 6, 5, 12, 9, 15, 5, 11, 6, 8, 13, 12, 5, 12, 13, 14, 11, 8, 5, 6,
+];
+const S_RIGHT: [u32; 80] = [
+    8, 9, 9, 11, 13, 15, 15, 5, 7, 7, 8, 11, 14, 14, 12, 6, 9, 13, 15, 7, 12, 8, 9, 11, 7, 7, 12,
+    7, 6, 15, 13, 11, 9, 7, 15, 11, 8, 6, 6, 14, 12, 13, 5, 14, 13, 13, 7, 5, 15, 5, 8, 11, 14, 14,
+    6, 14, 6, 9, 12, 9, 12, 5, 15, 8, 8, 5, 12, 9, 12, 5, 14, 6, 8, 13, 6, 5, 15, 13, 11, 11,
+];
+const K_LEFT: [u32; 5] = [0x00000000, 0x5a827999, 0x6ed9eba1, 0x8f1bbcdc, 0xa953fd4e];
+const K_RIGHT: [u32; 5] = [0x50a28be6, 0x5c4dd124, 0x6d703ef3, 0x7a6d76e9, 0x00000000];
+
+fn ripemd160_f(round: usize, x: u32, y: u32, z: u32) -> u32 {
+    match round {
+        0 => x ^ y ^ z,
+        1 => (x & y) | (!x & z),
+        2 => (x | !y) ^ z,
+        3 => (x & z) | (y & !z),
+        _ => x ^ (y | !z),
+    }
+}
+
+pub fn ripemd160(data: &[u8]) -> [u8; 20] {
+    let mut h: [u32; 5] = [0x67452301, 0xefcdab89, 0x98badcfe, 0x10325476, 0xc3d2e1f0];
+
+    let mut message = data.to_vec();
+    message.push(0x80);
+    while message.len() % 64 != 56 {
+        message.push(0);
+    }
+    message.extend(((data.len() as u64) * 8).to_le_bytes());
+
+    for block in message.chunks(64) {
+        let mut x = [0u32; 16];
+        for (i, word) in block.chunks(4).enumerate() {
+            x[i] = u32::from_le_bytes([word[0], word[1], word[2], word[3]]);
+        }
+
+        let (mut al, mut bl, mut cl, mut dl, mut el) = (h[0], h[1], h[2], h[3], h[4]);
+        let (mut ar, mut br, mut cr, mut dr, mut er) = (h[0], h[1], h[2], h[3], h[4]);
+        for j in 0..80 {
+            let round = j / 16;
+            let t = al
+                .wrapping_add(ripemd160_f(round, bl, cl, dl))
+                .wrapping_add(x[R_LEFT[j]])
+                .wrapping_add(K_LEFT[round])
+                .rotate_left(S_LEFT[j])
+                .wrapping_add(el);
+            al = el;
+            el = dl;
+            dl = cl.rotate_left(10);
+            cl = bl;
+            bl = t;
+
+            let t = ar
+                .wrapping_add(ripemd160_f(4 - round, br, cr, dr))
+                .wrapping_add(x[R_RIGHT[j]])
+                .wrapping_add(K_RIGHT[round])
+                .rotate_left(S_RIGHT[j])
+                .wrapping_add(er);
+            ar = er;
+            er = dr;
+            dr = cr.rotate_left(10);
+            cr = br;
+            br = t;
+        }
+
+        let t = h[1].wrapping_add(cl).wrapping_add(dr);
+        h[1] = h[2].wrapping_add(dl).wrapping_add(er);
+        h[2] = h[3].wrapping_add(el).wrapping_add(ar);
+        h[3] = h[4].wrapping_add(al).wrapping_add(br);
+        h[4] = h[0].wrapping_add(bl).wrapping_add(cr);
+        h[0] = t;
+    }
+
+    let mut digest = [0u8; 20];
+    for (i, word) in h.iter().enumerate() {
+        digest[i * 4..i * 4 + 4].copy_from_slice(&word.to_le_bytes());
+    }
+    digest
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hex(bytes: &[u8]) -> String {
+        bytes.iter().map(|b| format!("{:02x}", b)).collect()
+    }
+
+    #[test]
+    fn ripemd160_vectors() {
+        assert_eq!(
+            "9c1185a5c5e9fc54612808977ee8f548b2258d31",
+            hex(&ripemd160(b""))
+        );
+        assert_eq!(
+            "8eb208f7e05d987a9b044a8e98c6b087f15a0bfc",
+            hex(&ripemd160(b"abc"))
+        );
+        assert_eq!(
+            "b0e20b6e3116640286ed3a87a5713079b21f5189",
+            hex(&ripemd160(
+                b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789"
+            ))
+        );
+    }
+
+    #[test]
+    fn bech32_vectors() {
+        assert_eq!("a12uel5l", bech32_encode("a", &[]));
+        assert_eq!(
+            "cosmos1qypqxpq9qcrsszg2pvxq6rs0zqg3yyc5lzv7xu",
+            bech32_encode(
+                "cosmos",
+                &[1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16, 17, 18, 19, 20]
+            )
+        );
+    }
+
+    fn unhex(hex: &str) -> Vec<u8> {
+        (0..hex.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn cosmos_sdk_pubkey_hash() {
+        // the cosmos sdk's secp256k1 test key; its address there is the base58 encoding of this
+        // same ripemd160(sha256(pubkey)) hash, 1CKZ9Nx4zgds8tU7nJHotKSDr4a9bYJCa3
+        let pubkey = unhex("02950e1cdfcb133d6024109fd489f734eeb4502418e538c28481f22bce276f248c");
+        assert_eq!(
+            "7c2bb42a8be69791ec763e51f5a49bcd41e82237",
+            hex(&ripemd160(&Sha256::digest(&pubkey)))
+        );
+    }
+
+    #[test]
+    fn cosmjs_pubkey_address() {
+        // cosmjs' amino address test: AtQaCqFnshaZQp6rIkvAPyzThvCvXSDO+9AzbxVErqJP in base64
+        let pubkey = unhex("02d41a0aa167b21699429eab224bc03f2cd386f0af5d20cefbd0336f1544aea24f");
+        assert_eq!(
+            "cosmos1h806c7khnvmjlywdrkdgk2vrayy2mmvf9rxk2r",
+            pubkey_address("cosmos", &pubkey).unwrap()
+        );
+    }
+
+    #[test]
+    fn prefix() {
+        assert_eq!(
+            Some("tp"),
+            address_prefix("tp1qypqxpq9qcrsszg2pvxq6rs0zqg3yyc5")
+        );
+        assert_eq!(None, address_prefix("lp"));
+        assert_eq!(None, address_prefix("1abc"));
+    }
+
+    #[test]
+    fn pubkey_length_checked() {
+        assert!(pubkey_address("tp", &[2; 32]).is_err());
+    }
+}
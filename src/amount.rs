@@ -0,0 +1,406 @@
+//! Amounts are 128 bit and string encoded on the wire, so 18 decimal denoms neither overflow nor
+//! lose precision in JSON number handling. Older clients, and state written before the switch,
+//! encode amounts as bare numbers, which are still accepted when deserializing.
+
+use std::convert::TryFrom;
+use std::fmt;
+
+use cosmwasm_std::{OverflowError, OverflowOperation, Uint128};
+use schemars::JsonSchema;
+use serde::de::{self, Visitor};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+/// A signed counterpart to `Uint128`, used for exchange legs that may move in either direction.
+/// cosmwasm-std 1.0 has no signed 128 bit type, so this follows the shape of the later
+/// upstream `Int128`. Legs come straight from messages, so only checked arithmetic is offered.
+#[derive(Copy, Clone, Default, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, JsonSchema)]
+pub struct Int128(#[schemars(with = "String")] i128);
+
+impl Int128 {
+    pub const fn new(value: i128) -> Self {
+        Int128(value)
+    }
+
+    pub const fn zero() -> Self {
+        Int128(0)
+    }
+
+    pub const fn i128(&self) -> i128 {
+        self.0
+    }
+
+    pub const fn is_zero(&self) -> bool {
+        self.0 == 0
+    }
+
+    pub const fn is_negative(&self) -> bool {
+        self.0 < 0
+    }
+
+    pub const fn is_positive(&self) -> bool {
+        self.0 > 0
+    }
+
+    pub const fn unsigned_abs(&self) -> Uint128 {
+        Uint128::new(self.0.unsigned_abs())
+    }
+
+    pub fn checked_add(self, other: Self) -> Result<Self, OverflowError> {
+        self.0
+            .checked_add(other.0)
+            .map(Int128)
+            .ok_or_else(|| OverflowError::new(OverflowOperation::Add, self, other))
+    }
+
+    pub fn checked_sub(self, other: Self) -> Result<Self, OverflowError> {
+        self.0
+            .checked_sub(other.0)
+            .map(Int128)
+            .ok_or_else(|| OverflowError::new(OverflowOperation::Sub, self, other))
+    }
+}
+
+impl From<i64> for Int128 {
+    fn from(value: i64) -> Self {
+        Int128(value.into())
+    }
+}
+
+impl From<u64> for Int128 {
+    fn from(value: u64) -> Self {
+        Int128(value.into())
+    }
+}
+
+impl From<i128> for Int128 {
+    fn from(value: i128) -> Self {
+        Int128(value)
+    }
+}
+
+impl fmt::Display for Int128 {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl Serialize for Int128 {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(&self.0.to_string())
+    }
+}
+
+impl<'de> Deserialize<'de> for Int128 {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_any(AmountVisitor).map(Int128)
+    }
+}
+
+/// Deserializes a `Uint128` from either its string encoding or a bare (legacy) number.
+pub fn uint128_or_number<'de, D>(deserializer: D) -> Result<Uint128, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let amount = deserializer.deserialize_any(AmountVisitor)?;
+    u128::try_from(amount)
+        .map(Uint128::new)
+        .map_err(|_| de::Error::custom(format!("amount must not be negative: {}", amount)))
+}
+
+/// A `u64` amount, string encoded like `Uint128` and read from either a string or a number. The
+/// modules below apply it to plain `u64` fields, which JavaScript clients otherwise parse into
+/// doubles that lose precision above 2^53.
+struct U64Amount(u64);
+
+impl Serialize for U64Amount {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(&self.0.to_string())
+    }
+}
+
+impl<'de> Deserialize<'de> for U64Amount {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let amount = deserializer.deserialize_any(AmountVisitor)?;
+        u64::try_from(amount)
+            .map(U64Amount)
+            .map_err(|_| de::Error::custom(format!("amount out of range: {}", amount)))
+    }
+}
+
+pub mod u64_string {
+    use super::U64Amount;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    pub fn serialize<S: Serializer>(value: &u64, serializer: S) -> Result<S::Ok, S::Error> {
+        U64Amount(*value).serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u64, D::Error> {
+        U64Amount::deserialize(deserializer).map(|amount| amount.0)
+    }
+}
+
+pub mod option_u64_string {
+    use super::U64Amount;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    pub fn serialize<S: Serializer>(value: &Option<u64>, serializer: S) -> Result<S::Ok, S::Error> {
+        value.map(U64Amount).serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<u64>, D::Error> {
+        Option::<U64Amount>::deserialize(deserializer).map(|amount| amount.map(|amount| amount.0))
+    }
+}
+
+/// For per-denom amounts such as `capital_per_share_overrides`.
+pub mod u64_string_pairs {
+    use super::U64Amount;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    pub fn serialize<S: Serializer>(
+        value: &[(String, u64)],
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        value
+            .iter()
+            .map(|(denom, amount)| (denom, U64Amount(*amount)))
+            .collect::<Vec<_>>()
+            .serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Vec<(String, u64)>, D::Error> {
+        Vec::<(String, U64Amount)>::deserialize(deserializer).map(|pairs| {
+            pairs
+                .into_iter()
+                .map(|(denom, amount)| (denom, amount.0))
+                .collect()
+        })
+    }
+}
+
+pub mod option_u64_string_pairs {
+    use super::U64Amount;
+    use serde::{Deserialize, Deserializer};
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<Vec<(String, u64)>>, D::Error> {
+        Option::<Vec<(String, U64Amount)>>::deserialize(deserializer).map(|pairs| {
+            pairs.map(|pairs| {
+                pairs
+                    .into_iter()
+                    .map(|(denom, amount)| (denom, amount.0))
+                    .collect()
+            })
+        })
+    }
+}
+
+struct AmountVisitor;
+
+impl<'de> Visitor<'de> for AmountVisitor {
+    type Value = i128;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("an integer amount, as a string or a number")
+    }
+
+    fn visit_i64<E: de::Error>(self, value: i64) -> Result<i128, E> {
+        Ok(value.into())
+    }
+
+    fn visit_u64<E: de::Error>(self, value: u64) -> Result<i128, E> {
+        Ok(value.into())
+    }
+
+    fn visit_i128<E: de::Error>(self, value: i128) -> Result<i128, E> {
+        Ok(value)
+    }
+
+    fn visit_u128<E: de::Error>(self, value: u128) -> Result<i128, E> {
+        i128::try_from(value).map_err(|_| E::custom(format!("amount out of range: {}", value)))
+    }
+
+    fn visit_str<E: de::Error>(self, value: &str) -> Result<i128, E> {
+        value
+            .parse()
+            .map_err(|_| E::custom(format!("invalid amount: {}", value)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cosmwasm_std::{from_slice, to_vec};
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Withdrawal {
+        #[serde(deserialize_with = "uint128_or_number")]
+        amount: Uint128,
+    }
+
+    #[test]
+    fn int128_string_encoded() {
+        assert_eq!(
+            b"\"-1000\"".to_vec(),
+            to_vec(&Int128::from(-1_000i64)).unwrap()
+        );
+        assert_eq!(
+            Int128::new(i128::MAX),
+            from_slice(format!("\"{}\"", i128::MAX).as_bytes()).unwrap()
+        );
+    }
+
+    #[test]
+    fn int128_accepts_legacy_numbers() {
+        assert_eq!(
+            Int128::from(-1_000i64),
+            from_slice::<Int128>(b"-1000").unwrap()
+        );
+        assert_eq!(
+            Int128::from(1_000i64),
+            from_slice::<Int128>(b"1000").unwrap()
+        );
+        assert_eq!(
+            Int128::from(i64::MIN),
+            from_slice::<Int128>(i64::MIN.to_string().as_bytes()).unwrap()
+        );
+        assert_eq!(
+            Int128::new(u64::MAX.into()),
+            from_slice::<Int128>(u64::MAX.to_string().as_bytes()).unwrap()
+        );
+    }
+
+    #[test]
+    fn int128_rejects_garbage() {
+        assert!(from_slice::<Int128>(b"\"1.5\"").is_err());
+        assert!(from_slice::<Int128>(b"\"\"").is_err());
+        assert!(from_slice::<Int128>(b"true").is_err());
+        assert!(from_slice::<Int128>(format!("\"{}0\"", i128::MAX).as_bytes()).is_err());
+    }
+
+    #[test]
+    fn uint128_accepts_string_or_number() {
+        let expected = Withdrawal {
+            amount: Uint128::new(300),
+        };
+        assert_eq!(expected, from_slice(br#"{"amount":"300"}"#).unwrap());
+        assert_eq!(expected, from_slice(br#"{"amount":300}"#).unwrap());
+        assert_eq!(br#"{"amount":"300"}"#.to_vec(), to_vec(&expected).unwrap());
+
+        let large = format!(r#"{{"amount":"{}"}}"#, u128::MAX / 2);
+        assert_eq!(
+            Uint128::new(u128::MAX / 2),
+            from_slice::<Withdrawal>(large.as_bytes()).unwrap().amount
+        );
+    }
+
+    #[test]
+    fn uint128_rejects_negative() {
+        assert!(from_slice::<Withdrawal>(br#"{"amount":-1}"#).is_err());
+        assert!(from_slice::<Withdrawal>(br#"{"amount":"-1"}"#).is_err());
+    }
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Shares {
+        #[serde(with = "u64_string")]
+        per_share: u64,
+        #[serde(with = "option_u64_string", default)]
+        ceiling: Option<u64>,
+        #[serde(with = "u64_string_pairs")]
+        overrides: Vec<(String, u64)>,
+    }
+
+    #[test]
+    fn u64_string_encoded() {
+        let shares = Shares {
+            per_share: u64::MAX,
+            ceiling: Some(9_007_199_254_740_993),
+            overrides: vec![(String::from("wei_coin"), 100_000_000_000_000)],
+        };
+        let json = format!(
+            r#"{{"per_share":"{}","ceiling":"9007199254740993","overrides":[["wei_coin","100000000000000"]]}}"#,
+            u64::MAX
+        );
+        assert_eq!(json.as_bytes().to_vec(), to_vec(&shares).unwrap());
+        assert_eq!(shares, from_slice(json.as_bytes()).unwrap());
+    }
+
+    #[test]
+    fn u64_accepts_string_or_number() {
+        let expected = Shares {
+            per_share: 100,
+            ceiling: None,
+            overrides: vec![(String::from("wei_coin"), 5)],
+        };
+        assert_eq!(
+            expected,
+            from_slice(br#"{"per_share":100,"overrides":[["wei_coin",5]]}"#).unwrap()
+        );
+        assert_eq!(
+            expected,
+            from_slice(br#"{"per_share":"100","ceiling":null,"overrides":[["wei_coin","5"]]}"#)
+                .unwrap()
+        );
+        assert!(from_slice::<Shares>(br#"{"per_share":"-1","overrides":[]}"#).is_err());
+        assert!(from_slice::<Shares>(
+            format!(r#"{{"per_share":"{}0","overrides":[]}}"#, u64::MAX).as_bytes()
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn arithmetic() {
+        let legs = vec![
+            Int128::from(-1_000i64),
+            Int128::from(400i64),
+            Int128::new(i64::MAX as i128),
+        ];
+        let total = legs
+            .into_iter()
+            .try_fold(Int128::zero(), Int128::checked_add)
+            .unwrap();
+        assert_eq!(Int128::new(i64::MAX as i128 - 600), total);
+
+        // sums that overflow 64 bits stay exact
+        let total = Int128::from(i64::MAX)
+            .checked_add(Int128::from(i64::MAX))
+            .unwrap();
+        assert_eq!(Int128::new(2 * i64::MAX as i128), total);
+        assert_eq!(Uint128::new(1_000), Int128::from(-1_000i64).unsigned_abs());
+        assert_eq!(
+            Uint128::new(i128::MIN.unsigned_abs()),
+            Int128::new(i128::MIN).unsigned_abs()
+        );
+        assert!(Int128::from(-1i64).is_negative());
+        assert!(Int128::from(1i64).is_positive());
+        assert_eq!(
+            Ok(Int128::from(600i64)),
+            Int128::from(1_000i64).checked_sub(Int128::from(400i64))
+        );
+
+        // 128 bit overflow is an error, not a panic
+        assert_eq!(
+            Err(OverflowError::new(OverflowOperation::Add, i128::MAX, 1)),
+            Int128::new(i128::MAX).checked_add(Int128::from(1i64))
+        );
+        assert!(Int128::zero().checked_sub(Int128::new(i128::MIN)).is_err());
+    }
+}
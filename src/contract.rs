@@ -1,20 +1,54 @@
+use crate::address::{address_prefix, pubkey_address};
+use crate::amount::Int128;
 use crate::error::contract_error;
-use crate::raise_msg::RaiseExecuteMsg;
-use cosmwasm_std::{coin, wasm_execute, Addr, Storage};
+use crate::raise_msg::{RaiseExecuteMsg, RaiseQueryMsg, SubscriptionHoldings};
+use cosmwasm_std::{coin, wasm_execute, Addr, Coin, StdError, Storage, Timestamp, Uint128};
 use cosmwasm_std::{
-    coins, entry_point, to_binary, BankMsg, Binary, Deps, DepsMut, Env, MessageInfo, Response,
-    StdResult,
+    coins, entry_point, from_binary, from_slice, to_binary, to_vec, BankMsg, Binary,
+    ContractResult, Deps, DepsMut, Env, Event, MessageInfo, QuerierWrapper, QueryRequest, Response,
+    StdResult, SystemResult, WasmQuery,
 };
-use provwasm_std::{transfer_marker_coins, ProvenanceMsg};
+use provwasm_std::{transfer_marker_coins, Marker, MarkerAccess, MarkerType, ProvenanceMsg};
 use provwasm_std::{ProvenanceQuerier, ProvenanceQuery};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::convert::TryFrom;
+use std::slice;
 use std::vec::IntoIter;
 
 use crate::error::ContractError;
-use crate::msg::{AssetExchange, HandleMsg, QueryMsg};
+use crate::error::QueryContext;
+use crate::migrate::validate_migration;
+use crate::msg::{
+    display_exchanges, AbortedAuthorizationsResponse, AssetExchange, AuthorizationHistoryResponse,
+    AuthorizationView, AuthorizationsResponse, CapitalDenomRequirement, CapitalDenomsResponse,
+    DenomReconciliation, DepositsResponse, ExchangeDate, ExecuteMsg, HasAuthorizationResponse,
+    MetadataResponse, MigrationHistoryResponse, OverdueObligation, OverdueObligationsResponse,
+    PendingAdminActionsResponse, QueryMsg, RaiseHoldingsResponse, ReconcileResponse,
+    ScheduledActionsResponse, SignedAuthorization, SimulateCompleteAssetExchangeResponse,
+    SimulateWithdrawalResponse, StateResponse, StatsResponse, VersionResponse, WithdrawalCheck,
+    WithdrawalPath,
+};
 use crate::state::{
-    asset_exchange_authorization_storage, asset_exchange_authorization_storage_read, state_storage,
-    state_storage_read, AssetExchangeAuthorization,
+    aborted_authorizations_storage_read, asset_exchange_authorization_storage,
+    asset_exchange_authorization_storage_read, authorization_history_storage_read,
+    authorization_sequence_storage_read, capital_denom_requirements, check_idempotency_key,
+    consume_withdrawal_allowance, consume_withdrawal_approval, join_addresses, join_coins,
+    load_authorization_history, load_deposits, metadata_storage, metadata_storage_read,
+    migration_history_storage_read, next_authorization_id, next_scheduled_admin_action_id,
+    operation_stats_storage_read, pending_admin_actions_storage,
+    pending_admin_actions_storage_read, record_aborted_authorization, record_authorization,
+    record_authorization_history, record_deposit, record_idempotency_key, record_operation,
+    scheduled_admin_actions_storage, scheduled_admin_actions_storage_read,
+    signed_authorization_nonce_storage, signed_authorization_nonce_storage_read, state_storage,
+    state_storage_read, unexpired_withdrawal_approvals, validate_abort_reason, validate_lp_note,
+    withdrawal_allowances_storage, withdrawal_allowances_storage_read,
+    withdrawal_approvals_storage, AbortedAuthorization, AdminAction, AssetExchangeAuthorization,
+    AuthorizationStatus, Operation, PauseSource, PendingAdminAction, ScheduledAdminAction, State,
+    WithdrawalAllowance, WithdrawalApproval,
 };
+use crate::version::{API_VERSION, FEATURES};
+use cw2::get_contract_version;
 
 pub type ContractResponse = Result<Response<ProvenanceMsg>, ContractError>;
 
@@ -24,765 +58,8909 @@ pub fn execute(
     deps: DepsMut<ProvenanceQuery>,
     _env: Env,
     info: MessageInfo,
-    msg: HandleMsg,
+    msg: ExecuteMsg,
 ) -> ContractResponse {
+    if !info.funds.is_empty() && !matches!(msg, ExecuteMsg::Deposit { .. }) {
+        return contract_error("funds are only accepted with a deposit");
+    }
+    let mut state = state_storage_read(deps.storage).load()?;
+    if state.record_activity(&info.sender, &_env.block.time) {
+        state_storage(deps.storage).save(&state)?;
+    }
+    let mut query_cache = QueryCache::default();
+
     match msg {
-        HandleMsg::Recover { lp } => {
-            let mut state = state_storage_read(deps.storage).load()?;
+        ExecuteMsg::SetOperators { operators } => {
+            if !state.is_admin(&info.sender) {
+                return contract_error("only admin can set operators");
+            }
+
+            state.operators = operators
+                .iter()
+                .map(|operator| deps.api.addr_validate(operator.as_str()))
+                .collect::<StdResult<Vec<Addr>>>()?;
+            state_storage(deps.storage).save(&state)?;
 
-            if info.sender != state.admin {
+            Ok(Response::new()
+                .add_attribute("action", "set_operators")
+                .add_attribute("operators", join_addresses(&state.operators)))
+        }
+        ExecuteMsg::SetPaused { paused } => {
+            if !state.is_admin(&info.sender) {
+                return contract_error("only admin can pause subscription");
+            }
+
+            state.set_paused(paused, PauseSource::Admin)?;
+            state_storage(deps.storage).save(&state)?;
+
+            Ok(Response::new()
+                .add_attribute("action", "set_paused")
+                .add_attribute("paused", paused.to_string())
+                .add_attribute("pause_source", PauseSource::Admin.name()))
+        }
+        ExecuteMsg::Recover { lp } => {
+            if !state.is_admin(&info.sender) {
                 return contract_error("only admin can recover subscription");
             }
 
-            state.lp = lp;
+            propose_admin_action(
+                deps.storage,
+                &_env,
+                &info.sender,
+                state,
+                AdminAction::Recover { lp },
+            )
+        }
+        ExecuteMsg::UpdateRaise { raise } => {
+            if !state.is_admin(&info.sender) {
+                return contract_error("only admin can update raise");
+            }
+
+            let raise = deps.api.addr_validate(raise.as_str())?;
+            propose_admin_action(
+                deps.storage,
+                &_env,
+                &info.sender,
+                state,
+                AdminAction::UpdateRaise { raise },
+            )
+        }
+        ExecuteMsg::RegisterRaise { raise } => {
+            if !state.is_admin(&info.sender) {
+                return contract_error("only admin can register a raise");
+            }
+
+            let raise = deps.api.addr_validate(raise.as_str())?;
+            propose_admin_action(
+                deps.storage,
+                &_env,
+                &info.sender,
+                state,
+                AdminAction::RegisterRaise { raise },
+            )
+        }
+        ExecuteMsg::UpdateRequiredCapitalAttribute {
+            required_capital_attributes,
+        } => {
+            if !state.is_admin(&info.sender) {
+                return contract_error("only admin can update required capital attributes");
+            }
+            check_marker_transfer_permission(
+                &deps.querier,
+                &_env.contract.address,
+                &capital_denom_requirements(
+                    &state.like_capital_denoms,
+                    required_capital_attributes.clone(),
+                )?,
+            )?;
+
+            propose_admin_action(
+                deps.storage,
+                &_env,
+                &info.sender,
+                state,
+                AdminAction::UpdateRequiredCapitalAttribute {
+                    required_capital_attributes,
+                },
+            )
+        }
+        ExecuteMsg::ExecuteScheduledAdminAction { id } => {
+            let mut scheduled = scheduled_admin_actions_storage(deps.storage)
+                .may_load()?
+                .unwrap_or_default();
+            let index = match scheduled.iter().position(|action| action.id == id) {
+                Some(index) => index,
+                None => {
+                    return contract_error(&format!("no scheduled admin action with id {}", id))
+                }
+            };
+            if _env.block.time < scheduled[index].execute_after {
+                return contract_error(&format!(
+                    "scheduled admin action {} cannot be executed before {}",
+                    id,
+                    scheduled[index].execute_after.seconds()
+                ));
+            }
+
+            scheduled[index].action.check_pending_authorizations(
+                &asset_exchange_authorization_storage_read(deps.storage)
+                    .may_load()?
+                    .unwrap_or_default(),
+            )?;
+
+            let action = scheduled.remove(index).action;
+            let action_name = action.name();
+            scheduled_admin_actions_storage(deps.storage).save(&scheduled)?;
+
+            action.apply(&mut state)?;
             state_storage(deps.storage).save(&state)?;
 
-            Ok(Response::default())
+            Ok(Response::new()
+                .add_attribute("action", action_name)
+                .add_attribute("scheduled_action_id", id.to_string()))
         }
-        HandleMsg::AuthorizeAssetExchange {
+        ExecuteMsg::AuthorizeAssetExchange {
             exchanges,
             to,
             memo,
+            idempotency_key,
+            expires_at,
+            memo_hash,
+            raise,
         } => {
-            let state = state_storage(deps.storage).load()?;
-
             if info.sender != state.lp {
                 return contract_error("only the lp can authorize asset exchanges");
             }
-
-            let mut authorizations = asset_exchange_authorization_storage(deps.storage)
-                .may_load()?
-                .unwrap_or_default();
-            authorizations.push(AssetExchangeAuthorization {
+            let raise = state.select_raise(raise)?;
+            authorize_asset_exchange(
+                deps.storage,
+                &_env,
+                &state,
+                raise,
                 exchanges,
                 to,
                 memo,
-            });
-            asset_exchange_authorization_storage(deps.storage).save(&authorizations)?;
+                memo_hash,
+                idempotency_key,
+                expires_at,
+            )
+        }
+        ExecuteMsg::UpdateDefaultAuthorizationTtl {
+            default_authorization_ttl_seconds,
+        } => {
+            if !state.is_admin(&info.sender) {
+                return contract_error("only admin can update the default authorization ttl");
+            }
+
+            propose_admin_action(
+                deps.storage,
+                &_env,
+                &info.sender,
+                state,
+                AdminAction::UpdateDefaultAuthorizationTtl {
+                    default_authorization_ttl_seconds,
+                },
+            )
+        }
+        ExecuteMsg::UpdateCommitmentDenom { denom } => {
+            if !state.is_admin(&info.sender) {
+                return contract_error("only admin can update the commitment denom");
+            }
+
+            let old_denom = state.commitment_denom.clone();
+            let action = AdminAction::UpdateCommitmentDenom {
+                denom: denom.clone(),
+            };
+            check_denom_update(
+                deps.storage,
+                &deps.querier,
+                &mut query_cache,
+                &action,
+                &denom,
+            )?;
+            Ok(
+                propose_admin_action(deps.storage, &_env, &info.sender, state, action)?
+                    .add_attribute("old_denom", old_denom)
+                    .add_attribute("new_denom", denom),
+            )
+        }
+        ExecuteMsg::UpdateInvestmentDenom { denom, replaces } => {
+            if !state.is_admin(&info.sender) {
+                return contract_error("only admin can update the investment denom");
+            }
+
+            let old_denom = replaces
+                .clone()
+                .or_else(|| state.investment_denoms.first().cloned())
+                .unwrap_or_default();
+            let action = AdminAction::UpdateInvestmentDenom {
+                denom: denom.clone(),
+                replaces,
+            };
+            check_denom_update(
+                deps.storage,
+                &deps.querier,
+                &mut query_cache,
+                &action,
+                &denom,
+            )?;
+            Ok(
+                propose_admin_action(deps.storage, &_env, &info.sender, state, action)?
+                    .add_attribute("old_denom", old_denom)
+                    .add_attribute("new_denom", denom),
+            )
+        }
+        ExecuteMsg::SubmitSignedAuthorization {
+            payload,
+            signature,
+            pubkey,
+        } => {
+            let hash = Sha256::digest(payload.as_slice());
+            if !deps
+                .api
+                .secp256k1_verify(&hash, signature.as_slice(), pubkey.as_slice())
+                .map_err(StdError::from)?
+            {
+                return contract_error("invalid signature on signed authorization");
+            }
+            let signer = match address_prefix(state.lp.as_str()) {
+                Some(prefix) => pubkey_address(prefix, pubkey.as_slice())?,
+                None => return contract_error("lp address has no bech32 prefix"),
+            };
+            if signer != state.lp {
+                return contract_error("signed authorization must be signed by the lp");
+            }
+
+            let authorization: SignedAuthorization = from_slice(payload.as_slice())?;
+            if to_vec(&authorization)? != payload.as_slice() {
+                return contract_error("signed authorization payload is not canonically encoded");
+            }
+            if authorization.contract != _env.contract.address {
+                return contract_error("signed authorization is for a different contract");
+            }
+            let last_nonce = signed_authorization_nonce_storage_read(deps.storage)
+                .may_load()?
+                .unwrap_or_default();
+            if authorization.nonce <= last_nonce {
+                return Err(ContractError::from(format!(
+                    "signed authorization nonce must be greater than {}",
+                    last_nonce
+                )));
+            }
+            signed_authorization_nonce_storage(deps.storage).save(&authorization.nonce)?;
 
-            Ok(Response::default())
+            let raise = state.select_raise(authorization.raise.clone())?;
+            authorize_asset_exchange(
+                deps.storage,
+                &_env,
+                &state,
+                raise,
+                authorization.exchanges,
+                authorization.to,
+                authorization.memo,
+                None,
+                None,
+                None,
+            )
         }
-        HandleMsg::CancelAssetExchangeAuthorization {
+        ExecuteMsg::CancelAssetExchangeAuthorization {
             exchanges,
             to,
             memo,
+            raise,
         } => {
-            let state = state_storage(deps.storage).load()?;
-
             if info.sender != state.lp {
                 return contract_error("only the lp can cancel asset exchange authorization");
             }
 
-            remove_asset_exchange_authorization(deps.storage, exchanges, to, memo, true)?;
+            let cancelled = remove_asset_exchange_authorization(
+                deps.storage,
+                &state,
+                &state.select_raise(raise)?,
+                exchanges,
+                to,
+                memo,
+                true,
+            )?
+            .expect("authorization required");
+            let data = to_binary(&cancelled)?;
+            record_authorization_history(
+                deps.storage,
+                cancelled,
+                AuthorizationStatus::Cancelled,
+                &info.sender,
+                _env.block.time,
+            )?;
+            record_operation(deps.storage, Operation::Cancellation, &_env.block.time)?;
 
-            Ok(Response::default())
+            Ok(Response::new().set_data(data))
         }
-        HandleMsg::CompleteAssetExchange {
-            exchanges,
-            to,
-            memo,
+        ExecuteMsg::AmendAssetExchangeAuthorization {
+            old,
+            new_exchanges,
+            new_to,
+            new_memo,
         } => {
-            let state = state_storage(deps.storage).load()?;
-
-            if info.sender != state.lp && info.sender != state.admin {
-                return contract_error("only the lp or admin can complete asset exchange");
+            if info.sender != state.lp {
+                return contract_error("only the lp can amend asset exchange authorization");
+            }
+            let raise = state.select_raise(old.raise.clone())?;
+            // checked up front so a missing authorization never leaves the replacement behind
+            if find_asset_exchange_authorization(
+                &asset_exchange_authorization_storage_read(deps.storage)
+                    .may_load()?
+                    .unwrap_or_default(),
+                &state,
+                &raise,
+                &old.exchanges,
+                &old.to,
+                &old.memo,
+            )
+            .is_none()
+            {
+                return Err(ContractError::AuthorizationNotFound {
+                    exchanges: display_exchanges(&old.exchanges),
+                });
             }
 
-            remove_asset_exchange_authorization(
+            authorize_asset_exchange(
                 deps.storage,
-                exchanges.clone(),
-                to.clone(),
-                memo.clone(),
-                info.sender == state.admin,
+                &_env,
+                &state,
+                raise.clone(),
+                new_exchanges,
+                new_to,
+                new_memo,
+                None,
+                None,
+                None,
+            )?;
+            let amended = remove_asset_exchange_authorization(
+                deps.storage,
+                &state,
+                &raise,
+                old.exchanges,
+                old.to,
+                old.memo,
+                true,
+            )?
+            .expect("authorization required");
+            let amended_id = amended.id;
+            record_authorization_history(
+                deps.storage,
+                amended,
+                AuthorizationStatus::Cancelled,
+                &info.sender,
+                _env.block.time,
             )?;
 
-            let mut funds = Vec::new();
-
-            let total_investment: i64 = exchanges.iter().filter_map(|e| e.investment).sum();
-            if total_investment < 0 {
-                funds.push(coin(
-                    total_investment.unsigned_abs().into(),
-                    state.investment_denom.clone(),
-                ));
+            Ok(Response::new()
+                .add_attribute("action", "amend_asset_exchange_authorization")
+                .add_attribute("amended_authorization_id", amended_id.to_string())
+                .add_attribute(
+                    "authorization_id",
+                    authorization_sequence_storage_read(deps.storage)
+                        .load()?
+                        .to_string(),
+                ))
+        }
+        ExecuteMsg::CancelAuthorizedExchanges {
+            authorization,
+            exchanges_to_remove,
+        } => {
+            if info.sender != state.lp {
+                return contract_error("only the lp can cancel authorized exchanges");
             }
-
-            let total_commitment: i64 = exchanges
-                .iter()
-                .filter_map(|e| e.commitment_in_shares)
-                .sum();
-            if total_commitment < 0 {
-                funds.push(coin(
-                    total_commitment.unsigned_abs().into(),
-                    state.commitment_denom.clone(),
-                ));
+            if exchanges_to_remove.is_empty() {
+                return contract_error("no exchanges to remove were given");
             }
 
-            let response = Response::new();
-            let total_capital: i64 = exchanges.iter().filter_map(|e| e.capital).sum();
-            let response = if total_capital < 0 {
-                match state.required_capital_attribute {
-                    None => {
-                        funds.push(coin(
-                            total_capital.unsigned_abs().into(),
-                            state.capital_denom.clone(),
-                        ));
-                        response
+            let raise = state.select_raise(authorization.raise.clone())?;
+            let mut authorizations = asset_exchange_authorization_storage_read(deps.storage)
+                .may_load()?
+                .unwrap_or_default();
+            let index = find_asset_exchange_authorization(
+                &authorizations,
+                &state,
+                &raise,
+                &authorization.exchanges,
+                &authorization.to,
+                &authorization.memo,
+            )
+            .ok_or_else(|| ContractError::AuthorizationNotFound {
+                exchanges: display_exchanges(&authorization.exchanges),
+            })?;
+            let pending = &mut authorizations[index];
+            let authorization_id = pending.id;
+            let mut remaining = pending.exchanges.clone();
+            for exchange in &exchanges_to_remove {
+                let exchange_normalized = normalized(&state, slice::from_ref(exchange));
+                match remaining.iter().position(|authorized| {
+                    normalized(&state, slice::from_ref(authorized)) == exchange_normalized
+                }) {
+                    Some(index) => {
+                        remaining.remove(index);
                     }
-                    Some(_required_capital_attribute) => {
-                        let marker_transfer = transfer_marker_coins(
-                            total_capital.unsigned_abs().into(),
-                            &state.capital_denom,
-                            state.raise.clone(),
-                            _env.contract.address,
-                        )?;
-                        response.add_message(marker_transfer)
+                    None => {
+                        return contract_error(&format!(
+                            "[{}] is not an exchange of authorization {}",
+                            exchange, authorization_id
+                        ))
                     }
                 }
-            } else {
-                response
-            };
+            }
 
-            funds.sort_by_key(|coin| coin.denom.clone());
+            let response = Response::new()
+                .add_attribute("action", "cancel_authorized_exchanges")
+                .add_attribute("authorization_id", authorization_id.to_string())
+                .add_attribute("removed", display_exchanges(&exchanges_to_remove));
+            if remaining.is_empty() {
+                let cancelled = remove_asset_exchange_authorization(
+                    deps.storage,
+                    &state,
+                    &raise,
+                    authorization.exchanges,
+                    authorization.to,
+                    authorization.memo,
+                    true,
+                )?
+                .expect("authorization required");
+                record_authorization_history(
+                    deps.storage,
+                    cancelled,
+                    AuthorizationStatus::Cancelled,
+                    &info.sender,
+                    _env.block.time,
+                )?;
+                record_operation(deps.storage, Operation::Cancellation, &_env.block.time)?;
+                return Ok(response.add_attribute("cancelled", "true"));
+            }
 
-            Ok(response.add_message(wasm_execute(
-                &state.raise,
-                &RaiseExecuteMsg::CompleteAssetExchange {
-                    exchanges,
-                    to,
-                    memo,
-                },
-                funds,
-            )?))
+            let remaining_display = display_exchanges(&remaining);
+            pending.exchanges = remaining;
+            asset_exchange_authorization_storage(deps.storage).save(&authorizations)?;
+            Ok(response.add_attribute("remaining", remaining_display))
         }
-        HandleMsg::IssueWithdrawal { to, amount } => {
-            let state = state_storage(deps.storage).load()?;
+        ExecuteMsg::AbortAssetExchangeAuthorization {
+            exchanges,
+            to,
+            memo,
+            reason,
+            raise,
+        } => {
+            if info.sender != state.lp && !state.is_admin(&info.sender) {
+                return contract_error(
+                    "only the lp or an admin can abort asset exchange authorization",
+                );
+            }
+            validate_abort_reason(&reason)?;
 
-            if info.sender != state.lp {
-                return contract_error("only the lp can withdraw");
+            let authorization = remove_asset_exchange_authorization(
+                deps.storage,
+                &state,
+                &state.select_raise(raise)?,
+                exchanges,
+                to,
+                memo,
+                true,
+            )?
+            .expect("authorization required");
+            record_aborted_authorization(
+                deps.storage,
+                AbortedAuthorization {
+                    authorization_id: authorization.id,
+                    summary: authorization.to_string(),
+                    actor: info.sender.clone(),
+                    reason: reason.clone(),
+                    time: _env.block.time,
+                },
+            )?;
+            let authorization_id = authorization.id;
+            record_authorization_history(
+                deps.storage,
+                authorization,
+                AuthorizationStatus::Cancelled,
+                &info.sender,
+                _env.block.time,
+            )?;
+            record_operation(deps.storage, Operation::Cancellation, &_env.block.time)?;
+
+            Ok(Response::new()
+                .add_attribute("action", "abort_asset_exchange_authorization")
+                .add_event(
+                    Event::new("asset_exchange_authorization_aborted")
+                        .add_attribute("authorization_id", authorization_id.to_string())
+                        .add_attribute("actor", info.sender)
+                        .add_attribute("reason", reason),
+                ))
+        }
+        ExecuteMsg::PruneExpiredAuthorizations {} => {
+            if info.sender != state.lp
+                && !state.is_admin(&info.sender)
+                && !state.is_operator(&info.sender)
+            {
+                return contract_error(
+                    "only the lp, an admin or an operator can prune expired authorizations",
+                );
             }
 
-            let response = match state.required_capital_attribute {
-                None => {
-                    let send_capital = BankMsg::Send {
-                        to_address: to.to_string(),
-                        amount: coins(amount.into(), state.capital_denom),
-                    };
-                    Response::new().add_message(send_capital)
-                }
-                Some(required_capital_attribute) => {
-                    if !query_attributes(deps, &to)
-                        .any(|attr| attr.name == required_capital_attribute)
-                    {
-                        return contract_error(
-                            format!(
-                                "{} does not have required attribute of {}",
-                                &to, &required_capital_attribute
-                            )
-                            .as_str(),
-                        );
-                    }
+            let (expired, pending): (Vec<_>, Vec<_>) =
+                asset_exchange_authorization_storage_read(deps.storage)
+                    .may_load()?
+                    .unwrap_or_default()
+                    .into_iter()
+                    .partition(|authorization| {
+                        authorization.is_stale(state.stale_after_seconds, &_env.block.time)
+                    });
+            asset_exchange_authorization_storage(deps.storage).save(&pending)?;
 
-                    let marker_transfer = transfer_marker_coins(
-                        amount.into(),
-                        &state.capital_denom,
-                        to,
-                        _env.contract.address,
-                    )?;
-                    Response::new().add_message(marker_transfer)
-                }
-            };
+            let mut response = Response::new()
+                .add_attribute("action", "prune_expired_authorizations")
+                .add_attribute("pruned", expired.len().to_string());
+            for authorization in expired {
+                response = response.add_event(
+                    Event::new("stale_authorization_removed")
+                        .add_attribute("authorization_id", authorization.id.to_string()),
+                );
+                record_authorization_history(
+                    deps.storage,
+                    authorization,
+                    AuthorizationStatus::Cancelled,
+                    &info.sender,
+                    _env.block.time,
+                )?;
+            }
             Ok(response)
         }
-    }
-}
+        ExecuteMsg::CompleteAssetExchange {
+            exchanges,
+            to,
+            memo,
+            override_available_date,
+            raise,
+        } => {
+            let raise = state.select_raise(raise)?;
+            if override_available_date {
+                if !state.is_admin(&info.sender) {
+                    return contract_error("only an admin can override the available date");
+                }
+                if !matches!(memo.as_deref(), Some(memo) if !memo.trim().is_empty()) {
+                    return contract_error("overriding the available date requires a memo");
+                }
+            }
+            state.validate_exchanges(&exchanges)?;
+            check_recipient(&exchanges, to.as_ref())?;
 
-fn query_attributes(
-    deps: DepsMut<ProvenanceQuery>,
-    address: &Addr,
-) -> IntoIter<provwasm_std::Attribute> {
-    ProvenanceQuerier::new(&deps.querier)
-        .get_attributes(address.clone(), None as Option<String>)
-        .unwrap()
-        .attributes
-        .into_iter()
-}
+            let removed = remove_asset_exchange_authorization(
+                deps.storage,
+                &state,
+                &raise,
+                exchanges.clone(),
+                to.clone(),
+                memo.clone(),
+                false,
+            )?;
+            check_completion(
+                &state,
+                &_env,
+                &info.sender,
+                removed.as_ref(),
+                &exchanges,
+                override_available_date,
+            )?;
+            let mut data = None;
+            let (exchanges, to, memo) =
+                match removed {
+                    Some(authorization) => {
+                        // the stored authorization is canonical, not the message that matched it
+                        let completion = (
+                            authorization.exchanges.clone(),
+                            authorization.to.clone(),
+                            authorization.memo.clone().or(memo),
+                        );
+                        data = Some(to_binary(&authorization)?);
+                        if authorization.is_stale(state.stale_after_seconds, &_env.block.time) {
+                            let authorization_id = authorization.id;
+                            record_authorization_history(
+                                deps.storage,
+                                authorization,
+                                AuthorizationStatus::Cancelled,
+                                &info.sender,
+                                _env.block.time,
+                            )?;
+                            // refused without an error, which would roll back removing the
+                            // authorization
+                            return Ok(Response::new()
+                                .add_attribute("action", "complete_asset_exchange")
+                                .add_attribute("completed", "false")
+                                .add_event(Event::new("stale_authorization_removed").add_attribute(
+                                    "authorization_id",
+                                    authorization_id.to_string(),
+                                ))
+                                .set_data(data.unwrap_or_default()));
+                        }
+                        record_authorization_history(
+                            deps.storage,
+                            authorization,
+                            AuthorizationStatus::Completed,
+                            &info.sender,
+                            _env.block.time,
+                        )?;
+                        completion
+                    }
+                    None => (exchanges, to, memo),
+                };
 
-fn remove_asset_exchange_authorization(
-    storage: &mut dyn Storage,
-    exchanges: Vec<AssetExchange>,
-    to: Option<Addr>,
-    memo: Option<String>,
-    authorization_required: bool,
-) -> Result<(), ContractError> {
-    match asset_exchange_authorization_storage(storage).may_load()? {
-        Some(mut authorizations) => {
-            let authorization = AssetExchangeAuthorization {
+            let mut res = complete_exchanges(
+                deps,
+                &_env,
+                &mut query_cache,
+                state,
+                raise,
                 exchanges,
                 to,
                 memo,
-            };
-            let index = authorizations.iter().position(|e| &authorization == e);
-            match index {
-                Some(index) => {
-                    authorizations.remove(index);
-                    asset_exchange_authorization_storage(storage).save(&authorizations)?;
-                }
-                None => {
-                    if authorization_required {
-                        return Err(ContractError::from(
-                            "no previously authorized asset exchange matched",
-                        ));
-                    }
-                }
-            }
-        }
-        None => {
-            if authorization_required {
-                return Err(ContractError::from(
-                    "no previously authorized asset exchange matched",
-                ));
+            )?;
+            if let Some(data) = data {
+                res = res.set_data(data);
             }
+            Ok(if override_available_date {
+                res.add_attribute("availability_overridden", "true")
+            } else {
+                res
+            })
         }
-    }
-
-    Ok(())
-}
+        ExecuteMsg::CompleteAssetExchangePartial {
+            exchanges,
+            to,
+            memo,
+            fraction_exchanges,
+            raise,
+        } => {
+            let raise = state.select_raise(raise)?;
+            state.validate_exchanges(&exchanges)?;
+            state.validate_exchange_denoms(&fraction_exchanges)?;
 
-#[entry_point]
-pub fn query(deps: Deps<ProvenanceQuery>, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
-    match msg {
-        QueryMsg::GetState {} => to_binary(&state_storage_read(deps.storage).load()?),
-        QueryMsg::GetAssetExchangeAuthorizations {} => to_binary(
-            &asset_exchange_authorization_storage_read(deps.storage)
+            let mut authorizations = asset_exchange_authorization_storage_read(deps.storage)
                 .may_load()?
-                .unwrap_or_default(),
-        ),
-    }
-}
-
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use crate::mock::send_msg;
-    use crate::mock::{execute_args, load_markers};
-    use crate::mock::{marker_transfer_msg, msg_at_index};
-    use crate::msg::AssetExchange;
-    use crate::state::asset_exchange_authorization_storage_read;
-    use crate::state::State;
-    use cosmwasm_std::testing::MockStorage;
-    use cosmwasm_std::testing::{mock_env, mock_info};
-    use cosmwasm_std::testing::{MockApi, MOCK_CONTRACT_ADDR};
-    use cosmwasm_std::Addr;
-    use cosmwasm_std::OwnedDeps;
-    use provwasm_mocks::{mock_dependencies, ProvenanceMockQuerier};
-    use provwasm_std::MarkerMsgParams;
+                .unwrap_or_default();
+            let index = find_asset_exchange_authorization(
+                &authorizations,
+                &state,
+                &raise,
+                &exchanges,
+                &to,
+                &memo,
+            )
+            .ok_or_else(|| ContractError::AuthorizationNotFound {
+                exchanges: display_exchanges(&exchanges),
+            })?;
+            let authorization = &mut authorizations[index];
+            check_completion(
+                &state,
+                &_env,
+                &info.sender,
+                Some(authorization),
+                &exchanges,
+                false,
+            )?;
+            if authorization.is_stale(state.stale_after_seconds, &_env.block.time) {
+                return contract_error("authorization is past its stale window");
+            }
+            if fraction_exchanges.len() != exchanges.len() {
+                return contract_error(
+                    "a partial completion needs one partial exchange per authorized exchange",
+                );
+            }
 
-    pub fn default_deps(
-        update_state: Option<fn(&mut State)>,
-    ) -> OwnedDeps<MockStorage, MockApi, ProvenanceMockQuerier, ProvenanceQuery> {
-        let mut deps = mock_dependencies(&[]);
+            // amounts are split against the matched exchanges, but both parts keep the stored
+            // form, as the stored authorization is canonical
+            let mut completed = Vec::with_capacity(fraction_exchanges.len());
+            let mut remaining = Vec::with_capacity(fraction_exchanges.len());
+            for ((stored, authorized), partial) in authorization
+                .exchanges
+                .iter()
+                .zip(&exchanges)
+                .zip(&fraction_exchanges)
+            {
+                let remainder = state.partial_remainder(authorized, partial)?;
+                completed.push(AssetExchange {
+                    investment: partial.investment,
+                    commitment_in_shares: partial.commitment_in_shares,
+                    capital: partial.capital,
+                    ..stored.clone()
+                });
+                remaining.push(AssetExchange {
+                    investment: remainder.investment,
+                    commitment_in_shares: remainder.commitment_in_shares,
+                    capital: remainder.capital,
+                    ..stored.clone()
+                });
+            }
+            let to = authorization.to.clone();
+            let memo = authorization.memo.clone().or(memo);
+            authorization.exchanges = remaining;
+            let remaining = display_exchanges(&authorization.exchanges);
+            asset_exchange_authorization_storage(deps.storage).save(&authorizations)?;
 
-        let mut state = State::test_default();
-        if let Some(update) = update_state {
-            update(&mut state);
+            Ok(complete_exchanges(
+                deps,
+                &_env,
+                &mut query_cache,
+                state,
+                raise,
+                completed,
+                to,
+                memo,
+            )?
+            .add_attribute("remaining", remaining))
         }
-        state_storage(&mut deps.storage).save(&state).unwrap();
-
-        deps
-    }
+        ExecuteMsg::SetWithdrawalAllowance {
+            to,
+            amount,
+            capital_denom,
+            period_seconds,
+        } => {
+            if info.sender != state.lp {
+                return contract_error("only the lp can set withdrawal allowances");
+            }
+            if period_seconds == 0 {
+                return contract_error("withdrawal allowance period must be greater than zero");
+            }
 
-    pub fn capital_coin_deps(
-        update_state: Option<fn(&mut State)>,
-    ) -> OwnedDeps<MockStorage, MockApi, ProvenanceMockQuerier, ProvenanceQuery> {
-        let mut deps = mock_dependencies(&[]);
+            let to = deps.api.addr_validate(to.as_str())?;
+            let denom = state.resolve_capital_denom(capital_denom.as_ref())?;
+            let mut allowances = withdrawal_allowances_storage_read(deps.storage)
+                .may_load()?
+                .unwrap_or_default();
+            allowances.retain(|allowance| !allowance.applies_to(&state, &to, &denom));
+            allowances.push(WithdrawalAllowance {
+                to: to.clone(),
+                denom: denom.clone(),
+                amount,
+                period_seconds,
+                consumed: Uint128::zero(),
+                window_start: _env.block.time,
+            });
+            withdrawal_allowances_storage(deps.storage).save(&allowances)?;
 
-        let mut state = State::test_capital_coin();
-        if let Some(update) = update_state {
-            update(&mut state);
+            Ok(Response::new()
+                .add_attribute("action", "set_withdrawal_allowance")
+                .add_attribute("to", to.to_string())
+                .add_attribute("amount", amount.to_string())
+                .add_attribute("capital_denom", denom)
+                .add_attribute("period_seconds", period_seconds.to_string()))
         }
-        state_storage(&mut deps.storage).save(&state).unwrap();
-
-        deps
-    }
+        ExecuteMsg::ApproveWithdrawal {
+            to,
+            amount,
+            capital_denom,
+            ttl_seconds,
+            expires_at,
+        } => {
+            if !state.is_admin(&info.sender) {
+                return contract_error("only admin can approve withdrawals");
+            }
+            let expires_at = match (ttl_seconds, expires_at) {
+                (Some(ttl_seconds), None) => _env.block.time.plus_seconds(ttl_seconds),
+                (None, Some(expires_at)) => expires_at,
+                _ => return contract_error("exactly one of ttl_seconds or expires_at is required"),
+            };
+            if expires_at <= _env.block.time {
+                return contract_error("withdrawal approval must expire in the future");
+            }
 
-    pub fn restricted_capital_coin_deps(
-        update_state: Option<fn(&mut State)>,
-    ) -> OwnedDeps<MockStorage, MockApi, ProvenanceMockQuerier, ProvenanceQuery> {
-        let mut deps = mock_dependencies(&[]);
+            let to = deps.api.addr_validate(to.as_str())?;
+            let denom = state.resolve_capital_denom(capital_denom.as_ref())?;
+            let mut approvals = unexpired_withdrawal_approvals(deps.storage, &_env.block.time)?;
+            approvals.retain(|approval| {
+                approval.to != to || approval.amount != amount || approval.denom != denom
+            });
+            approvals.push(WithdrawalApproval {
+                to: to.clone(),
+                amount,
+                denom: denom.clone(),
+                approved_by: info.sender,
+                expires_at,
+            });
+            withdrawal_approvals_storage(deps.storage).save(&approvals)?;
 
-        let mut state = State::test_restricted_capital_coin();
-        if let Some(update) = update_state {
-            update(&mut state);
+            Ok(Response::new()
+                .add_attribute("action", "approve_withdrawal")
+                .add_attribute("to", to.to_string())
+                .add_attribute("amount", amount.to_string())
+                .add_attribute("capital_denom", denom)
+                .add_attribute("expires_at", expires_at.seconds().to_string()))
         }
-        state_storage(&mut deps.storage).save(&state).unwrap();
+        ExecuteMsg::RevokeWithdrawalApproval {
+            to,
+            amount,
+            capital_denom,
+        } => {
+            if !state.is_admin(&info.sender) {
+                return contract_error("only admin can revoke withdrawal approvals");
+            }
 
+            let mut approvals = unexpired_withdrawal_approvals(deps.storage, &_env.block.time)?;
+            let outstanding = approvals.len();
+            approvals.retain(|approval| {
+                approval.to != to
+                    || matches!(amount, Some(amount) if approval.amount != amount)
+                    || matches!(&capital_denom, Some(denom) if &approval.denom != denom)
+            });
+            let revoked = outstanding - approvals.len();
+            if revoked == 0 {
+                return contract_error(&format!("no outstanding withdrawal approval for {}", to));
+            }
+            withdrawal_approvals_storage(deps.storage).save(&approvals)?;
+
+            Ok(Response::new()
+                .add_attribute("action", "revoke_withdrawal_approval")
+                .add_attribute("to", to.to_string())
+                .add_attribute("revoked", revoked.to_string()))
+        }
+        ExecuteMsg::UpdateDefaultDistributionAddress {
+            default_distribution_address,
+        } => {
+            if info.sender != state.lp {
+                return contract_error("only the lp can update the default distribution address");
+            }
+
+            let old_address = state
+                .default_distribution_address
+                .as_ref()
+                .map(Addr::to_string)
+                .unwrap_or_default();
+            state.default_distribution_address = default_distribution_address
+                .map(|address| deps.api.addr_validate(address.as_str()))
+                .transpose()?;
+            let address = state
+                .default_distribution_address
+                .as_ref()
+                .map(Addr::to_string)
+                .unwrap_or_default();
+            state_storage(deps.storage).save(&state)?;
+
+            Ok(Response::new()
+                .add_attribute("action", "update_default_distribution_address")
+                .add_attribute("old_default_distribution_address", old_address)
+                .add_attribute("default_distribution_address", address))
+        }
+        ExecuteMsg::SetBeneficiary { beneficiary } => {
+            if info.sender != state.lp {
+                return contract_error("only the lp can set the beneficiary");
+            }
+
+            let old_beneficiary = state
+                .beneficiary
+                .as_ref()
+                .map(Addr::to_string)
+                .unwrap_or_default();
+            state.beneficiary = beneficiary
+                .map(|beneficiary| deps.api.addr_validate(beneficiary.as_str()))
+                .transpose()?;
+            let beneficiary = state
+                .beneficiary
+                .as_ref()
+                .map(Addr::to_string)
+                .unwrap_or_default();
+            state_storage(deps.storage).save(&state)?;
+
+            Ok(Response::new()
+                .add_attribute("action", "set_beneficiary")
+                .add_attribute("old_beneficiary", old_beneficiary)
+                .add_attribute("beneficiary", beneficiary))
+        }
+        ExecuteMsg::UpdateMetadata { metadata } => {
+            if !state.is_admin(&info.sender) {
+                return contract_error("only admin can update metadata");
+            }
+
+            let mut response = Response::new().add_attribute("action", "update_metadata");
+            match metadata {
+                Some(metadata) => {
+                    metadata.validate()?;
+                    response = response.add_attribute("name", metadata.name.clone());
+                    metadata_storage(deps.storage).save(&metadata)?;
+                }
+                None => metadata_storage(deps.storage).remove(),
+            }
+            Ok(response)
+        }
+        ExecuteMsg::SetLpNote { note } => {
+            if info.sender != state.lp {
+                return contract_error("only the lp can set the lp note");
+            }
+            if let Some(note) = &note {
+                validate_lp_note(note)?;
+            }
+
+            let changed = state.lp_note != note;
+            state.lp_note = note;
+            state_storage(deps.storage).save(&state)?;
+
+            let mut response = Response::new().add_attribute("action", "set_lp_note");
+            if changed {
+                response = response.add_event(
+                    Event::new("lp_note_updated")
+                        .add_attribute("lp_note", state.lp_note.unwrap_or_default()),
+                );
+            }
+            Ok(response)
+        }
+        ExecuteMsg::IssueWithdrawal {
+            to,
+            to_name,
+            amount,
+            capital_denom,
+        } => {
+            if info.sender != state.lp {
+                return contract_error("only the lp can withdraw");
+            }
+            state.check_not_paused()?;
+            if amount.is_zero() {
+                return contract_error("withdrawal amount must be positive");
+            }
+            let to = withdrawal_recipient(deps.as_ref(), to, to_name.as_deref())?;
+            let capital_denom = state.resolve_capital_denom(capital_denom.as_ref())?;
+            let withdrawal = coin(amount.u128(), &capital_denom);
+            let approved_by = match state.withdrawal_approval_threshold(&capital_denom) {
+                Some(threshold) if amount > threshold => Some(consume_withdrawal_approval(
+                    deps.storage,
+                    &to,
+                    &withdrawal,
+                    &_env.block.time,
+                )?),
+                _ => None,
+            };
+            consume_withdrawal_allowance(deps.storage, &state, &to, &withdrawal, &_env.block.time)?;
+            record_operation(deps.storage, Operation::Withdrawal, &_env.block.time)?;
+
+            let mut response = Response::new()
+                .add_attribute("action", "issue_withdrawal")
+                .add_attribute("to", to.to_string())
+                .add_attributes(to_name.map(|to_name| ("to_name", to_name)))
+                .add_attribute("amount", amount.to_string())
+                .add_attribute("capital_denom", capital_denom.clone())
+                .add_attributes(approved_by.map(|approved_by| ("approved_by", approved_by)));
+            let mut payments = vec![(to, amount)];
+            if let Some((fee, fee_recipient)) = state.withdrawal_fee(amount) {
+                payments[0].1 = amount - fee;
+                response = response
+                    .add_attribute("withdrawal_fee", fee.to_string())
+                    .add_attribute("fee_recipient", fee_recipient.to_string())
+                    .add_attribute("net_amount", payments[0].1.to_string());
+                payments.push((fee_recipient.clone(), fee));
+            }
+            // a fee that rounds down to nothing isn't sent
+            payments.retain(|(_, amount)| !amount.is_zero());
+
+            let restricted = query_cache.is_restricted(&deps.querier, &capital_denom);
+            if let Some(required_capital_attributes) =
+                state.enforced_capital_attributes(&capital_denom, restricted)
+            {
+                for (recipient, _) in &payments {
+                    check_capital_attributes(
+                        &deps.querier,
+                        &mut query_cache,
+                        recipient,
+                        required_capital_attributes,
+                    )?;
+                }
+            }
+            let marker_transfer = query_cache.requires_marker_transfer(
+                &deps.querier,
+                &_env.contract.address,
+                &capital_denom,
+            )?;
+            for (recipient, amount) in payments {
+                response = if marker_transfer {
+                    response.add_message(transfer_marker_coins(
+                        amount.u128(),
+                        &capital_denom,
+                        recipient,
+                        _env.contract.address.clone(),
+                    )?)
+                } else {
+                    response.add_message(BankMsg::Send {
+                        to_address: recipient.to_string(),
+                        amount: coins(amount.u128(), &capital_denom),
+                    })
+                };
+            }
+            Ok(response)
+        }
+        ExecuteMsg::ReturnCapital {
+            amount,
+            capital_denom,
+            memo,
+        } => {
+            if info.sender != state.lp {
+                return contract_error("only the lp can return capital");
+            }
+            state.check_not_paused()?;
+            if amount.is_zero() {
+                return contract_error("returned capital must be positive");
+            }
+
+            let capital_denom = state.resolve_capital_denom(capital_denom.as_ref())?;
+            let mut response = Response::new()
+                .add_attribute("action", "return_capital")
+                .add_attribute("amount", amount.to_string())
+                .add_attribute("capital_denom", capital_denom.clone());
+            let capital = coin(amount.u128(), capital_denom);
+            let restricted = query_cache.is_restricted(&deps.querier, &capital.denom);
+            if let Some(required_capital_attributes) =
+                state.enforced_capital_attributes(&capital.denom, restricted)
+            {
+                check_capital_attributes(
+                    &deps.querier,
+                    &mut query_cache,
+                    state.primary_raise(),
+                    required_capital_attributes,
+                )?;
+            }
+            let funds = if query_cache.requires_marker_transfer(
+                &deps.querier,
+                &_env.contract.address,
+                &capital.denom,
+            )? {
+                response = response.add_message(transfer_marker_coins(
+                    amount.u128(),
+                    &capital.denom,
+                    state.primary_raise().clone(),
+                    _env.contract.address,
+                )?);
+                vec![]
+            } else {
+                vec![capital.clone()]
+            };
+            Ok(response.add_message(wasm_execute(
+                state.primary_raise(),
+                &RaiseExecuteMsg::ReturnCapital { capital, memo },
+                funds,
+            )?))
+        }
+        ExecuteMsg::IssueMultiCoinWithdrawal {
+            to,
+            to_name,
+            amounts,
+        } => {
+            if info.sender != state.lp {
+                return contract_error("only the lp can withdraw");
+            }
+            state.check_not_paused()?;
+            if amounts.is_empty() {
+                return contract_error("a withdrawal requires at least one coin");
+            }
+            let to = withdrawal_recipient(deps.as_ref(), to, to_name.as_deref())?;
+            for (i, amount) in amounts.iter().enumerate() {
+                state.resolve_capital_denom(Some(&amount.denom))?;
+                if amounts[..i].iter().any(|other| other.denom == amount.denom) {
+                    return Err(ContractError::from(format!(
+                        "duplicate withdrawal denom {}",
+                        amount.denom
+                    )));
+                }
+                if amount.amount.is_zero() {
+                    return Err(ContractError::from(format!(
+                        "withdrawal of {} must be positive",
+                        amount.denom
+                    )));
+                }
+            }
+            let mut approved_by = Vec::new();
+            for amount in amounts.iter().filter(|amount| {
+                matches!(state.withdrawal_approval_threshold(&amount.denom),
+                    Some(threshold) if amount.amount > threshold)
+            }) {
+                approved_by.push(consume_withdrawal_approval(
+                    deps.storage,
+                    &to,
+                    amount,
+                    &_env.block.time,
+                )?);
+            }
+            for amount in &amounts {
+                consume_withdrawal_allowance(deps.storage, &state, &to, amount, &_env.block.time)?;
+            }
+            record_operation(deps.storage, Operation::Withdrawal, &_env.block.time)?;
+
+            let mut response = Response::new()
+                .add_attribute("action", "issue_withdrawal")
+                .add_attribute("to", to.to_string())
+                .add_attributes(to_name.map(|to_name| ("to_name", to_name)))
+                .add_attribute("amounts", join_coins(&amounts));
+            if !approved_by.is_empty() {
+                response = response.add_attribute("approved_by", join_addresses(&approved_by));
+            }
+            let mut payments = Vec::new();
+            let (mut nets, mut fees) = (Vec::new(), Vec::new());
+            for amount in &amounts {
+                match state.withdrawal_fee(amount.amount) {
+                    Some((fee, fee_recipient)) => {
+                        let net = coin((amount.amount - fee).u128(), &amount.denom);
+                        let fee = coin(fee.u128(), &amount.denom);
+                        payments.push((to.clone(), net.clone()));
+                        payments.push((fee_recipient.clone(), fee.clone()));
+                        nets.push(net);
+                        fees.push(fee);
+                    }
+                    None => payments.push((to.clone(), amount.clone())),
+                }
+            }
+            if let Some(fee_recipient) = state.fee_recipient.as_ref().filter(|_| !fees.is_empty()) {
+                response = response
+                    .add_attribute("withdrawal_fees", join_coins(&fees))
+                    .add_attribute("fee_recipient", fee_recipient.to_string())
+                    .add_attribute("net_amounts", join_coins(&nets));
+            }
+            payments.retain(|(_, amount)| !amount.amount.is_zero());
+
+            // unrestricted coins share one bank send per recipient
+            let mut bank_sends: Vec<(Addr, Vec<Coin>)> = Vec::new();
+            for (recipient, amount) in payments {
+                let restricted = query_cache.is_restricted(&deps.querier, &amount.denom);
+                if let Some(required_capital_attributes) =
+                    state.enforced_capital_attributes(&amount.denom, restricted)
+                {
+                    check_capital_attributes(
+                        &deps.querier,
+                        &mut query_cache,
+                        &recipient,
+                        required_capital_attributes,
+                    )?;
+                }
+                if query_cache.requires_marker_transfer(
+                    &deps.querier,
+                    &_env.contract.address,
+                    &amount.denom,
+                )? {
+                    response = response.add_message(transfer_marker_coins(
+                        amount.amount.u128(),
+                        &amount.denom,
+                        recipient,
+                        _env.contract.address.clone(),
+                    )?);
+                } else {
+                    match bank_sends.iter_mut().find(|(to, _)| to == &recipient) {
+                        Some((_, bank_coins)) => bank_coins.push(amount),
+                        None => bank_sends.push((recipient, vec![amount])),
+                    }
+                }
+            }
+            for (recipient, mut bank_coins) in bank_sends {
+                bank_coins.sort_by(|a, b| a.denom.cmp(&b.denom));
+                response = response.add_message(BankMsg::Send {
+                    to_address: recipient.to_string(),
+                    amount: bank_coins,
+                });
+            }
+            Ok(response)
+        }
+        ExecuteMsg::Deposit { memo } => {
+            if info.funds.is_empty() {
+                return contract_error("a deposit requires funds");
+            }
+            if let Some(unsupported) = info
+                .funds
+                .iter()
+                .find(|coin| !state.like_capital_denoms.contains(&coin.denom))
+            {
+                return Err(ContractError::from(format!(
+                    "unsupported deposit denom {}",
+                    unsupported.denom
+                )));
+            }
+
+            let deposit =
+                record_deposit(deps.storage, info.sender, info.funds, memo, _env.block.time)?;
+
+            let mut response = Response::new()
+                .add_attribute("action", "deposit")
+                .add_attribute("deposit_id", deposit.id.to_string())
+                .add_attribute("sender", deposit.sender.to_string())
+                .add_attribute("funds", join_coins(&deposit.coins));
+            if let Some(memo) = deposit.memo {
+                response = response.add_attribute("memo", memo);
+            }
+            Ok(response)
+        }
+        ExecuteMsg::SweepDust { to } => {
+            if info.sender != state.lp {
+                return contract_error("only the lp can sweep dust");
+            }
+            state.check_not_paused()?;
+
+            let to = deps.api.addr_validate(to.as_str())?;
+            let mut response = Response::new()
+                .add_attribute("action", "sweep_dust")
+                .add_attribute("to", to.to_string());
+            for denom in &state.like_capital_denoms {
+                let balance = deps
+                    .querier
+                    .query_balance(&_env.contract.address, denom)?
+                    .amount
+                    .u128();
+                let dust = balance % u128::from(state.capital_per_share(denom));
+                if dust == 0 {
+                    continue;
+                }
+                let restricted = query_cache.is_restricted(&deps.querier, denom);
+                if let Some(required_capital_attributes) =
+                    state.enforced_capital_attributes(denom, restricted)
+                {
+                    check_capital_attributes(
+                        &deps.querier,
+                        &mut query_cache,
+                        &to,
+                        required_capital_attributes,
+                    )?;
+                }
+                response = if query_cache.requires_marker_transfer(
+                    &deps.querier,
+                    &_env.contract.address,
+                    denom,
+                )? {
+                    response.add_message(transfer_marker_coins(
+                        dust,
+                        denom,
+                        to.clone(),
+                        _env.contract.address.clone(),
+                    )?)
+                } else {
+                    response.add_message(BankMsg::Send {
+                        to_address: to.to_string(),
+                        amount: coins(dust, denom),
+                    })
+                };
+                response = response.add_attribute(format!("swept_{}", denom), dust.to_string());
+            }
+            Ok(response)
+        }
+        ExecuteMsg::RefundUnexpectedFunds { denom, to } => {
+            if !state.is_admin(&info.sender) {
+                return contract_error("only admin can refund unexpected funds");
+            }
+            if denom == state.commitment_denom
+                || state.investment_denoms.contains(&denom)
+                || state.like_capital_denoms.contains(&denom)
+            {
+                return Err(ContractError::from(format!(
+                    "{} is used by the subscription and cannot be refunded",
+                    denom
+                )));
+            }
+
+            let to = deps.api.addr_validate(to.as_str())?;
+            let balance = deps.querier.query_balance(&_env.contract.address, &denom)?;
+            if balance.amount.is_zero() {
+                return Err(ContractError::from(format!(
+                    "the subscription holds no {} to refund",
+                    denom
+                )));
+            }
+
+            Ok(Response::new()
+                .add_attribute("action", "refund_unexpected_funds")
+                .add_attribute("to", to.to_string())
+                .add_attribute("refunded", balance.to_string())
+                .add_message(BankMsg::Send {
+                    to_address: to.to_string(),
+                    amount: vec![balance],
+                }))
+        }
+    }
+}
+
+/// Moving restricted capital relies on the contract holding transfer access on each restricted
+/// marker that carries an attribute requirement. Any other denom moves by bank send, so its
+/// requirement only takes effect when enforced on bank sends, and is refused otherwise.
+pub fn check_marker_transfer_permission(
+    querier: &QuerierWrapper<ProvenanceQuery>,
+    contract: &Addr,
+    requirements: &[CapitalDenomRequirement],
+) -> Result<(), ContractError> {
+    for requirement in requirements {
+        match ProvenanceQuerier::new(querier).get_marker_by_denom(&requirement.capital_denom) {
+            Ok(marker) if marker.marker_type == MarkerType::Restricted => {
+                check_transfer_grant(&marker, contract)?
+            }
+            // a bank send needs no access, and the attributes are checked before it
+            _ if requirement.enforce_attribute_on_bank_send => {}
+            _ => {
+                return Err(ContractError::UnenforcedCapitalAttribute {
+                    denom: requirement.capital_denom.clone(),
+                })
+            }
+        }
+    }
+    Ok(())
+}
+
+fn check_transfer_grant(marker: &Marker, contract: &Addr) -> Result<(), ContractError> {
+    let granted = marker.permissions.iter().any(|grant| {
+        &grant.address == contract && grant.permissions.contains(&MarkerAccess::Transfer)
+    });
+    if granted {
+        return Ok(());
+    }
+    let access_list: Vec<String> = marker
+        .permissions
+        .iter()
+        .map(|grant| format!("{}: {:?}", grant.address, grant.permissions))
+        .collect();
+    Err(ContractError::from(format!(
+        "{} lacks transfer permission on marker {}, access list is [{}]",
+        contract,
+        marker.denom,
+        access_list.join("; ")
+    )))
+}
+
+/// Asks the raise for its holdings on behalf of `subscription`. A raise that predates the query
+/// fails to parse it, which is reported as `RaiseQueryUnsupported` rather than a parse error.
+fn query_raise_holdings(
+    querier: &QuerierWrapper<ProvenanceQuery>,
+    raise: &Addr,
+    subscription: &Addr,
+) -> Result<SubscriptionHoldings, ContractError> {
+    let request: QueryRequest<ProvenanceQuery> = QueryRequest::Wasm(WasmQuery::Smart {
+        contract_addr: raise.to_string(),
+        msg: to_binary(&RaiseQueryMsg::GetSubscriptionHoldings {
+            subscription: subscription.clone(),
+        })?,
+    });
+    match querier.raw_query(&to_vec(&request)?) {
+        SystemResult::Err(error) => Err(ContractError::from(format!(
+            "unable to query raise {}: {}",
+            raise, error
+        ))),
+        SystemResult::Ok(ContractResult::Err(error)) if error.contains("unknown variant") => {
+            Err(ContractError::RaiseQueryUnsupported {
+                raise: raise.to_string(),
+                query: String::from("get_subscription_holdings"),
+            })
+        }
+        SystemResult::Ok(ContractResult::Err(error)) => Err(ContractError::from(format!(
+            "raise {} failed the holdings query: {}",
+            raise, error
+        ))),
+        SystemResult::Ok(ContractResult::Ok(holdings)) => Ok(from_binary(&holdings)?),
+    }
+}
+
+fn query_attributes(
+    querier: &QuerierWrapper<ProvenanceQuery>,
+    address: &Addr,
+) -> StdResult<IntoIter<provwasm_std::Attribute>> {
+    Ok(ProvenanceQuerier::new(querier)
+        .get_attributes(address.clone(), None as Option<String>)
+        .context(&format!("unable to query attributes of {}", address))?
+        .attributes
+        .into_iter())
+}
+
+/// Attribute names by address and marker types by denom, memoized for the length of one execute
+/// call so that checks of several legs paying the same address or moving the same denom query it
+/// once.
+#[derive(Default)]
+struct QueryCache {
+    attributes: BTreeMap<Addr, Vec<String>>,
+    markers: BTreeMap<String, Option<Marker>>,
+}
+
+impl QueryCache {
+    fn attributes(
+        &mut self,
+        querier: &QuerierWrapper<ProvenanceQuery>,
+        address: &Addr,
+    ) -> StdResult<&[String]> {
+        if !self.attributes.contains_key(address) {
+            let names = query_attributes(querier, address)?
+                .map(|attr| attr.name)
+                .collect();
+            self.attributes.insert(address.clone(), names);
+        }
+        Ok(&self.attributes[address])
+    }
+
+    fn is_restricted(&mut self, querier: &QuerierWrapper<ProvenanceQuery>, denom: &str) -> bool {
+        matches!(
+            self.marker(querier, denom),
+            Some(marker) if marker.marker_type == MarkerType::Restricted
+        )
+    }
+
+    fn marker(
+        &mut self,
+        querier: &QuerierWrapper<ProvenanceQuery>,
+        denom: &str,
+    ) -> Option<&Marker> {
+        self.markers
+            .entry(denom.to_string())
+            .or_insert_with(|| {
+                ProvenanceQuerier::new(querier)
+                    .get_marker_by_denom(denom)
+                    .ok()
+            })
+            .as_ref()
+    }
+
+    /// Restricted markers can only be moved by the contract with a marker transfer, not as funds.
+    /// The contract's transfer access is verified up front, since the marker module otherwise
+    /// only rejects the transfer once the rest of the execution has run.
+    fn requires_marker_transfer(
+        &mut self,
+        querier: &QuerierWrapper<ProvenanceQuery>,
+        contract: &Addr,
+        denom: &str,
+    ) -> Result<bool, ContractError> {
+        match self.marker(querier, denom) {
+            Some(marker) if marker.marker_type == MarkerType::Restricted => {
+                check_transfer_grant(marker, contract)?;
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
+    }
+}
+
+/// Whether the attribute satisfies the required name. A requirement ending in `.*` is satisfied by
+/// any attribute extending its prefix by at least one character; others must match exactly.
+fn attribute_matches(required: &str, attribute: &str) -> bool {
+    match required.strip_suffix('*') {
+        Some(prefix) if prefix.ends_with('.') => {
+            attribute.len() > prefix.len() && attribute.starts_with(prefix)
+        }
+        _ => required == attribute,
+    }
+}
+
+/// Passes when the address holds at least one of the required attributes.
+fn check_capital_attributes(
+    querier: &QuerierWrapper<ProvenanceQuery>,
+    query_cache: &mut QueryCache,
+    address: &Addr,
+    required_attributes: &[String],
+) -> Result<(), ContractError> {
+    if query_cache
+        .attributes(querier, address)?
+        .iter()
+        .any(|attr| {
+            required_attributes
+                .iter()
+                .any(|required| attribute_matches(required, attr))
+        })
+    {
+        Ok(())
+    } else {
+        Err(ContractError::from(format!(
+            "{} does not have required attribute of {}",
+            address,
+            required_attributes.join(" or ")
+        )))
+    }
+}
+
+/// Runs a sensitive admin action through approval and the timelock. The action is validated
+/// up front, collects approvals until the admin threshold is met, and is then either applied
+/// immediately or, when a delay is configured, scheduled for anyone to execute later.
+fn propose_admin_action(
+    storage: &mut dyn Storage,
+    env: &Env,
+    admin: &Addr,
+    mut state: State,
+    action: AdminAction,
+) -> ContractResponse {
+    action.clone().apply(&mut state.clone())?;
+
+    let approvals = approve_admin_action(storage, &state, admin, action.clone(), env)?;
+    if approvals < usize::from(state.admin_threshold) {
+        return Ok(Response::new()
+            .add_attribute("action", format!("approve_{}", action.name()))
+            .add_attribute("admin", admin)
+            .add_attribute("approvals", approvals.to_string()));
+    }
+
+    if state.admin_action_delay_seconds > 0 {
+        let id = next_scheduled_admin_action_id(storage)?;
+        let execute_after = env
+            .block
+            .time
+            .plus_seconds(state.admin_action_delay_seconds);
+        let action_name = action.name();
+
+        let mut scheduled = scheduled_admin_actions_storage(storage)
+            .may_load()?
+            .unwrap_or_default();
+        scheduled.push(ScheduledAdminAction {
+            id,
+            action,
+            execute_after,
+        });
+        scheduled_admin_actions_storage(storage).save(&scheduled)?;
+
+        return Ok(Response::new()
+            .add_attribute("action", format!("schedule_{}", action_name))
+            .add_attribute("admin", admin)
+            .add_attribute("scheduled_action_id", id.to_string())
+            .add_attribute("execute_after", execute_after.seconds().to_string()));
+    }
+
+    let action_name = action.name();
+    action.apply(&mut state)?;
+    state_storage(storage).save(&state)?;
+
+    Ok(Response::new()
+        .add_attribute("action", action_name)
+        .add_attribute("admin", admin))
+}
+
+/// The address a withdrawal goes to, given either directly or as a Provenance name.
+fn withdrawal_recipient(
+    deps: Deps<ProvenanceQuery>,
+    to: Option<Addr>,
+    to_name: Option<&str>,
+) -> Result<Addr, ContractError> {
+    match (to, to_name) {
+        (Some(to), None) => Ok(deps.api.addr_validate(to.as_str())?),
+        (None, Some(to_name)) => {
+            let name = ProvenanceQuerier::new(&deps.querier)
+                .resolve_name(to_name)
+                .map_err(|err| {
+                    ContractError::from(format!("unable to resolve name {}: {}", to_name, err))
+                })?;
+            Ok(deps.api.addr_validate(name.address.as_str())?)
+        }
+        (Some(_), Some(_)) => Err(ContractError::from(
+            "a withdrawal takes either to or to_name, not both",
+        )),
+        (None, None) => Err(ContractError::from(
+            "a withdrawal needs either to or to_name",
+        )),
+    }
+}
+
+/// A denom update has to name a marker that exists, and can't strand a pending authorization.
+fn check_denom_update(
+    storage: &dyn Storage,
+    querier: &QuerierWrapper<ProvenanceQuery>,
+    query_cache: &mut QueryCache,
+    action: &AdminAction,
+    denom: &str,
+) -> Result<(), ContractError> {
+    if query_cache.marker(querier, denom).is_none() {
+        return Err(ContractError::from(format!(
+            "no marker exists for denom {}",
+            denom
+        )));
+    }
+    action.check_pending_authorizations(
+        &asset_exchange_authorization_storage_read(storage)
+            .may_load()?
+            .unwrap_or_default(),
+    )
+}
+
+/// Records an admin's approval of a sensitive action and returns how many distinct admins have
+/// approved it so far. Once the threshold is reached the pending action is cleared. With a
+/// threshold of one nothing is stored, and the action proceeds immediately.
+fn approve_admin_action(
+    storage: &mut dyn Storage,
+    state: &State,
+    admin: &Addr,
+    action: AdminAction,
+    env: &Env,
+) -> Result<usize, ContractError> {
+    if state.admin_threshold <= 1 {
+        return Ok(1);
+    }
+
+    let mut pending: Vec<PendingAdminAction> = pending_admin_actions_storage(storage)
+        .may_load()?
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|pending| !pending.expired(&env.block.time))
+        .collect();
+
+    let approvals = match pending.iter().position(|pending| pending.action == action) {
+        Some(index) => {
+            if pending[index].approvals.contains(admin) {
+                return Err(ContractError::from(
+                    "admin has already approved this action",
+                ));
+            }
+            pending[index].approvals.push(admin.clone());
+            let approvals = pending[index].approvals.len();
+            if approvals >= usize::from(state.admin_threshold) {
+                pending.remove(index);
+            }
+            approvals
+        }
+        None => {
+            pending.push(PendingAdminAction {
+                action,
+                approvals: vec![admin.clone()],
+                proposed_at: env.block.time,
+            });
+            1
+        }
+    };
+
+    pending_admin_actions_storage(storage).save(&pending)?;
+
+    Ok(approvals)
+}
+
+#[allow(clippy::too_many_arguments)]
+fn authorize_asset_exchange(
+    storage: &mut dyn Storage,
+    env: &Env,
+    state: &State,
+    raise: Option<Addr>,
+    exchanges: Vec<AssetExchange>,
+    to: Option<Addr>,
+    memo: Option<String>,
+    memo_hash: Option<Binary>,
+    idempotency_key: Option<String>,
+    expires_at: Option<Timestamp>,
+) -> ContractResponse {
+    state.check_not_paused()?;
+    if let Some(memo_hash) = &memo_hash {
+        if memo.is_some() {
+            return contract_error("an authorization takes a memo or a memo hash, not both");
+        }
+        if memo_hash.len() != 32 {
+            return contract_error("memo hash must be a 32 byte sha256 digest");
+        }
+    }
+    let expires_at = state.authorization_expiration(expires_at, &env.block.time)?;
+    if let Some(key) = &idempotency_key {
+        check_idempotency_key(storage, key)?;
+    }
+    record_authorization(
+        storage,
+        env.block.height,
+        state.max_authorizations_per_block,
+    )?;
+
+    state.validate_exchanges(&exchanges)?;
+    check_recipient(&exchanges, to.as_ref())?;
+    state.check_commitment_ceiling(&exchanges)?;
+
+    let mut authorizations = asset_exchange_authorization_storage(storage)
+        .may_load()?
+        .unwrap_or_default();
+    let id = next_authorization_id(storage)?;
+    authorizations.push(AssetExchangeAuthorization {
+        id,
+        raise,
+        exchanges,
+        to,
+        memo,
+        created_at: Some(env.block.time),
+        expires_at,
+        memo_hash,
+        status: AuthorizationStatus::Pending,
+        resolved_at: None,
+        resolved_by: None,
+    });
+    asset_exchange_authorization_storage(storage).save(&authorizations)?;
+    if let Some(key) = idempotency_key {
+        record_idempotency_key(storage, key, id)?;
+    }
+    record_operation(storage, Operation::Authorization, &env.block.time)?;
+
+    Ok(Response::default())
+}
+
+/// Exchanges with their denoms resolved, or as given when a denom is unsupported.
+fn normalized(state: &State, exchanges: &[AssetExchange]) -> Vec<AssetExchange> {
+    state
+        .normalize_exchanges(exchanges)
+        .unwrap_or_else(|_| exchanges.to_vec())
+}
+
+/// The position of the first authorization for `raise` matching the exchanges, which are compared
+/// with their denoms resolved. Every lookup of a pending authorization by its content, including
+/// simulations and `HasAuthorization`, matches through here.
+fn find_asset_exchange_authorization(
+    authorizations: &[AssetExchangeAuthorization],
+    state: &State,
+    raise: &Option<Addr>,
+    exchanges: &[AssetExchange],
+    to: &Option<Addr>,
+    memo: &Option<String>,
+) -> Option<usize> {
+    let exchanges = normalized(state, exchanges);
+    authorizations.iter().position(|authorization| {
+        &authorization.raise == raise
+            && normalized(state, &authorization.exchanges) == exchanges
+            && &authorization.to == to
+            && authorization.matches_memo(memo)
+    })
+}
+
+/// Removes and returns the authorization matching the exchanges, so the stored form may differ
+/// from the exchanges given.
+fn remove_asset_exchange_authorization(
+    storage: &mut dyn Storage,
+    state: &State,
+    raise: &Option<Addr>,
+    exchanges: Vec<AssetExchange>,
+    to: Option<Addr>,
+    memo: Option<String>,
+    authorization_required: bool,
+) -> Result<Option<AssetExchangeAuthorization>, ContractError> {
+    let mut authorizations = asset_exchange_authorization_storage(storage)
+        .may_load()?
+        .unwrap_or_default();
+    match find_asset_exchange_authorization(&authorizations, state, raise, &exchanges, &to, &memo) {
+        Some(index) => {
+            let removed = authorizations.remove(index);
+            asset_exchange_authorization_storage(storage).save(&authorizations)?;
+            Ok(Some(removed))
+        }
+        None if authorizations.iter().any(|authorization| {
+            authorization.memo_hash.is_some()
+                && &authorization.raise == raise
+                && normalized(state, &authorization.exchanges) == normalized(state, &exchanges)
+                && authorization.to == to
+        }) =>
+        {
+            Err(ContractError::from(format!(
+                "memo does not match the memo hash authorized for [{}]",
+                display_exchanges(&exchanges)
+            )))
+        }
+        None if authorization_required => Err(ContractError::AuthorizationNotFound {
+            exchanges: display_exchanges(&exchanges),
+        }),
+        None => Ok(None),
+    }
+}
+
+/// Checks that `sender` may complete `exchanges`, given the authorization matching them, if any.
+/// Only the lp can complete an exchange without an authorization; an admin can settle one once
+/// it is overdue.
+fn check_completion(
+    state: &State,
+    env: &Env,
+    sender: &Addr,
+    authorization: Option<&AssetExchangeAuthorization>,
+    exchanges: &[AssetExchange],
+    override_available_date: bool,
+) -> Result<(), ContractError> {
+    let is_admin = state.is_admin(sender);
+    let is_operator = state.is_operator(sender);
+    if sender != &state.lp && !is_admin && !is_operator {
+        return Err(ContractError::from(
+            "only the lp, an admin or an operator can complete asset exchange",
+        ));
+    }
+    state.check_not_paused()?;
+
+    if !override_available_date {
+        if let Some(available_at) = available_at(exchanges) {
+            if env.block.time.seconds() < available_at {
+                return Err(ContractError::NotYetAvailable { available_at });
+            }
+        }
+    }
+
+    if authorization.is_none() && (is_admin || is_operator) {
+        return Err(ContractError::AuthorizationNotFound {
+            exchanges: display_exchanges(exchanges),
+        });
+    }
+    Ok(())
+}
+
+/// The latest available date among the exchanges, or `None` when no exchange has one.
+fn available_at(exchanges: &[AssetExchange]) -> Option<u64> {
+    exchanges
+        .iter()
+        .filter_map(|exchange| match exchange.date {
+            Some(ExchangeDate::Available(available)) => Some(available),
+            _ => None,
+        })
+        .max()
+}
+
+/// Sends the exchanges to the selected raise along with the coins they move, once any
+/// authorization has been dealt with.
+#[allow(clippy::too_many_arguments)]
+fn complete_exchanges(
+    deps: DepsMut<ProvenanceQuery>,
+    env: &Env,
+    query_cache: &mut QueryCache,
+    mut state: State,
+    raise: Option<Addr>,
+    exchanges: Vec<AssetExchange>,
+    to: Option<Addr>,
+    memo: Option<String>,
+) -> ContractResponse {
+    let net_commitment = state.check_commitment_ceiling(&exchanges)?;
+    if !net_commitment.is_zero() {
+        let shares = u64::try_from(net_commitment.unsigned_abs().u128())?;
+        state.total_commitment_in_shares = if net_commitment.is_positive() {
+            state.total_commitment_in_shares + shares
+        } else {
+            state.total_commitment_in_shares.saturating_sub(shares)
+        };
+        state_storage(deps.storage).save(&state)?;
+    }
+
+    let raise = state.raise_address(&raise).clone();
+    let to = state.distribution_address(&exchanges, to);
+    let plan = plan_completion(
+        &deps.querier,
+        query_cache,
+        &env.contract.address,
+        &state,
+        &raise,
+        &exchanges,
+        to.as_ref(),
+    )?;
+    let mut response = Response::new().add_attribute("action", "complete_asset_exchange");
+    for transfer in plan.marker_transfers {
+        response = response.add_message(transfer_marker_coins(
+            transfer.amount.u128(),
+            &transfer.denom,
+            raise.clone(),
+            env.contract.address.clone(),
+        )?);
+    }
+    record_operation(deps.storage, Operation::Completion, &env.block.time)?;
+
+    Ok(response.add_message(wasm_execute(
+        &raise,
+        &RaiseExecuteMsg::CompleteAssetExchange {
+            exchanges,
+            to,
+            memo,
+        },
+        plan.funds,
+    )?))
+}
+
+/// The coins a completion sends to the raise: `funds` are attached to the raise call, while
+/// restricted coins are moved by `marker_transfers` ahead of it.
+struct CompletionPlan {
+    funds: Vec<Coin>,
+    marker_transfers: Vec<Coin>,
+}
+
+#[allow(clippy::too_many_arguments)]
+fn plan_completion(
+    querier: &QuerierWrapper<ProvenanceQuery>,
+    query_cache: &mut QueryCache,
+    contract: &Addr,
+    state: &State,
+    raise: &Addr,
+    exchanges: &[AssetExchange],
+    to: Option<&Addr>,
+) -> Result<CompletionPlan, ContractError> {
+    let mut funds = Vec::new();
+    let mut marker_transfers = Vec::new();
+
+    let mut total_escrow: BTreeMap<String, Int128> = BTreeMap::new();
+    for exchange in exchanges {
+        if let Some(investment) = exchange.investment {
+            let denom = state.resolve_investment_denom(exchange.investment_denom.as_ref())?;
+            let total = total_escrow.entry(denom).or_default();
+            *total = total.checked_add(investment).map_err(StdError::overflow)?;
+        }
+    }
+    let total_commitment = exchanges
+        .iter()
+        .filter_map(|e| e.commitment_in_shares)
+        .try_fold(Int128::zero(), Int128::checked_add)
+        .map_err(StdError::overflow)?;
+    let total = total_escrow
+        .entry(state.commitment_denom.clone())
+        .or_default();
+    *total = total
+        .checked_add(total_commitment)
+        .map_err(StdError::overflow)?;
+
+    for (denom, total) in total_escrow {
+        if !total.is_negative() {
+            continue;
+        }
+        if query_cache.requires_marker_transfer(querier, contract, &denom)? {
+            marker_transfers.push(coin(total.unsigned_abs().u128(), denom));
+        } else {
+            funds.push(coin(total.unsigned_abs().u128(), denom));
+        }
+    }
+
+    for (denom, total) in state.net_capital(exchanges)? {
+        if !total.is_negative() {
+            continue;
+        }
+        let restricted = query_cache.is_restricted(querier, &denom);
+        if let Some(required_capital_attributes) =
+            state.enforced_capital_attributes(&denom, restricted)
+        {
+            // otherwise the transfer fails in the marker module, after storage has changed
+            check_capital_attributes(querier, query_cache, raise, required_capital_attributes)?;
+        }
+        if query_cache.requires_marker_transfer(querier, contract, &denom)? {
+            marker_transfers.push(coin(total.unsigned_abs().u128(), denom));
+        } else {
+            funds.push(coin(total.unsigned_abs().u128(), denom));
+        }
+    }
+
+    check_payout_recipients(querier, query_cache, state, exchanges, to)?;
+
+    Ok(CompletionPlan {
+        funds: merge_coins(funds),
+        marker_transfers,
+    })
+}
+
+/// Sums coins sharing a denom and sorts them by denom, since the bank module rejects funds that
+/// list a denom twice.
+fn merge_coins(coins: Vec<Coin>) -> Vec<Coin> {
+    let mut merged: BTreeMap<String, Uint128> = BTreeMap::new();
+    for coin in coins {
+        *merged.entry(coin.denom).or_default() += coin.amount;
+    }
+    merged
+        .into_iter()
+        .map(|(denom, amount)| Coin { denom, amount })
+        .collect()
+}
+
+/// The raise pays capital out to each exchange's own `to`, falling back to the message's, so
+/// every recipient of a restricted capital denom must hold the attribute that denom requires.
+/// Payouts without any recipient go to the subscription itself.
+fn check_payout_recipients(
+    querier: &QuerierWrapper<ProvenanceQuery>,
+    query_cache: &mut QueryCache,
+    state: &State,
+    exchanges: &[AssetExchange],
+    to: Option<&Addr>,
+) -> Result<(), ContractError> {
+    let mut payouts: BTreeMap<(String, Addr), Int128> = BTreeMap::new();
+    for exchange in exchanges {
+        if let (Some(capital), Some(recipient)) = (exchange.capital, exchange.to.as_ref().or(to)) {
+            let denom = state.resolve_capital_denom(exchange.capital_denom.as_ref())?;
+            let total = payouts.entry((denom, recipient.clone())).or_default();
+            *total = total.checked_add(capital).map_err(StdError::overflow)?;
+        }
+    }
+
+    for ((denom, recipient), total) in payouts {
+        if !total.is_positive() {
+            continue;
+        }
+        let restricted = query_cache.is_restricted(querier, &denom);
+        if let Some(required_capital_attributes) =
+            state.enforced_capital_attributes(&denom, restricted)
+        {
+            check_capital_attributes(
+                querier,
+                query_cache,
+                &recipient,
+                required_capital_attributes,
+            )?;
+        }
+    }
+    Ok(())
+}
+
+/// A recipient only means something when an exchange pays out to it.
+fn check_recipient(exchanges: &[AssetExchange], to: Option<&Addr>) -> Result<(), ContractError> {
+    match to {
+        Some(to) if !exchanges.iter().any(AssetExchange::pays_out) => {
+            Err(ContractError::from(format!(
+                "{} would receive nothing, no exchange pays out capital or investment",
+                to
+            )))
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Runs the checks and planning of `CompleteAssetExchange` without changing anything.
+fn simulate_complete_asset_exchange(
+    deps: Deps<ProvenanceQuery>,
+    env: &Env,
+    raise: Option<Addr>,
+    exchanges: Vec<AssetExchange>,
+    to: Option<Addr>,
+    memo: Option<String>,
+    sender: Addr,
+) -> StdResult<SimulateCompleteAssetExchangeResponse> {
+    let state = query_state(deps.storage)?;
+    let raise = state
+        .select_raise(raise)
+        .map_err(|error| StdError::generic_err(error.to_string()))?;
+    let authorizations = asset_exchange_authorization_storage_read(deps.storage)
+        .may_load()
+        .context("unable to read asset exchange authorizations")?
+        .unwrap_or_default();
+    let authorization =
+        find_asset_exchange_authorization(&authorizations, &state, &raise, &exchanges, &to, &memo)
+            .map(|index| &authorizations[index]);
+
+    let mut simulation = SimulateCompleteAssetExchangeResponse {
+        authorization_found: authorization.is_some(),
+        funds: vec![],
+        marker_transfers: vec![],
+        errors: vec![],
+    };
+    let plan = state
+        .validate_exchanges(&exchanges)
+        .and_then(|_| check_recipient(&exchanges, to.as_ref()))
+        .and_then(|_| check_completion(&state, env, &sender, authorization, &exchanges, false))
+        .and_then(|_| {
+            match authorization.filter(|authorization| {
+                authorization.is_stale(state.stale_after_seconds, &env.block.time)
+            }) {
+                Some(authorization) => Err(ContractError::from(format!(
+                    "authorization {} is stale and would be removed without completing",
+                    authorization.id
+                ))),
+                None => {
+                    state.check_commitment_ceiling(&exchanges)?;
+                    plan_completion(
+                        &deps.querier,
+                        &mut QueryCache::default(),
+                        &env.contract.address,
+                        &state,
+                        state.raise_address(&raise),
+                        &exchanges,
+                        state.distribution_address(&exchanges, to.clone()).as_ref(),
+                    )
+                }
+            }
+        });
+    match plan {
+        Ok(plan) => {
+            simulation.funds = plan.funds;
+            simulation.marker_transfers = plan.marker_transfers;
+        }
+        Err(error) => simulation.errors.push(error.to_string()),
+    }
+    Ok(simulation)
+}
+
+/// Runs the checks of `IssueWithdrawal` sent by the lp without withdrawing. Unlike the real
+/// withdrawal, which stops at the first failure, every check that can run is reported.
+fn simulate_withdrawal(
+    deps: Deps<ProvenanceQuery>,
+    env: &Env,
+    to: Addr,
+    amount: Uint128,
+    capital_denom: Option<String>,
+) -> StdResult<SimulateWithdrawalResponse> {
+    let state = query_state(deps.storage)?;
+    let mut simulation = SimulateWithdrawalResponse {
+        checks: vec![],
+        path: None,
+    };
+    let mut check = |name: &str, result: Result<(), ContractError>| {
+        simulation.checks.push(WithdrawalCheck {
+            name: String::from(name),
+            passed: result.is_ok(),
+            error: result.err().map(|error| error.to_string()),
+        })
+    };
+
+    check("not_paused", state.check_not_paused());
+    let capital_denom = state.resolve_capital_denom(capital_denom.as_ref());
+    let allowance = withdrawal_allowances_storage_read(deps.storage)
+        .may_load()
+        .context("unable to read withdrawal allowances")?
+        .unwrap_or_default()
+        .into_iter()
+        .find(|allowance| match &capital_denom {
+            Ok(denom) => allowance.applies_to(&state, &to, denom),
+            Err(_) => false,
+        });
+    check(
+        "allowance",
+        allowance.map_or(Ok(()), |mut allowance| {
+            allowance.consume(amount, &env.block.time)
+        }),
+    );
+    let path = match capital_denom {
+        Ok(capital_denom) => {
+            check("capital_denom", Ok(()));
+            let mut query_cache = QueryCache::default();
+            let restricted = query_cache.is_restricted(&deps.querier, &capital_denom);
+            if let Some(required_capital_attributes) =
+                state.enforced_capital_attributes(&capital_denom, restricted)
+            {
+                check(
+                    "attribute",
+                    check_capital_attributes(
+                        &deps.querier,
+                        &mut query_cache,
+                        &to,
+                        required_capital_attributes,
+                    ),
+                );
+            }
+            match query_cache.requires_marker_transfer(
+                &deps.querier,
+                &env.contract.address,
+                &capital_denom,
+            ) {
+                Ok(false) => Some(WithdrawalPath::BankSend),
+                Ok(true) => {
+                    check("transfer_permission", Ok(()));
+                    Some(WithdrawalPath::MarkerTransfer)
+                }
+                Err(error) => {
+                    check("transfer_permission", Err(error));
+                    Some(WithdrawalPath::MarkerTransfer)
+                }
+            }
+        }
+        Err(error) => {
+            check("capital_denom", Err(error));
+            None
+        }
+    };
+    simulation.path = path;
+    Ok(simulation)
+}
+
+fn reconcile(deps: Deps<ProvenanceQuery>, env: &Env) -> StdResult<ReconcileResponse> {
+    let state = query_state(deps.storage)?;
+    let denoms = std::iter::once((
+        state.commitment_denom,
+        Some(state.total_commitment_in_shares),
+    ))
+    .chain(
+        state
+            .investment_denoms
+            .into_iter()
+            .map(|investment_denom| (investment_denom, None)),
+    )
+    .map(|(denom, expected)| {
+        let held = deps
+            .querier
+            .query_balance(&env.contract.address, &denom)
+            .context(&format!("unable to query the balance of {}", denom))?
+            .amount;
+        // a balance beyond i128 is off by more than any delta can show anyway
+        let delta = expected.map(|expected| {
+            Int128::new(i128::try_from(held.u128()).unwrap_or(i128::MAX) - i128::from(expected))
+        });
+        Ok(DenomReconciliation {
+            denom,
+            held,
+            expected: expected.map(Uint128::from),
+            delta,
+        })
+    })
+    .collect::<StdResult<Vec<DenomReconciliation>>>()?;
+
+    Ok(ReconcileResponse {
+        // only the commitment denom has an expectation to be off from
+        balanced: denoms
+            .iter()
+            .filter_map(|denom| denom.delta)
+            .all(|delta| delta.is_zero()),
+        denoms,
+    })
+}
+
+fn query_state(storage: &dyn Storage) -> StdResult<State> {
+    state_storage_read(storage)
+        .load()
+        .context("state not initialized")
+}
+
+#[entry_point]
+pub fn query(deps: Deps<ProvenanceQuery>, env: Env, msg: QueryMsg) -> StdResult<Binary> {
+    match msg {
+        QueryMsg::GetState {} => to_binary(&StateResponse {
+            state: query_state(deps.storage)?.into(),
+        }),
+        QueryMsg::GetAssetExchangeAuthorizations { include_history } => {
+            let state = query_state(deps.storage)?;
+            let mut authorizations = asset_exchange_authorization_storage_read(deps.storage)
+                .may_load()
+                .context("unable to read asset exchange authorizations")?
+                .unwrap_or_default();
+            if include_history {
+                authorizations.extend(
+                    authorization_history_storage_read(deps.storage)
+                        .may_load()
+                        .context("unable to read authorization history")?
+                        .unwrap_or_default(),
+                );
+            }
+            let authorizations = authorizations
+                .into_iter()
+                .map(|authorization| authorization_view(&state, &env, authorization))
+                .collect();
+            to_binary(&AuthorizationsResponse { authorizations })
+        }
+        QueryMsg::ValidateMigration {
+            like_capital_denoms,
+            required_capital_attributes,
+        } => to_binary(&validate_migration(
+            deps,
+            like_capital_denoms,
+            required_capital_attributes,
+        )),
+        QueryMsg::GetMigrationHistory {} => to_binary(&MigrationHistoryResponse {
+            migrations: migration_history_storage_read(deps.storage)
+                .may_load()
+                .context("unable to read migration history")?
+                .unwrap_or_default(),
+        }),
+        QueryMsg::GetScheduledActions {} => to_binary(&ScheduledActionsResponse {
+            scheduled_actions: scheduled_admin_actions_storage_read(deps.storage)
+                .may_load()
+                .context("unable to read scheduled admin actions")?
+                .unwrap_or_default(),
+        }),
+        QueryMsg::GetOverdueObligations {} => to_binary(&OverdueObligationsResponse {
+            obligations: overdue_obligations(deps, &env)?,
+        }),
+        QueryMsg::SimulateCompleteAssetExchange {
+            exchanges,
+            to,
+            memo,
+            sender,
+            raise,
+        } => to_binary(&simulate_complete_asset_exchange(
+            deps, &env, raise, exchanges, to, memo, sender,
+        )?),
+        QueryMsg::SimulateWithdrawal {
+            to,
+            amount,
+            capital_denom,
+        } => to_binary(&simulate_withdrawal(deps, &env, to, amount, capital_denom)?),
+        QueryMsg::Reconcile {} => to_binary(&reconcile(deps, &env)?),
+        QueryMsg::HasAuthorization {
+            exchanges,
+            to,
+            memo,
+            raise,
+        } => {
+            let state = query_state(deps.storage)?;
+            let raise = state
+                .select_raise(raise)
+                .map_err(|error| StdError::generic_err(error.to_string()))?;
+            let authorizations = asset_exchange_authorization_storage_read(deps.storage)
+                .may_load()
+                .context("unable to read asset exchange authorizations")?
+                .unwrap_or_default();
+            let index = find_asset_exchange_authorization(
+                &authorizations,
+                &state,
+                &raise,
+                &exchanges,
+                &to,
+                &memo,
+            )
+            .map(u32::try_from)
+            .transpose()
+            .map_err(|err| StdError::generic_err(err.to_string()))?;
+            to_binary(&HasAuthorizationResponse {
+                found: index.is_some(),
+                index,
+            })
+        }
+        QueryMsg::GetVersion {} => {
+            let contract_version = get_contract_version(deps.storage)?;
+            to_binary(&VersionResponse {
+                contract: contract_version.contract,
+                version: contract_version.version,
+                api_version: API_VERSION,
+                features: FEATURES.iter().map(|feature| feature.to_string()).collect(),
+            })
+        }
+        QueryMsg::GetRaiseHoldings { raise } => {
+            let state = query_state(deps.storage)?;
+            let (raise, holdings) = state
+                .select_raise(raise)
+                .and_then(|raise| {
+                    let raise = state.raise_address(&raise).clone();
+                    query_raise_holdings(&deps.querier, &raise, &env.contract.address)
+                        .map(|holdings| (raise, holdings))
+                })
+                .map_err(|error| match error {
+                    ContractError::Std(error) => error,
+                    error => StdError::generic_err(error.to_string()),
+                })?;
+            to_binary(&RaiseHoldingsResponse { raise, holdings })
+        }
+        QueryMsg::GetMetadata {} => to_binary(&MetadataResponse {
+            metadata: metadata_storage_read(deps.storage)
+                .may_load()
+                .context("unable to read metadata")?,
+        }),
+        QueryMsg::GetCapitalDenoms {} => to_binary(&CapitalDenomsResponse {
+            capital_denoms: query_state(deps.storage)?.capital_denoms(),
+        }),
+        QueryMsg::GetAuthorizationHistory {
+            status,
+            start_after,
+            limit,
+        } => to_binary(&AuthorizationHistoryResponse {
+            authorizations: load_authorization_history(deps.storage, status, start_after, limit)
+                .context("unable to read authorization history")?,
+        }),
+        QueryMsg::GetAbortedAuthorizations {} => to_binary(&AbortedAuthorizationsResponse {
+            aborted_authorizations: aborted_authorizations_storage_read(deps.storage)
+                .may_load()
+                .context("unable to read aborted authorizations")?
+                .unwrap_or_default(),
+        }),
+        QueryMsg::GetStats {} => {
+            let stats = operation_stats_storage_read(deps.storage)
+                .may_load()
+                .context("unable to read operation stats")?
+                .unwrap_or_default();
+            let pending_authorizations = asset_exchange_authorization_storage_read(deps.storage)
+                .may_load()
+                .context("unable to read asset exchange authorizations")?
+                .unwrap_or_default()
+                .len();
+            let state = query_state(deps.storage)?;
+            to_binary(&StatsResponse {
+                authorizations: stats.authorizations,
+                cancellations: stats.cancellations,
+                completions: stats.completions,
+                withdrawals: stats.withdrawals,
+                pending_authorizations: pending_authorizations as u64,
+                last_activity: stats.last_activity,
+                last_lp_activity: state.last_lp_activity,
+                last_admin_activity: state.last_admin_activity,
+            })
+        }
+        QueryMsg::GetDeposits { start_after, limit } => to_binary(&DepositsResponse {
+            deposits: load_deposits(deps.storage, start_after, limit)
+                .context("unable to read deposits")?,
+        }),
+        QueryMsg::GetPendingAdminActions {} => to_binary(&PendingAdminActionsResponse {
+            pending_admin_actions: pending_admin_actions_storage_read(deps.storage)
+                .may_load()
+                .context("unable to read pending admin actions")?
+                .unwrap_or_default()
+                .into_iter()
+                .filter(|pending| !pending.expired(&env.block.time))
+                .collect(),
+        }),
+    }
+}
+
+fn authorization_view(
+    state: &State,
+    env: &Env,
+    authorization: AssetExchangeAuthorization,
+) -> AuthorizationView {
+    let stale = authorization.is_stale(state.stale_after_seconds, &env.block.time);
+    AuthorizationView::new(authorization, stale)
+}
+
+fn overdue_obligations(
+    deps: Deps<ProvenanceQuery>,
+    env: &Env,
+) -> StdResult<Vec<OverdueObligation>> {
+    let state = query_state(deps.storage)?;
+    let now = env.block.time.seconds();
+
+    let mut obligations = Vec::new();
+    for authorization in asset_exchange_authorization_storage_read(deps.storage)
+        .may_load()
+        .context("unable to read asset exchange authorizations")?
+        .unwrap_or_default()
+    {
+        let earliest_passed_due = authorization
+            .exchanges
+            .iter()
+            .filter_map(|exchange| match exchange.date {
+                Some(ExchangeDate::Due(due)) if due < now => Some(due),
+                _ => None,
+            })
+            .min();
+        if let Some(due) = earliest_passed_due {
+            let capital_owed = state
+                .net_capital(&authorization.exchanges)
+                .map_err(|error| StdError::generic_err(error.to_string()))?
+                .into_iter()
+                .filter(|(_, total)| total.is_negative())
+                .map(|(denom, total)| coin(total.unsigned_abs().u128(), denom))
+                .collect();
+            obligations.push(OverdueObligation {
+                authorization: authorization_view(&state, env, authorization),
+                seconds_overdue: now - due,
+                capital_owed,
+            });
+        }
+    }
+    Ok(obligations)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::accept_exchange;
+    use crate::mock::response_data;
+    use crate::mock::send_msg;
+    use crate::mock::ExchangeBuilder;
+    use crate::mock::MockDeps;
+    use crate::mock::{assert_attr, assert_no_attr, attr_value};
+    use crate::mock::{capital_coin_deps, default_deps, restricted_capital_coin_deps};
+    use crate::mock::{deps_with_balances, set_balance};
+    use crate::mock::{execute_args, load_attributes, load_marker_fixtures, load_markers};
+    use crate::mock::{marker_transfer_msg, msg_at_index};
+    use crate::msg::AuthorizationRef;
+    use crate::msg::CapitalDenom;
+    use crate::msg::CapitalDenomRequirement;
+    use crate::msg::QueryMsg;
+    use crate::state::asset_exchange_authorization_storage_read;
+    use crate::state::hash_memo;
+    use crate::state::withdrawal_approvals_storage_read;
+    use crate::state::Deposit;
+    use crate::state::SubscriptionMetadata;
+    use crate::state::DEFAULT_MAX_AUTHORIZATIONS_PER_BLOCK;
+    use crate::state::{
+        MAX_ABORTED_AUTHORIZATIONS, MAX_ABORT_REASON_LENGTH, MAX_AUTHORIZATION_HISTORY,
+        MAX_LP_NOTE_LENGTH,
+    };
+    use crate::state::{
+        MAX_METADATA_DESCRIPTION_LENGTH, MAX_METADATA_EXTERNAL_ID_LENGTH, MAX_METADATA_NAME_LENGTH,
+    };
+    use crate::version::{CONTRACT_NAME, CONTRACT_VERSION};
+    use cosmwasm_std::from_binary;
+    use cosmwasm_std::testing::MOCK_CONTRACT_ADDR;
+    use cosmwasm_std::testing::{mock_env, mock_info};
+    use cosmwasm_std::Addr;
+    use cosmwasm_std::CosmosMsg;
+    use provwasm_std::MarkerMsgParams;
+
+    #[test]
+    fn recover() {
+        execute(
+            default_deps(None).as_mut(),
+            mock_env(),
+            mock_info("admin", &[]),
+            ExecuteMsg::Recover {
+                lp: Addr::unchecked("lp_2"),
+            },
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn recover_to_admin_fail() {
+        let res = execute(
+            default_deps(None).as_mut(),
+            mock_env(),
+            mock_info("admin", &[]),
+            ExecuteMsg::Recover {
+                lp: Addr::unchecked("admin"),
+            },
+        );
+        assert!(res.is_err());
+    }
+
+    #[test]
+    fn recover_to_admin_without_distinct_roles() {
+        execute(
+            default_deps(Some(|state| state.require_distinct_roles = false)).as_mut(),
+            mock_env(),
+            mock_info("admin", &[]),
+            ExecuteMsg::Recover {
+                lp: Addr::unchecked("admin"),
+            },
+        )
+        .unwrap();
+    }
+
+    fn two_of_three_admins(state: &mut State) {
+        state.admins = vec![
+            Addr::unchecked("admin"),
+            Addr::unchecked("admin_2"),
+            Addr::unchecked("admin_3"),
+        ];
+        state.admin_threshold = 2;
+    }
+
+    fn recover_from(deps: &mut MockDeps, admin: &str, env: Env) -> ContractResponse {
+        execute(
+            deps.as_mut(),
+            env,
+            mock_info(admin, &[]),
+            ExecuteMsg::Recover {
+                lp: Addr::unchecked("lp_2"),
+            },
+        )
+    }
+
+    fn pending_admin_actions(deps: &MockDeps, env: Env) -> Vec<PendingAdminAction> {
+        let res: PendingAdminActionsResponse =
+            from_binary(&query(deps.as_ref(), env, QueryMsg::GetPendingAdminActions {}).unwrap())
+                .unwrap();
+        res.pending_admin_actions
+    }
+
+    #[test]
+    fn recover_two_of_three_admins() {
+        let mut deps = default_deps(Some(two_of_three_admins));
+
+        let res = recover_from(&mut deps, "admin", mock_env()).unwrap();
+        assert_attr(&res, "action", "approve_recover");
+        assert_attr(&res, "admin", "admin");
+        assert_attr(&res, "approvals", "1");
+        assert_eq!("lp", state_storage_read(&deps.storage).load().unwrap().lp);
+        assert_eq!(
+            vec![PendingAdminAction {
+                action: AdminAction::Recover {
+                    lp: Addr::unchecked("lp_2")
+                },
+                approvals: vec![Addr::unchecked("admin")],
+                proposed_at: mock_env().block.time,
+            }],
+            pending_admin_actions(&deps, mock_env())
+        );
+
+        let res = recover_from(&mut deps, "admin_3", mock_env()).unwrap();
+        assert_attr(&res, "action", "recover");
+        assert_attr(&res, "admin", "admin_3");
+        assert_no_attr(&res, "approvals");
+        assert_eq!("lp_2", state_storage_read(&deps.storage).load().unwrap().lp);
+        assert!(pending_admin_actions(&deps, mock_env()).is_empty());
+    }
+
+    #[test]
+    fn recover_duplicate_admin_approval() {
+        let mut deps = default_deps(Some(two_of_three_admins));
+
+        recover_from(&mut deps, "admin", mock_env()).unwrap();
+        assert!(recover_from(&mut deps, "admin", mock_env()).is_err());
+        assert_eq!("lp", state_storage_read(&deps.storage).load().unwrap().lp);
+        assert_eq!(
+            1,
+            pending_admin_actions(&deps, mock_env())[0].approvals.len()
+        );
+    }
+
+    #[test]
+    fn recover_expired_admin_approval() {
+        let mut deps = default_deps(Some(two_of_three_admins));
+        recover_from(&mut deps, "admin", mock_env()).unwrap();
+
+        let mut later = mock_env();
+        later.block.time = later
+            .block
+            .time
+            .plus_seconds(crate::state::ADMIN_ACTION_EXPIRY_SECONDS + 1);
+        assert!(pending_admin_actions(&deps, later.clone()).is_empty());
+
+        // the stale proposal lapsed, so this approval starts a new one
+        let res = recover_from(&mut deps, "admin_2", later.clone()).unwrap();
+        assert_attr(&res, "approvals", "1");
+        assert_eq!("lp", state_storage_read(&deps.storage).load().unwrap().lp);
+        assert_eq!(
+            vec![Addr::unchecked("admin_2")],
+            pending_admin_actions(&deps, later)[0].approvals
+        );
+    }
+
+    #[test]
+    fn recover_single_admin_stores_nothing() {
+        let mut deps = default_deps(None);
+        let res = recover_from(&mut deps, "admin", mock_env()).unwrap();
+        assert_attr(&res, "action", "recover");
+        assert_attr(&res, "admin", "admin");
+        assert_eq!(
+            None,
+            crate::state::pending_admin_actions_storage_read(&deps.storage)
+                .may_load()
+                .unwrap()
+        );
+    }
+
+    fn with_admin_action_delay(state: &mut State) {
+        state.admin_action_delay_seconds = 86_400;
+    }
+
+    fn scheduled_actions(deps: &MockDeps) -> Vec<ScheduledAdminAction> {
+        let res: ScheduledActionsResponse = from_binary(
+            &query(deps.as_ref(), mock_env(), QueryMsg::GetScheduledActions {}).unwrap(),
+        )
+        .unwrap();
+        res.scheduled_actions
+    }
+
+    fn execute_scheduled(deps: &mut MockDeps, env: Env, id: u64) -> ContractResponse {
+        execute(
+            deps.as_mut(),
+            env,
+            mock_info("anyone", &[]),
+            ExecuteMsg::ExecuteScheduledAdminAction { id },
+        )
+    }
+
+    #[test]
+    fn recover_scheduled() {
+        let mut deps = default_deps(Some(with_admin_action_delay));
+
+        let res = recover_from(&mut deps, "admin", mock_env()).unwrap();
+        let execute_after = mock_env().block.time.plus_seconds(86_400);
+        assert_attr(&res, "action", "schedule_recover");
+        assert_attr(&res, "scheduled_action_id", "1");
+        assert_attr(&res, "execute_after", &execute_after.seconds().to_string());
+        assert_eq!("lp", state_storage_read(&deps.storage).load().unwrap().lp);
+        assert_eq!(
+            vec![ScheduledAdminAction {
+                id: 1,
+                action: AdminAction::Recover {
+                    lp: Addr::unchecked("lp_2")
+                },
+                execute_after,
+            }],
+            scheduled_actions(&deps)
+        );
+    }
+
+    #[test]
+    fn recover_scheduled_premature_execution() {
+        let mut deps = default_deps(Some(with_admin_action_delay));
+        recover_from(&mut deps, "admin", mock_env()).unwrap();
+
+        let mut env = mock_env();
+        env.block.time = env.block.time.plus_seconds(86_399);
+        assert!(execute_scheduled(&mut deps, env, 1).is_err());
+        assert_eq!("lp", state_storage_read(&deps.storage).load().unwrap().lp);
+        assert_eq!(1, scheduled_actions(&deps).len());
+    }
+
+    #[test]
+    fn recover_scheduled_execution() {
+        let mut deps = default_deps(Some(with_admin_action_delay));
+        recover_from(&mut deps, "admin", mock_env()).unwrap();
+
+        let mut env = mock_env();
+        env.block.time = env.block.time.plus_seconds(86_400);
+        let res = execute_scheduled(&mut deps, env.clone(), 1).unwrap();
+        assert_attr(&res, "action", "recover");
+        assert_eq!("lp_2", state_storage_read(&deps.storage).load().unwrap().lp);
+        assert!(scheduled_actions(&deps).is_empty());
+
+        // executed actions are gone
+        assert!(execute_scheduled(&mut deps, env, 1).is_err());
+    }
+
+    #[test]
+    fn update_raise() {
+        let mut deps = default_deps(None);
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("admin", &[]),
+            ExecuteMsg::UpdateRaise {
+                raise: Addr::unchecked("raise_2"),
+            },
+        )
+        .unwrap();
+        assert_eq!(
+            vec![Addr::unchecked("raise_2")],
+            state_storage_read(&deps.storage).load().unwrap().raises
+        );
+    }
+
+    #[test]
+    fn update_raise_bad_actor() {
+        let res = execute(
+            default_deps(None).as_mut(),
+            mock_env(),
+            mock_info("lp", &[]),
+            ExecuteMsg::UpdateRaise {
+                raise: Addr::unchecked("raise_2"),
+            },
+        );
+        assert!(res.is_err());
+    }
+
+    #[test]
+    fn register_raise() {
+        let mut deps = default_deps(None);
+        let register = |deps: &mut MockDeps, sender: &str, raise: &str| {
+            execute(
+                deps.as_mut(),
+                mock_env(),
+                mock_info(sender, &[]),
+                ExecuteMsg::RegisterRaise {
+                    raise: Addr::unchecked(raise),
+                },
+            )
+        };
+
+        assert!(register(&mut deps, "lp", "raise_2").is_err());
+        register(&mut deps, "admin", "raise_2").unwrap();
+        assert_eq!(
+            vec![Addr::unchecked("raise_1"), Addr::unchecked("raise_2")],
+            state_storage_read(&deps.storage).load().unwrap().raises
+        );
+
+        let err = register(&mut deps, "admin", "raise_2").unwrap_err();
+        assert_eq!(
+            "Generic error: raise_2 is already a raise of this subscription",
+            err.to_string()
+        );
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("admin", &[]),
+            ExecuteMsg::UpdateRaise {
+                raise: Addr::unchecked("raise_2"),
+            },
+        )
+        .unwrap_err();
+        assert_eq!(
+            "Generic error: raise_2 is already registered as an additional raise",
+            err.to_string()
+        );
+    }
+
+    #[test]
+    fn update_required_capital_attribute() {
+        let mut deps = restricted_capital_coin_deps(None);
+        load_markers(&mut deps.querier);
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("admin", &[]),
+            ExecuteMsg::UpdateRequiredCapitalAttribute {
+                required_capital_attributes: vec![CapitalDenomRequirement {
+                    capital_denom: String::new(),
+                    required_attributes: vec![String::from("capital.test")],
+                    enforce_attribute_on_bank_send: false,
+                }],
+            },
+        )
+        .unwrap();
+        assert_eq!(
+            Some(&[String::from("capital.test")][..]),
+            state_storage_read(&deps.storage)
+                .load()
+                .unwrap()
+                .required_capital_attributes("restricted_capital_coin")
+        );
+    }
+
+    #[test]
+    fn update_required_capital_attribute_without_marker_transfer_grant() {
+        let mut deps = restricted_capital_coin_deps(None);
+        load_marker_fixtures(&mut deps.querier, &["restricted_capital_no_grant"]);
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("admin", &[]),
+            ExecuteMsg::UpdateRequiredCapitalAttribute {
+                required_capital_attributes: vec![CapitalDenomRequirement {
+                    capital_denom: String::new(),
+                    required_attributes: vec![String::from("capital.other")],
+                    enforce_attribute_on_bank_send: false,
+                }],
+            },
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("lacks transfer permission"));
+    }
+
+    #[test]
+    fn update_required_capital_attribute_enforced_on_bank_send() {
+        let mut deps = default_deps(None);
+        let update = |deps: &mut MockDeps, enforce_attribute_on_bank_send: bool| {
+            execute(
+                deps.as_mut(),
+                mock_env(),
+                mock_info("admin", &[]),
+                ExecuteMsg::UpdateRequiredCapitalAttribute {
+                    required_capital_attributes: vec![CapitalDenomRequirement {
+                        capital_denom: String::from("stable_coin"),
+                        required_attributes: vec![String::from("capital.kyc")],
+                        enforce_attribute_on_bank_send,
+                    }],
+                },
+            )
+        };
+
+        // stable_coin is no marker at all, so nothing but a bank send could enforce the attribute
+        assert!(matches!(
+            update(&mut deps, false).unwrap_err(),
+            ContractError::UnenforcedCapitalAttribute { denom } if denom == "stable_coin"
+        ));
+        update(&mut deps, true).unwrap();
+        assert_eq!(
+            Some(&[String::from("capital.kyc")][..]),
+            state_storage_read(&deps.storage)
+                .load()
+                .unwrap()
+                .enforced_capital_attributes("stable_coin", false)
+        );
+    }
+
+    #[test]
+    fn update_required_capital_attribute_unrestricted_marker() {
+        let mut deps = capital_coin_deps(None);
+        load_markers(&mut deps.querier);
+        let update = |deps: &mut MockDeps, enforce_attribute_on_bank_send: bool| {
+            execute(
+                deps.as_mut(),
+                mock_env(),
+                mock_info("admin", &[]),
+                ExecuteMsg::UpdateRequiredCapitalAttribute {
+                    required_capital_attributes: vec![CapitalDenomRequirement {
+                        capital_denom: String::from("capital_coin"),
+                        required_attributes: vec![String::from("capital.test")],
+                        enforce_attribute_on_bank_send,
+                    }],
+                },
+            )
+        };
+
+        // capital_coin moves by bank send, so the contract needs no transfer grant on it
+        assert!(matches!(
+            update(&mut deps, false).unwrap_err(),
+            ContractError::UnenforcedCapitalAttribute { denom } if denom == "capital_coin"
+        ));
+        update(&mut deps, true).unwrap();
+    }
+
+    #[test]
+    fn update_required_capital_attribute_unsupported_denom() {
+        let mut deps = default_deps(Some(with_admin_action_delay));
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("admin", &[]),
+            ExecuteMsg::UpdateRequiredCapitalAttribute {
+                required_capital_attributes: vec![CapitalDenomRequirement {
+                    capital_denom: String::from("other_coin"),
+                    required_attributes: vec![String::from("capital.test")],
+                    enforce_attribute_on_bank_send: false,
+                }],
+            },
+        );
+        assert!(res.is_err());
+        assert!(scheduled_actions(&deps).is_empty());
+    }
+
+    #[test]
+    fn update_required_capital_attribute_empty_list() {
+        let mut deps = restricted_capital_coin_deps(Some(with_admin_action_delay));
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("admin", &[]),
+            ExecuteMsg::UpdateRequiredCapitalAttribute {
+                required_capital_attributes: vec![CapitalDenomRequirement {
+                    capital_denom: String::from("restricted_capital_coin"),
+                    required_attributes: vec![],
+                    enforce_attribute_on_bank_send: false,
+                }],
+            },
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("requires at least one attribute"));
+    }
+
+    #[test]
+    fn bad_actor_recover_fail() {
+        let res = execute(
+            default_deps(None).as_mut(),
+            mock_env(),
+            mock_info("bad_actor", &[]),
+            ExecuteMsg::Recover {
+                lp: Addr::unchecked("bad_actor"),
+            },
+        );
+        assert!(res.is_err());
+    }
+
+    #[test]
+    fn authorize_asset_exchange() {
+        let mut deps = default_deps(None);
+
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("lp", &[]),
+            ExecuteMsg::AuthorizeAssetExchange {
+                exchanges: vec![ExchangeBuilder::new()
+                    .investment(1_000)
+                    .commitment(1_000)
+                    .capital(1_000)
+                    .build()],
+                to: Some(Addr::unchecked("lp_side_account")),
+                memo: Some(String::from("memo")),
+                idempotency_key: None,
+                expires_at: None,
+                memo_hash: None,
+                raise: None,
+            },
+        )
+        .unwrap();
+
+        // verify asset exchange authorization saved
+        assert_eq!(
+            1,
+            asset_exchange_authorization_storage_read(&deps.storage)
+                .load()
+                .unwrap()
+                .len()
+        );
+    }
+
+    fn authorize(deps: &mut MockDeps, env: Env) -> ContractResponse {
+        execute(
+            deps.as_mut(),
+            env,
+            mock_info("lp", &[]),
+            ExecuteMsg::AuthorizeAssetExchange {
+                exchanges: vec![accept_exchange()],
+                to: None,
+                memo: None,
+                idempotency_key: None,
+                expires_at: None,
+                memo_hash: None,
+                raise: None,
+            },
+        )
+    }
+
+    fn authorize_with_key(deps: &mut MockDeps, key: &str) -> ContractResponse {
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("lp", &[]),
+            ExecuteMsg::AuthorizeAssetExchange {
+                exchanges: vec![accept_exchange()],
+                to: None,
+                memo: None,
+                idempotency_key: Some(String::from(key)),
+                expires_at: None,
+                memo_hash: None,
+                raise: None,
+            },
+        )
+    }
+
+    #[test]
+    fn authorize_asset_exchange_retry_with_same_key() {
+        let mut deps = default_deps(None);
+        authorize_with_key(&mut deps, "request-1").unwrap();
+
+        match authorize_with_key(&mut deps, "request-1").unwrap_err() {
+            ContractError::DuplicateRequest {
+                key,
+                authorization_id,
+            } => {
+                assert_eq!("request-1", key);
+                assert_eq!(1, authorization_id);
+            }
+            err => panic!("unexpected error: {}", err),
+        }
+        assert_eq!(
+            1,
+            asset_exchange_authorization_storage_read(&deps.storage)
+                .load()
+                .unwrap()
+                .len()
+        );
+    }
+
+    #[test]
+    fn authorize_asset_exchange_different_keys() {
+        let mut deps = default_deps(None);
+        authorize_with_key(&mut deps, "request-1").unwrap();
+        authorize_with_key(&mut deps, "request-2").unwrap();
+        authorize(&mut deps, mock_env()).unwrap();
+        assert_eq!(
+            3,
+            asset_exchange_authorization_storage_read(&deps.storage)
+                .load()
+                .unwrap()
+                .len()
+        );
+    }
+
+    #[test]
+    fn authorize_asset_exchange_key_too_long() {
+        let mut deps = default_deps(None);
+        assert!(authorize_with_key(&mut deps, &"k".repeat(65)).is_err());
+        assert!(authorize_with_key(&mut deps, "").is_err());
+    }
+
+    const SIGNING_LP: &str = "tp189kc9499dk6yfxuaf2lpjdtfxrkuxqhcq8faea";
+    const SIGNING_LP_PUBKEY: &str = "AvWy7/+6iOxagShnJ3WOqFoBJyCggO/ZVJK/st/MUgX8";
+    const OTHER_PUBKEY: &str = "A8DmM4CgB/Cm81LEUFreegI+5xfJnC2EvRulxyyPxcEF";
+    const PAYLOAD_1: &str = r#"{"exchanges":[{"com":"1000"}],"to":null,"memo":null,"nonce":1,"contract":"cosmos2contract"}"#;
+    const PAYLOAD_1_SIGNATURE: &str =
+        "uCPNb4PYTK6zaONGEekl9FciFuada6TjkwpG9VKrV2greUgITF/YCUWJU2idr6sh+SiQF+oJ73rZedA2cWG0GQ==";
+    const PAYLOAD_1_OTHER_SIGNATURE: &str =
+        "4O1aG6+uTlE+ALEXpUl0qrt2jnR2vSTfkG8aS1IrXEZK5eV+y0BNSdTykRBJjtFB9WLvRTG3Wn1mJDQ8YaNakA==";
+    const PAYLOAD_2: &str = r#"{"exchanges":[{"com":"1000"}],"to":null,"memo":null,"nonce":2,"contract":"cosmos2contract"}"#;
+    const PAYLOAD_2_SIGNATURE: &str =
+        "8IkPwi18lw5L3Vg0fAOUsUp71mV66r74S4KcwO7IvT5tdyjCEpqZr+xKYBWTKe5NhEnCwHBU11fVsTi17S737w==";
+
+    fn signing_lp_deps() -> MockDeps {
+        default_deps(Some(|state| state.lp = Addr::unchecked(SIGNING_LP)))
+    }
+
+    fn submit_signed(
+        deps: &mut MockDeps,
+        payload: &str,
+        signature: &str,
+        pubkey: &str,
+    ) -> ContractResponse {
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("anyone", &[]),
+            ExecuteMsg::SubmitSignedAuthorization {
+                payload: Binary::from(payload.as_bytes()),
+                signature: Binary::from_base64(signature).unwrap(),
+                pubkey: Binary::from_base64(pubkey).unwrap(),
+            },
+        )
+    }
+
+    #[test]
+    fn submit_signed_authorization() {
+        let mut deps = signing_lp_deps();
+        submit_signed(&mut deps, PAYLOAD_1, PAYLOAD_1_SIGNATURE, SIGNING_LP_PUBKEY).unwrap();
+
+        let authorizations = asset_exchange_authorization_storage_read(&deps.storage)
+            .load()
+            .unwrap();
+        assert_eq!(1, authorizations.len());
+        assert_eq!(
+            vec![ExchangeBuilder::new().commitment(1_000).build()],
+            authorizations[0].exchanges
+        );
+    }
+
+    #[test]
+    fn submit_signed_authorization_wrong_signer() {
+        let mut deps = signing_lp_deps();
+        let err = submit_signed(
+            &mut deps,
+            PAYLOAD_1,
+            PAYLOAD_1_OTHER_SIGNATURE,
+            OTHER_PUBKEY,
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("must be signed by the lp"));
+    }
+
+    #[test]
+    fn submit_signed_authorization_bad_signature() {
+        let mut deps = signing_lp_deps();
+        let err = submit_signed(&mut deps, PAYLOAD_2, PAYLOAD_1_SIGNATURE, SIGNING_LP_PUBKEY)
+            .unwrap_err();
+        assert!(err.to_string().contains("invalid signature"));
+    }
+
+    #[test]
+    fn submit_signed_authorization_replayed_nonce() {
+        let mut deps = signing_lp_deps();
+        submit_signed(&mut deps, PAYLOAD_1, PAYLOAD_1_SIGNATURE, SIGNING_LP_PUBKEY).unwrap();
+
+        let err = submit_signed(&mut deps, PAYLOAD_1, PAYLOAD_1_SIGNATURE, SIGNING_LP_PUBKEY)
+            .unwrap_err();
+        assert!(err.to_string().contains("nonce must be greater than 1"));
+
+        submit_signed(&mut deps, PAYLOAD_2, PAYLOAD_2_SIGNATURE, SIGNING_LP_PUBKEY).unwrap();
+        assert_eq!(
+            2,
+            asset_exchange_authorization_storage_read(&deps.storage)
+                .load()
+                .unwrap()
+                .len()
+        );
+    }
+
+    fn authorize_memo(
+        deps: &mut MockDeps,
+        memo: Option<&str>,
+        memo_hash: Option<Binary>,
+    ) -> ContractResponse {
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("lp", &[]),
+            ExecuteMsg::AuthorizeAssetExchange {
+                exchanges: vec![accept_exchange()],
+                to: None,
+                memo: memo.map(String::from),
+                idempotency_key: None,
+                memo_hash,
+                expires_at: None,
+                raise: None,
+            },
+        )
+    }
+
+    fn complete_with_memo(
+        deps: &mut MockDeps,
+        sender: &str,
+        memo: Option<&str>,
+    ) -> ContractResponse {
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(sender, &[]),
+            ExecuteMsg::CompleteAssetExchange {
+                exchanges: vec![accept_exchange()],
+                to: None,
+                memo: memo.map(String::from),
+                override_available_date: false,
+                raise: None,
+            },
+        )
+    }
+
+    fn raise_memo(res: &ContractResponse) -> Option<String> {
+        let res = res.as_ref().unwrap();
+        match execute_args::<RaiseExecuteMsg>(&res.messages.last().unwrap().msg).1 {
+            RaiseExecuteMsg::CompleteAssetExchange { memo, .. } => memo,
+            msg => panic!("unexpected raise message: {:?}", msg),
+        }
+    }
+
+    #[test]
+    fn hashed_memo_revealed_at_completion() {
+        let mut deps = default_deps(None);
+        authorize_memo(&mut deps, None, Some(hash_memo("wire 4471"))).unwrap();
+
+        let authorizations = queried_authorizations(&deps);
+        assert_eq!(None, authorizations[0].memo);
+        assert_eq!(Some(hash_memo("wire 4471")), authorizations[0].memo_hash);
+
+        let res = complete_with_memo(&mut deps, "admin", Some("wire 4471"));
+        assert_eq!(Some(String::from("wire 4471")), raise_memo(&res));
+        assert!(queried_authorizations(&deps).is_empty());
+    }
+
+    #[test]
+    fn hashed_memo_incorrect_reveal() {
+        let mut deps = default_deps(None);
+        authorize_memo(&mut deps, None, Some(hash_memo("wire 4471"))).unwrap();
+
+        for memo in [Some("wire 4472"), None] {
+            let err = complete_with_memo(&mut deps, "lp", memo).unwrap_err();
+            assert!(err
+                .to_string()
+                .contains("memo does not match the memo hash"));
+        }
+        assert_eq!(1, queried_authorizations(&deps).len());
+    }
+
+    #[test]
+    fn plain_and_hashed_memos_side_by_side() {
+        let mut deps = default_deps(None);
+        authorize_memo(&mut deps, Some("wire 4471"), None).unwrap();
+        authorize_memo(&mut deps, None, Some(hash_memo("wire 4472"))).unwrap();
+
+        let res = complete_with_memo(&mut deps, "admin", Some("wire 4472"));
+        assert_eq!(Some(String::from("wire 4472")), raise_memo(&res));
+        let remaining = queried_authorizations(&deps);
+        assert_eq!(
+            vec![1],
+            remaining.iter().map(|a| a.id).collect::<Vec<u64>>()
+        );
+
+        // a memo hash must be a sha256 digest
+        let err = authorize_memo(&mut deps, None, Some(Binary::from(b"wire 4471".to_vec())));
+        assert!(err.is_err());
+        let res = complete_with_memo(&mut deps, "admin", Some("wire 4471"));
+        assert_eq!(Some(String::from("wire 4471")), raise_memo(&res));
+
+        let err =
+            authorize_memo(&mut deps, Some("wire 4473"), Some(hash_memo("wire 4473"))).unwrap_err();
+        assert!(err.to_string().contains("a memo or a memo hash, not both"));
+    }
+
+    #[test]
+    fn authorize_asset_exchange_rate_limited() {
+        let mut deps = default_deps(None);
+
+        for _ in 0..DEFAULT_MAX_AUTHORIZATIONS_PER_BLOCK {
+            authorize(&mut deps, mock_env()).unwrap();
+        }
+        let err = authorize(&mut deps, mock_env()).unwrap_err();
+        assert!(err.to_string().contains("retry next block"));
+
+        // cancel is unaffected by the limit
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("lp", &[]),
+            ExecuteMsg::CancelAssetExchangeAuthorization {
+                exchanges: vec![accept_exchange()],
+                to: None,
+                memo: None,
+                raise: None,
+            },
+        )
+        .unwrap();
+
+        let mut env = mock_env();
+        env.block.height += 1;
+        authorize(&mut deps, env).unwrap();
+        assert_eq!(
+            DEFAULT_MAX_AUTHORIZATIONS_PER_BLOCK as usize,
+            asset_exchange_authorization_storage_read(&deps.storage)
+                .load()
+                .unwrap()
+                .len()
+        );
+    }
+
+    #[test]
+    fn authorize_asset_exchange_configured_rate() {
+        let mut deps = default_deps(Some(|state| state.max_authorizations_per_block = 1));
+
+        authorize(&mut deps, mock_env()).unwrap();
+        assert!(authorize(&mut deps, mock_env()).is_err());
+    }
+
+    #[test]
+    fn authorize_asset_exchange_unsupported_capital_denom() {
+        let mut deps = default_deps(None);
+
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("lp", &[]),
+            ExecuteMsg::AuthorizeAssetExchange {
+                exchanges: vec![ExchangeBuilder::new()
+                    .capital(1_000)
+                    .denom("other_coin")
+                    .build()],
+                to: None,
+                memo: None,
+                idempotency_key: None,
+                expires_at: None,
+                memo_hash: None,
+                raise: None,
+            },
+        );
+
+        // verify error
+        assert!(res.is_err());
+    }
+
+    #[test]
+    fn authorize_asset_exchange_at_commitment_ceiling() {
+        let mut deps = default_deps(Some(|state| {
+            state.max_commitment_in_shares = Some(1_000);
+            state.total_commitment_in_shares = 400;
+        }));
+
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("lp", &[]),
+            ExecuteMsg::AuthorizeAssetExchange {
+                exchanges: vec![ExchangeBuilder::new().commitment(600).build()],
+                to: None,
+                memo: None,
+                idempotency_key: None,
+                expires_at: None,
+                memo_hash: None,
+                raise: None,
+            },
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn authorize_asset_exchange_above_commitment_ceiling() {
+        let mut deps = default_deps(Some(|state| {
+            state.max_commitment_in_shares = Some(1_000);
+            state.total_commitment_in_shares = 400;
+        }));
+
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("lp", &[]),
+            ExecuteMsg::AuthorizeAssetExchange {
+                exchanges: vec![ExchangeBuilder::new().commitment(601).build()],
+                to: None,
+                memo: None,
+                idempotency_key: None,
+                expires_at: None,
+                memo_hash: None,
+                raise: None,
+            },
+        );
+
+        // verify error reports remaining headroom
+        match res {
+            Err(ContractError::CommitmentCeilingExceeded { headroom }) => assert_eq!(600, headroom),
+            _ => panic!("expected commitment ceiling error"),
+        }
+    }
+
+    #[test]
+    fn authorize_asset_exchange_bad_actor() {
+        let mut deps = default_deps(None);
+
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("bad_actor", &[]),
+            ExecuteMsg::AuthorizeAssetExchange {
+                exchanges: vec![ExchangeBuilder::new()
+                    .investment(1_000)
+                    .commitment(1_000)
+                    .capital(1_000)
+                    .build()],
+                to: Some(Addr::unchecked("lp_side_account")),
+                memo: Some(String::from("memo")),
+                idempotency_key: None,
+                expires_at: None,
+                memo_hash: None,
+                raise: None,
+            },
+        );
+
+        // verify error
+        assert!(res.is_err());
+    }
+
+    #[test]
+    fn cancel_asset_exchange_authorization() {
+        let mut deps = default_deps(None);
+
+        let exchange = ExchangeBuilder::new()
+            .investment(1_000)
+            .commitment(1_000)
+            .capital(1_000)
+            .build();
+        let to = Some(Addr::unchecked("lp_side_account"));
+        let memo = Some(String::from("memo"));
+
+        asset_exchange_authorization_storage(&mut deps.storage)
+            .save(&vec![AssetExchangeAuthorization {
+                id: 1,
+                raise: None,
+                exchanges: vec![exchange.clone()],
+                to: to.clone(),
+                memo: memo.clone(),
+                created_at: None,
+                expires_at: None,
+                memo_hash: None,
+                status: AuthorizationStatus::Pending,
+                resolved_at: None,
+                resolved_by: None,
+            }])
+            .unwrap();
+
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("lp", &[]),
+            ExecuteMsg::CancelAssetExchangeAuthorization {
+                exchanges: vec![exchange.clone()],
+                to: to.clone(),
+                memo: memo.clone(),
+                raise: None,
+            },
+        )
+        .unwrap();
+
+        // verify asset exchange authorization removed
+        assert_eq!(
+            0,
+            asset_exchange_authorization_storage_read(&deps.storage)
+                .load()
+                .unwrap()
+                .len()
+        );
+    }
+
+    fn cancel_authorized_exchanges(
+        deps: &mut MockDeps,
+        exchanges: Vec<AssetExchange>,
+        exchanges_to_remove: Vec<AssetExchange>,
+    ) -> ContractResponse {
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("lp", &[]),
+            ExecuteMsg::CancelAuthorizedExchanges {
+                authorization: AuthorizationRef {
+                    exchanges,
+                    to: None,
+                    memo: None,
+                    raise: None,
+                },
+                exchanges_to_remove,
+            },
+        )
+    }
+
+    #[test]
+    fn cancel_some_authorized_exchanges() {
+        let mut deps = default_deps(None);
+        let first = ExchangeBuilder::new().commitment(10).build();
+        let second = ExchangeBuilder::new().commitment(20).build();
+        authorize_exchanges(&mut deps, vec![first.clone(), second.clone()]).unwrap();
+
+        let res = cancel_authorized_exchanges(
+            &mut deps,
+            vec![first.clone(), second.clone()],
+            vec![first],
+        )
+        .unwrap();
+        assert_attr(&res, "removed", "com:+10");
+        assert_attr(&res, "remaining", "com:+20");
+
+        let authorizations = asset_exchange_authorization_storage_read(&deps.storage)
+            .load()
+            .unwrap();
+        assert_eq!(1, authorizations.len());
+        assert_eq!(vec![second], authorizations[0].exchanges);
+        assert_eq!(AuthorizationStatus::Pending, authorizations[0].status);
+    }
+
+    #[test]
+    fn cancel_all_authorized_exchanges() {
+        let mut deps = default_deps(None);
+        let exchanges = vec![
+            ExchangeBuilder::new().commitment(10).build(),
+            ExchangeBuilder::new().commitment(20).build(),
+        ];
+        authorize_exchanges(&mut deps, exchanges.clone()).unwrap();
+
+        let res = cancel_authorized_exchanges(&mut deps, exchanges.clone(), exchanges).unwrap();
+        assert_attr(&res, "cancelled", "true");
+        assert!(asset_exchange_authorization_storage_read(&deps.storage)
+            .load()
+            .unwrap()
+            .is_empty());
+        assert_eq!(
+            AuthorizationStatus::Cancelled,
+            queried_authorization_history(&deps, true)[0].status
+        );
+    }
+
+    #[test]
+    fn cancel_unauthorized_exchange_refused() {
+        let mut deps = default_deps(None);
+        let first = ExchangeBuilder::new().commitment(10).build();
+        let second = ExchangeBuilder::new().commitment(20).build();
+        authorize_exchanges(&mut deps, vec![first.clone()]).unwrap();
+
+        let err =
+            cancel_authorized_exchanges(&mut deps, vec![first.clone()], vec![second]).unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("is not an exchange of authorization"));
+
+        // removing the same exchange twice needs it authorized twice
+        let err = cancel_authorized_exchanges(
+            &mut deps,
+            vec![first.clone()],
+            vec![first.clone(), first.clone()],
+        )
+        .unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("is not an exchange of authorization"));
+        assert_eq!(
+            vec![first],
+            asset_exchange_authorization_storage_read(&deps.storage)
+                .load()
+                .unwrap()[0]
+                .exchanges
+        );
+    }
+
+    #[test]
+    fn cancel_asset_exchange_authorization_bad_actor() {
+        let mut deps = default_deps(None);
+
+        let exchange = ExchangeBuilder::new()
+            .investment(1_000)
+            .commitment(1_000)
+            .capital(1_000)
+            .build();
+        let to = Some(Addr::unchecked("lp_side_account"));
+        let memo = Some(String::from("memo"));
+
+        asset_exchange_authorization_storage(&mut deps.storage)
+            .save(&vec![AssetExchangeAuthorization {
+                id: 1,
+                raise: None,
+                exchanges: vec![exchange.clone()],
+                to: to.clone(),
+                memo: memo.clone(),
+                created_at: None,
+                expires_at: None,
+                memo_hash: None,
+                status: AuthorizationStatus::Pending,
+                resolved_at: None,
+                resolved_by: None,
+            }])
+            .unwrap();
+
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("bad_actor", &[]),
+            ExecuteMsg::CancelAssetExchangeAuthorization {
+                exchanges: vec![exchange.clone()],
+                to: to.clone(),
+                memo: memo.clone(),
+                raise: None,
+            },
+        );
+
+        // verify error
+        assert!(res.is_err());
+    }
+
+    #[test]
+    fn complete_asset_exchange_accept_only() {
+        let mut deps = capital_coin_deps(None);
+        load_markers(&mut deps.querier);
+        let exchange = ExchangeBuilder::new()
+            .investment(1_000)
+            .commitment(1_000)
+            .capital(1_000)
+            .build();
+        let to = Some(Addr::unchecked("lp_side_account"));
+        let memo = Some(String::from("memo"));
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("lp", &[]),
+            ExecuteMsg::CompleteAssetExchange {
+                exchanges: vec![exchange.clone()],
+                to: to.clone(),
+                memo: memo.clone(),
+                override_available_date: false,
+                raise: None,
+            },
+        )
+        .unwrap();
+
+        // verify exec message sent
+        assert_eq!(1, res.messages.len());
+        let (recipient, msg, funds) = execute_args::<RaiseExecuteMsg>(msg_at_index(&res, 0));
+        assert_eq!("raise_1", recipient);
+        assert_eq!(
+            RaiseExecuteMsg::CompleteAssetExchange {
+                exchanges: vec![exchange.clone()],
+                to,
+                memo
+            },
+            msg
+        );
+
+        // verify no funds sent
+        assert_eq!(0, funds.len());
+
+        assert_attr(&res, "action", "complete_asset_exchange");
+        assert_no_attr(&res, "amount");
+    }
+
+    #[test]
+    fn recipient_requires_a_payout() {
+        let mut deps = default_deps(None);
+        let send_only = || {
+            vec![ExchangeBuilder::new()
+                .investment(-1_000)
+                .capital(-1_000)
+                .build()]
+        };
+        let authorize_to = |deps: &mut MockDeps, exchanges: Vec<AssetExchange>| {
+            execute(
+                deps.as_mut(),
+                mock_env(),
+                mock_info("lp", &[]),
+                ExecuteMsg::AuthorizeAssetExchange {
+                    exchanges,
+                    to: Some(Addr::unchecked("lp_side_account")),
+                    memo: None,
+                    idempotency_key: None,
+                    expires_at: None,
+                    memo_hash: None,
+                    raise: None,
+                },
+            )
+        };
+
+        let err = authorize_to(&mut deps, send_only()).unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("lp_side_account would receive nothing"));
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("lp", &[]),
+            ExecuteMsg::CompleteAssetExchange {
+                exchanges: send_only(),
+                to: Some(Addr::unchecked("lp_side_account")),
+                memo: None,
+                override_available_date: false,
+                raise: None,
+            },
+        )
+        .unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("lp_side_account would receive nothing"));
+
+        // an exchange paying out investment gives the recipient something
+        let mixed = [
+            send_only(),
+            vec![ExchangeBuilder::new().investment(500).build()],
+        ]
+        .concat();
+        authorize_to(&mut deps, mixed).unwrap();
+    }
+
+    fn assert_unique_denoms(coins: &[Coin]) {
+        let mut denoms: Vec<&String> = coins.iter().map(|coin| &coin.denom).collect();
+        denoms.sort();
+        denoms.dedup();
+        assert_eq!(coins.len(), denoms.len(), "duplicate denoms in {:?}", coins);
+    }
+
+    fn completion_funds(deps: &mut MockDeps, exchanges: Vec<AssetExchange>) -> Vec<Coin> {
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("lp", &[]),
+            ExecuteMsg::CompleteAssetExchange {
+                exchanges,
+                to: None,
+                memo: None,
+                override_available_date: false,
+                raise: None,
+            },
+        )
+        .unwrap();
+        let (_, _, funds) = execute_args::<RaiseExecuteMsg>(&res.messages.last().unwrap().msg);
+        assert_unique_denoms(funds);
+        funds.clone()
+    }
+
+    #[test]
+    fn completion_funds_never_repeat_a_denom() {
+        let mut deps = default_deps(Some(|state| {
+            state.investment_denoms.push(String::from("stable_coin"));
+            state
+                .like_capital_denoms
+                .push(String::from("raise_1.commitment"));
+        }));
+        let send = |investment_denom: &str, capital_denom: &str| {
+            ExchangeBuilder::new()
+                .investment(-1_000)
+                .investment_denom(investment_denom)
+                .commitment(-10)
+                .capital(-100)
+                .denom(capital_denom)
+                .build()
+        };
+
+        for exchanges in [
+            vec![send("raise_1.investment", "stable_coin")],
+            vec![send("stable_coin", "stable_coin")],
+            vec![send("raise_1.investment", "raise_1.commitment")],
+            vec![
+                send("stable_coin", "raise_1.commitment"),
+                send("raise_1.investment", "stable_coin"),
+            ],
+        ] {
+            completion_funds(&mut deps, exchanges);
+        }
+    }
+
+    #[test]
+    fn completion_funds_merge_overlapping_denoms() {
+        // a raise reusing its commitment denom as its capital denom
+        let mut deps = default_deps(Some(|state| {
+            state.like_capital_denoms = vec![String::from("raise_1.commitment")]
+        }));
+        let funds = completion_funds(
+            &mut deps,
+            vec![ExchangeBuilder::new()
+                .commitment(-10)
+                .capital(-1_000)
+                .build()],
+        );
+        assert_eq!(vec![coin(1_010, "raise_1.commitment")], funds);
+    }
+
+    #[test]
+    fn complete_asset_exchange_send_only() {
+        let mut deps = capital_coin_deps(None);
+        load_markers(&mut deps.querier);
+        let exchange = ExchangeBuilder::new()
+            .investment(-1_000)
+            .commitment(-1_000)
+            .capital(-1_000)
+            .build();
+        let to = None;
+        let memo = Some(String::from("memo"));
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("lp", &[]),
+            ExecuteMsg::CompleteAssetExchange {
+                exchanges: vec![exchange.clone(), exchange.clone()],
+                to: to.clone(),
+                memo: memo.clone(),
+                override_available_date: false,
+                raise: None,
+            },
+        )
+        .unwrap();
+
+        // verify exec message sent
+        assert_eq!(1, res.messages.len());
+        let (recipient, msg, funds) = execute_args::<RaiseExecuteMsg>(msg_at_index(&res, 0));
+        assert_eq!("raise_1", recipient);
+        assert_eq!(
+            RaiseExecuteMsg::CompleteAssetExchange {
+                exchanges: vec![exchange.clone(), exchange.clone()],
+                to,
+                memo
+            },
+            msg
+        );
+
+        // verify funds sent
+        assert_eq!(3, funds.len());
+        let capital = funds.first().unwrap();
+        assert_eq!(2_000, capital.amount.u128());
+        let commitment = funds.get(1).unwrap();
+        assert_eq!(2_000, commitment.amount.u128());
+        let investment = funds.get(2).unwrap();
+        assert_eq!(2_000, investment.amount.u128());
+    }
+
+    #[test]
+    fn complete_asset_exchange_overflowing_legs() {
+        let mut deps = capital_coin_deps(None);
+        load_markers(&mut deps.querier);
+        let huge = |investment: i128, capital: i128| AssetExchange {
+            investment: Some(Int128::new(investment)),
+            capital: Some(Int128::new(capital)),
+            ..ExchangeBuilder::new().build()
+        };
+
+        for exchanges in [
+            vec![huge(i128::MAX, 1_000), huge(1, 1_000)],
+            vec![huge(1_000, i128::MAX), huge(1_000, i128::MAX)],
+            vec![huge(i128::MIN, 1_000), huge(-1, 1_000)],
+        ] {
+            let err = execute(
+                deps.as_mut(),
+                mock_env(),
+                mock_info("lp", &[]),
+                ExecuteMsg::CompleteAssetExchange {
+                    exchanges,
+                    to: None,
+                    memo: None,
+                    override_available_date: false,
+                    raise: None,
+                },
+            )
+            .unwrap_err();
+            assert!(matches!(err, ContractError::Std(StdError::Overflow { .. })));
+        }
+    }
+
+    #[test]
+    fn complete_asset_exchange_returns_stored_authorization() {
+        let mut deps = capital_coin_deps(None);
+        load_markers(&mut deps.querier);
+        let authorized = || ExchangeBuilder::new().commitment(-1_000).capital(-1_000);
+        authorize_exchanges(&mut deps, vec![authorized().build()]).unwrap();
+
+        // the default capital denom named explicitly still matches the authorization
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("lp", &[]),
+            ExecuteMsg::CompleteAssetExchange {
+                exchanges: vec![authorized().denom("capital_coin").build()],
+                to: None,
+                memo: None,
+                override_available_date: false,
+                raise: None,
+            },
+        )
+        .unwrap();
+
+        let removed: AssetExchangeAuthorization = response_data(&res);
+        assert_eq!(1, removed.id);
+        assert_eq!(vec![authorized().build()], removed.exchanges);
+        assert!(asset_exchange_authorization_storage_read(&deps.storage)
+            .load()
+            .unwrap()
+            .is_empty());
+        let (_, msg, _) = execute_args::<RaiseExecuteMsg>(msg_at_index(&res, 0));
+        assert_eq!(
+            RaiseExecuteMsg::CompleteAssetExchange {
+                exchanges: vec![authorized().build()],
+                to: None,
+                memo: None,
+            },
+            msg
+        );
+    }
+
+    fn has_authorization(
+        deps: &MockDeps,
+        exchanges: Vec<AssetExchange>,
+        memo: Option<&str>,
+    ) -> HasAuthorizationResponse {
+        from_binary(
+            &query(
+                deps.as_ref(),
+                mock_env(),
+                QueryMsg::HasAuthorization {
+                    exchanges,
+                    to: None,
+                    memo: memo.map(String::from),
+                    raise: None,
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn has_authorization_matches_like_completion() {
+        let mut deps = capital_coin_deps(None);
+        load_markers(&mut deps.querier);
+        let exchange = || ExchangeBuilder::new().commitment(-1_000).capital(-1_000);
+        for memo in ["first", "second"] {
+            execute(
+                deps.as_mut(),
+                mock_env(),
+                mock_info("lp", &[]),
+                ExecuteMsg::AuthorizeAssetExchange {
+                    exchanges: vec![exchange().build()],
+                    to: None,
+                    memo: Some(String::from(memo)),
+                    idempotency_key: None,
+                    expires_at: None,
+                    memo_hash: None,
+                    raise: None,
+                },
+            )
+            .unwrap();
+        }
+
+        assert_eq!(
+            HasAuthorizationResponse {
+                found: true,
+                index: Some(1),
+            },
+            has_authorization(&deps, vec![exchange().build()], Some("second"))
+        );
+        // denoms are resolved before matching, as completion does
+        assert_eq!(
+            Some(0),
+            has_authorization(
+                &deps,
+                vec![exchange().denom("capital_coin").build()],
+                Some("first")
+            )
+            .index
+        );
+
+        // a near miss is not found, and completing it is refused the same way
+        let near_miss = has_authorization(&deps, vec![exchange().build()], Some("third"));
+        assert_eq!(
+            HasAuthorizationResponse {
+                found: false,
+                index: None,
+            },
+            near_miss
+        );
+        let complete = |deps: &mut MockDeps, memo: &str| {
+            execute(
+                deps.as_mut(),
+                mock_env(),
+                mock_info("admin", &[]),
+                ExecuteMsg::CompleteAssetExchange {
+                    exchanges: vec![exchange().build()],
+                    to: None,
+                    memo: Some(String::from(memo)),
+                    override_available_date: false,
+                    raise: None,
+                },
+            )
+        };
+        let err = complete(&mut deps, "third").unwrap_err();
+        assert!(matches!(err, ContractError::AuthorizationNotFound { .. }));
+
+        complete(&mut deps, "second").unwrap();
+        assert!(!has_authorization(&deps, vec![exchange().build()], Some("second")).found);
+        assert_eq!(
+            Some(0),
+            has_authorization(&deps, vec![exchange().build()], Some("first")).index
+        );
+    }
+
+    fn with_second_raise(state: &mut State) {
+        state.raises.push(Addr::unchecked("raise_2"));
+    }
+
+    fn authorize_for_raise(
+        deps: &mut MockDeps,
+        raise: Option<&str>,
+        exchanges: Vec<AssetExchange>,
+    ) {
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("lp", &[]),
+            ExecuteMsg::AuthorizeAssetExchange {
+                exchanges,
+                to: None,
+                memo: Some(String::from("memo")),
+                idempotency_key: None,
+                expires_at: None,
+                memo_hash: None,
+                raise: raise.map(Addr::unchecked),
+            },
+        )
+        .unwrap();
+    }
+
+    fn authorization_index_for_raise(
+        deps: &MockDeps,
+        raise: Option<&str>,
+        exchanges: Vec<AssetExchange>,
+    ) -> Option<u32> {
+        from_binary::<HasAuthorizationResponse>(
+            &query(
+                deps.as_ref(),
+                mock_env(),
+                QueryMsg::HasAuthorization {
+                    exchanges,
+                    to: None,
+                    memo: Some(String::from("memo")),
+                    raise: raise.map(Addr::unchecked),
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap()
+        .index
+    }
+
+    #[test]
+    fn raises_keep_separate_authorizations() {
+        let mut deps = capital_coin_deps(Some(with_second_raise));
+        load_markers(&mut deps.querier);
+        let exchanges = || {
+            vec![ExchangeBuilder::new()
+                .commitment(-1_000)
+                .capital(-1_000)
+                .build()]
+        };
+        authorize_for_raise(&mut deps, None, exchanges());
+        authorize_for_raise(&mut deps, Some("raise_2"), exchanges());
+
+        // naming the primary raise is the same as naming none
+        assert_eq!(
+            Some(0),
+            authorization_index_for_raise(&deps, Some("raise_1"), exchanges())
+        );
+        assert_eq!(
+            Some(1),
+            authorization_index_for_raise(&deps, Some("raise_2"), exchanges())
+        );
+
+        let complete = |deps: &mut MockDeps, raise: &str| {
+            execute(
+                deps.as_mut(),
+                mock_env(),
+                mock_info("admin", &[]),
+                ExecuteMsg::CompleteAssetExchange {
+                    exchanges: exchanges(),
+                    to: None,
+                    memo: Some(String::from("memo")),
+                    override_available_date: false,
+                    raise: Some(Addr::unchecked(raise)),
+                },
+            )
+        };
+        let res = complete(&mut deps, "raise_2").unwrap();
+        let (recipient, _, _) = execute_args::<RaiseExecuteMsg>(msg_at_index(&res, 0));
+        assert_eq!("raise_2", recipient);
+        let completed: AssetExchangeAuthorization = response_data(&res);
+        assert_eq!(Some(Addr::unchecked("raise_2")), completed.raise);
+
+        // the primary raise's identical authorization is left alone
+        assert_eq!(
+            None,
+            authorization_index_for_raise(&deps, Some("raise_2"), exchanges())
+        );
+        assert_eq!(
+            Some(0),
+            authorization_index_for_raise(&deps, None, exchanges())
+        );
+        let err = complete(&mut deps, "raise_2").unwrap_err();
+        assert!(matches!(err, ContractError::AuthorizationNotFound { .. }));
+
+        let res = complete(&mut deps, "raise_1").unwrap();
+        let (recipient, _, _) = execute_args::<RaiseExecuteMsg>(msg_at_index(&res, 0));
+        assert_eq!("raise_1", recipient);
+        assert_eq!(
+            None,
+            authorization_index_for_raise(&deps, None, exchanges())
+        );
+    }
+
+    #[test]
+    fn raises_cancel_within_raise() {
+        let mut deps = capital_coin_deps(Some(with_second_raise));
+        let exchanges = || {
+            vec![ExchangeBuilder::new()
+                .commitment(-1_000)
+                .capital(-1_000)
+                .build()]
+        };
+        authorize_for_raise(&mut deps, Some("raise_2"), exchanges());
+
+        let cancel = |deps: &mut MockDeps, raise: Option<&str>| {
+            execute(
+                deps.as_mut(),
+                mock_env(),
+                mock_info("lp", &[]),
+                ExecuteMsg::CancelAssetExchangeAuthorization {
+                    exchanges: exchanges(),
+                    to: None,
+                    memo: Some(String::from("memo")),
+                    raise: raise.map(Addr::unchecked),
+                },
+            )
+        };
+        let err = cancel(&mut deps, None).unwrap_err();
+        assert!(matches!(err, ContractError::AuthorizationNotFound { .. }));
+        cancel(&mut deps, Some("raise_2")).unwrap();
+        assert_eq!(
+            None,
+            authorization_index_for_raise(&deps, Some("raise_2"), exchanges())
+        );
+    }
+
+    #[test]
+    fn unregistered_raise() {
+        let mut deps = capital_coin_deps(Some(with_second_raise));
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("lp", &[]),
+            ExecuteMsg::CompleteAssetExchange {
+                exchanges: vec![ExchangeBuilder::new().commitment(-1_000).build()],
+                to: None,
+                memo: None,
+                override_available_date: false,
+                raise: Some(Addr::unchecked("raise_3")),
+            },
+        )
+        .unwrap_err();
+        assert_eq!(
+            "raise_3 is not a raise of this subscription",
+            err.to_string()
+        );
+        assert_eq!(0, stats(&deps).completions);
+    }
+
+    #[test]
+    fn complete_asset_exchange_restricted_marker_second_raise() {
+        let mut deps = restricted_capital_coin_deps(Some(|state| {
+            state.required_capital_attributes = vec![];
+            with_second_raise(state);
+        }));
+        load_markers(&mut deps.querier);
+
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("lp", &[]),
+            ExecuteMsg::CompleteAssetExchange {
+                exchanges: vec![ExchangeBuilder::new().capital(-1_000).build()],
+                to: None,
+                memo: None,
+                override_available_date: false,
+                raise: Some(Addr::unchecked("raise_2")),
+            },
+        )
+        .unwrap();
+
+        assert_eq!(
+            &MarkerMsgParams::TransferMarkerCoins {
+                coin: coin(1_000, "restricted_capital_coin"),
+                to: Addr::unchecked("raise_2"),
+                from: Addr::unchecked(MOCK_CONTRACT_ADDR),
+            },
+            marker_transfer_msg(msg_at_index(&res, 0)),
+        );
+        let (recipient, _, funds) = execute_args::<RaiseExecuteMsg>(msg_at_index(&res, 1));
+        assert_eq!("raise_2", recipient);
+        assert!(funds.is_empty());
+    }
+
+    #[test]
+    fn cancel_asset_exchange_authorization_returns_stored_authorization() {
+        let mut deps = default_deps(None);
+        let authorized = || ExchangeBuilder::new().commitment(10).capital(1_000);
+        authorize_exchanges(&mut deps, vec![authorized().build()]).unwrap();
+
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("lp", &[]),
+            ExecuteMsg::CancelAssetExchangeAuthorization {
+                exchanges: vec![authorized().denom("stable_coin").build()],
+                to: None,
+                memo: None,
+                raise: None,
+            },
+        )
+        .unwrap();
+
+        let removed: AssetExchangeAuthorization = response_data(&res);
+        assert_eq!(vec![authorized().build()], removed.exchanges);
+        assert_eq!(Some(mock_env().block.time), removed.created_at);
+    }
+
+    #[test]
+    fn complete_asset_exchange_restricted_raise_missing_attribute() {
+        let mut deps = restricted_capital_coin_deps(None);
+        load_markers(&mut deps.querier);
+
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("lp", &[]),
+            ExecuteMsg::CompleteAssetExchange {
+                exchanges: vec![ExchangeBuilder::new().capital(-1_000).build()],
+                to: None,
+                memo: None,
+                override_available_date: false,
+                raise: None,
+            },
+        )
+        .unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("raise_1 does not have required attribute of capital.test"));
+        assert_eq!(0, stats(&deps).completions);
+    }
+
+    #[test]
+    fn complete_asset_exchange_restricted_marker_without_attribute_requirement() {
+        let mut deps =
+            restricted_capital_coin_deps(Some(|state| state.required_capital_attributes = vec![]));
+        load_markers(&mut deps.querier);
+
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("lp", &[]),
+            ExecuteMsg::CompleteAssetExchange {
+                exchanges: vec![ExchangeBuilder::new().capital(-1_000).build()],
+                to: None,
+                memo: None,
+                override_available_date: false,
+                raise: None,
+            },
+        )
+        .unwrap();
+
+        assert_eq!(
+            &MarkerMsgParams::TransferMarkerCoins {
+                coin: coin(1_000, "restricted_capital_coin"),
+                to: Addr::unchecked("raise_1"),
+                from: Addr::unchecked(MOCK_CONTRACT_ADDR),
+            },
+            marker_transfer_msg(msg_at_index(&res, 0)),
+        );
+        let (_, _, funds) = execute_args::<RaiseExecuteMsg>(msg_at_index(&res, 1));
+        assert!(funds.is_empty());
+    }
+
+    #[test]
+    fn complete_asset_exchange_restricted_marker_without_transfer_grant() {
+        let mut deps = restricted_capital_coin_deps(None);
+        load_attributes(&mut deps.querier, &[("raise_1", "capital.test")]);
+        load_marker_fixtures(&mut deps.querier, &["restricted_capital_no_grant"]);
+
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("lp", &[]),
+            ExecuteMsg::CompleteAssetExchange {
+                exchanges: vec![ExchangeBuilder::new().capital(-1_000).build()],
+                to: None,
+                memo: None,
+                override_available_date: false,
+                raise: None,
+            },
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains(
+            "lacks transfer permission on marker restricted_capital_coin, access list is [tp1"
+        ));
+    }
+
+    #[test]
+    fn complete_asset_exchange_restricted_marker_send_only() {
+        let mut deps = restricted_capital_coin_deps(None);
+        load_attributes(&mut deps.querier, &[("raise_1", "capital.test")]);
+        load_markers(&mut deps.querier);
+        let exchange = ExchangeBuilder::new()
+            .investment(-1_000)
+            .commitment(-1_000)
+            .capital(-1_000)
+            .build();
+        let to = None;
+        let memo = Some(String::from("memo"));
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("lp", &[]),
+            ExecuteMsg::CompleteAssetExchange {
+                exchanges: vec![exchange.clone(), exchange.clone()],
+                to: to.clone(),
+                memo: memo.clone(),
+                override_available_date: false,
+                raise: None,
+            },
+        )
+        .unwrap();
+
+        // verify exec message sent
+        assert_eq!(2, res.messages.len());
+        let (recipient, msg, funds) = execute_args::<RaiseExecuteMsg>(msg_at_index(&res, 1));
+        assert_eq!("raise_1", recipient);
+        assert_eq!(
+            RaiseExecuteMsg::CompleteAssetExchange {
+                exchanges: vec![exchange.clone(), exchange.clone()],
+                to,
+                memo
+            },
+            msg
+        );
+
+        // verify funds sent
+        assert_eq!(
+            &MarkerMsgParams::TransferMarkerCoins {
+                coin: coin(2_000, "restricted_capital_coin"),
+                to: Addr::unchecked("raise_1"),
+                from: Addr::unchecked(MOCK_CONTRACT_ADDR),
+            },
+            marker_transfer_msg(msg_at_index(&res, 0)),
+        );
+        assert_eq!(2, funds.len());
+        // let capital = funds.first().unwrap();
+        // assert_eq!(2_000, capital.amount.u128());
+        let commitment = funds.first().unwrap();
+        assert_eq!(2_000, commitment.amount.u128());
+        let investment = funds.get(1).unwrap();
+        assert_eq!(2_000, investment.amount.u128());
+    }
+
+    #[test]
+    fn complete_asset_exchange_multiple_capital_denoms() {
+        let mut deps = restricted_capital_coin_deps(Some(|state| {
+            state.like_capital_denoms.push(String::from("capital_coin"))
+        }));
+        load_attributes(&mut deps.querier, &[("raise_1", "capital.test")]);
+        load_markers(&mut deps.querier);
+        let exchanges = vec![
+            ExchangeBuilder::new()
+                .capital(-1_000)
+                .denom("capital_coin")
+                .build(),
+            ExchangeBuilder::new()
+                .capital(-2_000)
+                .denom("restricted_capital_coin")
+                .build(),
+        ];
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("lp", &[]),
+            ExecuteMsg::CompleteAssetExchange {
+                exchanges,
+                to: None,
+                memo: None,
+                override_available_date: false,
+                raise: None,
+            },
+        )
+        .unwrap();
+
+        // verify restricted capital transferred and unrestricted capital attached as funds
+        assert_eq!(2, res.messages.len());
+        assert_eq!(
+            &MarkerMsgParams::TransferMarkerCoins {
+                coin: coin(2_000, "restricted_capital_coin"),
+                to: Addr::unchecked("raise_1"),
+                from: Addr::unchecked(MOCK_CONTRACT_ADDR),
+            },
+            marker_transfer_msg(msg_at_index(&res, 0)),
+        );
+        let (_, _, funds) = execute_args::<RaiseExecuteMsg>(msg_at_index(&res, 1));
+        assert_eq!(&vec![coin(1_000, "capital_coin")], funds);
+    }
+
+    #[test]
+    fn complete_asset_exchange_multiple_investment_denoms() {
+        let mut deps = default_deps(Some(|state| {
+            state
+                .investment_denoms
+                .push(String::from("raise_1.investment_b"))
+        }));
+        let exchanges = vec![
+            ExchangeBuilder::new().investment(-1_000).build(),
+            ExchangeBuilder::new()
+                .investment(-2_000)
+                .investment_denom("raise_1.investment_b")
+                .build(),
+            ExchangeBuilder::new()
+                .investment(-500)
+                .investment_denom("raise_1.investment")
+                .build(),
+        ];
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("lp", &[]),
+            ExecuteMsg::CompleteAssetExchange {
+                exchanges,
+                to: None,
+                memo: None,
+                override_available_date: false,
+                raise: None,
+            },
+        )
+        .unwrap();
+
+        let (_, _, funds) = execute_args::<RaiseExecuteMsg>(msg_at_index(&res, 0));
+        assert_eq!(
+            &vec![
+                coin(1_500, "raise_1.investment"),
+                coin(2_000, "raise_1.investment_b"),
+            ],
+            funds
+        );
+    }
+
+    #[test]
+    fn complete_asset_exchange_restricted_investment_marker() {
+        let mut deps = default_deps(None);
+        load_marker_fixtures(&mut deps.querier, &["capital", "restricted_investment"]);
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("lp", &[]),
+            ExecuteMsg::CompleteAssetExchange {
+                exchanges: vec![ExchangeBuilder::new()
+                    .investment(-1_000)
+                    .commitment(-10)
+                    .build()],
+                to: None,
+                memo: None,
+                override_available_date: false,
+                raise: None,
+            },
+        )
+        .unwrap();
+
+        // verify restricted investment transferred and commitment attached as funds
+        assert_eq!(2, res.messages.len());
+        assert_eq!(
+            &MarkerMsgParams::TransferMarkerCoins {
+                coin: coin(1_000, "raise_1.investment"),
+                to: Addr::unchecked("raise_1"),
+                from: Addr::unchecked(MOCK_CONTRACT_ADDR),
+            },
+            marker_transfer_msg(msg_at_index(&res, 0)),
+        );
+        let (_, _, funds) = execute_args::<RaiseExecuteMsg>(msg_at_index(&res, 1));
+        assert_eq!(&vec![coin(10, "raise_1.commitment")], funds);
+    }
+
+    #[test]
+    fn complete_asset_exchange_unsupported_investment_denom() {
+        let res = execute(
+            default_deps(None).as_mut(),
+            mock_env(),
+            mock_info("lp", &[]),
+            ExecuteMsg::CompleteAssetExchange {
+                exchanges: vec![ExchangeBuilder::new()
+                    .investment(-1_000)
+                    .investment_denom("other_investment")
+                    .build()],
+                to: None,
+                memo: None,
+                override_available_date: false,
+                raise: None,
+            },
+        );
+        assert!(res.is_err());
+    }
+
+    fn admin_complete_with_denom(deps: &mut MockDeps, capital_denom: &str) -> ContractResponse {
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("admin", &[]),
+            ExecuteMsg::CompleteAssetExchange {
+                exchanges: vec![
+                    ExchangeBuilder::new()
+                        .commitment(10)
+                        .due(mock_env().block.time.seconds() - 1)
+                        .build(),
+                    ExchangeBuilder::new()
+                        .capital(-1_000)
+                        .denom(capital_denom)
+                        .build(),
+                ],
+                to: None,
+                memo: None,
+                override_available_date: false,
+                raise: None,
+            },
+        )
+    }
+
+    #[test]
+    fn admin_complete_asset_exchange_unsupported_capital_denom() {
+        let mut deps = capital_coin_deps(None);
+
+        match admin_complete_with_denom(&mut deps, "bogus_coin").unwrap_err() {
+            ContractError::UnsupportedCapitalDenom { denom } => assert_eq!("bogus_coin", denom),
+            err => panic!("unexpected error: {}", err),
+        }
+        assert!(matches!(
+            admin_complete_with_denom(&mut deps, ""),
+            Err(ContractError::UnsupportedCapitalDenom { .. })
+        ));
+
+        let res = admin_complete_with_denom(&mut deps, "capital_coin").unwrap();
+        assert_eq!(1, res.messages.len());
+    }
+
+    fn authorize_exchanges(deps: &mut MockDeps, exchanges: Vec<AssetExchange>) -> ContractResponse {
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("lp", &[]),
+            ExecuteMsg::AuthorizeAssetExchange {
+                exchanges,
+                to: None,
+                memo: None,
+                idempotency_key: None,
+                expires_at: None,
+                memo_hash: None,
+                raise: None,
+            },
+        )
+    }
+
+    #[test]
+    fn date_only_exchange_rejected() {
+        let mut deps = default_deps(None);
+        let due = mock_env().block.time.seconds() - 1;
+        let date_only = || vec![ExchangeBuilder::new().due(due).build()];
+
+        let err = authorize_exchanges(&mut deps, date_only()).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidExchange { .. }));
+        assert!(asset_exchange_authorization_storage_read(&deps.storage)
+            .may_load()
+            .unwrap()
+            .is_none());
+
+        let simulation = assert_simulation_matches(&mut deps, "admin", date_only());
+        assert!(simulation.errors[0].starts_with("invalid asset exchange [due:"));
+
+        // a date alongside a real amount is still an exchange
+        authorize_exchanges(
+            &mut deps,
+            vec![ExchangeBuilder::new().commitment(10).due(due).build()],
+        )
+        .unwrap();
+        let err = authorize_exchanges(&mut deps, [date_only(), vec![accept_exchange()]].concat())
+            .unwrap_err();
+        assert!(matches!(err, ContractError::InvalidExchange { .. }));
+    }
+
+    fn complete_before_available(
+        deps: &mut MockDeps,
+        sender: &str,
+        memo: Option<&str>,
+        override_available_date: bool,
+    ) -> ContractResponse {
+        let available = mock_env().block.time.seconds() + 100;
+        let exchanges = vec![ExchangeBuilder::new()
+            .commitment(10)
+            .available(available)
+            .build()];
+        let memo = memo.map(String::from);
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("lp", &[]),
+            ExecuteMsg::AuthorizeAssetExchange {
+                exchanges: exchanges.clone(),
+                to: None,
+                memo: memo.clone(),
+                idempotency_key: None,
+                expires_at: None,
+                memo_hash: None,
+                raise: None,
+            },
+        )
+        .unwrap();
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(sender, &[]),
+            ExecuteMsg::CompleteAssetExchange {
+                exchanges,
+                to: None,
+                memo,
+                override_available_date,
+                raise: None,
+            },
+        )
+    }
+
+    #[test]
+    fn complete_before_available_date_refused() {
+        let mut deps = default_deps(None);
+        let err = complete_before_available(&mut deps, "lp", None, false).unwrap_err();
+        assert_eq!(
+            format!(
+                "asset exchange is not available until {}",
+                mock_env().block.time.seconds() + 100
+            ),
+            err.to_string()
+        );
+
+        let mut deps = default_deps(None);
+        let err =
+            complete_before_available(&mut deps, "admin", Some("wind-down"), false).unwrap_err();
+        assert!(matches!(err, ContractError::NotYetAvailable { .. }));
+    }
+
+    #[test]
+    fn admin_overrides_available_date() {
+        let mut deps = default_deps(None);
+        let res = complete_before_available(&mut deps, "admin", Some("wind-down"), true).unwrap();
+        assert_eq!(1, res.messages.len());
+        assert_attr(&res, "availability_overridden", "true");
+        assert_eq!(
+            vec![AuthorizationStatus::Completed],
+            queried_authorization_history(&deps, true)
+                .iter()
+                .map(|authorization| authorization.status)
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn lp_cannot_override_available_date() {
+        let mut deps = default_deps(None);
+        let err = complete_before_available(&mut deps, "lp", Some("wind-down"), true).unwrap_err();
+        assert_eq!(
+            "Generic error: only an admin can override the available date",
+            err.to_string()
+        );
+        assert_eq!(1, queried_authorizations(&deps).len());
+    }
+
+    #[test]
+    fn available_date_override_requires_memo() {
+        for memo in [None, Some(""), Some("  ")] {
+            let mut deps = default_deps(None);
+            let err = complete_before_available(&mut deps, "admin", memo, true).unwrap_err();
+            assert_eq!(
+                "Generic error: overriding the available date requires a memo",
+                err.to_string()
+            );
+        }
+    }
+
+    #[test]
+    fn complete_asset_exchange_ambiguous_capital_denom() {
+        let mut deps = capital_coin_deps(Some(|state| {
+            state.like_capital_denoms.push(String::from("stable_coin"))
+        }));
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("lp", &[]),
+            ExecuteMsg::CompleteAssetExchange {
+                exchanges: vec![ExchangeBuilder::new().capital(-1_000).build()],
+                to: None,
+                memo: None,
+                override_available_date: false,
+                raise: None,
+            },
+        );
+        assert!(res.is_err());
+    }
+
+    #[test]
+    fn complete_asset_exchange_tracks_commitment_ceiling() {
+        let mut deps = default_deps(Some(|state| state.max_commitment_in_shares = Some(1_000)));
+        let exchange = ExchangeBuilder::new().commitment(1_000).build();
+
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("lp", &[]),
+            ExecuteMsg::CompleteAssetExchange {
+                exchanges: vec![exchange.clone()],
+                to: None,
+                memo: None,
+                override_available_date: false,
+                raise: None,
+            },
+        )
+        .unwrap();
+
+        // verify running total updated
+        assert_eq!(
+            1_000,
+            state_storage_read(&deps.storage)
+                .load()
+                .unwrap()
+                .total_commitment_in_shares
+        );
+
+        // verify further commitment rejected
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("lp", &[]),
+            ExecuteMsg::CompleteAssetExchange {
+                exchanges: vec![exchange],
+                to: None,
+                memo: None,
+                override_available_date: false,
+                raise: None,
+            },
+        );
+        assert!(res.is_err());
+    }
+
+    #[test]
+    fn complete_asset_exchange_admin() {
+        let mut deps = capital_coin_deps(None);
+        load_markers(&mut deps.querier);
+        let exchange = ExchangeBuilder::new()
+            .investment(1_000)
+            .commitment(1_000)
+            .capital(1_000)
+            .build();
+        let to = Some(Addr::unchecked("lp_side_account"));
+        let memo = Some(String::from("memo"));
+
+        asset_exchange_authorization_storage(&mut deps.storage)
+            .save(&vec![AssetExchangeAuthorization {
+                id: 1,
+                raise: None,
+                exchanges: vec![exchange.clone()],
+                to: to.clone(),
+                memo: memo.clone(),
+                created_at: None,
+                expires_at: None,
+                memo_hash: None,
+                status: AuthorizationStatus::Pending,
+                resolved_at: None,
+                resolved_by: None,
+            }])
+            .unwrap();
+
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("admin", &[]),
+            ExecuteMsg::CompleteAssetExchange {
+                exchanges: vec![exchange.clone()],
+                to: to.clone(),
+                memo: memo.clone(),
+                override_available_date: false,
+                raise: None,
+            },
+        )
+        .unwrap();
+
+        // verify exec message sent
+        assert_eq!(1, res.messages.len());
+        let (recipient, msg, funds) = execute_args::<RaiseExecuteMsg>(msg_at_index(&res, 0));
+        assert_eq!("raise_1", recipient);
+        assert_eq!(
+            RaiseExecuteMsg::CompleteAssetExchange {
+                exchanges: vec![exchange.clone()],
+                to,
+                memo
+            },
+            msg
+        );
+
+        // verify no funds sent
+        assert_eq!(0, funds.len());
+
+        // verify asset exchange authorization removed
+        assert_eq!(
+            0,
+            asset_exchange_authorization_storage_read(&deps.storage)
+                .load()
+                .unwrap()
+                .len()
+        );
+    }
+
+    fn with_operator(state: &mut State) {
+        state.operators = vec![Addr::unchecked("operator")];
+    }
+
+    #[test]
+    fn set_operators() {
+        let mut deps = default_deps(None);
+
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("admin", &[]),
+            ExecuteMsg::SetOperators {
+                operators: vec![Addr::unchecked("operator"), Addr::unchecked("operator_2")],
+            },
+        )
+        .unwrap();
+        assert_attr(&res, "operators", "operator,operator_2");
+        assert_eq!(
+            vec![Addr::unchecked("operator"), Addr::unchecked("operator_2")],
+            state_storage_read(&deps.storage).load().unwrap().operators
+        );
+    }
+
+    #[test]
+    fn paused_blocks_lp_flows() {
+        let mut deps = deps_with_balances(&[("stable_coin", 10_000)]);
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("admin", &[]),
+            ExecuteMsg::SetPaused { paused: true },
+        )
+        .unwrap();
+        assert_attr(&res, "pause_source", "admin");
+
+        assert!(authorize(&mut deps, mock_env()).is_err());
+        assert!(withdraw_to(&mut deps, mock_env(), "lp_side_account", 1_000).is_err());
+        assert!(execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("lp", &[]),
+            ExecuteMsg::CompleteAssetExchange {
+                exchanges: vec![accept_exchange()],
+                to: None,
+                memo: None,
+                override_available_date: false,
+                raise: None,
+            },
+        )
+        .is_err());
+
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("admin", &[]),
+            ExecuteMsg::SetPaused { paused: false },
+        )
+        .unwrap();
+        authorize(&mut deps, mock_env()).unwrap();
+    }
+
+    #[test]
+    fn set_paused_bad_actor() {
+        let res = execute(
+            default_deps(None).as_mut(),
+            mock_env(),
+            mock_info("lp", &[]),
+            ExecuteMsg::SetPaused { paused: true },
+        );
+        assert!(res.is_err());
+    }
+
+    #[test]
+    fn set_operators_bad_actor() {
+        let res = execute(
+            default_deps(Some(with_operator)).as_mut(),
+            mock_env(),
+            mock_info("operator", &[]),
+            ExecuteMsg::SetOperators {
+                operators: vec![Addr::unchecked("bad_actor")],
+            },
+        );
+        assert!(res.is_err());
+    }
+
+    #[test]
+    fn complete_asset_exchange_operator() {
+        let mut deps = capital_coin_deps(Some(with_operator));
+        let exchange = ExchangeBuilder::new()
+            .investment(1_000)
+            .capital(-1_000)
+            .build();
+        asset_exchange_authorization_storage(&mut deps.storage)
+            .save(&vec![AssetExchangeAuthorization {
+                id: 1,
+                raise: None,
+                exchanges: vec![exchange.clone()],
+                to: None,
+                memo: None,
+                created_at: None,
+                expires_at: None,
+                memo_hash: None,
+                status: AuthorizationStatus::Pending,
+                resolved_at: None,
+                resolved_by: None,
+            }])
+            .unwrap();
+
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("operator", &[]),
+            ExecuteMsg::CompleteAssetExchange {
+                exchanges: vec![exchange],
+                to: None,
+                memo: None,
+                override_available_date: false,
+                raise: None,
+            },
+        )
+        .unwrap();
+        assert_eq!(1, res.messages.len());
+        assert!(asset_exchange_authorization_storage_read(&deps.storage)
+            .load()
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn complete_asset_exchange_operator_unauthorized() {
+        let res = execute(
+            capital_coin_deps(Some(with_operator)).as_mut(),
+            mock_env(),
+            mock_info("operator", &[]),
+            ExecuteMsg::CompleteAssetExchange {
+                exchanges: vec![ExchangeBuilder::new()
+                    .investment(1_000)
+                    .capital(-1_000)
+                    .build()],
+                to: None,
+                memo: None,
+                override_available_date: false,
+                raise: None,
+            },
+        );
+        assert!(res.is_err());
+    }
+
+    #[test]
+    fn operator_restricted_actions() {
+        let mut deps = default_deps(Some(with_operator));
+
+        for msg in [
+            ExecuteMsg::Recover {
+                lp: Addr::unchecked("operator"),
+            },
+            ExecuteMsg::AuthorizeAssetExchange {
+                exchanges: vec![accept_exchange()],
+                to: None,
+                memo: None,
+                idempotency_key: None,
+                expires_at: None,
+                memo_hash: None,
+                raise: None,
+            },
+            ExecuteMsg::IssueWithdrawal {
+                to: Some(Addr::unchecked("operator")),
+                to_name: None,
+                amount: Uint128::new(1_000),
+                capital_denom: None,
+            },
+            ExecuteMsg::IssueMultiCoinWithdrawal {
+                to: Some(Addr::unchecked("operator")),
+                to_name: None,
+                amounts: vec![coin(1_000, "stable_coin")],
+            },
+            ExecuteMsg::ReturnCapital {
+                amount: Uint128::new(1_000),
+                capital_denom: None,
+                memo: None,
+            },
+            ExecuteMsg::UpdateDefaultDistributionAddress {
+                default_distribution_address: Some(Addr::unchecked("operator")),
+            },
+            ExecuteMsg::SetBeneficiary {
+                beneficiary: Some(Addr::unchecked("operator")),
+            },
+            ExecuteMsg::SetLpNote {
+                note: Some(String::from("operator")),
+            },
+        ] {
+            let res = execute(deps.as_mut(), mock_env(), mock_info("operator", &[]), msg);
+            assert!(res.is_err());
+        }
+        assert_eq!("lp", state_storage_read(&deps.storage).load().unwrap().lp);
+    }
+
+    #[test]
+    fn complete_asset_exchange_bad_actor() {
+        let mut deps = default_deps(None);
+
+        let exchange = ExchangeBuilder::new()
+            .investment(1_000)
+            .commitment(1_000)
+            .capital(1_000)
+            .build();
+        let to = Some(Addr::unchecked("lp_side_account"));
+        let memo = Some(String::from("memo"));
+
+        asset_exchange_authorization_storage(&mut deps.storage)
+            .save(&vec![AssetExchangeAuthorization {
+                id: 1,
+                raise: None,
+                exchanges: vec![exchange.clone()],
+                to: to.clone(),
+                memo: memo.clone(),
+                created_at: None,
+                expires_at: None,
+                memo_hash: None,
+                status: AuthorizationStatus::Pending,
+                resolved_at: None,
+                resolved_by: None,
+            }])
+            .unwrap();
+
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("bad_actor", &[]),
+            ExecuteMsg::CompleteAssetExchange {
+                exchanges: vec![exchange.clone()],
+                to: to.clone(),
+                memo: memo.clone(),
+                override_available_date: false,
+                raise: None,
+            },
+        );
+
+        // verify error
+        assert!(res.is_err());
+    }
+
+    #[test]
+    fn withdraw() {
+        let mut deps = deps_with_balances(&[("stable_coin", 10_000)]);
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("lp", &[]),
+            ExecuteMsg::IssueWithdrawal {
+                to: Some(Addr::unchecked("lp_side_account")),
+                to_name: None,
+                amount: Uint128::new(10_000),
+                capital_denom: None,
+            },
+        )
+        .unwrap();
+
+        // verify send message sent
+        assert_eq!(1, res.messages.len());
+        let (to_address, coins) = send_msg(msg_at_index(&res, 0));
+        assert_eq!("lp_side_account", to_address);
+        assert_eq!(10_000, coins.first().unwrap().amount.u128());
+
+        assert_attr(&res, "action", "issue_withdrawal");
+        assert_attr(&res, "to", "lp_side_account");
+        assert_attr(&res, "amount", "10000");
+        assert_attr(&res, "capital_denom", "stable_coin");
+    }
+
+    #[test]
+    fn withdraw_zero() {
+        let mut deps = deps_with_balances(&[("stable_coin", 10_000)]);
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("lp", &[]),
+            ExecuteMsg::IssueWithdrawal {
+                to: Some(Addr::unchecked("lp_side_account")),
+                to_name: None,
+                amount: Uint128::zero(),
+                capital_denom: None,
+            },
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("must be positive"));
+    }
+
+    fn with_withdrawal_fee(state: &mut State) {
+        state.withdrawal_fee_bps = Some(25);
+        state.fee_recipient = Some(Addr::unchecked("marketpalace"));
+    }
+
+    #[test]
+    fn withdraw_with_fee() {
+        let mut deps = deps_with_balances(&[("stable_coin", 20_000)]);
+        let mut state = State::test_default();
+        with_withdrawal_fee(&mut state);
+        state_storage(&mut deps.storage).save(&state).unwrap();
+
+        // 0.25% of 10_001 is 25.0025, rounded down in the lp's favor
+        let res = withdraw_to(&mut deps, mock_env(), "lp_side_account", 10_001).unwrap();
+        assert_eq!(2, res.messages.len());
+        let (to_address, amount) = send_msg(msg_at_index(&res, 0));
+        assert_eq!("lp_side_account", to_address);
+        assert_eq!(&coins(9_976, "stable_coin"), amount);
+        let (to_address, amount) = send_msg(msg_at_index(&res, 1));
+        assert_eq!("marketpalace", to_address);
+        assert_eq!(&coins(25, "stable_coin"), amount);
+        assert_attr(&res, "amount", "10001");
+        assert_attr(&res, "withdrawal_fee", "25");
+        assert_attr(&res, "fee_recipient", "marketpalace");
+        assert_attr(&res, "net_amount", "9976");
+
+        // a fee that rounds to nothing isn't sent
+        let res = withdraw_to(&mut deps, mock_env(), "lp_side_account", 399).unwrap();
+        assert_eq!(1, res.messages.len());
+        assert_attr(&res, "withdrawal_fee", "0");
+        assert_attr(&res, "net_amount", "399");
+    }
+
+    #[test]
+    fn withdraw_without_fee() {
+        let mut deps = deps_with_balances(&[("stable_coin", 20_000)]);
+        let mut state = State::test_default();
+        state.withdrawal_fee_bps = Some(0);
+        state.fee_recipient = Some(Addr::unchecked("marketpalace"));
+        state_storage(&mut deps.storage).save(&state).unwrap();
+
+        let res = withdraw_to(&mut deps, mock_env(), "lp_side_account", 10_000).unwrap();
+        assert_eq!(1, res.messages.len());
+        assert_eq!(
+            &coins(10_000, "stable_coin"),
+            send_msg(msg_at_index(&res, 0)).1
+        );
+        assert_no_attr(&res, "withdrawal_fee");
+        assert_no_attr(&res, "fee_recipient");
+    }
+
+    #[test]
+    fn withdraw_with_fee_restricted_marker() {
+        let mut deps = restricted_capital_coin_deps(Some(with_withdrawal_fee));
+        load_markers(&mut deps.querier);
+        load_attributes(&mut deps.querier, &[("lp_side_account", "capital.test")]);
+
+        let err = withdraw_to(&mut deps, mock_env(), "lp_side_account", 10_000).unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("marketpalace does not have required attribute of capital.test"));
+
+        let mut deps = restricted_capital_coin_deps(Some(|state| {
+            with_withdrawal_fee(state);
+            state.required_capital_attributes = vec![];
+        }));
+        load_markers(&mut deps.querier);
+        let res = withdraw_to(&mut deps, mock_env(), "lp_side_account", 10_000).unwrap();
+        assert_eq!(
+            &MarkerMsgParams::TransferMarkerCoins {
+                coin: coin(9_975, "restricted_capital_coin"),
+                to: Addr::unchecked("lp_side_account"),
+                from: Addr::unchecked(MOCK_CONTRACT_ADDR),
+            },
+            marker_transfer_msg(msg_at_index(&res, 0)),
+        );
+        assert_eq!(
+            &MarkerMsgParams::TransferMarkerCoins {
+                coin: coin(25, "restricted_capital_coin"),
+                to: Addr::unchecked("marketpalace"),
+                from: Addr::unchecked(MOCK_CONTRACT_ADDR),
+            },
+            marker_transfer_msg(msg_at_index(&res, 1)),
+        );
+    }
+
+    fn withdraw_to_name(
+        deps: &mut MockDeps,
+        to: Option<&str>,
+        to_name: Option<&str>,
+    ) -> ContractResponse {
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("lp", &[]),
+            ExecuteMsg::IssueWithdrawal {
+                to: to.map(Addr::unchecked),
+                to_name: to_name.map(String::from),
+                amount: Uint128::new(10_000),
+                capital_denom: None,
+            },
+        )
+    }
+
+    #[test]
+    fn withdraw_to_name_success() {
+        let mut deps = deps_with_balances(&[("stable_coin", 10_000)]);
+        deps.querier
+            .with_names(&[("treasury.acme.pb", "treasury_account", false)]);
+
+        let res = withdraw_to_name(&mut deps, None, Some("treasury.acme.pb")).unwrap();
+        let (to_address, coins) = send_msg(msg_at_index(&res, 0));
+        assert_eq!("treasury_account", to_address);
+        assert_eq!(10_000, coins[0].amount.u128());
+        assert_attr(&res, "to", "treasury_account");
+        assert_attr(&res, "to_name", "treasury.acme.pb");
+    }
+
+    #[test]
+    fn withdraw_to_unresolvable_name() {
+        let mut deps = deps_with_balances(&[("stable_coin", 10_000)]);
+        deps.querier
+            .with_names(&[("treasury.acme.pb", "treasury_account", false)]);
+
+        let err = withdraw_to_name(&mut deps, None, Some("ops.acme.pb")).unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("unable to resolve name ops.acme.pb"));
+    }
+
+    #[test]
+    fn withdraw_to_name_checks_attributes() {
+        let mut deps = restricted_capital_coin_deps(None);
+        load_markers(&mut deps.querier);
+        deps.querier
+            .with_names(&[("treasury.acme.pb", "treasury_account", false)]);
+
+        let err = withdraw_to_name(&mut deps, None, Some("treasury.acme.pb")).unwrap_err();
+        assert!(err.to_string().contains("treasury_account does not have"));
+    }
+
+    #[test]
+    fn withdraw_needs_exactly_one_recipient() {
+        let mut deps = deps_with_balances(&[("stable_coin", 10_000)]);
+        deps.querier
+            .with_names(&[("treasury.acme.pb", "treasury_account", false)]);
+
+        let err = withdraw_to_name(&mut deps, Some("lp_side_account"), Some("treasury.acme.pb"))
+            .unwrap_err();
+        assert!(err.to_string().contains("not both"));
+        let err = withdraw_to_name(&mut deps, None, None).unwrap_err();
+        assert!(err.to_string().contains("either to or to_name"));
+    }
+
+    fn set_allowance(deps: &mut MockDeps, to: &str, amount: u64) -> ContractResponse {
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("lp", &[]),
+            ExecuteMsg::SetWithdrawalAllowance {
+                to: Addr::unchecked(to),
+                amount: Uint128::from(amount),
+                capital_denom: None,
+                period_seconds: 86_400,
+            },
+        )
+    }
+
+    fn set_coin_allowance(deps: &mut MockDeps, to: &str, amount: Coin) -> ContractResponse {
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("lp", &[]),
+            ExecuteMsg::SetWithdrawalAllowance {
+                to: Addr::unchecked(to),
+                amount: amount.amount,
+                capital_denom: Some(amount.denom),
+                period_seconds: 86_400,
+            },
+        )
+    }
+
+    fn withdraw_to(deps: &mut MockDeps, env: Env, to: &str, amount: u64) -> ContractResponse {
+        execute(
+            deps.as_mut(),
+            env,
+            mock_info("lp", &[]),
+            ExecuteMsg::IssueWithdrawal {
+                to: Some(Addr::unchecked(to)),
+                to_name: None,
+                amount: Uint128::from(amount),
+                capital_denom: None,
+            },
+        )
+    }
+
+    fn with_approval_threshold(deps: &mut MockDeps) {
+        let mut state = state_storage_read(&deps.storage).load().unwrap();
+        state.withdrawal_approval_thresholds = state
+            .like_capital_denoms
+            .iter()
+            .map(|denom| coin(10_000, denom))
+            .collect();
+        state_storage(&mut deps.storage).save(&state).unwrap();
+    }
+
+    fn approve_withdrawal(deps: &mut MockDeps, to: &str, amount: u64) -> ContractResponse {
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("admin", &[]),
+            ExecuteMsg::ApproveWithdrawal {
+                to: Addr::unchecked(to),
+                amount: Uint128::from(amount),
+                capital_denom: None,
+                ttl_seconds: Some(3_600),
+                expires_at: None,
+            },
+        )
+    }
+
+    fn approve_coin_withdrawal(deps: &mut MockDeps, to: &str, amount: Coin) -> ContractResponse {
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("admin", &[]),
+            ExecuteMsg::ApproveWithdrawal {
+                to: Addr::unchecked(to),
+                amount: amount.amount,
+                capital_denom: Some(amount.denom),
+                ttl_seconds: Some(3_600),
+                expires_at: None,
+            },
+        )
+    }
+
+    fn env_after(seconds: u64) -> Env {
+        let mut env = mock_env();
+        env.block.time = env.block.time.plus_seconds(seconds);
+        env
+    }
+
+    #[test]
+    fn withdraw_above_threshold_requires_approval() {
+        let mut deps = deps_with_balances(&[("stable_coin", 100_000)]);
+        with_approval_threshold(&mut deps);
+
+        withdraw_to(&mut deps, mock_env(), "ops", 10_000).unwrap();
+        let err = withdraw_to(&mut deps, mock_env(), "ops", 20_000).unwrap_err();
+        assert!(matches!(
+            err,
+            ContractError::WithdrawalApprovalRequired { .. }
+        ));
+
+        approve_withdrawal(&mut deps, "ops", 20_000).unwrap();
+        let err = withdraw_to(&mut deps, mock_env(), "ops", 25_000).unwrap_err();
+        assert!(matches!(
+            err,
+            ContractError::WithdrawalApprovalRequired { .. }
+        ));
+        let res = withdraw_to(&mut deps, mock_env(), "ops", 20_000).unwrap();
+        assert_attr(&res, "approved_by", "admin");
+
+        // an approval covers a single withdrawal
+        withdraw_to(&mut deps, mock_env(), "ops", 20_000).unwrap_err();
+    }
+
+    #[test]
+    fn withdrawal_approval_expires() {
+        let mut deps = deps_with_balances(&[("stable_coin", 100_000)]);
+        with_approval_threshold(&mut deps);
+        approve_withdrawal(&mut deps, "ops", 20_000).unwrap();
+
+        withdraw_to(&mut deps, env_after(3_599), "ops", 20_000).unwrap();
+
+        approve_withdrawal(&mut deps, "ops", 20_000).unwrap();
+        let err = withdraw_to(&mut deps, env_after(3_600), "ops", 20_000).unwrap_err();
+        assert!(matches!(
+            err,
+            ContractError::WithdrawalApprovalExpired { expires_at, .. }
+                if expires_at == mock_env().block.time.plus_seconds(3_600)
+        ));
+
+        // expired approvals are pruned the next time approvals are written
+        execute(
+            deps.as_mut(),
+            env_after(3_600),
+            mock_info("admin", &[]),
+            ExecuteMsg::ApproveWithdrawal {
+                to: Addr::unchecked("treasury"),
+                amount: Uint128::new(50_000),
+                capital_denom: None,
+                ttl_seconds: None,
+                expires_at: Some(mock_env().block.time.plus_seconds(7_200)),
+            },
+        )
+        .unwrap();
+        let approvals = withdrawal_approvals_storage_read(&deps.storage)
+            .load()
+            .unwrap();
+        assert_eq!(1, approvals.len());
+        assert_eq!(Addr::unchecked("treasury"), approvals[0].to);
+    }
+
+    #[test]
+    fn approve_withdrawal_validation() {
+        let mut deps = default_deps(None);
+        let approve = |deps: &mut MockDeps,
+                       sender: &str,
+                       ttl_seconds: Option<u64>,
+                       expires_at: Option<Timestamp>| {
+            execute(
+                deps.as_mut(),
+                mock_env(),
+                mock_info(sender, &[]),
+                ExecuteMsg::ApproveWithdrawal {
+                    to: Addr::unchecked("ops"),
+                    amount: Uint128::new(20_000),
+                    capital_denom: None,
+                    ttl_seconds,
+                    expires_at,
+                },
+            )
+        };
+
+        let err = approve(&mut deps, "lp", Some(3_600), None).unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("only admin can approve withdrawals"));
+        let err = approve(&mut deps, "admin", None, None).unwrap_err();
+        assert!(err.to_string().contains("exactly one of ttl_seconds"));
+        let err = approve(
+            &mut deps,
+            "admin",
+            Some(3_600),
+            Some(mock_env().block.time.plus_seconds(3_600)),
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("exactly one of ttl_seconds"));
+        let err = approve(&mut deps, "admin", None, Some(mock_env().block.time)).unwrap_err();
+        assert!(err.to_string().contains("must expire in the future"));
+    }
+
+    #[test]
+    fn revoke_withdrawal_approval() {
+        let mut deps = deps_with_balances(&[("stable_coin", 100_000)]);
+        with_approval_threshold(&mut deps);
+        approve_withdrawal(&mut deps, "ops", 20_000).unwrap();
+        approve_withdrawal(&mut deps, "ops", 30_000).unwrap();
+        approve_withdrawal(&mut deps, "treasury", 20_000).unwrap();
+        let revoke = |deps: &mut MockDeps, sender: &str, amount: Option<u128>| {
+            execute(
+                deps.as_mut(),
+                mock_env(),
+                mock_info(sender, &[]),
+                ExecuteMsg::RevokeWithdrawalApproval {
+                    to: Addr::unchecked("ops"),
+                    amount: amount.map(Uint128::new),
+                    capital_denom: None,
+                },
+            )
+        };
+
+        let err = revoke(&mut deps, "lp", None).unwrap_err();
+        assert!(err.to_string().contains("only admin can revoke"));
+
+        let res = revoke(&mut deps, "admin", Some(20_000)).unwrap();
+        assert_attr(&res, "revoked", "1");
+        withdraw_to(&mut deps, mock_env(), "ops", 20_000).unwrap_err();
+
+        let res = revoke(&mut deps, "admin", None).unwrap();
+        assert_attr(&res, "revoked", "1");
+        withdraw_to(&mut deps, mock_env(), "ops", 30_000).unwrap_err();
+        let err = revoke(&mut deps, "admin", None).unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("no outstanding withdrawal approval for ops"));
+
+        withdraw_to(&mut deps, mock_env(), "treasury", 20_000).unwrap();
+    }
+
+    #[test]
+    fn revoke_withdrawal_approval_by_denom() {
+        let mut deps = default_deps(Some(|state| {
+            state.like_capital_denoms.push(String::from("other_coin"))
+        }));
+        approve_coin_withdrawal(&mut deps, "ops", coin(20_000, "stable_coin")).unwrap();
+        approve_coin_withdrawal(&mut deps, "ops", coin(20_000, "other_coin")).unwrap();
+
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("admin", &[]),
+            ExecuteMsg::RevokeWithdrawalApproval {
+                to: Addr::unchecked("ops"),
+                amount: Some(Uint128::new(20_000)),
+                capital_denom: Some(String::from("other_coin")),
+            },
+        )
+        .unwrap();
+        assert_attr(&res, "revoked", "1");
+        let approvals = withdrawal_approvals_storage_read(&deps.storage)
+            .load()
+            .unwrap();
+        assert_eq!(1, approvals.len());
+        assert_eq!("stable_coin", approvals[0].denom);
+    }
+
+    #[test]
+    fn withdraw_allowance_exhausted() {
+        let mut deps = deps_with_balances(&[("stable_coin", 100_000)]);
+        set_allowance(&mut deps, "ops", 50_000).unwrap();
+
+        withdraw_to(&mut deps, mock_env(), "ops", 20_000).unwrap();
+        withdraw_to(&mut deps, mock_env(), "ops", 30_000).unwrap();
+        match withdraw_to(&mut deps, mock_env(), "ops", 1).unwrap_err() {
+            ContractError::WithdrawalAllowanceExceeded { to, remaining } => {
+                assert_eq!("ops", to);
+                assert_eq!(Uint128::zero(), remaining);
+            }
+            err => panic!("unexpected error: {}", err),
+        }
+
+        // destinations without an allowance are unaffected
+        withdraw_to(&mut deps, mock_env(), "lp_side_account", 50_000).unwrap();
+    }
+
+    #[test]
+    fn withdraw_allowance_overage() {
+        let mut deps = deps_with_balances(&[("stable_coin", 100_000)]);
+        set_allowance(&mut deps, "ops", 50_000).unwrap();
+        withdraw_to(&mut deps, mock_env(), "ops", 45_000).unwrap();
+
+        let err = withdraw_to(&mut deps, mock_env(), "ops", 10_000).unwrap_err();
+        assert!(err.to_string().contains("remaining allowance is 5000"));
+    }
+
+    #[test]
+    fn withdraw_allowance_window_reset() {
+        let mut deps = deps_with_balances(&[("stable_coin", 100_000)]);
+        set_allowance(&mut deps, "ops", 50_000).unwrap();
+        withdraw_to(&mut deps, mock_env(), "ops", 50_000).unwrap();
+
+        let mut env = mock_env();
+        env.block.time = env.block.time.plus_seconds(86_399);
+        assert!(withdraw_to(&mut deps, env.clone(), "ops", 1).is_err());
+
+        env.block.time = env.block.time.plus_seconds(1);
+        withdraw_to(&mut deps, env, "ops", 50_000).unwrap();
+    }
+
+    #[test]
+    fn set_withdrawal_allowance_bad_actor() {
+        let res = execute(
+            default_deps(None).as_mut(),
+            mock_env(),
+            mock_info("admin", &[]),
+            ExecuteMsg::SetWithdrawalAllowance {
+                to: Addr::unchecked("ops"),
+                amount: Uint128::new(50_000),
+                capital_denom: None,
+                period_seconds: 86_400,
+            },
+        );
+        assert!(res.is_err());
+    }
+
+    const IBC_DENOM: &str = "ibc/27394FB092D2ECCD56123C74F36E4C1F926001CEADA9CA97EA622B25F41E5EB2";
+
+    #[test]
+    fn withdraw_ibc_denom() {
+        let mut deps = restricted_capital_coin_deps(Some(|state| {
+            state.like_capital_denoms.push(String::from(IBC_DENOM))
+        }));
+        set_balance(&mut deps, IBC_DENOM, 5_000);
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("lp", &[]),
+            ExecuteMsg::IssueWithdrawal {
+                to: Some(Addr::unchecked("lp_side_account")),
+                to_name: None,
+                amount: Uint128::new(5_000),
+                capital_denom: Some(String::from(IBC_DENOM)),
+            },
+        )
+        .unwrap();
+
+        // verify ibc capital is bank sent without any attribute check
+        let (to_address, coins) = send_msg(msg_at_index(&res, 0));
+        assert_eq!("lp_side_account", to_address);
+        assert_eq!(&coin(5_000, IBC_DENOM), coins.first().unwrap());
+    }
+
+    #[test]
+    fn complete_asset_exchange_ibc_and_restricted_denoms() {
+        let mut deps = restricted_capital_coin_deps(Some(|state| {
+            state.like_capital_denoms.push(String::from(IBC_DENOM))
+        }));
+        load_attributes(&mut deps.querier, &[("raise_1", "capital.test")]);
+        load_markers(&mut deps.querier);
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("lp", &[]),
+            ExecuteMsg::CompleteAssetExchange {
+                exchanges: vec![
+                    ExchangeBuilder::new()
+                        .capital(-1_000)
+                        .denom(IBC_DENOM)
+                        .build(),
+                    ExchangeBuilder::new()
+                        .capital(-2_000)
+                        .denom("restricted_capital_coin")
+                        .build(),
+                ],
+                to: None,
+                memo: None,
+                override_available_date: false,
+                raise: None,
+            },
+        )
+        .unwrap();
+
+        assert_eq!(
+            &MarkerMsgParams::TransferMarkerCoins {
+                coin: coin(2_000, "restricted_capital_coin"),
+                to: Addr::unchecked("raise_1"),
+                from: Addr::unchecked(MOCK_CONTRACT_ADDR),
+            },
+            marker_transfer_msg(msg_at_index(&res, 0)),
+        );
+        let (_, _, funds) = execute_args::<RaiseExecuteMsg>(msg_at_index(&res, 1));
+        assert_eq!(&vec![coin(1_000, IBC_DENOM)], funds);
+    }
+
+    #[test]
+    fn complete_asset_exchange_per_exchange_recipients() {
+        let mut deps = restricted_capital_coin_deps(Some(|state| {
+            state.like_capital_denoms.push(String::from("capital_coin"))
+        }));
+        load_attributes(&mut deps.querier, &[("side_a", "capital.test")]);
+        load_markers(&mut deps.querier);
+        let exchanges = vec![
+            ExchangeBuilder::new()
+                .capital(1_000)
+                .denom("restricted_capital_coin")
+                .to("side_a")
+                .build(),
+            ExchangeBuilder::new()
+                .capital(500)
+                .denom("capital_coin")
+                .to("side_b")
+                .build(),
+        ];
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("lp", &[]),
+            ExecuteMsg::CompleteAssetExchange {
+                exchanges: exchanges.clone(),
+                to: Some(Addr::unchecked("lp_side_account")),
+                memo: None,
+                override_available_date: false,
+                raise: None,
+            },
+        )
+        .unwrap();
+
+        // the raise routes each payout to its own recipient
+        assert_eq!(1, res.messages.len());
+        let (_, msg, funds) = execute_args::<RaiseExecuteMsg>(msg_at_index(&res, 0));
+        assert_eq!(
+            RaiseExecuteMsg::CompleteAssetExchange {
+                exchanges,
+                to: Some(Addr::unchecked("lp_side_account")),
+                memo: None,
+            },
+            msg
+        );
+        assert!(funds.is_empty());
+    }
+
+    fn raise_recipient(
+        deps: &mut MockDeps,
+        exchange: AssetExchange,
+        to: Option<&str>,
+    ) -> Option<Addr> {
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("lp", &[]),
+            ExecuteMsg::CompleteAssetExchange {
+                exchanges: vec![exchange],
+                to: to.map(Addr::unchecked),
+                memo: None,
+                override_available_date: false,
+                raise: None,
+            },
+        )
+        .unwrap();
+        match execute_args::<RaiseExecuteMsg>(&res.messages.last().unwrap().msg).1 {
+            RaiseExecuteMsg::CompleteAssetExchange { to, .. } => to,
+            msg => panic!("unexpected raise message: {:?}", msg),
+        }
+    }
+
+    #[test]
+    fn complete_asset_exchange_default_distribution_address() {
+        let mut deps = default_deps(Some(|state| {
+            state.default_distribution_address = Some(Addr::unchecked("lp_side_account"))
+        }));
+        let payout = || ExchangeBuilder::new().capital(1_000).build();
+
+        assert_eq!(
+            Some(Addr::unchecked("lp_side_account")),
+            raise_recipient(&mut deps, payout(), None)
+        );
+        assert_eq!(
+            Some(Addr::unchecked("other_account")),
+            raise_recipient(&mut deps, payout(), Some("other_account"))
+        );
+
+        // nothing is paid out, so the raise isn't given a recipient
+        assert_eq!(
+            None,
+            raise_recipient(
+                &mut deps,
+                ExchangeBuilder::new().capital(-1_000).build(),
+                None
+            )
+        );
+
+        // without a default the raise still decides
+        assert_eq!(
+            None,
+            raise_recipient(&mut default_deps(None), payout(), None)
+        );
+    }
+
+    #[test]
+    fn complete_asset_exchange_default_distribution_address_restricted() {
+        let mut deps = restricted_capital_coin_deps(Some(|state| {
+            state.default_distribution_address = Some(Addr::unchecked("side_b"))
+        }));
+        load_markers(&mut deps.querier);
+
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("lp", &[]),
+            ExecuteMsg::CompleteAssetExchange {
+                exchanges: vec![ExchangeBuilder::new().capital(1_000).build()],
+                to: None,
+                memo: None,
+                override_available_date: false,
+                raise: None,
+            },
+        )
+        .unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("side_b does not have required attribute of capital.test"));
+
+        load_attributes(&mut deps.querier, &[("side_b", "capital.test")]);
+        assert_eq!(
+            Some(Addr::unchecked("side_b")),
+            raise_recipient(
+                &mut deps,
+                ExchangeBuilder::new().capital(1_000).build(),
+                None
+            )
+        );
+    }
+
+    #[test]
+    fn complete_asset_exchange_beneficiary() {
+        let payout = || ExchangeBuilder::new().capital(1_000).build();
+        let mut deps = default_deps(Some(|state| {
+            state.beneficiary = Some(Addr::unchecked("custodian"));
+            state.default_distribution_address = Some(Addr::unchecked("lp_side_account"));
+        }));
+        assert_eq!(
+            Some(Addr::unchecked("custodian")),
+            raise_recipient(&mut deps, payout(), None)
+        );
+        assert_eq!(
+            Some(Addr::unchecked("other_account")),
+            raise_recipient(&mut deps, payout(), Some("other_account"))
+        );
+        assert_eq!(
+            None,
+            raise_recipient(
+                &mut deps,
+                ExchangeBuilder::new().capital(-1_000).build(),
+                None
+            )
+        );
+    }
+
+    #[test]
+    fn set_beneficiary() {
+        let mut deps = default_deps(None);
+        let set = |beneficiary: Option<&str>| ExecuteMsg::SetBeneficiary {
+            beneficiary: beneficiary.map(Addr::unchecked),
+        };
+
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("lp", &[]),
+            set(Some("custodian")),
+        )
+        .unwrap();
+        assert_attr(&res, "action", "set_beneficiary");
+        assert_attr(&res, "old_beneficiary", "");
+        assert_attr(&res, "beneficiary", "custodian");
+        assert_eq!(
+            Some(Addr::unchecked("custodian")),
+            state_storage_read(&deps.storage)
+                .load()
+                .unwrap()
+                .beneficiary
+        );
+
+        assert!(execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("admin", &[]),
+            set(None)
+        )
+        .is_err());
+
+        let res = execute(deps.as_mut(), mock_env(), mock_info("lp", &[]), set(None)).unwrap();
+        assert_attr(&res, "old_beneficiary", "custodian");
+        assert_attr(&res, "beneficiary", "");
+        assert_eq!(
+            None,
+            state_storage_read(&deps.storage)
+                .load()
+                .unwrap()
+                .beneficiary
+        );
+    }
+
+    #[test]
+    fn update_default_distribution_address() {
+        let mut deps = default_deps(None);
+        let update = |address: Option<&str>| ExecuteMsg::UpdateDefaultDistributionAddress {
+            default_distribution_address: address.map(Addr::unchecked),
+        };
+
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("lp", &[]),
+            update(Some("lp_side_account")),
+        )
+        .unwrap();
+        assert_attr(&res, "old_default_distribution_address", "");
+        assert_attr(&res, "default_distribution_address", "lp_side_account");
+        assert_eq!(
+            Some(Addr::unchecked("lp_side_account")),
+            state_storage_read(&deps.storage)
+                .load()
+                .unwrap()
+                .default_distribution_address
+        );
+
+        assert!(execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("admin", &[]),
+            update(None)
+        )
+        .is_err());
+
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("lp", &[]),
+            update(Some("custodian")),
+        )
+        .unwrap();
+        assert_attr(&res, "old_default_distribution_address", "lp_side_account");
+        assert_attr(&res, "default_distribution_address", "custodian");
+
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("lp", &[]),
+            update(None),
+        )
+        .unwrap();
+        assert_attr(&res, "old_default_distribution_address", "custodian");
+        assert_attr(&res, "default_distribution_address", "");
+        assert_eq!(
+            None,
+            state_storage_read(&deps.storage)
+                .load()
+                .unwrap()
+                .default_distribution_address
+        );
+    }
+
+    fn update_metadata(
+        deps: &mut MockDeps,
+        sender: &str,
+        metadata: Option<SubscriptionMetadata>,
+    ) -> ContractResponse {
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(sender, &[]),
+            ExecuteMsg::UpdateMetadata { metadata },
+        )
+    }
+
+    fn queried_metadata(deps: &MockDeps) -> Option<SubscriptionMetadata> {
+        let res: MetadataResponse =
+            from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::GetMetadata {}).unwrap())
+                .unwrap();
+        res.metadata
+    }
+
+    #[test]
+    fn update_and_clear_metadata() {
+        let mut deps = default_deps(None);
+        assert_eq!(None, queried_metadata(&deps));
+        let metadata = SubscriptionMetadata {
+            name: String::from("Fund III / Acme Pension"),
+            description: None,
+            external_id: Some(String::from("ACME-0042")),
+        };
+
+        let res = update_metadata(&mut deps, "admin", Some(metadata.clone())).unwrap();
+        assert_attr(&res, "name", "Fund III / Acme Pension");
+        assert_eq!(Some(metadata.clone()), queried_metadata(&deps));
+
+        assert!(update_metadata(&mut deps, "lp", None).is_err());
+        assert_eq!(Some(metadata), queried_metadata(&deps));
+
+        update_metadata(&mut deps, "admin", None).unwrap();
+        assert_eq!(None, queried_metadata(&deps));
+    }
+
+    fn queried_capital_denoms(deps: &MockDeps) -> Vec<CapitalDenom> {
+        let res: CapitalDenomsResponse =
+            from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::GetCapitalDenoms {}).unwrap())
+                .unwrap();
+        res.capital_denoms
+    }
+
+    #[test]
+    fn capital_denoms_single() {
+        let deps = default_deps(None);
+        assert_eq!(
+            vec![CapitalDenom {
+                denom: String::from("stable_coin"),
+                required_attributes: vec![],
+                is_default: true,
+            }],
+            queried_capital_denoms(&deps)
+        );
+    }
+
+    #[test]
+    fn capital_denoms_multiple() {
+        let deps = default_deps(Some(|state| {
+            state.like_capital_denoms = vec![String::from("stable_coin"), String::from("usdf")]
+        }));
+        assert_eq!(
+            vec![
+                CapitalDenom {
+                    denom: String::from("stable_coin"),
+                    required_attributes: vec![],
+                    is_default: false,
+                },
+                CapitalDenom {
+                    denom: String::from("usdf"),
+                    required_attributes: vec![],
+                    is_default: false,
+                },
+            ],
+            queried_capital_denoms(&deps)
+        );
+    }
+
+    #[test]
+    fn capital_denoms_restricted() {
+        let deps = default_deps(Some(|state| {
+            state.like_capital_denoms = vec![String::from("stable_coin"), String::from("usdf")];
+            state.required_capital_attributes = vec![CapitalDenomRequirement {
+                capital_denom: String::from("usdf"),
+                required_attributes: vec![String::from("capital.test")],
+                enforce_attribute_on_bank_send: false,
+            }];
+        }));
+        let capital_denoms = queried_capital_denoms(&deps);
+        assert_eq!(Vec::<String>::new(), capital_denoms[0].required_attributes);
+        assert_eq!(
+            vec![String::from("capital.test")],
+            capital_denoms[1].required_attributes
+        );
+    }
+
+    fn reconcile_deps(commitment_held: u128) -> MockDeps {
+        let mut deps = deps_with_balances(&[
+            ("raise_1.commitment", commitment_held),
+            ("raise_1.investment", 500),
+        ]);
+        let mut state = State::test_default();
+        state.total_commitment_in_shares = 1_000;
+        state_storage(&mut deps.storage).save(&state).unwrap();
+        deps
+    }
+
+    fn reconciled(deps: &MockDeps) -> ReconcileResponse {
+        from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::Reconcile {}).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn reconcile_balanced() {
+        let deps = reconcile_deps(1_000);
+        assert_eq!(
+            ReconcileResponse {
+                balanced: true,
+                denoms: vec![
+                    DenomReconciliation {
+                        denom: String::from("raise_1.commitment"),
+                        held: Uint128::new(1_000),
+                        expected: Some(Uint128::new(1_000)),
+                        delta: Some(Int128::zero()),
+                    },
+                    DenomReconciliation {
+                        denom: String::from("raise_1.investment"),
+                        held: Uint128::new(500),
+                        expected: None,
+                        delta: None,
+                    },
+                ],
+            },
+            reconciled(&deps)
+        );
+    }
+
+    #[test]
+    fn get_version() {
+        let mut deps = default_deps(None);
+        cw2::set_contract_version(&mut deps.storage, CONTRACT_NAME, CONTRACT_VERSION).unwrap();
+
+        let res: VersionResponse =
+            from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::GetVersion {}).unwrap())
+                .unwrap();
+        assert_eq!(CONTRACT_NAME, res.contract);
+        assert_eq!(CONTRACT_VERSION, res.version);
+        assert_eq!(API_VERSION, res.api_version);
+        assert_eq!(FEATURES.to_vec(), res.features);
+    }
+
+    /// Answers smart queries to raise_1 as a raise would, with `answer` given the parsed query.
+    fn with_raise(deps: &mut MockDeps, answer: fn(&RaiseQueryMsg) -> ContractResult<Binary>) {
+        deps.querier.base.update_wasm(move |query| match query {
+            WasmQuery::Smart { contract_addr, msg } if contract_addr == "raise_1" => {
+                SystemResult::Ok(match from_binary(msg) {
+                    Ok(msg) => answer(&msg),
+                    Err(error) => ContractResult::Err(error.to_string()),
+                })
+            }
+            _ => panic!("unexpected wasm query {:?}", query),
+        });
+    }
+
+    #[test]
+    fn get_raise_holdings() {
+        let mut deps = default_deps(None);
+        with_raise(&mut deps, |msg| match msg {
+            RaiseQueryMsg::GetSubscriptionHoldings { subscription } => {
+                assert_eq!(MOCK_CONTRACT_ADDR, subscription.as_str());
+                ContractResult::Ok(
+                    to_binary(&SubscriptionHoldings {
+                        capital_credited: coins(1_000, "stable_coin"),
+                        commitment_outstanding: Uint128::new(10),
+                    })
+                    .unwrap(),
+                )
+            }
+        });
+
+        let res: RaiseHoldingsResponse = from_binary(
+            &query(
+                deps.as_ref(),
+                mock_env(),
+                QueryMsg::GetRaiseHoldings { raise: None },
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(Addr::unchecked("raise_1"), res.raise);
+        assert_eq!(coins(1_000, "stable_coin"), res.holdings.capital_credited);
+        assert_eq!(Uint128::new(10), res.holdings.commitment_outstanding);
+    }
+
+    #[test]
+    fn get_raise_holdings_unsupported() {
+        let mut deps = default_deps(None);
+        // how a raise built before the query fails to parse it
+        with_raise(&mut deps, |_| {
+            ContractResult::Err(String::from(
+                "Error parsing into type raise::msg::QueryMsg: unknown variant `get_subscription_holdings`, expected `get_state`",
+            ))
+        });
+
+        let err = query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::GetRaiseHoldings { raise: None },
+        )
+        .unwrap_err();
+        assert_eq!(
+            "Generic error: raise raise_1 does not support the get_subscription_holdings query",
+            err.to_string()
+        );
+    }
+
+    #[test]
+    fn get_raise_holdings_raise_error() {
+        let mut deps = default_deps(None);
+        with_raise(&mut deps, |_| {
+            ContractResult::Err(String::from("subscription not found"))
+        });
+
+        let err = query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::GetRaiseHoldings { raise: None },
+        )
+        .unwrap_err();
+        assert_eq!(
+            "Generic error: raise raise_1 failed the holdings query: subscription not found",
+            err.to_string()
+        );
+    }
+
+    #[test]
+    fn reconcile_unbalanced() {
+        let mut deps = reconcile_deps(900);
+        let res = reconciled(&deps);
+        assert!(!res.balanced);
+        assert_eq!(Some(Int128::new(-100)), res.denoms[0].delta);
+
+        set_balance(&mut deps, "raise_1.commitment", 1_250);
+        let res = reconciled(&deps);
+        assert!(!res.balanced);
+        assert_eq!(Some(Int128::new(250)), res.denoms[0].delta);
+        // investment isn't tracked, so no balance of it unbalances the response
+        let mut deps = reconcile_deps(1_000);
+        set_balance(&mut deps, "raise_1.investment", 0);
+        assert!(reconciled(&deps).balanced);
+    }
+
+    #[test]
+    fn queries_before_instantiation() {
+        let deps = provwasm_mocks::mock_dependencies(&[]);
+        for msg in [
+            QueryMsg::GetState {},
+            QueryMsg::GetAssetExchangeAuthorizations {
+                include_history: false,
+            },
+            QueryMsg::GetOverdueObligations {},
+            QueryMsg::GetStats {},
+            QueryMsg::GetCapitalDenoms {},
+            QueryMsg::Reconcile {},
+            QueryMsg::SimulateCompleteAssetExchange {
+                exchanges: vec![ExchangeBuilder::new().capital(-1_000).build()],
+                to: None,
+                memo: None,
+                sender: Addr::unchecked("lp"),
+                raise: None,
+            },
+            QueryMsg::SimulateWithdrawal {
+                to: Addr::unchecked("lp"),
+                amount: Uint128::new(1_000),
+                capital_denom: None,
+            },
+        ] {
+            let err = query(deps.as_ref(), mock_env(), msg.clone()).unwrap_err();
+            assert!(
+                err.to_string().contains("state not initialized"),
+                "{:?} failed with {}",
+                msg,
+                err
+            );
+        }
+
+        // nothing recorded yet is an empty answer rather than an error
+        let res: MigrationHistoryResponse = from_binary(
+            &query(deps.as_ref(), mock_env(), QueryMsg::GetMigrationHistory {}).unwrap(),
+        )
+        .unwrap();
+        assert!(res.migrations.is_empty());
+    }
+
+    #[test]
+    fn update_metadata_length_caps() {
+        let mut deps = default_deps(None);
+        let metadata = || SubscriptionMetadata {
+            name: String::from("Fund III"),
+            description: None,
+            external_id: None,
+        };
+
+        for (metadata, error) in [
+            (
+                SubscriptionMetadata {
+                    name: String::new(),
+                    ..metadata()
+                },
+                "metadata name must be between 1 and 64 bytes",
+            ),
+            (
+                SubscriptionMetadata {
+                    description: Some("d".repeat(MAX_METADATA_DESCRIPTION_LENGTH + 1)),
+                    ..metadata()
+                },
+                "metadata description must be between 1 and 512 bytes",
+            ),
+            (
+                SubscriptionMetadata {
+                    external_id: Some("e".repeat(MAX_METADATA_EXTERNAL_ID_LENGTH + 1)),
+                    ..metadata()
+                },
+                "metadata external id must be between 1 and 64 bytes",
+            ),
+        ] {
+            let err = update_metadata(&mut deps, "admin", Some(metadata)).unwrap_err();
+            assert!(err.to_string().contains(error), "{}", err);
+        }
+        assert_eq!(None, queried_metadata(&deps));
+
+        let at_caps = SubscriptionMetadata {
+            name: "n".repeat(MAX_METADATA_NAME_LENGTH),
+            description: Some("d".repeat(MAX_METADATA_DESCRIPTION_LENGTH)),
+            external_id: Some("e".repeat(MAX_METADATA_EXTERNAL_ID_LENGTH)),
+        };
+        update_metadata(&mut deps, "admin", Some(at_caps.clone())).unwrap();
+        assert_eq!(Some(at_caps), queried_metadata(&deps));
+    }
+
+    fn set_lp_note(deps: &mut MockDeps, sender: &str, note: Option<&str>) -> ContractResponse {
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(sender, &[]),
+            ExecuteMsg::SetLpNote {
+                note: note.map(String::from),
+            },
+        )
+    }
+
+    fn queried_lp_note(deps: &MockDeps) -> Option<String> {
+        let res: StateResponse =
+            from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::GetState {}).unwrap()).unwrap();
+        res.state.lp_note
+    }
+
+    #[test]
+    fn set_and_clear_lp_note() {
+        let mut deps = default_deps(None);
+
+        let res = set_lp_note(&mut deps, "lp", Some("custodian acct 7731")).unwrap();
+        assert_eq!(
+            vec![Event::new("lp_note_updated").add_attribute("lp_note", "custodian acct 7731")],
+            res.events
+        );
+        assert_eq!(
+            Some(String::from("custodian acct 7731")),
+            queried_lp_note(&deps)
+        );
+
+        // setting the same note again changes nothing
+        let res = set_lp_note(&mut deps, "lp", Some("custodian acct 7731")).unwrap();
+        assert!(res.events.is_empty());
+
+        let res = set_lp_note(&mut deps, "lp", None).unwrap();
+        assert_eq!(
+            vec![Event::new("lp_note_updated").add_attribute("lp_note", "")],
+            res.events
+        );
+        assert_eq!(None, queried_lp_note(&deps));
+    }
+
+    #[test]
+    fn set_lp_note_invalid() {
+        let mut deps = default_deps(None);
+
+        set_lp_note(&mut deps, "lp", Some(&"n".repeat(MAX_LP_NOTE_LENGTH))).unwrap();
+        let err =
+            set_lp_note(&mut deps, "lp", Some(&"n".repeat(MAX_LP_NOTE_LENGTH + 1))).unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("lp note must be between 1 and 128 bytes"));
+        assert!(set_lp_note(&mut deps, "lp", Some(" ")).is_err());
+        assert!(set_lp_note(&mut deps, "admin", None).is_err());
+        assert_eq!(Some("n".repeat(MAX_LP_NOTE_LENGTH)), queried_lp_note(&deps));
+    }
+
+    #[test]
+    fn complete_asset_exchange_recipient_missing_attribute() {
+        let mut deps = restricted_capital_coin_deps(None);
+        load_attributes(&mut deps.querier, &[("side_a", "capital.test")]);
+        load_markers(&mut deps.querier);
+
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("lp", &[]),
+            ExecuteMsg::CompleteAssetExchange {
+                exchanges: vec![
+                    ExchangeBuilder::new().capital(1_000).to("side_a").build(),
+                    ExchangeBuilder::new().capital(1_000).to("side_b").build(),
+                ],
+                to: None,
+                memo: None,
+                override_available_date: false,
+                raise: None,
+            },
+        )
+        .unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("side_b does not have required attribute of capital.test"));
+
+        // an exchange without its own recipient falls back to the message's
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("lp", &[]),
+            ExecuteMsg::CompleteAssetExchange {
+                exchanges: vec![
+                    ExchangeBuilder::new().capital(1_000).to("side_a").build(),
+                    ExchangeBuilder::new().capital(1_000).build(),
+                ],
+                to: Some(Addr::unchecked("side_b")),
+                memo: None,
+                override_available_date: false,
+                raise: None,
+            },
+        )
+        .unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("side_b does not have required attribute of capital.test"));
+    }
+
+    #[test]
+    fn complete_asset_exchange_recipient_any_of_attributes() {
+        let mut deps = restricted_capital_coin_deps(Some(|state| {
+            state.required_capital_attributes[0].required_attributes =
+                vec![String::from("capital.us"), String::from("capital.intl")]
+        }));
+        load_attributes(&mut deps.querier, &[("intl_account", "capital.intl")]);
+        load_markers(&mut deps.querier);
+        let complete = |deps: &mut MockDeps, to: &str| {
+            execute(
+                deps.as_mut(),
+                mock_env(),
+                mock_info("lp", &[]),
+                ExecuteMsg::CompleteAssetExchange {
+                    exchanges: vec![ExchangeBuilder::new().capital(1_000).build()],
+                    to: Some(Addr::unchecked(to)),
+                    memo: None,
+                    override_available_date: false,
+                    raise: None,
+                },
+            )
+        };
+
+        complete(&mut deps, "intl_account").unwrap();
+        let err = complete(&mut deps, "side_b").unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("side_b does not have required attribute of capital.us or capital.intl"));
+    }
+
+    #[test]
+    fn complete_asset_exchange_recipient_is_part_of_match() {
+        let mut deps = default_deps(Some(|state| {
+            state.operators = vec![Addr::unchecked("operator")]
+        }));
+        load_markers(&mut deps.querier);
+        let exchange = ExchangeBuilder::new().capital(1_000).to("side_a");
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("lp", &[]),
+            ExecuteMsg::AuthorizeAssetExchange {
+                exchanges: vec![exchange.clone().build()],
+                to: None,
+                memo: None,
+                idempotency_key: None,
+                expires_at: None,
+                memo_hash: None,
+                raise: None,
+            },
+        )
+        .unwrap();
+
+        let complete = |deps: &mut MockDeps, exchange: AssetExchange| {
+            execute(
+                deps.as_mut(),
+                mock_env(),
+                mock_info("operator", &[]),
+                ExecuteMsg::CompleteAssetExchange {
+                    exchanges: vec![exchange],
+                    to: None,
+                    memo: None,
+                    override_available_date: false,
+                    raise: None,
+                },
+            )
+        };
+        assert!(matches!(
+            complete(
+                &mut deps,
+                ExchangeBuilder::new().capital(1_000).to("side_b").build()
+            ),
+            Err(ContractError::AuthorizationNotFound { .. })
+        ));
+        assert!(matches!(
+            complete(&mut deps, ExchangeBuilder::new().capital(1_000).build()),
+            Err(ContractError::AuthorizationNotFound { .. })
+        ));
+        complete(&mut deps, exchange.build()).unwrap();
+        assert!(queried_authorizations(&deps).is_empty());
+    }
+
+    #[test]
+    fn update_required_capital_attribute_ibc_denom() {
+        let mut deps = restricted_capital_coin_deps(Some(|state| {
+            state.like_capital_denoms.push(String::from(IBC_DENOM))
+        }));
+        load_markers(&mut deps.querier);
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("admin", &[]),
+            ExecuteMsg::UpdateRequiredCapitalAttribute {
+                required_capital_attributes: vec![CapitalDenomRequirement {
+                    capital_denom: String::from(IBC_DENOM),
+                    required_attributes: vec![String::from("capital.test")],
+                    enforce_attribute_on_bank_send: false,
+                }],
+            },
+        );
+        assert!(res.is_err());
+    }
+
+    #[test]
+    fn withdraw_capital_denom() {
+        let mut deps = deps_with_balances(&[("stable_coin", 10_000)]);
+        set_balance(&mut deps, "capital_coin", 5_000);
+        let mut state = state_storage_read(&deps.storage).load().unwrap();
+        state.like_capital_denoms.push(String::from("capital_coin"));
+        state_storage(&mut deps.storage).save(&state).unwrap();
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("lp", &[]),
+            ExecuteMsg::IssueWithdrawal {
+                to: Some(Addr::unchecked("lp_side_account")),
+                to_name: None,
+                amount: Uint128::new(5_000),
+                capital_denom: Some(String::from("capital_coin")),
+            },
+        )
+        .unwrap();
+
+        // verify send message sent in requested denom
+        let (_, coins) = send_msg(msg_at_index(&res, 0));
+        assert_eq!("capital_coin", coins.first().unwrap().denom);
+        assert_eq!(Some("capital_coin"), attr_value(&res, "capital_denom"));
+    }
+
+    #[test]
+    fn withdraw_restricted_marker() {
+        let mut deps = restricted_capital_coin_deps(None);
+        load_attributes(&mut deps.querier, &[("lp_side_account", "capital.test")]);
+        load_markers(&mut deps.querier);
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("lp", &[]),
+            ExecuteMsg::IssueWithdrawal {
+                to: Some(Addr::unchecked("lp_side_account")),
+                to_name: None,
+                amount: Uint128::new(10_000),
+                capital_denom: None,
+            },
+        )
+        .unwrap();
+
+        // verify send message sent
+        assert_eq!(1, res.messages.len());
+        assert_eq!(
+            &MarkerMsgParams::TransferMarkerCoins {
+                coin: coin(10_000, "restricted_capital_coin"),
+                to: Addr::unchecked("lp_side_account"),
+                from: Addr::unchecked(MOCK_CONTRACT_ADDR),
+            },
+            marker_transfer_msg(msg_at_index(&res, 0)),
+        );
+        assert_attr(&res, "capital_denom", "restricted_capital_coin");
+    }
+
+    #[test]
+    fn withdraw_restricted_marker_without_attribute_requirement() {
+        let mut deps =
+            restricted_capital_coin_deps(Some(|state| state.required_capital_attributes = vec![]));
+        load_markers(&mut deps.querier);
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("lp", &[]),
+            ExecuteMsg::IssueWithdrawal {
+                to: Some(Addr::unchecked("lp_side_account")),
+                to_name: None,
+                amount: Uint128::new(10_000),
+                capital_denom: None,
+            },
+        )
+        .unwrap();
+
+        assert_eq!(
+            &MarkerMsgParams::TransferMarkerCoins {
+                coin: coin(10_000, "restricted_capital_coin"),
+                to: Addr::unchecked("lp_side_account"),
+                from: Addr::unchecked(MOCK_CONTRACT_ADDR),
+            },
+            marker_transfer_msg(msg_at_index(&res, 0)),
+        );
+    }
+
+    #[test]
+    fn withdraw_restricted_marker_without_transfer_grant() {
+        let mut deps = restricted_capital_coin_deps(None);
+        load_attributes(&mut deps.querier, &[("lp_side_account", "capital.test")]);
+        load_marker_fixtures(&mut deps.querier, &["restricted_capital_no_grant"]);
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("lp", &[]),
+            ExecuteMsg::IssueWithdrawal {
+                to: Some(Addr::unchecked("lp_side_account")),
+                to_name: None,
+                amount: Uint128::new(10_000),
+                capital_denom: None,
+            },
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains(
+            "cosmos2contract lacks transfer permission on marker restricted_capital_coin"
+        ));
+    }
+
+    #[test]
+    fn withdraw_unrestricted_denom_with_attribute_requirement() {
+        let mut deps = capital_coin_deps(Some(|state| {
+            state.required_capital_attributes = vec![CapitalDenomRequirement {
+                capital_denom: String::from("capital_coin"),
+                required_attributes: vec![String::from("capital.test")],
+                enforce_attribute_on_bank_send: true,
+            }]
+        }));
+        load_markers(&mut deps.querier);
+        let withdraw = |deps: &mut MockDeps, to: &str| {
+            execute(
+                deps.as_mut(),
+                mock_env(),
+                mock_info("lp", &[]),
+                ExecuteMsg::IssueWithdrawal {
+                    to: Some(Addr::unchecked(to)),
+                    to_name: None,
+                    amount: Uint128::new(10_000),
+                    capital_denom: None,
+                },
+            )
+        };
+
+        let err = withdraw(&mut deps, "lp_side_account").unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("lp_side_account does not have required attribute of capital.test"));
+
+        load_attributes(&mut deps.querier, &[("lp_side_account", "capital.test")]);
+        let res = withdraw(&mut deps, "lp_side_account").unwrap();
+        let (to_address, amount) = send_msg(msg_at_index(&res, 0));
+        assert_eq!("lp_side_account", to_address);
+        assert_eq!(&coins(10_000, "capital_coin"), amount);
+    }
+
+    #[test]
+    fn withdraw_unrestricted_denom_without_bank_send_enforcement() {
+        let mut deps = capital_coin_deps(Some(|state| {
+            state.required_capital_attributes = vec![CapitalDenomRequirement {
+                capital_denom: String::from("capital_coin"),
+                required_attributes: vec![String::from("capital.test")],
+                enforce_attribute_on_bank_send: false,
+            }]
+        }));
+        load_markers(&mut deps.querier);
+
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("lp", &[]),
+            ExecuteMsg::IssueWithdrawal {
+                to: Some(Addr::unchecked("lp_side_account")),
+                to_name: None,
+                amount: Uint128::new(10_000),
+                capital_denom: None,
+            },
+        )
+        .unwrap();
+        let (to_address, amount) = send_msg(msg_at_index(&res, 0));
+        assert_eq!("lp_side_account", to_address);
+        assert_eq!(&coins(10_000, "capital_coin"), amount);
+    }
+
+    #[test]
+    fn complete_payout_with_bank_send_enforcement() {
+        let deps = |enforce_attribute_on_bank_send: bool| {
+            let mut deps = capital_coin_deps(None);
+            let mut state = State::test_capital_coin();
+            state.required_capital_attributes = vec![CapitalDenomRequirement {
+                capital_denom: String::from("capital_coin"),
+                required_attributes: vec![String::from("capital.test")],
+                enforce_attribute_on_bank_send,
+            }];
+            state_storage(&mut deps.storage).save(&state).unwrap();
+            load_markers(&mut deps.querier);
+            deps
+        };
+        let payout = || ExchangeBuilder::new().capital(1_000).build();
+
+        assert_eq!(
+            Some(Addr::unchecked("lp_side_account")),
+            raise_recipient(&mut deps(false), payout(), Some("lp_side_account"))
+        );
+
+        let mut deps = deps(true);
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("lp", &[]),
+            ExecuteMsg::CompleteAssetExchange {
+                exchanges: vec![payout()],
+                to: Some(Addr::unchecked("lp_side_account")),
+                memo: None,
+                override_available_date: false,
+                raise: None,
+            },
+        )
+        .unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("lp_side_account does not have required attribute of capital.test"));
+        load_attributes(&mut deps.querier, &[("lp_side_account", "capital.test")]);
+        assert_eq!(
+            Some(Addr::unchecked("lp_side_account")),
+            raise_recipient(&mut deps, payout(), Some("lp_side_account"))
+        );
+    }
+
+    #[test]
+    fn withdraw_restricted_marker_any_of_attributes() {
+        let mut deps = restricted_capital_coin_deps(Some(|state| {
+            state.required_capital_attributes[0].required_attributes =
+                vec![String::from("capital.us"), String::from("capital.intl")]
+        }));
+        load_markers(&mut deps.querier);
+        let withdraw = |deps: &mut MockDeps, to: &str| {
+            execute(
+                deps.as_mut(),
+                mock_env(),
+                mock_info("lp", &[]),
+                ExecuteMsg::IssueWithdrawal {
+                    to: Some(Addr::unchecked(to)),
+                    to_name: None,
+                    amount: Uint128::new(10_000),
+                    capital_denom: None,
+                },
+            )
+        };
+
+        // holding neither attribute
+        load_attributes(&mut deps.querier, &[("lp_side_account", "capital.test")]);
+        let err = withdraw(&mut deps, "lp_side_account").unwrap_err();
+        assert!(err.to_string().contains(
+            "lp_side_account does not have required attribute of capital.us or capital.intl"
+        ));
+
+        // holding only the second
+        load_attributes(&mut deps.querier, &[("intl_account", "capital.intl")]);
+        let res = withdraw(&mut deps, "intl_account").unwrap();
+        assert_eq!(
+            &MarkerMsgParams::TransferMarkerCoins {
+                coin: coin(10_000, "restricted_capital_coin"),
+                to: Addr::unchecked("intl_account"),
+                from: Addr::unchecked(MOCK_CONTRACT_ADDR),
+            },
+            marker_transfer_msg(msg_at_index(&res, 0)),
+        );
+    }
+
+    #[test]
+    fn withdraw_restricted_marker_wildcard_attribute() {
+        let mut deps = restricted_capital_coin_deps(Some(|state| {
+            state.required_capital_attributes[0].required_attributes =
+                vec![String::from("capital.kyc.*")]
+        }));
+        load_markers(&mut deps.querier);
+        let withdraw = |deps: &mut MockDeps, to: &str| {
+            execute(
+                deps.as_mut(),
+                mock_env(),
+                mock_info("lp", &[]),
+                ExecuteMsg::IssueWithdrawal {
+                    to: Some(Addr::unchecked(to)),
+                    to_name: None,
+                    amount: Uint128::new(10_000),
+                    capital_denom: None,
+                },
+            )
+        };
+
+        load_attributes(&mut deps.querier, &[("ca_account", "capital.kyc.ca")]);
+        withdraw(&mut deps, "ca_account").unwrap();
+
+        load_attributes(
+            &mut deps.querier,
+            &[
+                ("other_account", "capital.kyc"),
+                ("other_account", "capital.kycx.us"),
+            ],
+        );
+        let err = withdraw(&mut deps, "other_account").unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("other_account does not have required attribute of capital.kyc.*"));
+    }
+
+    #[test]
+    fn attribute_matching() {
+        assert!(attribute_matches("capital.kyc.*", "capital.kyc.us"));
+        assert!(!attribute_matches("capital.kyc.*", "capital.kyc."));
+        assert!(!attribute_matches("capital.kyc.*", "capital.kyc"));
+        assert!(!attribute_matches("capital.kyc.*", "capital.kycx.us"));
+        assert!(attribute_matches("capital.kyc", "capital.kyc"));
+        assert!(!attribute_matches("capital.kyc", "capital.kyc.us"));
+        assert!(!attribute_matches("capital*", "capital.kyc"));
+    }
+
+    fn return_capital(deps: &mut MockDeps, amount: u128) -> ContractResponse {
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("lp", &[]),
+            ExecuteMsg::ReturnCapital {
+                amount: Uint128::new(amount),
+                capital_denom: None,
+                memo: Some(String::from("excess")),
+            },
+        )
+    }
+
+    #[test]
+    fn return_capital_attaches_funds() {
+        let mut deps = default_deps(None);
+        load_markers(&mut deps.querier);
+
+        let res = return_capital(&mut deps, 500).unwrap();
+
+        assert_eq!(1, res.messages.len());
+        let (recipient, msg, funds) = execute_args::<RaiseExecuteMsg>(msg_at_index(&res, 0));
+        assert_eq!("raise_1", recipient);
+        assert_eq!(
+            RaiseExecuteMsg::ReturnCapital {
+                capital: coin(500, "stable_coin"),
+                memo: Some(String::from("excess")),
+            },
+            msg
+        );
+        assert_eq!(&vec![coin(500, "stable_coin")], funds);
+        assert_attr(&res, "action", "return_capital");
+
+        assert!(return_capital(&mut deps, 0).is_err());
+    }
+
+    #[test]
+    fn return_capital_restricted() {
+        let mut deps = restricted_capital_coin_deps(None);
+        load_markers(&mut deps.querier);
+
+        let err = return_capital(&mut deps, 500).unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("raise_1 does not have required attribute of capital.test"));
+
+        load_attributes(&mut deps.querier, &[("raise_1", "capital.test")]);
+        let res = return_capital(&mut deps, 500).unwrap();
+
+        assert_eq!(2, res.messages.len());
+        assert_eq!(
+            &MarkerMsgParams::TransferMarkerCoins {
+                coin: coin(500, "restricted_capital_coin"),
+                to: Addr::unchecked("raise_1"),
+                from: Addr::unchecked(MOCK_CONTRACT_ADDR),
+            },
+            marker_transfer_msg(msg_at_index(&res, 0)),
+        );
+        let (_, msg, funds) = execute_args::<RaiseExecuteMsg>(msg_at_index(&res, 1));
+        assert_eq!(
+            RaiseExecuteMsg::ReturnCapital {
+                capital: coin(500, "restricted_capital_coin"),
+                memo: Some(String::from("excess")),
+            },
+            msg
+        );
+        assert!(funds.is_empty());
+    }
+
+    fn withdraw_coins(deps: &mut MockDeps, amounts: Vec<Coin>) -> ContractResponse {
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("lp", &[]),
+            ExecuteMsg::IssueMultiCoinWithdrawal {
+                to: Some(Addr::unchecked("lp_side_account")),
+                to_name: None,
+                amounts,
+            },
+        )
+    }
+
+    #[test]
+    fn withdraw_multiple_coins() {
+        let mut deps = restricted_capital_coin_deps(Some(|state| {
+            state.like_capital_denoms.push(String::from("stable_coin"))
+        }));
+        load_attributes(&mut deps.querier, &[("lp_side_account", "capital.test")]);
+        load_markers(&mut deps.querier);
+
+        let res = withdraw_coins(
+            &mut deps,
+            vec![
+                coin(10_000, "restricted_capital_coin"),
+                coin(500, "stable_coin"),
+            ],
+        )
+        .unwrap();
+
+        assert_eq!(2, res.messages.len());
+        assert_eq!(
+            &MarkerMsgParams::TransferMarkerCoins {
+                coin: coin(10_000, "restricted_capital_coin"),
+                to: Addr::unchecked("lp_side_account"),
+                from: Addr::unchecked(MOCK_CONTRACT_ADDR),
+            },
+            marker_transfer_msg(msg_at_index(&res, 0)),
+        );
+        let (to_address, amount) = send_msg(msg_at_index(&res, 1));
+        assert_eq!("lp_side_account", to_address);
+        assert_eq!(&vec![coin(500, "stable_coin")], amount);
+        assert_attr(
+            &res,
+            "amounts",
+            "10000restricted_capital_coin,500stable_coin",
+        );
+        assert_eq!(1, stats(&deps).withdrawals);
+    }
+
+    #[test]
+    fn withdraw_multiple_coins_invalid() {
+        let mut deps = restricted_capital_coin_deps(Some(|state| {
+            state.like_capital_denoms.push(String::from("stable_coin"))
+        }));
+        load_markers(&mut deps.querier);
+
+        let err = withdraw_coins(
+            &mut deps,
+            vec![coin(500, "stable_coin"), coin(300, "stable_coin")],
+        )
+        .unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("duplicate withdrawal denom stable_coin"));
+
+        assert!(matches!(
+            withdraw_coins(&mut deps, vec![coin(500, "other_coin")]),
+            Err(ContractError::UnsupportedCapitalDenom { .. })
+        ));
+        assert!(withdraw_coins(&mut deps, vec![]).is_err());
+        assert!(withdraw_coins(&mut deps, vec![coin(0, "stable_coin")]).is_err());
+
+        // lp_side_account lacks the attribute the restricted denom requires
+        let err = withdraw_coins(
+            &mut deps,
+            vec![
+                coin(500, "stable_coin"),
+                coin(10_000, "restricted_capital_coin"),
+            ],
+        )
+        .unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("lp_side_account does not have required attribute of capital.test"));
+    }
+
+    #[test]
+    fn withdraw_multiple_coins_above_threshold_requires_approval() {
+        let mut deps = deps_with_balances(&[("stable_coin", 100_000), ("other_coin", 100_000)]);
+        let mut state = state_storage_read(&deps.storage).load().unwrap();
+        state.like_capital_denoms.push(String::from("other_coin"));
+        state_storage(&mut deps.storage).save(&state).unwrap();
+        with_approval_threshold(&mut deps);
+
+        withdraw_coins(
+            &mut deps,
+            vec![coin(10_000, "stable_coin"), coin(10_000, "other_coin")],
+        )
+        .unwrap();
+        let err = withdraw_coins(
+            &mut deps,
+            vec![coin(10_000, "stable_coin"), coin(20_000, "other_coin")],
+        )
+        .unwrap_err();
+        assert!(matches!(
+            err,
+            ContractError::WithdrawalApprovalRequired { .. }
+        ));
+
+        // an approval only covers its own denom
+        approve_coin_withdrawal(&mut deps, "lp_side_account", coin(20_000, "stable_coin")).unwrap();
+        let err = withdraw_coins(
+            &mut deps,
+            vec![coin(10_000, "stable_coin"), coin(20_000, "other_coin")],
+        )
+        .unwrap_err();
+        assert!(matches!(
+            err,
+            ContractError::WithdrawalApprovalRequired { amount, .. }
+                if amount == coin(20_000, "other_coin")
+        ));
+        approve_coin_withdrawal(&mut deps, "lp_side_account", coin(20_000, "other_coin")).unwrap();
+        let res = withdraw_coins(
+            &mut deps,
+            vec![coin(10_000, "stable_coin"), coin(20_000, "other_coin")],
+        )
+        .unwrap();
+        assert_attr(&res, "approved_by", "admin");
+
+        // each coin above the threshold needs its own approval, and the stable_coin one from
+        // above is still outstanding
+        approve_coin_withdrawal(&mut deps, "lp_side_account", coin(30_000, "other_coin")).unwrap();
+        let res = withdraw_coins(
+            &mut deps,
+            vec![coin(20_000, "stable_coin"), coin(30_000, "other_coin")],
+        )
+        .unwrap();
+        assert_attr(&res, "approved_by", "admin,admin");
+    }
+
+    #[test]
+    fn withdraw_multiple_coins_with_fee() {
+        let mut deps = restricted_capital_coin_deps(Some(|state| {
+            with_withdrawal_fee(state);
+            state.like_capital_denoms.push(String::from("stable_coin"));
+            state.required_capital_attributes = vec![];
+        }));
+        load_markers(&mut deps.querier);
+
+        let res = withdraw_coins(
+            &mut deps,
+            vec![
+                coin(10_000, "restricted_capital_coin"),
+                coin(2_000, "stable_coin"),
+            ],
+        )
+        .unwrap();
+
+        assert_eq!(4, res.messages.len());
+        assert_eq!(
+            &MarkerMsgParams::TransferMarkerCoins {
+                coin: coin(9_975, "restricted_capital_coin"),
+                to: Addr::unchecked("lp_side_account"),
+                from: Addr::unchecked(MOCK_CONTRACT_ADDR),
+            },
+            marker_transfer_msg(msg_at_index(&res, 0)),
+        );
+        assert_eq!(
+            &MarkerMsgParams::TransferMarkerCoins {
+                coin: coin(25, "restricted_capital_coin"),
+                to: Addr::unchecked("marketpalace"),
+                from: Addr::unchecked(MOCK_CONTRACT_ADDR),
+            },
+            marker_transfer_msg(msg_at_index(&res, 1)),
+        );
+        let (to_address, amount) = send_msg(msg_at_index(&res, 2));
+        assert_eq!("lp_side_account", to_address);
+        assert_eq!(&coins(1_995, "stable_coin"), amount);
+        let (to_address, amount) = send_msg(msg_at_index(&res, 3));
+        assert_eq!("marketpalace", to_address);
+        assert_eq!(&coins(5, "stable_coin"), amount);
+        assert_attr(
+            &res,
+            "withdrawal_fees",
+            "25restricted_capital_coin,5stable_coin",
+        );
+        assert_attr(&res, "fee_recipient", "marketpalace");
+        assert_attr(
+            &res,
+            "net_amounts",
+            "9975restricted_capital_coin,1995stable_coin",
+        );
+
+        // a fee that rounds to nothing isn't sent
+        let res = withdraw_coins(&mut deps, vec![coin(399, "stable_coin")]).unwrap();
+        assert_eq!(1, res.messages.len());
+        assert_attr(&res, "withdrawal_fees", "0stable_coin");
+    }
+
+    #[test]
+    fn withdraw_multiple_coins_to_name() {
+        let mut deps = deps_with_balances(&[("stable_coin", 10_000), ("other_coin", 10_000)]);
+        let mut state = state_storage_read(&deps.storage).load().unwrap();
+        state.like_capital_denoms.push(String::from("other_coin"));
+        state_storage(&mut deps.storage).save(&state).unwrap();
+        deps.querier
+            .with_names(&[("treasury.acme.pb", "treasury_account", false)]);
+
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("lp", &[]),
+            ExecuteMsg::IssueMultiCoinWithdrawal {
+                to: None,
+                to_name: Some(String::from("treasury.acme.pb")),
+                amounts: vec![coin(500, "stable_coin"), coin(300, "other_coin")],
+            },
+        )
+        .unwrap();
+        let (to_address, _) = send_msg(msg_at_index(&res, 0));
+        assert_eq!("treasury_account", to_address);
+        assert_attr(&res, "to", "treasury_account");
+        assert_attr(&res, "to_name", "treasury.acme.pb");
+
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("lp", &[]),
+            ExecuteMsg::IssueMultiCoinWithdrawal {
+                to: Some(Addr::unchecked("Treasury_Account")),
+                to_name: None,
+                amounts: vec![coin(500, "stable_coin")],
+            },
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::Std(_)));
+    }
+
+    #[test]
+    fn withdraw_multiple_coins_allowance() {
+        let mut deps = deps_with_balances(&[("stable_coin", 100_000), ("other_coin", 100_000)]);
+        let mut state = state_storage_read(&deps.storage).load().unwrap();
+        state.like_capital_denoms.push(String::from("other_coin"));
+        state_storage(&mut deps.storage).save(&state).unwrap();
+        set_coin_allowance(&mut deps, "lp_side_account", coin(50_000, "stable_coin")).unwrap();
+        set_coin_allowance(&mut deps, "lp_side_account", coin(1_000, "other_coin")).unwrap();
+
+        withdraw_coins(
+            &mut deps,
+            vec![coin(500, "stable_coin"), coin(300, "other_coin")],
+        )
+        .unwrap();
+        withdraw_coins(&mut deps, vec![coin(30_000, "stable_coin")]).unwrap();
+        let err = withdraw_coins(&mut deps, vec![coin(800, "other_coin")]).unwrap_err();
+        assert!(matches!(
+            err,
+            ContractError::WithdrawalAllowanceExceeded { .. }
+        ));
+        let err = withdraw_coins(&mut deps, vec![coin(20_000, "stable_coin")]).unwrap_err();
+        assert!(matches!(
+            err,
+            ContractError::WithdrawalAllowanceExceeded { .. }
+        ));
+
+        // replacing one denom's allowance leaves the other's in place
+        set_coin_allowance(&mut deps, "lp_side_account", coin(100_000, "stable_coin")).unwrap();
+        let allowances = withdrawal_allowances_storage_read(&deps.storage)
+            .load()
+            .unwrap();
+        assert_eq!(2, allowances.len());
+        withdraw_coins(&mut deps, vec![coin(20_000, "stable_coin")]).unwrap();
+        assert!(withdraw_coins(&mut deps, vec![coin(800, "other_coin")]).is_err());
+    }
+
+    #[test]
+    fn withdraw_bad_actor() {
+        let mut deps = deps_with_balances(&[("stable_coin", 10_000)]);
+
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("bad_actor", &[]),
+            ExecuteMsg::IssueWithdrawal {
+                to: Some(Addr::unchecked("lp_side_account")),
+                to_name: None,
+                amount: Uint128::new(10_000),
+                capital_denom: None,
+            },
+        );
+        assert!(res.is_err());
+    }
+
+    const STALE_AFTER_SECONDS: u64 = 30 * 24 * 60 * 60;
+
+    fn deps_with_due_authorization(due: u64) -> MockDeps {
+        let mut deps = default_deps(Some(|state| {
+            state.stale_after_seconds = Some(STALE_AFTER_SECONDS)
+        }));
+        asset_exchange_authorization_storage(&mut deps.storage)
+            .save(&vec![AssetExchangeAuthorization {
+                id: 7,
+                raise: None,
+                exchanges: vec![ExchangeBuilder::new().capital(1_000).due(due).build()],
+                to: None,
+                memo: None,
+                created_at: None,
+                expires_at: None,
+                memo_hash: None,
+                status: AuthorizationStatus::Pending,
+                resolved_at: None,
+                resolved_by: None,
+            }])
+            .unwrap();
         deps
     }
 
+    fn complete_due(deps: &mut MockDeps, sender: &str, due: u64) -> ContractResponse {
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(sender, &[]),
+            ExecuteMsg::CompleteAssetExchange {
+                exchanges: vec![ExchangeBuilder::new().capital(1_000).due(due).build()],
+                to: None,
+                memo: None,
+                override_available_date: false,
+                raise: None,
+            },
+        )
+    }
+
+    fn queried_authorizations(deps: &MockDeps) -> Vec<AuthorizationView> {
+        queried_authorization_history(deps, false)
+    }
+
+    fn queried_authorization_history(
+        deps: &MockDeps,
+        include_history: bool,
+    ) -> Vec<AuthorizationView> {
+        let res: AuthorizationsResponse = from_binary(
+            &query(
+                deps.as_ref(),
+                mock_env(),
+                QueryMsg::GetAssetExchangeAuthorizations { include_history },
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        res.authorizations
+    }
+
+    #[test]
+    fn complete_at_stale_boundary() {
+        let due = mock_env().block.time.seconds() - STALE_AFTER_SECONDS;
+        let mut deps = deps_with_due_authorization(due);
+        assert!(!queried_authorizations(&deps)[0].stale);
+
+        let res = complete_due(&mut deps, "admin", due).unwrap();
+        assert_eq!(1, res.messages.len());
+        assert!(res.events.is_empty());
+    }
+
+    #[test]
+    fn complete_stale_authorization() {
+        let due = mock_env().block.time.seconds() - STALE_AFTER_SECONDS - 1;
+        let mut deps = deps_with_due_authorization(due);
+        assert!(queried_authorizations(&deps)[0].stale);
+
+        let res = complete_due(&mut deps, "admin", due).unwrap();
+        assert!(res.messages.is_empty());
+        assert_attr(&res, "completed", "false");
+        assert_eq!(
+            vec![Event::new("stale_authorization_removed").add_attribute("authorization_id", "7")],
+            res.events
+        );
+        assert!(queried_authorizations(&deps).is_empty());
+
+        // with the authorization gone a retry fails outright
+        assert!(complete_due(&mut deps, "admin", due).is_err());
+    }
+
+    #[test]
+    fn stale_window_unset() {
+        let due = mock_env().block.time.seconds() - 10 * STALE_AFTER_SECONDS;
+        let mut deps = deps_with_due_authorization(due);
+        let mut state = state_storage_read(&deps.storage).load().unwrap();
+        state.stale_after_seconds = None;
+        state_storage(&mut deps.storage).save(&state).unwrap();
+        assert!(!queried_authorizations(&deps)[0].stale);
+
+        let res = complete_due(&mut deps, "lp", due).unwrap();
+        assert_eq!(1, res.messages.len());
+    }
+
+    const AUTHORIZATION_TTL_SECONDS: u64 = 7 * 24 * 60 * 60;
+
+    fn authorize_expiring(deps: &mut MockDeps, expires_at: Option<Timestamp>) -> ContractResponse {
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("lp", &[]),
+            ExecuteMsg::AuthorizeAssetExchange {
+                exchanges: vec![accept_exchange()],
+                to: None,
+                memo: None,
+                idempotency_key: None,
+                expires_at,
+                memo_hash: None,
+                raise: None,
+            },
+        )
+    }
+
+    fn authorization_ttl_deps() -> MockDeps {
+        default_deps(Some(|state| {
+            state.default_authorization_ttl_seconds = Some(AUTHORIZATION_TTL_SECONDS)
+        }))
+    }
+
+    #[test]
+    fn authorization_stamped_with_default_ttl() {
+        let mut deps = authorization_ttl_deps();
+        authorize_expiring(&mut deps, None).unwrap();
+
+        assert_eq!(
+            Some(
+                mock_env()
+                    .block
+                    .time
+                    .plus_seconds(AUTHORIZATION_TTL_SECONDS)
+            ),
+            queried_authorizations(&deps)[0].expires_at
+        );
+    }
+
+    #[test]
+    fn authorization_without_default_ttl() {
+        let mut deps = default_deps(None);
+        authorize_expiring(&mut deps, None).unwrap();
+        assert_eq!(None, queried_authorizations(&deps)[0].expires_at);
+
+        // without a default any future expiration is accepted
+        let expires_at = mock_env()
+            .block
+            .time
+            .plus_seconds(10 * AUTHORIZATION_TTL_SECONDS);
+        authorize_expiring(&mut deps, Some(expires_at)).unwrap();
+        assert_eq!(
+            Some(expires_at),
+            queried_authorizations(&deps)[1].expires_at
+        );
+    }
+
+    #[test]
+    fn authorization_explicit_expiration() {
+        let mut deps = authorization_ttl_deps();
+        let expires_at = mock_env().block.time.plus_seconds(60);
+        authorize_expiring(&mut deps, Some(expires_at)).unwrap();
+        assert_eq!(
+            Some(expires_at),
+            queried_authorizations(&deps)[0].expires_at
+        );
+
+        let beyond = mock_env()
+            .block
+            .time
+            .plus_seconds(AUTHORIZATION_TTL_SECONDS + 1);
+        let err = authorize_expiring(&mut deps, Some(beyond)).unwrap_err();
+        assert!(err.to_string().contains("beyond the default ttl"));
+
+        let err = authorize_expiring(&mut deps, Some(mock_env().block.time)).unwrap_err();
+        assert!(err.to_string().contains("is not in the future"));
+        assert_eq!(1, queried_authorizations(&deps).len());
+    }
+
+    #[test]
+    fn complete_expired_authorization() {
+        let due = mock_env().block.time.seconds();
+        let mut deps = deps_with_due_authorization(due);
+        let mut authorizations = asset_exchange_authorization_storage_read(&deps.storage)
+            .load()
+            .unwrap();
+        authorizations[0].expires_at = Some(mock_env().block.time.minus_seconds(1));
+        asset_exchange_authorization_storage(&mut deps.storage)
+            .save(&authorizations)
+            .unwrap();
+        assert!(queried_authorizations(&deps)[0].stale);
+
+        let res = complete_due(&mut deps, "admin", due).unwrap();
+        assert!(res.messages.is_empty());
+        assert_attr(&res, "completed", "false");
+        assert!(queried_authorizations(&deps).is_empty());
+    }
+
+    #[test]
+    fn prune_expired_authorizations() {
+        let now = mock_env().block.time.seconds();
+        let mut deps = deps_with_due_authorization(now - STALE_AFTER_SECONDS - 1);
+        state_storage(&mut deps.storage)
+            .update(|mut state| -> StdResult<_> {
+                state.operators = vec![Addr::unchecked("operator")];
+                Ok(state)
+            })
+            .unwrap();
+        let mut authorizations = asset_exchange_authorization_storage_read(&deps.storage)
+            .load()
+            .unwrap();
+        let authorization = |id, due, expires_at| AssetExchangeAuthorization {
+            id,
+            exchanges: vec![ExchangeBuilder::new().capital(1_000).due(due).build()],
+            expires_at,
+            ..authorizations[0].clone()
+        };
+        let expired = authorization(8, now, Some(mock_env().block.time.minus_seconds(1)));
+        let pending = authorization(9, now, None);
+        authorizations.extend([expired, pending]);
+        asset_exchange_authorization_storage(&mut deps.storage)
+            .save(&authorizations)
+            .unwrap();
+        let prune = |deps: &mut MockDeps, sender: &str| {
+            execute(
+                deps.as_mut(),
+                mock_env(),
+                mock_info(sender, &[]),
+                ExecuteMsg::PruneExpiredAuthorizations {},
+            )
+        };
+
+        assert!(prune(&mut deps, "stranger").is_err());
+
+        let res = prune(&mut deps, "operator").unwrap();
+        assert_attr(&res, "pruned", "2");
+        assert_eq!(
+            vec![
+                Event::new("stale_authorization_removed").add_attribute("authorization_id", "7"),
+                Event::new("stale_authorization_removed").add_attribute("authorization_id", "8"),
+            ],
+            res.events
+        );
+        let remaining = queried_authorizations(&deps);
+        assert_eq!(
+            vec![9],
+            remaining.iter().map(|view| view.id).collect::<Vec<_>>()
+        );
+        let cancelled: Vec<u64> = queried_authorization_history(&deps, true)
+            .iter()
+            .filter(|view| view.status == AuthorizationStatus::Cancelled)
+            .map(|view| view.id)
+            .collect();
+        assert_eq!(vec![7, 8], cancelled);
+
+        let res = prune(&mut deps, "lp").unwrap();
+        assert_attr(&res, "pruned", "0");
+        assert_eq!(1, queried_authorizations(&deps).len());
+    }
+
+    #[test]
+    fn update_default_authorization_ttl() {
+        let mut deps = default_deps(None);
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("admin", &[]),
+            ExecuteMsg::UpdateDefaultAuthorizationTtl {
+                default_authorization_ttl_seconds: Some(AUTHORIZATION_TTL_SECONDS),
+            },
+        )
+        .unwrap();
+        assert_eq!(
+            Some(AUTHORIZATION_TTL_SECONDS),
+            state_storage_read(&deps.storage)
+                .load()
+                .unwrap()
+                .default_authorization_ttl_seconds
+        );
+
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("admin", &[]),
+            ExecuteMsg::UpdateDefaultAuthorizationTtl {
+                default_authorization_ttl_seconds: Some(0),
+            },
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("ttl"));
+
+        assert!(execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("lp", &[]),
+            ExecuteMsg::UpdateDefaultAuthorizationTtl {
+                default_authorization_ttl_seconds: None,
+            },
+        )
+        .is_err());
+    }
+
+    fn update_commitment_denom(deps: &mut MockDeps, sender: &str) -> ContractResponse {
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(sender, &[]),
+            ExecuteMsg::UpdateCommitmentDenom {
+                denom: String::from("raise_2.commitment"),
+            },
+        )
+    }
+
+    fn update_investment_denom(deps: &mut MockDeps, replaces: Option<&str>) -> ContractResponse {
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("admin", &[]),
+            ExecuteMsg::UpdateInvestmentDenom {
+                denom: String::from("raise_2.investment"),
+                replaces: replaces.map(String::from),
+            },
+        )
+    }
+
+    #[test]
+    fn update_commitment_denom_success() {
+        let mut deps = default_deps(None);
+        load_marker_fixtures(&mut deps.querier, &["commitment"]);
+
+        assert!(update_commitment_denom(&mut deps, "lp").is_err());
+        let res = update_commitment_denom(&mut deps, "admin").unwrap();
+        assert_attr(&res, "old_denom", "raise_1.commitment");
+        assert_attr(&res, "new_denom", "raise_2.commitment");
+        assert_eq!(
+            "raise_2.commitment",
+            state_storage_read(&deps.storage)
+                .load()
+                .unwrap()
+                .commitment_denom
+        );
+    }
+
+    #[test]
+    fn update_commitment_denom_without_marker() {
+        let mut deps = default_deps(None);
+        let err = update_commitment_denom(&mut deps, "admin").unwrap_err();
+        assert_eq!(
+            "Generic error: no marker exists for denom raise_2.commitment",
+            err.to_string()
+        );
+    }
+
+    #[test]
+    fn update_commitment_denom_with_pending_authorization() {
+        let mut deps = default_deps(None);
+        load_marker_fixtures(&mut deps.querier, &["commitment", "investment"]);
+        authorize(&mut deps, mock_env()).unwrap();
+
+        let err = update_commitment_denom(&mut deps, "admin").unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("with a commitment leg is still pending"));
+        assert_eq!(
+            "raise_1.commitment",
+            state_storage_read(&deps.storage)
+                .load()
+                .unwrap()
+                .commitment_denom
+        );
+
+        // the pending authorization moves no investment
+        update_investment_denom(&mut deps, None).unwrap();
+    }
+
+    #[test]
+    fn update_commitment_denom_scheduled_with_pending_authorization() {
+        let mut deps = default_deps(Some(with_admin_action_delay));
+        load_marker_fixtures(&mut deps.querier, &["commitment"]);
+        update_commitment_denom(&mut deps, "admin").unwrap();
+        authorize(&mut deps, mock_env()).unwrap();
+
+        let mut env = mock_env();
+        env.block.time = env.block.time.plus_seconds(86_400);
+        let err = execute_scheduled(&mut deps, env, 1).unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("with a commitment leg is still pending"));
+        assert_eq!(1, scheduled_actions(&deps).len());
+    }
+
+    #[test]
+    fn update_investment_denom_success() {
+        let mut deps = default_deps(Some(|state| {
+            state
+                .investment_denoms
+                .push(String::from("raise_1.investment_b"))
+        }));
+        load_marker_fixtures(&mut deps.querier, &["investment"]);
+
+        let err = update_investment_denom(&mut deps, None).unwrap_err();
+        assert!(err.to_string().contains("must be given"));
+        assert!(update_investment_denom(&mut deps, Some("stable_coin")).is_err());
+
+        let res = update_investment_denom(&mut deps, Some("raise_1.investment_b")).unwrap();
+        assert_attr(&res, "old_denom", "raise_1.investment_b");
+        assert_attr(&res, "new_denom", "raise_2.investment");
+        assert_eq!(
+            vec!["raise_1.investment", "raise_2.investment"],
+            state_storage_read(&deps.storage)
+                .load()
+                .unwrap()
+                .investment_denoms
+        );
+    }
+
+    #[test]
+    fn update_investment_denom_with_pending_authorization() {
+        let mut deps = default_deps(None);
+        load_marker_fixtures(&mut deps.querier, &["investment"]);
+        authorize_exchanges(
+            &mut deps,
+            vec![ExchangeBuilder::new()
+                .investment(1_000)
+                .commitment(-10)
+                .build()],
+        )
+        .unwrap();
+
+        let err = update_investment_denom(&mut deps, None).unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("with an investment leg is still pending"));
+
+        let err = update_investment_denom(&mut deps, Some("raise_1.investment")).unwrap_err();
+        assert!(err.to_string().contains("still pending"));
+    }
+
+    #[test]
+    fn admin_completion_without_authorization() {
+        let mut deps = default_deps(Some(|state| state.default_grace_period_seconds = 60));
+        let due = mock_env().block.time.seconds() - 10 * 60;
+
+        assert_eq!(
+            format!(
+                "no previously authorized asset exchange matched [cap:+1000 due:{}]",
+                due
+            ),
+            complete_due(&mut deps, "admin", due)
+                .unwrap_err()
+                .to_string()
+        );
+    }
+
+    #[test]
+    fn overdue_obligations() {
+        let mut deps = default_deps(Some(|state| {
+            state.like_capital_denoms =
+                vec![String::from("stable_coin"), String::from("other_coin")]
+        }));
+        let now = mock_env().block.time.seconds();
+        let authorization = |id, exchanges| AssetExchangeAuthorization {
+            id,
+            raise: None,
+            exchanges,
+            to: None,
+            memo: None,
+            created_at: None,
+            expires_at: None,
+            memo_hash: None,
+            status: AuthorizationStatus::Pending,
+            resolved_at: None,
+            resolved_by: None,
+        };
+        let overdue = authorization(
+            2,
+            vec![
+                ExchangeBuilder::new()
+                    .capital(-1_000)
+                    .denom("stable_coin")
+                    .due(now - 100)
+                    .build(),
+                ExchangeBuilder::new()
+                    .capital(-500)
+                    .denom("stable_coin")
+                    .due(now - 50)
+                    .build(),
+                ExchangeBuilder::new()
+                    .capital(300)
+                    .denom("other_coin")
+                    .due(now + 50)
+                    .build(),
+            ],
+        );
+        asset_exchange_authorization_storage(&mut deps.storage)
+            .save(&vec![
+                authorization(
+                    1,
+                    vec![ExchangeBuilder::new()
+                        .capital(-1_000)
+                        .denom("stable_coin")
+                        .due(now)
+                        .build()],
+                ),
+                overdue.clone(),
+                authorization(
+                    3,
+                    vec![ExchangeBuilder::new()
+                        .capital(-1_000)
+                        .denom("stable_coin")
+                        .build()],
+                ),
+                authorization(
+                    4,
+                    vec![ExchangeBuilder::new()
+                        .commitment(10)
+                        .available(now - 100)
+                        .build()],
+                ),
+            ])
+            .unwrap();
+
+        let res: OverdueObligationsResponse = from_binary(
+            &query(
+                deps.as_ref(),
+                mock_env(),
+                QueryMsg::GetOverdueObligations {},
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(
+            vec![OverdueObligation {
+                authorization: AuthorizationView::new(overdue, false),
+                seconds_overdue: 100,
+                capital_owed: vec![coin(1_500, "stable_coin")],
+            }],
+            res.obligations
+        );
+    }
+
+    fn stats(deps: &MockDeps) -> StatsResponse {
+        from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::GetStats {}).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn operation_stats() {
+        let mut deps = deps_with_balances(&[("stable_coin", 10_000)]);
+        assert_eq!(
+            StatsResponse {
+                authorizations: 0,
+                cancellations: 0,
+                completions: 0,
+                withdrawals: 0,
+                pending_authorizations: 0,
+                last_activity: None,
+                last_lp_activity: None,
+                last_admin_activity: None,
+            },
+            stats(&deps)
+        );
+
+        for _ in 0..3 {
+            authorize(&mut deps, mock_env()).unwrap();
+        }
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("lp", &[]),
+            ExecuteMsg::CancelAssetExchangeAuthorization {
+                exchanges: vec![accept_exchange()],
+                to: None,
+                memo: None,
+                raise: None,
+            },
+        )
+        .unwrap();
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("lp", &[]),
+            ExecuteMsg::CompleteAssetExchange {
+                exchanges: vec![accept_exchange()],
+                to: None,
+                memo: None,
+                override_available_date: false,
+                raise: None,
+            },
+        )
+        .unwrap();
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("lp", &[]),
+            ExecuteMsg::IssueWithdrawal {
+                to: Some(Addr::unchecked("lp_side_account")),
+                to_name: None,
+                amount: Uint128::new(1_000),
+                capital_denom: None,
+            },
+        )
+        .unwrap();
+
+        // failed operations are not counted
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("lp", &[]),
+            ExecuteMsg::CancelAssetExchangeAuthorization {
+                exchanges: vec![ExchangeBuilder::new().capital(1_000).build()],
+                to: None,
+                memo: None,
+                raise: None,
+            },
+        )
+        .unwrap_err();
+
+        assert_eq!(
+            StatsResponse {
+                authorizations: 3,
+                cancellations: 1,
+                completions: 1,
+                withdrawals: 1,
+                pending_authorizations: 1,
+                last_activity: Some(mock_env().block.time),
+                last_lp_activity: Some(mock_env().block.time),
+                last_admin_activity: None,
+            },
+            stats(&deps)
+        );
+    }
+
+    #[test]
+    fn role_activity() {
+        let mut deps = default_deps(None);
+        let lp_time = mock_env().block.time;
+        authorize(&mut deps, mock_env()).unwrap();
+
+        let mut admin_env = mock_env();
+        admin_env.block.time = lp_time.plus_seconds(3_600);
+        execute(
+            deps.as_mut(),
+            admin_env.clone(),
+            mock_info("admin", &[]),
+            ExecuteMsg::SetOperators {
+                operators: vec![Addr::unchecked("operator")],
+            },
+        )
+        .unwrap();
+
+        // an operator is neither the lp nor an admin
+        let mut later = mock_env();
+        later.block.time = lp_time.plus_seconds(7_200);
+        execute(
+            deps.as_mut(),
+            later,
+            mock_info("operator", &[]),
+            ExecuteMsg::CompleteAssetExchange {
+                exchanges: vec![accept_exchange()],
+                to: None,
+                memo: None,
+                override_available_date: false,
+                raise: None,
+            },
+        )
+        .unwrap();
+
+        let stats = stats(&deps);
+        assert_eq!(Some(lp_time), stats.last_lp_activity);
+        assert_eq!(Some(admin_env.block.time), stats.last_admin_activity);
+        let res: StateResponse =
+            from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::GetState {}).unwrap()).unwrap();
+        assert_eq!(Some(lp_time), res.state.last_lp_activity);
+        assert_eq!(Some(admin_env.block.time), res.state.last_admin_activity);
+    }
+
+    fn deposit(deps: &mut MockDeps, funds: &[Coin], memo: Option<&str>) -> ContractResponse {
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("lp", funds),
+            ExecuteMsg::Deposit {
+                memo: memo.map(String::from),
+            },
+        )
+    }
+
+    fn deposits(deps: &MockDeps, start_after: Option<u64>, limit: Option<u32>) -> Vec<u64> {
+        let res: DepositsResponse = from_binary(
+            &query(
+                deps.as_ref(),
+                mock_env(),
+                QueryMsg::GetDeposits { start_after, limit },
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        res.deposits.into_iter().map(|deposit| deposit.id).collect()
+    }
+
+    #[test]
+    fn deposit_recorded() {
+        let mut deps = default_deps(None);
+        let res = deposit(&mut deps, &[coin(1_000, "stable_coin")], Some("q3 call")).unwrap();
+        assert_attr(&res, "action", "deposit");
+        assert_attr(&res, "deposit_id", "1");
+        assert_attr(&res, "sender", "lp");
+        assert_attr(&res, "funds", "1000stable_coin");
+        assert_attr(&res, "memo", "q3 call");
+
+        let res: DepositsResponse = from_binary(
+            &query(
+                deps.as_ref(),
+                mock_env(),
+                QueryMsg::GetDeposits {
+                    start_after: None,
+                    limit: None,
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(
+            vec![Deposit {
+                id: 1,
+                sender: Addr::unchecked("lp"),
+                coins: vec![coin(1_000, "stable_coin")],
+                memo: Some(String::from("q3 call")),
+                time: mock_env().block.time,
+            }],
+            res.deposits
+        );
+    }
+
+    #[test]
+    fn deposit_unsupported_denom() {
+        let mut deps = default_deps(None);
+        let err = deposit(
+            &mut deps,
+            &[coin(1_000, "stable_coin"), coin(5, "dust_coin")],
+            None,
+        )
+        .unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("unsupported deposit denom dust_coin"));
+        assert!(deposits(&deps, None, None).is_empty());
+    }
+
+    #[test]
+    fn deposit_without_funds() {
+        let mut deps = default_deps(None);
+        assert!(deposit(&mut deps, &[], None).is_err());
+    }
+
+    #[test]
+    fn funds_rejected_outside_deposit() {
+        let mut deps = default_deps(None);
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("lp", &[coin(1_000, "stable_coin")]),
+            ExecuteMsg::AuthorizeAssetExchange {
+                exchanges: vec![accept_exchange()],
+                to: None,
+                memo: None,
+                idempotency_key: None,
+                expires_at: None,
+                memo_hash: None,
+                raise: None,
+            },
+        );
+        assert!(res.is_err());
+    }
+
+    #[test]
+    fn deposits_paginated() {
+        let mut deps = default_deps(None);
+        for _ in 0..35 {
+            deposit(&mut deps, &[coin(100, "stable_coin")], None).unwrap();
+        }
+
+        assert_eq!((1..=10).collect::<Vec<u64>>(), deposits(&deps, None, None));
+        assert_eq!(vec![4, 5], deposits(&deps, Some(3), Some(2)));
+        assert_eq!(
+            (6..=35).collect::<Vec<u64>>(),
+            deposits(&deps, Some(5), Some(100))
+        );
+        assert!(deposits(&deps, Some(35), None).is_empty());
+        assert!(deposits(&deps, Some(u64::MAX), None).is_empty());
+    }
+
+    fn refund(deps: &mut MockDeps, sender: &str, denom: &str) -> ContractResponse {
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(sender, &[]),
+            ExecuteMsg::RefundUnexpectedFunds {
+                denom: String::from(denom),
+                to: Addr::unchecked("lp_side_account"),
+            },
+        )
+    }
+
     #[test]
-    fn recover() {
+    fn refund_unexpected_funds() {
+        let mut deps = deps_with_balances(&[("stable_coin", 10_000), ("airdrop", 42)]);
+
+        let res = refund(&mut deps, "admin", "airdrop").unwrap();
+        assert_eq!(1, res.messages.len());
+        let (to_address, coins) = send_msg(msg_at_index(&res, 0));
+        assert_eq!("lp_side_account", to_address);
+        assert_eq!(&vec![coin(42, "airdrop")], coins);
+        assert_attr(&res, "action", "refund_unexpected_funds");
+        assert_attr(&res, "refunded", "42airdrop");
+    }
+
+    #[test]
+    fn refund_protected_denoms() {
+        let mut deps = deps_with_balances(&[
+            ("stable_coin", 10_000),
+            ("raise_1.commitment", 100),
+            ("raise_1.investment", 100),
+        ]);
+        for denom in ["stable_coin", "raise_1.commitment", "raise_1.investment"] {
+            let err = refund(&mut deps, "admin", denom).unwrap_err();
+            assert!(err.to_string().contains("cannot be refunded"));
+        }
+    }
+
+    #[test]
+    fn refund_without_balance() {
+        let mut deps = deps_with_balances(&[("stable_coin", 10_000)]);
+        let err = refund(&mut deps, "admin", "airdrop").unwrap_err();
+        assert!(err.to_string().contains("holds no airdrop"));
+    }
+
+    #[test]
+    fn refund_admin_only() {
+        let mut deps = deps_with_balances(&[("airdrop", 42)]);
+        assert!(refund(&mut deps, "lp", "airdrop").is_err());
+    }
+
+    fn sweep_dust(deps: &mut MockDeps, sender: &str) -> ContractResponse {
         execute(
-            default_deps(None).as_mut(),
+            deps.as_mut(),
             mock_env(),
-            mock_info("admin", &vec![]),
-            HandleMsg::Recover {
-                lp: Addr::unchecked("lp_2"),
+            mock_info(sender, &[]),
+            ExecuteMsg::SweepDust {
+                to: Addr::unchecked("lp_side_account"),
             },
         )
-        .unwrap();
     }
 
     #[test]
-    fn bad_actor_recover_fail() {
+    fn sweep_dust_remainders() {
+        let mut deps = deps_with_balances(&[
+            ("stable_coin", 10_050),
+            ("other_coin", 2_000_000),
+            ("airdrop", 7),
+        ]);
+        state_storage(&mut deps.storage)
+            .update(|mut state| -> StdResult<_> {
+                state.like_capital_denoms =
+                    vec![String::from("stable_coin"), String::from("other_coin")];
+                state.capital_per_share_overrides = vec![(String::from("other_coin"), 1_000_000)];
+                Ok(state)
+            })
+            .unwrap();
+
+        let res = sweep_dust(&mut deps, "lp").unwrap();
+
+        // whole shares and denoms other than like capital stay put
+        assert_eq!(1, res.messages.len());
+        let (to_address, coins) = send_msg(msg_at_index(&res, 0));
+        assert_eq!("lp_side_account", to_address);
+        assert_eq!(&vec![coin(50, "stable_coin")], coins);
+        assert_attr(&res, "action", "sweep_dust");
+        assert_attr(&res, "swept_stable_coin", "50");
+        assert_no_attr(&res, "swept_other_coin");
+    }
+
+    #[test]
+    fn sweep_dust_restricted_marker() {
+        let mut deps = restricted_capital_coin_deps(None);
+        set_balance(&mut deps, "restricted_capital_coin", 10_042);
+        load_markers(&mut deps.querier);
+
+        assert!(sweep_dust(&mut deps, "lp").is_err());
+
+        load_attributes(&mut deps.querier, &[("lp_side_account", "capital.test")]);
+        let res = sweep_dust(&mut deps, "lp").unwrap();
+        assert_eq!(
+            &MarkerMsgParams::TransferMarkerCoins {
+                coin: coin(42, "restricted_capital_coin"),
+                to: Addr::unchecked("lp_side_account"),
+                from: Addr::unchecked(MOCK_CONTRACT_ADDR),
+            },
+            marker_transfer_msg(msg_at_index(&res, 0)),
+        );
+        assert_attr(&res, "swept_restricted_capital_coin", "42");
+    }
+
+    #[test]
+    fn sweep_dust_lp_only() {
+        let mut deps = deps_with_balances(&[("stable_coin", 10_050)]);
+        assert!(sweep_dust(&mut deps, "admin").is_err());
+    }
+
+    /// Simulates completing `exchanges`, then completes them for real and checks that the
+    /// simulation predicted the outcome.
+    fn assert_simulation_matches(
+        deps: &mut MockDeps,
+        sender: &str,
+        exchanges: Vec<AssetExchange>,
+    ) -> SimulateCompleteAssetExchangeResponse {
+        let simulation: SimulateCompleteAssetExchangeResponse = from_binary(
+            &query(
+                deps.as_ref(),
+                mock_env(),
+                QueryMsg::SimulateCompleteAssetExchange {
+                    exchanges: exchanges.clone(),
+                    to: None,
+                    memo: None,
+                    sender: Addr::unchecked(sender),
+                    raise: None,
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap();
+
         let res = execute(
-            default_deps(None).as_mut(),
+            deps.as_mut(),
             mock_env(),
-            mock_info("bad_actor", &vec![]),
-            HandleMsg::Recover {
-                lp: Addr::unchecked("bad_actor"),
+            mock_info(sender, &[]),
+            ExecuteMsg::CompleteAssetExchange {
+                exchanges,
+                to: None,
+                memo: None,
+                override_available_date: false,
+                raise: None,
             },
         );
-        assert_eq!(true, res.is_err());
+        match res {
+            Ok(res) => {
+                assert!(simulation.errors.is_empty());
+                let (raise_call, transfers) = res.messages.split_last().unwrap();
+                let (_, _, funds) = execute_args::<RaiseExecuteMsg>(&raise_call.msg);
+                assert_eq!(&simulation.funds, funds);
+                let transfers: Vec<Coin> = transfers
+                    .iter()
+                    .map(|transfer| match marker_transfer_msg(&transfer.msg) {
+                        MarkerMsgParams::TransferMarkerCoins { coin, .. } => coin.clone(),
+                        _ => unreachable!(),
+                    })
+                    .collect();
+                assert_eq!(simulation.marker_transfers, transfers);
+            }
+            Err(error) => assert_eq!(vec![error.to_string()], simulation.errors),
+        }
+        simulation
     }
 
     #[test]
-    fn authorize_asset_exchange() {
+    fn simulate_complete_asset_exchange() {
+        let mut deps = restricted_capital_coin_deps(None);
+        load_attributes(&mut deps.querier, &[("raise_1", "capital.test")]);
+        load_markers(&mut deps.querier);
+        let exchange = ExchangeBuilder::new()
+            .investment(-1_000)
+            .commitment(-1_000)
+            .capital(-1_000)
+            .build();
+        asset_exchange_authorization_storage(&mut deps.storage)
+            .save(&vec![AssetExchangeAuthorization {
+                id: 1,
+                raise: None,
+                exchanges: vec![exchange.clone()],
+                to: None,
+                memo: None,
+                created_at: None,
+                expires_at: None,
+                memo_hash: None,
+                status: AuthorizationStatus::Pending,
+                resolved_at: None,
+                resolved_by: None,
+            }])
+            .unwrap();
+
+        let simulation = assert_simulation_matches(&mut deps, "lp", vec![exchange.clone()]);
+        assert!(simulation.authorization_found);
+        assert!(!simulation.funds.is_empty());
+        assert!(!simulation.marker_transfers.is_empty());
+
+        // the authorization was used up, so the lp completes without one
+        let simulation = assert_simulation_matches(&mut deps, "lp", vec![exchange]);
+        assert!(!simulation.authorization_found);
+    }
+
+    #[test]
+    fn simulate_complete_asset_exchange_defaulted_denom() {
+        let mut deps = restricted_capital_coin_deps(None);
+        load_attributes(&mut deps.querier, &[("raise_1", "capital.test")]);
+        load_markers(&mut deps.querier);
+        let exchange = || {
+            ExchangeBuilder::new()
+                .investment(-1_000)
+                .commitment(-1_000)
+                .capital(-1_000)
+        };
+        authorize_exchanges(&mut deps, vec![exchange().build()]).unwrap();
+
+        // the authorization left both denoms to their defaults, the completion names them
+        let simulation = assert_simulation_matches(
+            &mut deps,
+            "admin",
+            vec![exchange()
+                .investment_denom("raise_1.investment")
+                .denom("restricted_capital_coin")
+                .build()],
+        );
+        assert!(simulation.authorization_found);
+        assert!(simulation.errors.is_empty());
+        assert!(asset_exchange_authorization_storage_read(&deps.storage)
+            .load()
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn simulate_complete_asset_exchange_failure() {
         let mut deps = default_deps(None);
+        let exchanges = vec![ExchangeBuilder::new().capital(-1_000).build()];
+
+        let simulation = assert_simulation_matches(&mut deps, "admin", exchanges.clone());
+        assert!(!simulation.authorization_found);
+        assert!(simulation.funds.is_empty());
+        assert_eq!(1, simulation.errors.len());
+
+        let simulation = assert_simulation_matches(&mut deps, "stranger", exchanges);
+        assert_eq!(1, simulation.errors.len());
+    }
+
+    /// Simulates a withdrawal, then performs it, checking the simulation predicted the outcome.
+    fn assert_withdrawal_simulation_matches(
+        deps: &mut MockDeps,
+        to: &str,
+        amount: u128,
+        capital_denom: Option<&str>,
+    ) -> SimulateWithdrawalResponse {
+        let simulation: SimulateWithdrawalResponse = from_binary(
+            &query(
+                deps.as_ref(),
+                mock_env(),
+                QueryMsg::SimulateWithdrawal {
+                    to: Addr::unchecked(to),
+                    amount: Uint128::new(amount),
+                    capital_denom: capital_denom.map(String::from),
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap();
+
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("lp", &[]),
+            ExecuteMsg::IssueWithdrawal {
+                to: Some(Addr::unchecked(to)),
+                to_name: None,
+                amount: Uint128::new(amount),
+                capital_denom: capital_denom.map(String::from),
+            },
+        );
+        let failed: Vec<&WithdrawalCheck> = simulation
+            .checks
+            .iter()
+            .filter(|check| !check.passed)
+            .collect();
+        match res {
+            Ok(res) => {
+                assert!(failed.is_empty(), "{:?}", failed);
+                let expected_path = match &res.messages[0].msg {
+                    CosmosMsg::Bank(_) => WithdrawalPath::BankSend,
+                    _ => WithdrawalPath::MarkerTransfer,
+                };
+                assert_eq!(Some(expected_path), simulation.path);
+            }
+            Err(error) => assert_eq!(Some(error.to_string()), failed[0].error),
+        }
+        simulation
+    }
+
+    fn failed_checks(simulation: &SimulateWithdrawalResponse) -> Vec<&str> {
+        simulation
+            .checks
+            .iter()
+            .filter(|check| !check.passed)
+            .map(|check| check.name.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn simulate_withdrawal() {
+        let mut deps = restricted_capital_coin_deps(Some(|state| {
+            state.like_capital_denoms.push(String::from("stable_coin"))
+        }));
+        load_attributes(&mut deps.querier, &[("lp_side_account", "capital.test")]);
+        load_markers(&mut deps.querier);
+
+        let simulation = assert_withdrawal_simulation_matches(
+            &mut deps,
+            "lp_side_account",
+            500,
+            Some("stable_coin"),
+        );
+        assert_eq!(Some(WithdrawalPath::BankSend), simulation.path);
+        assert_eq!(
+            vec!["not_paused", "allowance", "capital_denom"],
+            simulation
+                .checks
+                .iter()
+                .map(|check| check.name.as_str())
+                .collect::<Vec<_>>()
+        );
+
+        let simulation = assert_withdrawal_simulation_matches(
+            &mut deps,
+            "lp_side_account",
+            500,
+            Some("restricted_capital_coin"),
+        );
+        assert_eq!(Some(WithdrawalPath::MarkerTransfer), simulation.path);
+        assert_eq!(5, simulation.checks.len());
+    }
+
+    #[test]
+    fn simulate_withdrawal_failures() {
+        let mut deps = restricted_capital_coin_deps(Some(|state| {
+            state.like_capital_denoms.push(String::from("stable_coin"))
+        }));
+        load_markers(&mut deps.querier);
+
+        let simulation = assert_withdrawal_simulation_matches(
+            &mut deps,
+            "lp_side_account",
+            500,
+            Some("restricted_capital_coin"),
+        );
+        assert_eq!(vec!["attribute"], failed_checks(&simulation));
+        assert_eq!(Some(WithdrawalPath::MarkerTransfer), simulation.path);
 
+        let simulation = assert_withdrawal_simulation_matches(
+            &mut deps,
+            "lp_side_account",
+            500,
+            Some("other_coin"),
+        );
+        assert_eq!(vec!["capital_denom"], failed_checks(&simulation));
+        assert_eq!(None, simulation.path);
+
+        let simulation =
+            assert_withdrawal_simulation_matches(&mut deps, "lp_side_account", 500, None);
+        assert_eq!(vec!["capital_denom"], failed_checks(&simulation));
+
+        set_coin_allowance(&mut deps, "lp_side_account", coin(100, "stable_coin")).unwrap();
+        let simulation = assert_withdrawal_simulation_matches(
+            &mut deps,
+            "lp_side_account",
+            500,
+            Some("stable_coin"),
+        );
+        assert_eq!(vec!["allowance"], failed_checks(&simulation));
+
+        load_attributes(&mut deps.querier, &[("lp_side_account", "capital.test")]);
         execute(
             deps.as_mut(),
             mock_env(),
-            mock_info("lp", &vec![]),
-            HandleMsg::AuthorizeAssetExchange {
-                exchanges: vec![AssetExchange {
-                    investment: Some(1_000),
-                    commitment_in_shares: Some(1_000),
-                    capital: Some(1_000),
-                    date: None,
-                }],
-                to: Some(Addr::unchecked("lp_side_account")),
-                memo: Some(String::from("memo")),
+            mock_info("admin", &[]),
+            ExecuteMsg::SetPaused { paused: true },
+        )
+        .unwrap();
+        let simulation = assert_withdrawal_simulation_matches(
+            &mut deps,
+            "lp_side_account",
+            50,
+            Some("restricted_capital_coin"),
+        );
+        assert_eq!(vec!["not_paused"], failed_checks(&simulation));
+    }
+
+    fn abort(deps: &mut MockDeps, sender: &str, reason: &str) -> ContractResponse {
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(sender, &[]),
+            ExecuteMsg::AbortAssetExchangeAuthorization {
+                exchanges: vec![accept_exchange()],
+                to: None,
+                memo: None,
+                reason: String::from(reason),
+                raise: None,
             },
         )
+    }
+
+    fn aborted_authorizations(deps: &MockDeps) -> Vec<AbortedAuthorization> {
+        let res: AbortedAuthorizationsResponse = from_binary(
+            &query(
+                deps.as_ref(),
+                mock_env(),
+                QueryMsg::GetAbortedAuthorizations {},
+            )
+            .unwrap(),
+        )
         .unwrap();
+        res.aborted_authorizations
+    }
 
-        // verify asset exchange authorization saved
+    #[test]
+    fn abort_asset_exchange_authorization() {
+        let mut deps = default_deps(None);
+        authorize(&mut deps, mock_env()).unwrap();
+        authorize(&mut deps, mock_env()).unwrap();
+
+        let res = abort(&mut deps, "lp", "duplicate submission").unwrap();
         assert_eq!(
-            1,
-            asset_exchange_authorization_storage_read(&deps.storage)
-                .load()
-                .unwrap()
-                .len()
+            vec![Event::new("asset_exchange_authorization_aborted")
+                .add_attribute("authorization_id", "1")
+                .add_attribute("actor", "lp")
+                .add_attribute("reason", "duplicate submission")],
+            res.events
+        );
+        abort(&mut deps, "admin", "kyc lapsed").unwrap();
+
+        assert!(queried_authorizations(&deps).is_empty());
+        assert_eq!(
+            vec![
+                AbortedAuthorization {
+                    authorization_id: 1,
+                    summary: String::from("#1 [com:+1000]"),
+                    actor: Addr::unchecked("lp"),
+                    reason: String::from("duplicate submission"),
+                    time: mock_env().block.time,
+                },
+                AbortedAuthorization {
+                    authorization_id: 2,
+                    summary: String::from("#2 [com:+1000]"),
+                    actor: Addr::unchecked("admin"),
+                    reason: String::from("kyc lapsed"),
+                    time: mock_env().block.time,
+                },
+            ],
+            aborted_authorizations(&deps)
         );
+
+        // nothing left to abort
+        assert!(abort(&mut deps, "admin", "kyc lapsed").is_err());
     }
 
     #[test]
-    fn authorize_asset_exchange_bad_actor() {
+    fn abort_asset_exchange_authorization_rejected() {
         let mut deps = default_deps(None);
+        authorize(&mut deps, mock_env()).unwrap();
 
-        let res = execute(
+        assert!(abort(&mut deps, "operator", "no reason").is_err());
+        assert!(abort(&mut deps, "lp", "").is_err());
+        assert!(abort(&mut deps, "lp", " ").is_err());
+        assert!(abort(&mut deps, "lp", &"x".repeat(MAX_ABORT_REASON_LENGTH + 1)).is_err());
+
+        assert_eq!(1, queried_authorizations(&deps).len());
+        assert!(aborted_authorizations(&deps).is_empty());
+    }
+
+    #[test]
+    fn aborted_authorizations_bounded() {
+        let mut deps = default_deps(None);
+        for height in 0..=MAX_ABORTED_AUTHORIZATIONS {
+            let mut env = mock_env();
+            env.block.height += height as u64;
+            authorize(&mut deps, env).unwrap();
+            abort(&mut deps, "lp", "retry").unwrap();
+        }
+
+        let aborted = aborted_authorizations(&deps);
+        assert_eq!(MAX_ABORTED_AUTHORIZATIONS, aborted.len());
+        assert_eq!(2, aborted[0].authorization_id);
+    }
+
+    fn cancel_accept_exchange(deps: &mut MockDeps) -> ContractResponse {
+        execute(
             deps.as_mut(),
             mock_env(),
-            mock_info("bad_actor", &vec![]),
-            HandleMsg::AuthorizeAssetExchange {
-                exchanges: vec![AssetExchange {
-                    investment: Some(1_000),
-                    commitment_in_shares: Some(1_000),
-                    capital: Some(1_000),
-                    date: None,
-                }],
-                to: Some(Addr::unchecked("lp_side_account")),
-                memo: Some(String::from("memo")),
+            mock_info("lp", &[]),
+            ExecuteMsg::CancelAssetExchangeAuthorization {
+                exchanges: vec![accept_exchange()],
+                to: None,
+                memo: None,
+                raise: None,
+            },
+        )
+    }
+
+    fn statuses(authorizations: &[AuthorizationView]) -> Vec<(u64, AuthorizationStatus)> {
+        authorizations
+            .iter()
+            .map(|authorization| (authorization.id, authorization.status))
+            .collect()
+    }
+
+    #[test]
+    fn completed_authorization_kept_as_history() {
+        let mut deps = default_deps(None);
+        authorize(&mut deps, mock_env()).unwrap();
+        let id = queried_authorizations(&deps)[0].id;
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("lp", &[]),
+            ExecuteMsg::CompleteAssetExchange {
+                exchanges: vec![accept_exchange()],
+                to: None,
+                memo: None,
+                override_available_date: false,
+                raise: None,
             },
+        )
+        .unwrap();
+
+        // no longer pending, so neither listed by default nor matched
+        assert!(queried_authorizations(&deps).is_empty());
+        assert!(matches!(
+            cancel_accept_exchange(&mut deps).unwrap_err(),
+            ContractError::AuthorizationNotFound { .. }
+        ));
+
+        let mut env = mock_env();
+        env.block.height += 1;
+        authorize(&mut deps, env).unwrap();
+        let pending_id = queried_authorizations(&deps)[0].id;
+        assert_eq!(
+            vec![
+                (pending_id, AuthorizationStatus::Pending),
+                (id, AuthorizationStatus::Completed),
+            ],
+            statuses(&queried_authorization_history(&deps, true))
         );
 
-        // verify error
-        assert!(res.is_err());
+        cancel_accept_exchange(&mut deps).unwrap();
+        assert_eq!(
+            vec![
+                (id, AuthorizationStatus::Completed),
+                (pending_id, AuthorizationStatus::Cancelled),
+            ],
+            statuses(&queried_authorization_history(&deps, true))
+        );
     }
 
     #[test]
-    fn cancel_asset_exchange_authorization() {
+    fn authorization_history_bounded() {
         let mut deps = default_deps(None);
+        for height in 0..=MAX_AUTHORIZATION_HISTORY {
+            let mut env = mock_env();
+            env.block.height += height as u64;
+            authorize(&mut deps, env).unwrap();
+            cancel_accept_exchange(&mut deps).unwrap();
+        }
 
-        let exchange = AssetExchange {
-            investment: Some(1_000),
-            commitment_in_shares: Some(1_000),
-            capital: Some(1_000),
-            date: None,
+        let history = queried_authorization_history(&deps, true);
+        assert_eq!(MAX_AUTHORIZATION_HISTORY, history.len());
+        assert_eq!(2, history[0].id);
+    }
+
+    fn commitments(commitment: i64) -> Vec<AssetExchange> {
+        vec![ExchangeBuilder::new().commitment(commitment).build()]
+    }
+
+    fn resolve_commitment(deps: &mut MockDeps, commitment: i64, complete: bool) {
+        let (exchanges, to, memo) = (commitments(commitment), None, None);
+        let msg = if complete {
+            ExecuteMsg::CompleteAssetExchange {
+                exchanges,
+                to,
+                memo,
+                override_available_date: false,
+                raise: None,
+            }
+        } else {
+            ExecuteMsg::CancelAssetExchangeAuthorization {
+                exchanges,
+                to,
+                memo,
+                raise: None,
+            }
         };
-        let to = Some(Addr::unchecked("lp_side_account"));
-        let memo = Some(String::from("memo"));
+        execute(deps.as_mut(), mock_env(), mock_info("lp", &[]), msg).unwrap();
+    }
 
-        asset_exchange_authorization_storage(&mut deps.storage)
-            .save(&vec![AssetExchangeAuthorization {
-                exchanges: vec![exchange.clone()],
-                to: to.clone(),
-                memo: memo.clone(),
-            }])
-            .unwrap();
+    fn authorization_history(
+        deps: &MockDeps,
+        status: Option<AuthorizationStatus>,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    ) -> Vec<AssetExchangeAuthorization> {
+        let res: AuthorizationHistoryResponse = from_binary(
+            &query(
+                deps.as_ref(),
+                mock_env(),
+                QueryMsg::GetAuthorizationHistory {
+                    status,
+                    start_after,
+                    limit,
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        res.authorizations
+    }
+
+    fn ids(authorizations: &[AssetExchangeAuthorization]) -> Vec<u64> {
+        authorizations
+            .iter()
+            .map(|authorization| authorization.id)
+            .collect()
+    }
+
+    #[test]
+    fn authorization_history_status_filters() {
+        let mut deps = default_deps(None);
+        for commitment in 1..=3 {
+            authorize_exchanges(&mut deps, commitments(commitment)).unwrap();
+        }
+        let all = authorization_history(&deps, None, None, None);
+        let (first, second, third) = (all[0].id, all[1].id, all[2].id);
+        resolve_commitment(&mut deps, 1, true);
+        resolve_commitment(&mut deps, 2, false);
+
+        assert_eq!(
+            vec![first, second, third],
+            ids(&authorization_history(&deps, None, None, None))
+        );
+        assert_eq!(
+            vec![third],
+            ids(&authorization_history(
+                &deps,
+                Some(AuthorizationStatus::Pending),
+                None,
+                None
+            ))
+        );
+        let cancelled =
+            authorization_history(&deps, Some(AuthorizationStatus::Cancelled), None, None);
+        assert_eq!(vec![second], ids(&cancelled));
+        let completed =
+            authorization_history(&deps, Some(AuthorizationStatus::Completed), None, None);
+        assert_eq!(vec![first], ids(&completed));
+        assert_eq!(Some(Addr::unchecked("lp")), completed[0].resolved_by);
+        assert_eq!(Some(mock_env().block.time), completed[0].resolved_at);
+        assert_eq!(Some(mock_env().block.time), completed[0].created_at);
+    }
+
+    #[test]
+    fn authorization_history_paginated() {
+        let mut deps = default_deps(None);
+        for commitment in 1..=5 {
+            authorize_exchanges(&mut deps, commitments(commitment)).unwrap();
+        }
+        let all = ids(&authorization_history(&deps, None, None, None));
+        resolve_commitment(&mut deps, 2, false);
+        resolve_commitment(&mut deps, 3, true);
 
+        let page = authorization_history(&deps, None, None, Some(2));
+        assert_eq!(all[..2].to_vec(), ids(&page));
+
+        // a new authorization lands after every existing id and never shifts the next page
+        let mut env = mock_env();
+        env.block.height += 1;
         execute(
             deps.as_mut(),
-            mock_env(),
-            mock_info("lp", &vec![]),
-            HandleMsg::CancelAssetExchangeAuthorization {
-                exchanges: vec![exchange.clone()],
-                to: to.clone(),
-                memo: memo.clone(),
+            env,
+            mock_info("lp", &[]),
+            ExecuteMsg::AuthorizeAssetExchange {
+                exchanges: commitments(6),
+                to: None,
+                memo: None,
+                idempotency_key: None,
+                expires_at: None,
+                memo_hash: None,
+                raise: None,
             },
         )
-        .unwrap();
-
-        // verify asset exchange authorization removed
-        assert_eq!(
-            0,
-            asset_exchange_authorization_storage_read(&deps.storage)
-                .load()
-                .unwrap()
-                .len()
+        .unwrap();
+        let page = authorization_history(&deps, None, Some(page[1].id), Some(2));
+        assert_eq!(all[2..4].to_vec(), ids(&page));
+        assert_eq!(
+            vec![AuthorizationStatus::Completed, AuthorizationStatus::Pending],
+            page.iter()
+                .map(|authorization| authorization.status)
+                .collect::<Vec<_>>()
         );
-    }
-
-    #[test]
-    fn cancel_asset_exchange_authorization_bad_actor() {
-        let mut deps = default_deps(None);
 
-        let exchange = AssetExchange {
-            investment: Some(1_000),
-            commitment_in_shares: Some(1_000),
-            capital: Some(1_000),
-            date: None,
-        };
-        let to = Some(Addr::unchecked("lp_side_account"));
-        let memo = Some(String::from("memo"));
+        let pending = authorization_history(
+            &deps,
+            Some(AuthorizationStatus::Pending),
+            Some(all[0]),
+            None,
+        );
+        assert_eq!(3, pending.len());
+        assert_eq!(all[3], pending[0].id);
+    }
 
-        asset_exchange_authorization_storage(&mut deps.storage)
-            .save(&vec![AssetExchangeAuthorization {
-                exchanges: vec![exchange.clone()],
-                to: to.clone(),
-                memo: memo.clone(),
-            }])
-            .unwrap();
+    fn capital_call(capital: i64) -> Vec<AssetExchange> {
+        vec![ExchangeBuilder::new().capital(capital).build()]
+    }
 
-        let res = execute(
+    fn complete_partial(deps: &mut MockDeps, partial: i64) -> ContractResponse {
+        execute(
             deps.as_mut(),
             mock_env(),
-            mock_info("bad_actor", &vec![]),
-            HandleMsg::CancelAssetExchangeAuthorization {
-                exchanges: vec![exchange.clone()],
-                to: to.clone(),
-                memo: memo.clone(),
+            mock_info("lp", &[]),
+            ExecuteMsg::CompleteAssetExchangePartial {
+                exchanges: capital_call(-100_000),
+                to: None,
+                memo: None,
+                fraction_exchanges: capital_call(partial),
+                raise: None,
             },
-        );
-
-        // verify error
-        assert!(res.is_err());
+        )
     }
 
-    #[test]
-    fn complete_asset_exchange_accept_only() {
-        let mut deps = capital_coin_deps(None);
-        load_markers(&mut deps.querier);
-        let exchange = AssetExchange {
-            investment: Some(1_000),
-            commitment_in_shares: Some(1_000),
-            capital: Some(1_000),
-            date: None,
-        };
-        let to = Some(Addr::unchecked("lp_side_account"));
-        let memo = Some(String::from("memo"));
-        let res = execute(
+    fn authorize_capital_call(deps: &mut MockDeps) {
+        execute(
             deps.as_mut(),
             mock_env(),
-            mock_info("lp", &vec![]),
-            HandleMsg::CompleteAssetExchange {
-                exchanges: vec![exchange.clone()],
-                to: to.clone(),
-                memo: memo.clone(),
+            mock_info("lp", &[]),
+            ExecuteMsg::AuthorizeAssetExchange {
+                exchanges: capital_call(-100_000),
+                to: None,
+                memo: None,
+                idempotency_key: None,
+                expires_at: None,
+                memo_hash: None,
+                raise: None,
             },
         )
         .unwrap();
+    }
 
-        // verify exec message sent
-        assert_eq!(1, res.messages.len());
-        let (recipient, msg, funds) = execute_args::<RaiseExecuteMsg>(msg_at_index(&res, 0));
-        assert_eq!("raise_1", recipient);
+    #[test]
+    fn complete_asset_exchange_partial() {
+        let mut deps = default_deps(None);
+        authorize_capital_call(&mut deps);
+
+        let res = complete_partial(&mut deps, -60_000).unwrap();
+        let (_, msg, funds) = execute_args::<RaiseExecuteMsg>(msg_at_index(&res, 0));
         assert_eq!(
             RaiseExecuteMsg::CompleteAssetExchange {
-                exchanges: vec![exchange.clone()],
-                to,
-                memo
+                exchanges: capital_call(-60_000),
+                to: None,
+                memo: None,
             },
             msg
         );
+        assert_eq!(&vec![coin(60_000, "stable_coin")], funds);
+        assert_attr(&res, "remaining", "cap:-40000");
 
-        // verify no funds sent
-        assert_eq!(0, funds.len());
-    }
+        let remaining = queried_authorizations(&deps);
+        assert_eq!(1, remaining.len());
+        assert_eq!(1, remaining[0].id);
+        assert_eq!(capital_call(-40_000), remaining[0].exchanges);
 
-    #[test]
-    fn complete_asset_exchange_send_only() {
-        let mut deps = capital_coin_deps(None);
-        load_markers(&mut deps.querier);
-        let exchange = AssetExchange {
-            investment: Some(-1_000),
-            commitment_in_shares: Some(-1_000),
-            capital: Some(-1_000),
-            date: None,
-        };
-        let to = Some(Addr::unchecked("lp_side_account"));
-        let memo = Some(String::from("memo"));
-        let res = execute(
+        // the remainder completes as an ordinary authorization
+        execute(
             deps.as_mut(),
             mock_env(),
-            mock_info("lp", &vec![]),
-            HandleMsg::CompleteAssetExchange {
-                exchanges: vec![exchange.clone(), exchange.clone()],
-                to: to.clone(),
-                memo: memo.clone(),
+            mock_info("admin", &[]),
+            ExecuteMsg::CompleteAssetExchange {
+                exchanges: capital_call(-40_000),
+                to: None,
+                memo: None,
+                override_available_date: false,
+                raise: None,
             },
         )
         .unwrap();
+        assert!(queried_authorizations(&deps).is_empty());
+    }
 
-        // verify exec message sent
-        assert_eq!(1, res.messages.len());
-        let (recipient, msg, funds) = execute_args::<RaiseExecuteMsg>(msg_at_index(&res, 0));
-        assert_eq!("raise_1", recipient);
+    #[test]
+    fn complete_asset_exchange_partial_rejected() {
+        let mut deps = default_deps(None);
+        authorize_capital_call(&mut deps);
+
+        // the whole amount, more than authorized, the wrong sign, and a fraction of a share
+        for partial in [-100_000, -120_000, 60_000, 0, -60_050] {
+            assert!(complete_partial(&mut deps, partial).is_err());
+        }
         assert_eq!(
-            RaiseExecuteMsg::CompleteAssetExchange {
-                exchanges: vec![exchange.clone(), exchange.clone()],
-                to,
-                memo
-            },
-            msg
+            capital_call(-100_000),
+            queried_authorizations(&deps)[0].exchanges
         );
+    }
 
-        // verify funds sent
-        assert_eq!(3, funds.len());
-        let capital = funds.get(0).unwrap();
-        assert_eq!(2_000, capital.amount.u128());
-        let commitment = funds.get(1).unwrap();
-        assert_eq!(2_000, commitment.amount.u128());
-        let investment = funds.get(2).unwrap();
-        assert_eq!(2_000, investment.amount.u128());
+    #[test]
+    fn complete_asset_exchange_partial_requires_authorization() {
+        let mut deps = default_deps(None);
+        assert!(complete_partial(&mut deps, -60_000).is_err());
     }
 
     #[test]
-    fn complete_asset_exchange_restricted_marker_send_only() {
-        let mut deps = restricted_capital_coin_deps(None);
-        deps.querier
-            .with_attributes("raise_1", &[("capital.test", "", "")]);
-        load_markers(&mut deps.querier);
-        let exchange = AssetExchange {
-            investment: Some(-1_000),
-            commitment_in_shares: Some(-1_000),
-            capital: Some(-1_000),
-            date: None,
-        };
-        let to = Some(Addr::unchecked("lp_side_account"));
-        let memo = Some(String::from("memo"));
-        let res = execute(
+    fn complete_asset_exchange_partial_keeps_recipient() {
+        let mut deps = default_deps(Some(with_operator));
+        let payout = |capital, to| vec![ExchangeBuilder::new().capital(capital).to(to).build()];
+        execute(
             deps.as_mut(),
             mock_env(),
-            mock_info("lp", &vec![]),
-            HandleMsg::CompleteAssetExchange {
-                exchanges: vec![exchange.clone(), exchange.clone()],
-                to: to.clone(),
-                memo: memo.clone(),
+            mock_info("lp", &[]),
+            ExecuteMsg::AuthorizeAssetExchange {
+                exchanges: payout(100_000, "lp_wallet"),
+                to: None,
+                memo: None,
+                idempotency_key: None,
+                expires_at: None,
+                memo_hash: None,
+                raise: None,
             },
         )
         .unwrap();
 
-        // verify exec message sent
-        assert_eq!(2, res.messages.len());
-        let (recipient, msg, funds) = execute_args::<RaiseExecuteMsg>(msg_at_index(&res, 1));
-        assert_eq!("raise_1", recipient);
-        assert_eq!(
-            RaiseExecuteMsg::CompleteAssetExchange {
-                exchanges: vec![exchange.clone(), exchange.clone()],
-                to,
-                memo
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("operator", &[]),
+            ExecuteMsg::CompleteAssetExchangePartial {
+                exchanges: payout(100_000, "lp_wallet"),
+                to: None,
+                memo: None,
+                fraction_exchanges: payout(60_000, "bad_actor"),
+                raise: None,
             },
-            msg
         );
-
-        // verify funds sent
+        assert!(res.is_err());
         assert_eq!(
-            &MarkerMsgParams::TransferMarkerCoins {
-                coin: coin(2_000, "restricted_capital_coin"),
-                to: Addr::unchecked("raise_1"),
-                from: Addr::unchecked(MOCK_CONTRACT_ADDR),
+            payout(100_000, "lp_wallet"),
+            queried_authorizations(&deps)[0].exchanges
+        );
+
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("operator", &[]),
+            ExecuteMsg::CompleteAssetExchangePartial {
+                exchanges: payout(100_000, "lp_wallet"),
+                to: None,
+                memo: None,
+                fraction_exchanges: payout(60_000, "lp_wallet"),
+                raise: None,
             },
-            marker_transfer_msg(msg_at_index(&res, 0)),
+        )
+        .unwrap();
+        assert_eq!(
+            payout(40_000, "lp_wallet"),
+            queried_authorizations(&deps)[0].exchanges
         );
-        assert_eq!(2, funds.len());
-        // let capital = funds.get(0).unwrap();
-        // assert_eq!(2_000, capital.amount.u128());
-        let commitment = funds.get(0).unwrap();
-        assert_eq!(2_000, commitment.amount.u128());
-        let investment = funds.get(1).unwrap();
-        assert_eq!(2_000, investment.amount.u128());
+    }
+
+    fn amend(
+        deps: &mut MockDeps,
+        old: Vec<AssetExchange>,
+        new_exchanges: Vec<AssetExchange>,
+    ) -> ContractResponse {
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("lp", &[]),
+            ExecuteMsg::AmendAssetExchangeAuthorization {
+                old: AuthorizationRef {
+                    exchanges: old,
+                    to: None,
+                    memo: None,
+                    raise: None,
+                },
+                new_exchanges,
+                new_to: None,
+                new_memo: Some(String::from("amended")),
+            },
+        )
     }
 
     #[test]
-    fn complete_asset_exchange_admin() {
-        let mut deps = capital_coin_deps(None);
-        load_markers(&mut deps.querier);
-        let exchange = AssetExchange {
-            investment: Some(1_000),
-            commitment_in_shares: Some(1_000),
-            capital: Some(1_000),
-            date: None,
+    fn authorization_lookups_resolve_denoms() {
+        // authorized with the capital denom left to its default, referred to by name
+        let named = |capital| {
+            vec![ExchangeBuilder::new()
+                .capital(capital)
+                .denom("stable_coin")
+                .build()]
         };
-        let to = Some(Addr::unchecked("lp_side_account"));
-        let memo = Some(String::from("memo"));
-
-        asset_exchange_authorization_storage(&mut deps.storage)
-            .save(&vec![AssetExchangeAuthorization {
-                exchanges: vec![exchange.clone()],
-                to: to.clone(),
-                memo: memo.clone(),
-            }])
-            .unwrap();
 
+        let mut deps = default_deps(None);
+        authorize_capital_call(&mut deps);
         let res = execute(
             deps.as_mut(),
             mock_env(),
-            mock_info("admin", &vec![]),
-            HandleMsg::CompleteAssetExchange {
-                exchanges: vec![exchange.clone()],
-                to: to.clone(),
-                memo: memo.clone(),
+            mock_info("lp", &[]),
+            ExecuteMsg::CompleteAssetExchangePartial {
+                exchanges: named(-100_000),
+                to: None,
+                memo: None,
+                fraction_exchanges: named(-60_000),
+                raise: None,
             },
         )
         .unwrap();
-
-        // verify exec message sent
-        assert_eq!(1, res.messages.len());
-        let (recipient, msg, funds) = execute_args::<RaiseExecuteMsg>(msg_at_index(&res, 0));
-        assert_eq!("raise_1", recipient);
+        // both parts keep the stored form rather than the one named in the message
+        let (_, msg, _) = execute_args::<RaiseExecuteMsg>(msg_at_index(&res, 0));
         assert_eq!(
             RaiseExecuteMsg::CompleteAssetExchange {
-                exchanges: vec![exchange.clone()],
-                to,
-                memo
+                exchanges: capital_call(-60_000),
+                to: None,
+                memo: None,
             },
             msg
         );
-
-        // verify no funds sent
-        assert_eq!(0, funds.len());
-
-        // verify asset exchange authorization removed
         assert_eq!(
-            0,
-            asset_exchange_authorization_storage_read(&deps.storage)
-                .load()
-                .unwrap()
-                .len()
+            capital_call(-40_000),
+            queried_authorizations(&deps)[0].exchanges
         );
-    }
 
-    #[test]
-    fn complete_asset_exchange_bad_actor() {
         let mut deps = default_deps(None);
+        authorize_capital_call(&mut deps);
+        amend(&mut deps, named(-100_000), capital_call(-60_000)).unwrap();
 
-        let exchange = AssetExchange {
-            investment: Some(1_000),
-            commitment_in_shares: Some(1_000),
-            capital: Some(1_000),
-            date: None,
-        };
-        let to = Some(Addr::unchecked("lp_side_account"));
-        let memo = Some(String::from("memo"));
+        let mut deps = default_deps(None);
+        authorize_capital_call(&mut deps);
+        let res = cancel_authorized_exchanges(&mut deps, named(-100_000), named(-100_000)).unwrap();
+        assert_attr(&res, "cancelled", "true");
+    }
 
-        asset_exchange_authorization_storage(&mut deps.storage)
-            .save(&vec![AssetExchangeAuthorization {
-                exchanges: vec![exchange.clone()],
-                to: to.clone(),
-                memo: memo.clone(),
-            }])
-            .unwrap();
+    #[test]
+    fn amend_asset_exchange_authorization() {
+        let mut deps = default_deps(None);
+        authorize_capital_call(&mut deps);
 
-        let res = execute(
-            deps.as_mut(),
-            mock_env(),
-            mock_info("bad_actor", &vec![]),
-            HandleMsg::CompleteAssetExchange {
-                exchanges: vec![exchange.clone()],
-                to: to.clone(),
-                memo: memo.clone(),
-            },
-        );
+        let res = amend(&mut deps, capital_call(-100_000), capital_call(-60_000)).unwrap();
+        assert_attr(&res, "amended_authorization_id", "1");
+        assert_attr(&res, "authorization_id", "2");
 
-        // verify error
-        assert!(res.is_err());
+        let authorizations = queried_authorizations(&deps);
+        assert_eq!(1, authorizations.len());
+        assert_eq!(capital_call(-60_000), authorizations[0].exchanges);
+        assert_eq!(Some(String::from("amended")), authorizations[0].memo);
     }
 
     #[test]
-    fn withdraw() {
-        let mut deps = capital_coin_deps(None);
-        load_markers(&mut deps.querier);
-        let res = execute(
-            deps.as_mut(),
-            mock_env(),
-            mock_info("lp", &vec![]),
-            HandleMsg::IssueWithdrawal {
-                to: Addr::unchecked("lp_side_account"),
-                amount: 10_000,
-            },
-        )
-        .unwrap();
+    fn amend_missing_authorization() {
+        let mut deps = default_deps(None);
+        authorize_capital_call(&mut deps);
 
-        // verify send message sent
-        assert_eq!(1, res.messages.len());
-        let (to_address, coins) = send_msg(msg_at_index(&res, 0));
-        assert_eq!("lp_side_account", to_address);
-        assert_eq!(10_000, coins.first().unwrap().amount.u128());
+        assert!(amend(&mut deps, capital_call(-90_000), capital_call(-60_000)).is_err());
+        let authorizations = queried_authorizations(&deps);
+        assert_eq!(1, authorizations.len());
+        assert_eq!(capital_call(-100_000), authorizations[0].exchanges);
     }
 
     #[test]
-    fn withdraw_restricted_marker() {
-        let mut deps = restricted_capital_coin_deps(None);
-        deps.querier
-            .with_attributes("lp_side_account", &[("capital.test", "", "")]);
-        load_markers(&mut deps.querier);
+    fn amend_validates_replacement() {
+        let mut deps = default_deps(None);
+        authorize_capital_call(&mut deps);
+
+        let unsupported = vec![ExchangeBuilder::new()
+            .capital(-60_000)
+            .denom("other_coin")
+            .build()];
+        assert!(amend(&mut deps, capital_call(-100_000), unsupported).is_err());
+        assert_eq!(
+            capital_call(-100_000),
+            queried_authorizations(&deps)[0].exchanges
+        );
+
+        // only the lp amends
         let res = execute(
             deps.as_mut(),
             mock_env(),
-            mock_info("lp", &vec![]),
-            HandleMsg::IssueWithdrawal {
-                to: Addr::unchecked("lp_side_account"),
-                amount: 10_000,
-            },
-        )
-        .unwrap();
-
-        // verify send message sent
-        assert_eq!(1, res.messages.len());
-        assert_eq!(
-            &MarkerMsgParams::TransferMarkerCoins {
-                coin: coin(10_000, "restricted_capital_coin"),
-                to: Addr::unchecked("lp_side_account"),
-                from: Addr::unchecked(MOCK_CONTRACT_ADDR),
+            mock_info("admin", &[]),
+            ExecuteMsg::AmendAssetExchangeAuthorization {
+                old: AuthorizationRef {
+                    exchanges: capital_call(-100_000),
+                    to: None,
+                    memo: None,
+                    raise: None,
+                },
+                new_exchanges: capital_call(-60_000),
+                new_to: None,
+                new_memo: None,
             },
-            marker_transfer_msg(msg_at_index(&res, 0)),
         );
-    }
-
-    #[test]
-    fn withdraw_bad_actor() {
-        let mut deps = default_deps(None);
+        assert!(res.is_err());
 
+        // a capital call pays nothing out, so it can't name a recipient
         let res = execute(
             deps.as_mut(),
             mock_env(),
-            mock_info("bad_actor", &vec![]),
-            HandleMsg::IssueWithdrawal {
-                to: Addr::unchecked("lp_side_account"),
-                amount: 10_000,
+            mock_info("lp", &[]),
+            ExecuteMsg::AmendAssetExchangeAuthorization {
+                old: AuthorizationRef {
+                    exchanges: capital_call(-100_000),
+                    to: None,
+                    memo: None,
+                    raise: None,
+                },
+                new_exchanges: capital_call(-60_000),
+                new_to: Some(Addr::unchecked("lp_side_account")),
+                new_memo: None,
             },
         );
         assert!(res.is_err());
+        assert_eq!(None, queried_authorizations(&deps)[0].to);
     }
 }
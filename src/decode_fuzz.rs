@@ -0,0 +1,341 @@
+//! Structured mutation of known-good messages. Every mutant must either fail to decode or decode
+//! to a value that re-serializes to the same value and keeps everything the input said: a field
+//! that was given can't go missing from the decoded form, which is how a misspelled or duplicated
+//! exchange leg would otherwise turn into a silently smaller exchange.
+
+use std::fmt::Debug;
+
+use cosmwasm_std::{from_binary, to_vec, Binary};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::{json, Map, Value};
+
+use crate::mock::ExchangeBuilder;
+use crate::msg::{AssetExchange, ExecuteMsg, QueryMsg};
+
+/// The long field names of an exchange and the short names they are written with.
+const EXCHANGE_ALIASES: &[(&str, &str)] = &[
+    ("investment", "inv"),
+    ("investment_denom", "inv_d"),
+    ("commitment_in_shares", "com"),
+    ("capital", "cap"),
+    ("capital_denom", "cap_d"),
+];
+
+fn alias(key: &str) -> Option<&'static str> {
+    EXCHANGE_ALIASES.iter().find_map(|(long, short)| match key {
+        _ if key == *long => Some(*short),
+        _ if key == *short => Some(*long),
+        _ => None,
+    })
+}
+
+fn execute_fixtures() -> Vec<Value> {
+    vec![
+        json!({"authorize_asset_exchange": {
+            "exchanges": [
+                {"inv": "1000", "inv_d": "raise_1.investment", "com": "-10"},
+                {"cap": "-1000", "cap_d": "stable_coin", "date": {"due": 1_700_000_000u64}},
+                {"com": "5", "to": "side_a"}
+            ],
+            "to": "lp_side_account",
+            "memo": "memo",
+            "idempotency_key": "key-1",
+            "expires_at": "1700086400000000000",
+            "memo_hash": null
+        }}),
+        json!({"complete_asset_exchange": {
+            "exchanges": [{"com": "10", "cap": "1000"}],
+            "to": null,
+            "memo": "memo",
+            "override_available_date": false
+        }}),
+        json!({"cancel_authorized_exchanges": {
+            "authorization": {"exchanges": [{"com": "10"}, {"com": "20"}], "to": null, "memo": null},
+            "exchanges_to_remove": [{"com": "10"}]
+        }}),
+        json!({"issue_withdrawal": {
+            "to": "lp",
+            "to_name": null,
+            "amount": "300",
+            "capital_denom": "stable_coin"
+        }}),
+        json!({"set_withdrawal_allowance": {"to": "ops", "amount": 5000, "period_seconds": 86400}}),
+        json!({"approve_withdrawal": {"to": "ops", "amount": "5000", "ttl_seconds": 3600}}),
+        json!({"return_capital": {"amount": "500", "capital_denom": null, "memo": "excess"}}),
+        json!({"set_paused": {"paused": true}}),
+    ]
+}
+
+fn query_fixtures() -> Vec<Value> {
+    vec![
+        json!({"get_authorization_history": {"status": "pending", "start_after": 3, "limit": 5}}),
+        json!({"simulate_complete_asset_exchange": {
+            "exchanges": [{"inv": "-100", "com": "-1", "cap": "100"}],
+            "to": null,
+            "memo": null,
+            "sender": "lp"
+        }}),
+        json!({"simulate_withdrawal": {"to": "lp", "amount": "300", "capital_denom": null}}),
+        json!({"get_deposits": {"start_after": null, "limit": 10}}),
+        json!({"get_version": {}}),
+        json!({"get_raise_holdings": {}}),
+    ]
+}
+
+fn exchange_fixtures() -> Vec<Value> {
+    vec![
+        json!({"inv": "1000", "inv_d": "raise_1.investment", "com": "-10", "cap": "-1000", "cap_d": "stable_coin"}),
+        json!({"investment": 1000, "commitment_in_shares": -10, "capital": -1000}),
+        json!({"com": "10", "date": {"avl": 1_700_000_000u64}, "to": "side_a"}),
+    ]
+}
+
+/// Replacements for a scalar: wrong types, edge amounts, and the number/string spelling of it.
+fn scalar_replacements(value: &Value) -> Vec<Value> {
+    let mut replacements = vec![
+        Value::Null,
+        json!(true),
+        json!(0),
+        json!(-1),
+        json!(1.5),
+        json!(""),
+        json!("-1"),
+        json!("1.5"),
+        json!("340282366920938463463374607431768211456"),
+        json!([]),
+        json!({}),
+    ];
+    match value {
+        Value::Number(number) => replacements.push(Value::String(number.to_string())),
+        Value::String(string) => {
+            if let Ok(number) = string.parse::<i64>() {
+                replacements.push(json!(number));
+            }
+        }
+        _ => {}
+    }
+    replacements
+}
+
+/// Every single-step mutation of `value`: removed, renamed, duplicated and retyped fields,
+/// emptied and grown arrays.
+fn mutants(value: &Value) -> Vec<Value> {
+    let mut mutated = vec![Value::Null];
+    match value {
+        Value::Object(map) => {
+            for (key, field) in map {
+                let with = |edit: &dyn Fn(&mut Map<String, Value>)| {
+                    let mut map = map.clone();
+                    edit(&mut map);
+                    Value::Object(map)
+                };
+                mutated.push(with(&|map| {
+                    map.remove(key);
+                }));
+                mutated.push(with(&|map| {
+                    let field = map.remove(key).unwrap();
+                    map.insert(format!("{}s", key), field);
+                }));
+                if let Some(alias) = alias(key) {
+                    mutated.push(with(&|map| {
+                        let field = map.remove(key).unwrap();
+                        map.insert(alias.to_string(), field);
+                    }));
+                    mutated.push(with(&|map| {
+                        map.insert(alias.to_string(), field.clone());
+                    }));
+                }
+                for mutant in mutants(field) {
+                    mutated.push(with(&|map| {
+                        map.insert(key.clone(), mutant.clone());
+                    }));
+                }
+            }
+        }
+        Value::Array(items) => {
+            mutated.push(json!([]));
+            if let Some(first) = items.first() {
+                let mut grown = items.clone();
+                grown.push(first.clone());
+                mutated.push(Value::Array(grown));
+            }
+            for (index, item) in items.iter().enumerate() {
+                for mutant in mutants(item) {
+                    let mut items = items.clone();
+                    items[index] = mutant;
+                    mutated.push(Value::Array(items));
+                }
+            }
+        }
+        scalar => mutated.extend(scalar_replacements(scalar)),
+    }
+    mutated
+}
+
+fn scalar_text(value: &Value) -> Option<String> {
+    match value {
+        Value::Bool(bool) => Some(bool.to_string()),
+        Value::Number(number) => Some(number.to_string()),
+        Value::String(string) => Some(string.clone()),
+        _ => None,
+    }
+}
+
+/// Checks that everything `input` gave survives into `output`, the re-serialized decoding.
+/// Fields may be renamed to their short alias, and amounts may move between number and string.
+fn assert_preserved(input: &Value, output: &Value, path: &str) {
+    match (input, output) {
+        (Value::Null, _) => {}
+        (Value::Object(input), Value::Object(output)) => {
+            for (key, field) in input.iter().filter(|(_, field)| !field.is_null()) {
+                let decoded = output
+                    .get(key)
+                    .or_else(|| alias(key).and_then(|alias| output.get(alias)))
+                    .unwrap_or_else(|| panic!("{}.{} was dropped", path, key));
+                assert_preserved(field, decoded, &format!("{}.{}", path, key));
+            }
+        }
+        (Value::Array(input), Value::Array(output)) => {
+            assert_eq!(input.len(), output.len(), "{} changed length", path);
+            for (index, (input, output)) in input.iter().zip(output).enumerate() {
+                assert_preserved(input, output, &format!("{}[{}]", path, index));
+            }
+        }
+        (input, output) => assert_eq!(
+            scalar_text(input),
+            scalar_text(output),
+            "{} decoded to {} from {}",
+            path,
+            output,
+            input
+        ),
+    }
+}
+
+/// Decodes `input` as `T`. A decoded value must re-serialize to itself and preserve the input.
+fn check<T>(input: &Value) -> bool
+where
+    T: Serialize + DeserializeOwned + PartialEq + Debug,
+{
+    let bytes = Binary::from(serde_json::to_vec(input).unwrap());
+    match from_binary::<T>(&bytes) {
+        Err(_) => false,
+        Ok(decoded) => {
+            let canonical = to_vec(&decoded).unwrap();
+            assert_eq!(
+                decoded,
+                from_binary::<T>(&Binary::from(canonical.clone())).unwrap(),
+                "{} does not round trip",
+                input
+            );
+            let output: Value = serde_json::from_slice(&canonical).unwrap();
+            assert_preserved(input, &output, "");
+            true
+        }
+    }
+}
+
+/// Runs every mutant of every fixture, returning how many decoded and how many were refused.
+fn fuzz<T>(fixtures: Vec<Value>) -> (usize, usize)
+where
+    T: Serialize + DeserializeOwned + PartialEq + Debug,
+{
+    let mut outcomes = (0, 0);
+    for fixture in fixtures {
+        assert!(check::<T>(&fixture), "fixture {} must decode", fixture);
+        for mutant in mutants(&fixture) {
+            if check::<T>(&mutant) {
+                outcomes.0 += 1;
+            } else {
+                outcomes.1 += 1;
+            }
+        }
+    }
+    outcomes
+}
+
+#[test]
+fn mutated_execute_msgs() {
+    let (decoded, refused) = fuzz::<ExecuteMsg>(execute_fixtures());
+    assert!(decoded > 0 && refused > 0);
+}
+
+#[test]
+fn mutated_query_msgs() {
+    let (decoded, refused) = fuzz::<QueryMsg>(query_fixtures());
+    assert!(decoded > 0 && refused > 0);
+}
+
+#[test]
+fn mutated_asset_exchanges() {
+    let (decoded, refused) = fuzz::<AssetExchange>(exchange_fixtures());
+    assert!(decoded > 0 && refused > 0);
+}
+
+#[test]
+#[should_panic(expected = ".cap was dropped")]
+fn dropped_leg_detected() {
+    assert_preserved(
+        &json!({"com": "10", "cap": "100"}),
+        &json!({"com": "10"}),
+        "",
+    );
+}
+
+fn decode<T: DeserializeOwned>(json: &str) -> Result<T, String> {
+    from_binary(&Binary::from(json.as_bytes())).map_err(|err| err.to_string())
+}
+
+/// Payload shapes that have caused trouble, or would have before the message types rejected
+/// them.
+#[test]
+fn regression_fixtures() {
+    // a misspelled denom on a capital leg used to fall back to the default denom
+    let err =
+        decode::<AssetExchange>(r#"{"cap":"-1000","capital_denoms":"stable_coin"}"#).unwrap_err();
+    assert!(err.contains("unknown field `capital_denoms`"), "{}", err);
+    let err = decode::<ExecuteMsg>(
+        r#"{"issue_withdrawal":{"to":"lp","amount":"300","capital_denoms":"stable_coin"}}"#,
+    )
+    .unwrap_err();
+    assert!(err.contains("unknown field `capital_denoms`"), "{}", err);
+
+    // a leg given under both its short and long name, or twice, can't pick one silently
+    let err = decode::<AssetExchange>(r#"{"cap":"100","capital":"200"}"#).unwrap_err();
+    assert!(err.contains("duplicate field `cap`"), "{}", err);
+    let err = decode::<AssetExchange>(r#"{"com":"10","com":"20"}"#).unwrap_err();
+    assert!(err.contains("duplicate field `com`"), "{}", err);
+
+    // legacy bare numbers read the same as their string encoding
+    assert_eq!(
+        decode::<AssetExchange>(r#"{"com":"-10","cap":"1000"}"#).unwrap(),
+        decode::<AssetExchange>(r#"{"com":-10,"cap":1000}"#).unwrap()
+    );
+    assert_eq!(
+        ExchangeBuilder::new()
+            .commitment(-10)
+            .capital(1_000)
+            .build(),
+        decode::<AssetExchange>(r#"{"com":-10,"cap":1000}"#).unwrap()
+    );
+
+    // fractional and out of range amounts are refused rather than truncated
+    assert!(decode::<AssetExchange>(r#"{"cap":1000.5}"#).is_err());
+    assert!(decode::<AssetExchange>(r#"{"cap":"1000.5"}"#).is_err());
+    assert!(
+        decode::<AssetExchange>(r#"{"cap":"170141183460469231731687303715884105728"}"#).is_err()
+    );
+    assert!(decode::<ExecuteMsg>(r#"{"issue_withdrawal":{"to":"lp","amount":-300}}"#).is_err());
+
+    // an omitted optional is still None, not an error
+    assert_eq!(
+        ExecuteMsg::IssueWithdrawal {
+            to: Some(cosmwasm_std::Addr::unchecked("lp")),
+            to_name: None,
+            amount: cosmwasm_std::Uint128::new(300),
+            capital_denom: None,
+        },
+        decode::<ExecuteMsg>(r#"{"issue_withdrawal":{"to":"lp","amount":"300"}}"#).unwrap()
+    );
+}
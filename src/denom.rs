@@ -0,0 +1,85 @@
+use crate::error::ContractError;
+
+/// How a capital denom is held and moved. IBC vouchers are plain bank coins, so marker
+/// transfers and attribute requirements never apply to them.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DenomKind {
+    Native,
+    Ibc,
+}
+
+pub fn classify_denom(denom: &str) -> Result<DenomKind, ContractError> {
+    if let Some(hash) = denom.strip_prefix("ibc/") {
+        if hash.len() == 64 && hash.bytes().all(|b| matches!(b, b'0'..=b'9' | b'A'..=b'F')) {
+            return Ok(DenomKind::Ibc);
+        }
+        return Err(ContractError::from(format!(
+            "invalid ibc denom {}, expected ibc/ followed by a 64 character uppercase hex hash",
+            denom
+        )));
+    }
+
+    let mut chars = denom.chars();
+    let valid = (3..=128).contains(&denom.len())
+        && matches!(chars.next(), Some(c) if c.is_ascii_alphabetic())
+        && chars.all(|c| c.is_ascii_alphanumeric() || "/:._-".contains(c));
+    if valid {
+        Ok(DenomKind::Native)
+    } else {
+        Err(ContractError::from(format!("invalid denom {}", denom)))
+    }
+}
+
+pub fn is_ibc_denom(denom: &str) -> bool {
+    matches!(classify_denom(denom), Ok(DenomKind::Ibc))
+}
+
+pub fn validate_like_capital_denoms(denoms: &[String]) -> Result<(), ContractError> {
+    if denoms.is_empty() {
+        return Err(ContractError::from(
+            "at least one like capital denom is required",
+        ));
+    }
+    for denom in denoms {
+        classify_denom(denom)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const IBC_DENOM: &str = "ibc/27394FB092D2ECCD56123C74F36E4C1F926001CEADA9CA97EA622B25F41E5EB2";
+
+    #[test]
+    fn classify() {
+        assert_eq!(DenomKind::Native, classify_denom("stable_coin").unwrap());
+        assert_eq!(
+            DenomKind::Native,
+            classify_denom("raise_1.commitment").unwrap()
+        );
+        assert_eq!(DenomKind::Ibc, classify_denom(IBC_DENOM).unwrap());
+    }
+
+    #[test]
+    fn malformed_ibc_hash() {
+        assert!(classify_denom("ibc/27394FB092D2ECCD").is_err());
+        assert!(classify_denom(&IBC_DENOM.to_lowercase()).is_err());
+        assert!(classify_denom(&format!("{}0", IBC_DENOM)).is_err());
+    }
+
+    #[test]
+    fn invalid_native() {
+        assert!(classify_denom("").is_err());
+        assert!(classify_denom("1coin").is_err());
+        assert!(classify_denom("stable coin").is_err());
+    }
+
+    #[test]
+    fn like_capital_denoms() {
+        assert!(validate_like_capital_denoms(&[]).is_err());
+        validate_like_capital_denoms(&[String::from("stable_coin"), String::from(IBC_DENOM)])
+            .unwrap();
+    }
+}
@@ -1,6 +1,6 @@
 use std::num::TryFromIntError;
 
-use cosmwasm_std::{Response, StdError};
+use cosmwasm_std::{Coin, Response, StdError, StdResult, Timestamp, Uint128};
 use provwasm_std::ProvenanceMsg;
 use thiserror::Error;
 
@@ -11,6 +11,60 @@ pub enum ContractError {
 
     #[error("Unauthorized")]
     Unauthorized {},
+
+    #[error("unsupported capital denom: {denom}")]
+    UnsupportedCapitalDenom { denom: String },
+
+    #[error("unsupported investment denom: {denom}")]
+    UnsupportedInvestmentDenom { denom: String },
+
+    #[error("capital denom must be specified when multiple like capital denoms are supported")]
+    CapitalDenomRequired {},
+
+    #[error("commitment ceiling exceeded, remaining headroom is {headroom} shares")]
+    CommitmentCeilingExceeded { headroom: u64 },
+
+    #[error(
+        "duplicate request, idempotency key {key} already created authorization {authorization_id}"
+    )]
+    DuplicateRequest { key: String, authorization_id: u64 },
+
+    #[error("withdrawal allowance for {to} exceeded, remaining allowance is {remaining}")]
+    WithdrawalAllowanceExceeded { to: String, remaining: Uint128 },
+
+    #[error("withdrawal of {amount} to {to} requires an admin approval")]
+    WithdrawalApprovalRequired { to: String, amount: Coin },
+
+    #[error("approval for withdrawal of {amount} to {to} expired at {expires_at}")]
+    WithdrawalApprovalExpired {
+        to: String,
+        amount: Coin,
+        expires_at: Timestamp,
+    },
+
+    #[error(
+        "attribute requirement on {denom} would never be checked, it is not a restricted marker \
+         and the requirement is not enforced on bank sends"
+    )]
+    UnenforcedCapitalAttribute { denom: String },
+
+    #[error("invalid asset exchange [{exchange}], it moves no investment, commitment or capital")]
+    InvalidExchange { exchange: String },
+
+    #[error("no previously authorized asset exchange matched [{exchanges}]")]
+    AuthorizationNotFound { exchanges: String },
+
+    #[error("asset exchange is not available until {available_at}")]
+    NotYetAvailable { available_at: u64 },
+
+    #[error("{raise} is not a raise of this subscription")]
+    UnregisteredRaise { raise: String },
+
+    #[error("raise {raise} does not support the {query} query")]
+    RaiseQueryUnsupported { raise: String, query: String },
+
+    #[error("contract state is already initialized")]
+    AlreadyInitialized {},
     // Add any other custom errors you like here.
     // Look at https://docs.rs/thiserror/1.0.21/thiserror/ for details.
 }
@@ -36,3 +90,15 @@ impl From<TryFromIntError> for ContractError {
 pub fn contract_error(err: &str) -> Result<Response<ProvenanceMsg>, ContractError> {
     Err(ContractError::Std(StdError::generic_err(err)))
 }
+
+/// Names what a query was reading when it failed, since a bare `StdError` such as not found
+/// reaches the caller with no hint of which key.
+pub trait QueryContext<T> {
+    fn context(self, context: &str) -> StdResult<T>;
+}
+
+impl<T> QueryContext<T> for StdResult<T> {
+    fn context(self, context: &str) -> StdResult<T> {
+        self.map_err(|error| StdError::generic_err(format!("{}: {}", context, error)))
+    }
+}
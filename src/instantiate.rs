@@ -1,12 +1,23 @@
-use std::convert::TryInto;
-
+use crate::contract::check_marker_transfer_permission;
 use crate::contract::ContractResponse;
+use crate::denom::validate_like_capital_denoms;
+use crate::error::contract_error;
+use crate::error::ContractError;
 use crate::msg::AssetExchange;
 use crate::msg::InstantiateMsg;
 use crate::state::asset_exchange_authorization_storage;
+use crate::state::capital_denom_requirements;
+use crate::state::join_addresses;
+use crate::state::metadata_storage;
+use crate::state::next_authorization_id;
+use crate::state::record_operation;
 use crate::state::state_storage;
+use crate::state::state_storage_read;
 use crate::state::AssetExchangeAuthorization;
+use crate::state::AuthorizationStatus;
+use crate::state::Operation;
 use crate::state::State;
+use crate::state::DEFAULT_MAX_AUTHORIZATIONS_PER_BLOCK;
 use crate::version::CONTRACT_NAME;
 use crate::version::CONTRACT_VERSION;
 use cosmwasm_std::entry_point;
@@ -16,54 +27,157 @@ use cosmwasm_std::MessageInfo;
 use cosmwasm_std::Response;
 use cw2::set_contract_version;
 use provwasm_std::ProvenanceQuery;
+use std::convert::TryFrom;
 
 #[entry_point]
 pub fn instantiate(
     deps: DepsMut<ProvenanceQuery>,
-    _env: Env,
+    env: Env,
     info: MessageInfo,
     msg: InstantiateMsg,
 ) -> ContractResponse {
+    // replaying instantiate would clobber the state and seed a second authorization
+    if state_storage_read(deps.storage).may_load()?.is_some() {
+        return Err(ContractError::AlreadyInitialized {});
+    }
     set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
 
+    let require_distinct_roles = msg.require_distinct_roles.unwrap_or(true);
+
+    if msg.investment_denoms.is_empty() {
+        return contract_error("at least one investment denom is required");
+    }
+    validate_like_capital_denoms(&msg.like_capital_denoms)?;
+
+    let required_capital_attributes =
+        capital_denom_requirements(&msg.like_capital_denoms, msg.required_capital_attributes)?;
+
     let state = State {
-        raise: info.sender,
-        admin: msg.admin,
+        raises: vec![info.sender],
+        admins: msg.admins,
+        admin_threshold: msg.admin_threshold.unwrap_or(1),
         lp: msg.lp.clone(),
         commitment_denom: msg.commitment_denom,
-        investment_denom: msg.investment_denom,
-        capital_denom: msg.capital_denom,
+        investment_denoms: msg.investment_denoms,
+        like_capital_denoms: msg.like_capital_denoms,
         capital_per_share: msg.capital_per_share,
-        required_capital_attribute: msg.required_capital_attribute,
+        capital_per_share_overrides: msg.capital_per_share_overrides.unwrap_or_default(),
+        required_capital_attributes,
+        require_distinct_roles,
+        max_commitment_in_shares: msg.max_commitment_in_shares,
+        total_commitment_in_shares: 0,
+        operators: vec![],
+        admin_action_delay_seconds: msg.admin_action_delay_seconds.unwrap_or_default(),
+        max_authorizations_per_block: msg
+            .max_authorizations_per_block
+            .unwrap_or(DEFAULT_MAX_AUTHORIZATIONS_PER_BLOCK),
+        paused: None,
+        stale_after_seconds: msg.stale_after_seconds,
+        default_grace_period_seconds: msg.default_grace_period_seconds.unwrap_or_default(),
+        default_authorization_ttl_seconds: msg.default_authorization_ttl_seconds,
+        default_distribution_address: None,
+        lp_note: None,
+        last_lp_activity: None,
+        last_admin_activity: None,
+        withdrawal_fee_bps: msg.withdrawal_fee_bps,
+        fee_recipient: msg
+            .fee_recipient
+            .map(|fee_recipient| deps.api.addr_validate(fee_recipient.as_str()))
+            .transpose()?,
+        withdrawal_approval_thresholds: msg.withdrawal_approval_thresholds.unwrap_or_default(),
+        beneficiary: None,
     };
+    state.validate_admins()?;
+    state.validate_capital_per_share()?;
+    state.validate_withdrawal_fee()?;
+    state.validate_withdrawal_approval_thresholds()?;
+    check_marker_transfer_permission(
+        &deps.querier,
+        &env.contract.address,
+        &state.required_capital_attributes,
+    )?;
 
     state_storage(deps.storage).save(&state)?;
+    if let Some(metadata) = msg.metadata {
+        metadata.validate()?;
+        metadata_storage(deps.storage).save(&metadata)?;
+    }
 
-    if let Some(commitment) = msg.initial_commitment {
+    // a zero commitment would seed an authorization that moves nothing
+    let initial_commitment = msg.initial_commitment.filter(|commitment| *commitment > 0);
+    let initial_commitment_authorized = initial_commitment.is_some();
+    if let Some(commitment) = initial_commitment {
+        let commitment = i64::try_from(commitment).map_err(|_| {
+            ContractError::from(format!(
+                "initial_commitment {} exceeds the maximum of {}",
+                commitment,
+                i64::MAX
+            ))
+        })?;
+        if commitment as u64 > msg.max_commitment_in_shares.unwrap_or(u64::MAX) {
+            return contract_error("initial commitment exceeds the commitment ceiling");
+        }
+
+        let id = next_authorization_id(deps.storage)?;
         asset_exchange_authorization_storage(deps.storage).save(&vec![
             AssetExchangeAuthorization {
+                id,
+                raise: None,
                 exchanges: vec![AssetExchange {
                     investment: None,
-                    commitment_in_shares: Some(commitment.try_into()?),
+                    investment_denom: None,
+                    commitment_in_shares: Some(commitment.into()),
                     capital: None,
+                    capital_denom: None,
                     date: None,
+                    to: None,
                 }],
                 to: None,
                 memo: None,
+                created_at: Some(env.block.time),
+                expires_at: None,
+                memo_hash: None,
+                status: AuthorizationStatus::Pending,
+                resolved_at: None,
+                resolved_by: None,
             },
         ])?;
+        record_operation(deps.storage, Operation::Authorization, &env.block.time)?;
     }
 
-    Ok(Response::default())
+    Ok(Response::new()
+        .add_attribute("lp", state.lp.to_string())
+        .add_attribute("admin", join_addresses(&state.admins))
+        .add_attribute("admin_threshold", state.admin_threshold.to_string())
+        .add_attribute("raise", state.primary_raise().to_string())
+        .add_attribute("commitment_denom", state.commitment_denom)
+        .add_attribute("investment_denoms", state.investment_denoms.join(","))
+        .add_attribute("like_capital_denoms", state.like_capital_denoms.join(","))
+        .add_attribute("capital_per_share", state.capital_per_share.to_string())
+        .add_attribute(
+            "initial_commitment_authorized",
+            initial_commitment_authorized.to_string(),
+        ))
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::amount::Int128;
     use crate::contract::query;
+    use crate::mock::attr_value;
+    use crate::mock::MockDeps;
+    use crate::mock::{load_marker_fixtures, load_markers};
+    use crate::msg::CapitalDenomRequirement;
+    use crate::msg::MetadataResponse;
     use crate::msg::QueryMsg;
+    use crate::msg::{StateResponse, StateView};
     use crate::state::asset_exchange_authorization_storage_read;
+    use crate::state::SubscriptionMetadata;
+    use crate::state::MAX_METADATA_NAME_LENGTH;
+    use cosmwasm_std::attr;
     use cosmwasm_std::from_binary;
+    use cosmwasm_std::from_slice;
     use cosmwasm_std::testing::mock_env;
     use cosmwasm_std::testing::mock_info;
     use cosmwasm_std::Addr;
@@ -77,33 +191,606 @@ mod tests {
         let res = instantiate(
             deps.as_mut(),
             mock_env(),
-            mock_info("lp", &[]),
+            mock_info("raise_1", &[]),
             InstantiateMsg {
-                admin: Addr::unchecked("admin"),
+                admins: vec![Addr::unchecked("admin")],
                 lp: Addr::unchecked("lp"),
                 commitment_denom: String::from("raise_1.commitment"),
-                investment_denom: String::from("raise_1.investment"),
-                capital_denom: String::from("stable_coin"),
+                investment_denoms: vec![String::from("raise_1.investment")],
+                like_capital_denoms: vec![String::from("stable_coin")],
                 capital_per_share: 100,
+                capital_per_share_overrides: None,
                 initial_commitment: Some(100),
-                required_capital_attribute: None,
+                required_capital_attributes: vec![],
+                require_distinct_roles: None,
+                max_commitment_in_shares: None,
+                admin_threshold: None,
+                admin_action_delay_seconds: None,
+                max_authorizations_per_block: None,
+                stale_after_seconds: None,
+                default_grace_period_seconds: None,
+                default_authorization_ttl_seconds: None,
+                metadata: None,
+                withdrawal_fee_bps: None,
+                fee_recipient: None,
+                withdrawal_approval_thresholds: None,
             },
         )
         .unwrap();
         assert_eq!(0, res.messages.len());
+        assert_eq!(
+            vec![
+                attr("lp", "lp"),
+                attr("admin", "admin"),
+                attr("admin_threshold", "1"),
+                attr("raise", "raise_1"),
+                attr("commitment_denom", "raise_1.commitment"),
+                attr("investment_denoms", "raise_1.investment"),
+                attr("like_capital_denoms", "stable_coin"),
+                attr("capital_per_share", "100"),
+                attr("initial_commitment_authorized", "true"),
+            ],
+            res.attributes
+        );
 
         // it worked, let's query the state
         let res = query(deps.as_ref(), mock_env(), QueryMsg::GetState {}).unwrap();
-        let state: State = from_binary(&res).unwrap();
-        assert_eq!("lp", state.lp);
+        let res: StateResponse = from_binary(&res).unwrap();
+        assert_eq!(StateView::from(State::test_default()), res.state);
 
         // verify authorized asset exchange for commitment
+        let authorizations = asset_exchange_authorization_storage_read(&deps.storage)
+            .load()
+            .unwrap();
+        assert_eq!(1, authorizations.len());
+        State::test_default()
+            .validate_exchanges(&authorizations[0].exchanges)
+            .unwrap();
+    }
+
+    fn instantiate_with_initial_commitment(
+        deps: &mut MockDeps,
+        initial_commitment: u64,
+    ) -> ContractResponse {
+        instantiate(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("raise_1", &[]),
+            InstantiateMsg {
+                admins: vec![Addr::unchecked("admin")],
+                lp: Addr::unchecked("lp"),
+                commitment_denom: String::from("raise_1.commitment"),
+                investment_denoms: vec![String::from("raise_1.investment")],
+                like_capital_denoms: vec![String::from("stable_coin")],
+                capital_per_share: 100,
+                capital_per_share_overrides: None,
+                initial_commitment: Some(initial_commitment),
+                required_capital_attributes: vec![],
+                require_distinct_roles: None,
+                max_commitment_in_shares: None,
+                admin_threshold: None,
+                admin_action_delay_seconds: None,
+                max_authorizations_per_block: None,
+                stale_after_seconds: None,
+                default_grace_period_seconds: None,
+                default_authorization_ttl_seconds: None,
+                metadata: None,
+                withdrawal_fee_bps: None,
+                fee_recipient: None,
+                withdrawal_approval_thresholds: None,
+            },
+        )
+    }
+
+    #[test]
+    fn initialization_zero_initial_commitment() {
+        let mut deps = mock_dependencies(&[]);
+
+        let res = instantiate_with_initial_commitment(&mut deps, 0).unwrap();
         assert_eq!(
-            1,
-            asset_exchange_authorization_storage_read(&deps.storage)
-                .load()
-                .unwrap()
-                .len()
+            Some("false"),
+            attr_value(&res, "initial_commitment_authorized")
         );
+        assert!(asset_exchange_authorization_storage_read(&deps.storage)
+            .may_load()
+            .unwrap()
+            .unwrap_or_default()
+            .is_empty());
+    }
+
+    #[test]
+    fn initialization_largest_initial_commitment() {
+        let mut deps = mock_dependencies(&[]);
+
+        instantiate_with_initial_commitment(&mut deps, i64::MAX as u64).unwrap();
+        let authorizations = asset_exchange_authorization_storage_read(&deps.storage)
+            .load()
+            .unwrap();
+        assert_eq!(
+            Some(Int128::from(i64::MAX)),
+            authorizations[0].exchanges[0].commitment_in_shares
+        );
+    }
+
+    #[test]
+    fn initialization_oversized_initial_commitment() {
+        let mut deps = mock_dependencies(&[]);
+
+        let err = instantiate_with_initial_commitment(&mut deps, i64::MAX as u64 + 1).unwrap_err();
+        assert!(err.to_string().contains("initial_commitment"));
+        assert!(err.to_string().contains(&i64::MAX.to_string()));
+    }
+
+    #[test]
+    fn initialization_twice() {
+        let mut deps = mock_dependencies(&[]);
+        instantiate_with_initial_commitment(&mut deps, 1_000).unwrap();
+        let state = state_storage_read(&deps.storage).load().unwrap();
+
+        let err = instantiate_with_initial_commitment(&mut deps, 500).unwrap_err();
+        assert!(matches!(err, ContractError::AlreadyInitialized {}));
+        assert_eq!(state, state_storage_read(&deps.storage).load().unwrap());
+        let authorizations = asset_exchange_authorization_storage_read(&deps.storage)
+            .load()
+            .unwrap();
+        assert_eq!(1, authorizations.len());
+        assert_eq!(
+            Some(Int128::from(1_000i64)),
+            authorizations[0].exchanges[0].commitment_in_shares
+        );
+    }
+
+    #[test]
+    fn initialization_same_admin_and_lp() {
+        let mut deps = mock_dependencies(&[]);
+
+        let res = instantiate(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("raise_1", &[]),
+            InstantiateMsg {
+                admins: vec![Addr::unchecked("admin")],
+                lp: Addr::unchecked("admin"),
+                commitment_denom: String::from("raise_1.commitment"),
+                investment_denoms: vec![String::from("raise_1.investment")],
+                like_capital_denoms: vec![String::from("stable_coin")],
+                capital_per_share: 100,
+                capital_per_share_overrides: None,
+                initial_commitment: None,
+                required_capital_attributes: vec![],
+                require_distinct_roles: None,
+                max_commitment_in_shares: None,
+                admin_threshold: None,
+                admin_action_delay_seconds: None,
+                max_authorizations_per_block: None,
+                stale_after_seconds: None,
+                default_grace_period_seconds: None,
+                default_authorization_ttl_seconds: None,
+                metadata: None,
+                withdrawal_fee_bps: None,
+                fee_recipient: None,
+                withdrawal_approval_thresholds: None,
+            },
+        );
+        assert!(res.is_err());
+    }
+
+    #[test]
+    fn initialization_with_multiple_admins() {
+        let mut deps = mock_dependencies(&[]);
+
+        let msg = InstantiateMsg {
+            admins: vec![Addr::unchecked("admin"), Addr::unchecked("admin_2")],
+            lp: Addr::unchecked("lp"),
+            commitment_denom: String::from("raise_1.commitment"),
+            investment_denoms: vec![String::from("raise_1.investment")],
+            like_capital_denoms: vec![String::from("stable_coin")],
+            capital_per_share: 100,
+            capital_per_share_overrides: None,
+            initial_commitment: None,
+            required_capital_attributes: vec![],
+            require_distinct_roles: None,
+            max_commitment_in_shares: None,
+            admin_threshold: Some(3),
+            admin_action_delay_seconds: None,
+            max_authorizations_per_block: None,
+            stale_after_seconds: None,
+            default_grace_period_seconds: None,
+            default_authorization_ttl_seconds: None,
+            metadata: None,
+            withdrawal_fee_bps: None,
+            fee_recipient: None,
+            withdrawal_approval_thresholds: None,
+        };
+        assert!(instantiate(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("raise_1", &[]),
+            msg.clone()
+        )
+        .is_err());
+
+        let res = instantiate(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("raise_1", &[]),
+            InstantiateMsg {
+                admin_threshold: Some(2),
+                ..msg
+            },
+        )
+        .unwrap();
+        assert_eq!(Some("admin,admin_2"), attr_value(&res, "admin"));
+
+        let state = state_storage(&mut deps.storage).load().unwrap();
+        assert_eq!(2, state.admin_threshold);
+    }
+
+    #[test]
+    fn initialization_same_admin_and_lp_opt_out() {
+        let mut deps = mock_dependencies(&[]);
+
+        instantiate(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("raise_1", &[]),
+            InstantiateMsg {
+                admins: vec![Addr::unchecked("admin")],
+                lp: Addr::unchecked("admin"),
+                commitment_denom: String::from("raise_1.commitment"),
+                investment_denoms: vec![String::from("raise_1.investment")],
+                like_capital_denoms: vec![String::from("stable_coin")],
+                capital_per_share: 100,
+                capital_per_share_overrides: None,
+                initial_commitment: None,
+                required_capital_attributes: vec![],
+                require_distinct_roles: Some(false),
+                max_commitment_in_shares: None,
+                admin_threshold: None,
+                admin_action_delay_seconds: None,
+                max_authorizations_per_block: None,
+                stale_after_seconds: None,
+                default_grace_period_seconds: None,
+                default_authorization_ttl_seconds: None,
+                metadata: None,
+                withdrawal_fee_bps: None,
+                fee_recipient: None,
+                withdrawal_approval_thresholds: None,
+            },
+        )
+        .unwrap();
+
+        let state = state_storage(&mut deps.storage).load().unwrap();
+        assert_eq!(
+            State {
+                lp: Addr::unchecked("admin"),
+                require_distinct_roles: false,
+                ..State::test_default()
+            },
+            state
+        );
+    }
+
+    #[test]
+    fn initialization_with_per_denom_requirements() {
+        let mut deps = mock_dependencies(&[]);
+        load_markers(&mut deps.querier);
+
+        instantiate(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("raise_1", &[]),
+            InstantiateMsg {
+                admins: vec![Addr::unchecked("admin")],
+                lp: Addr::unchecked("lp"),
+                commitment_denom: String::from("raise_1.commitment"),
+                investment_denoms: vec![String::from("raise_1.investment")],
+                like_capital_denoms: vec![
+                    String::from("stable_coin"),
+                    String::from("restricted_capital_coin"),
+                ],
+                capital_per_share: 100,
+                capital_per_share_overrides: None,
+                initial_commitment: None,
+                required_capital_attributes: vec![CapitalDenomRequirement {
+                    capital_denom: String::from("restricted_capital_coin"),
+                    required_attributes: vec![String::from("capital.test")],
+                    enforce_attribute_on_bank_send: false,
+                }],
+                require_distinct_roles: None,
+                max_commitment_in_shares: None,
+                admin_threshold: None,
+                admin_action_delay_seconds: None,
+                max_authorizations_per_block: None,
+                stale_after_seconds: None,
+                default_grace_period_seconds: None,
+                default_authorization_ttl_seconds: None,
+                metadata: None,
+                withdrawal_fee_bps: None,
+                fee_recipient: None,
+                withdrawal_approval_thresholds: None,
+            },
+        )
+        .unwrap();
+
+        let state = state_storage(&mut deps.storage).load().unwrap();
+        assert_eq!(None, state.required_capital_attributes("stable_coin"));
+        assert_eq!(
+            Some(&[String::from("capital.test")][..]),
+            state.required_capital_attributes("restricted_capital_coin")
+        );
+    }
+
+    fn per_denom_requirements_msg() -> InstantiateMsg {
+        InstantiateMsg {
+            admins: vec![Addr::unchecked("admin")],
+            lp: Addr::unchecked("lp"),
+            commitment_denom: String::from("raise_1.commitment"),
+            investment_denoms: vec![String::from("raise_1.investment")],
+            like_capital_denoms: vec![String::from("restricted_capital_coin")],
+            capital_per_share: 100,
+            capital_per_share_overrides: None,
+            initial_commitment: None,
+            required_capital_attributes: vec![CapitalDenomRequirement {
+                capital_denom: String::from("restricted_capital_coin"),
+                required_attributes: vec![String::from("capital.test")],
+                enforce_attribute_on_bank_send: false,
+            }],
+            require_distinct_roles: None,
+            max_commitment_in_shares: None,
+            admin_threshold: None,
+            admin_action_delay_seconds: None,
+            max_authorizations_per_block: None,
+            stale_after_seconds: None,
+            default_grace_period_seconds: None,
+            default_authorization_ttl_seconds: None,
+            metadata: None,
+            withdrawal_fee_bps: None,
+            fee_recipient: None,
+            withdrawal_approval_thresholds: None,
+        }
+    }
+
+    #[test]
+    fn initialization_without_marker_transfer_grant() {
+        let mut deps = mock_dependencies(&[]);
+        load_marker_fixtures(&mut deps.querier, &["restricted_capital_no_grant"]);
+
+        let err = instantiate(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("raise_1", &[]),
+            per_denom_requirements_msg(),
+        )
+        .unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("lacks transfer permission on marker restricted_capital_coin"));
+        assert!(err
+            .to_string()
+            .contains("tp19dmdhtyd2x2uzsecdsjdrjjn06pawnj3eas5wk"));
+    }
+
+    #[test]
+    fn initialization_with_marker_transfer_grant() {
+        let mut deps = mock_dependencies(&[]);
+        load_markers(&mut deps.querier);
+
+        instantiate(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("raise_1", &[]),
+            per_denom_requirements_msg(),
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn initialization_with_capital_per_share_overrides() {
+        let mut deps = mock_dependencies(&[]);
+        load_markers(&mut deps.querier);
+        let mut msg = per_denom_requirements_msg();
+        msg.like_capital_denoms.push(String::from("wei_coin"));
+        msg.capital_per_share_overrides =
+            Some(vec![(String::from("wei_coin"), 100_000_000_000_000)]);
+
+        instantiate(deps.as_mut(), mock_env(), mock_info("raise_1", &[]), msg).unwrap();
+
+        let state = state_storage(&mut deps.storage).load().unwrap();
+        assert_eq!(100, state.capital_per_share("restricted_capital_coin"));
+        assert_eq!(100_000_000_000_000, state.capital_per_share("wei_coin"));
+    }
+
+    #[test]
+    fn initialization_with_unsupported_capital_per_share_override() {
+        let mut deps = mock_dependencies(&[]);
+        load_markers(&mut deps.querier);
+        let mut msg = per_denom_requirements_msg();
+        msg.capital_per_share_overrides = Some(vec![(String::from("wei_coin"), 1)]);
+
+        assert!(instantiate(deps.as_mut(), mock_env(), mock_info("raise_1", &[]), msg).is_err());
+    }
+
+    #[test]
+    fn initialization_with_metadata() {
+        let mut deps = mock_dependencies(&[]);
+        load_markers(&mut deps.querier);
+        let metadata = SubscriptionMetadata {
+            name: String::from("Fund III / Acme Pension"),
+            description: Some(String::from("Class A commitment")),
+            external_id: Some(String::from("ACME-0042")),
+        };
+        let mut msg = per_denom_requirements_msg();
+        msg.metadata = Some(metadata.clone());
+
+        instantiate(deps.as_mut(), mock_env(), mock_info("raise_1", &[]), msg).unwrap();
+
+        let res: MetadataResponse =
+            from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::GetMetadata {}).unwrap())
+                .unwrap();
+        assert_eq!(Some(metadata), res.metadata);
+    }
+
+    #[test]
+    fn initialization_with_oversized_metadata() {
+        let mut deps = mock_dependencies(&[]);
+        load_markers(&mut deps.querier);
+        let mut msg = per_denom_requirements_msg();
+        msg.metadata = Some(SubscriptionMetadata {
+            name: "n".repeat(MAX_METADATA_NAME_LENGTH + 1),
+            description: None,
+            external_id: None,
+        });
+
+        let err =
+            instantiate(deps.as_mut(), mock_env(), mock_info("raise_1", &[]), msg).unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("metadata name must be between 1 and 64 bytes"));
+    }
+
+    #[test]
+    fn initialization_with_malformed_ibc_denom() {
+        let mut deps = mock_dependencies(&[]);
+        let mut msg = per_denom_requirements_msg();
+        msg.like_capital_denoms
+            .push(String::from("ibc/27394FB092D2ECCD56123C74F36E4C1F"));
+        msg.required_capital_attributes = vec![];
+
+        let err =
+            instantiate(deps.as_mut(), mock_env(), mock_info("raise_1", &[]), msg).unwrap_err();
+        assert!(err.to_string().contains("invalid ibc denom"));
+    }
+
+    #[test]
+    fn initialization_with_unsupported_requirement_denom() {
+        let mut deps = mock_dependencies(&[]);
+
+        let res = instantiate(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("raise_1", &[]),
+            InstantiateMsg {
+                admins: vec![Addr::unchecked("admin")],
+                lp: Addr::unchecked("lp"),
+                commitment_denom: String::from("raise_1.commitment"),
+                investment_denoms: vec![String::from("raise_1.investment")],
+                like_capital_denoms: vec![String::from("stable_coin")],
+                capital_per_share: 100,
+                capital_per_share_overrides: None,
+                initial_commitment: None,
+                required_capital_attributes: vec![CapitalDenomRequirement {
+                    capital_denom: String::from("restricted_capital_coin"),
+                    required_attributes: vec![String::from("capital.test")],
+                    enforce_attribute_on_bank_send: false,
+                }],
+                require_distinct_roles: None,
+                max_commitment_in_shares: None,
+                admin_threshold: None,
+                admin_action_delay_seconds: None,
+                max_authorizations_per_block: None,
+                stale_after_seconds: None,
+                default_grace_period_seconds: None,
+                default_authorization_ttl_seconds: None,
+                metadata: None,
+                withdrawal_fee_bps: None,
+                fee_recipient: None,
+                withdrawal_approval_thresholds: None,
+            },
+        );
+        assert!(res.is_err());
+    }
+
+    #[test]
+    fn initialization_with_legacy_payload() {
+        let mut deps = mock_dependencies(&[]);
+        load_markers(&mut deps.querier);
+
+        let msg: InstantiateMsg = from_slice(
+            br#"{
+                "admin": "admin",
+                "lp": "lp",
+                "commitment_denom": "raise_1.commitment",
+                "investment_denom": "raise_1.investment",
+                "capital_denom": "restricted_capital_coin",
+                "capital_per_share": 100,
+                "initial_commitment": null,
+                "required_capital_attribute": "capital.test"
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(
+            vec![String::from("restricted_capital_coin")],
+            msg.like_capital_denoms
+        );
+
+        instantiate(deps.as_mut(), mock_env(), mock_info("raise_1", &[]), msg).unwrap();
+
+        let state = state_storage(&mut deps.storage).load().unwrap();
+        assert_eq!(State::test_restricted_capital_coin(), state);
+    }
+
+    #[test]
+    fn initialization_with_string_amounts() {
+        let msg: InstantiateMsg = from_slice(
+            br#"{
+                "admins": ["admin"],
+                "lp": "lp",
+                "commitment_denom": "raise_1.commitment",
+                "investment_denoms": ["raise_1.investment"],
+                "like_capital_denoms": ["stable_coin", "wei_coin"],
+                "capital_per_share": "100",
+                "capital_per_share_overrides": [["wei_coin", "100000000000000"]],
+                "initial_commitment": "9007199254740993",
+                "max_commitment_in_shares": "9007199254740993"
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(100, msg.capital_per_share);
+        assert_eq!(
+            Some(vec![(String::from("wei_coin"), 100_000_000_000_000)]),
+            msg.capital_per_share_overrides
+        );
+        assert_eq!(Some(9_007_199_254_740_993), msg.initial_commitment);
+        assert_eq!(Some(9_007_199_254_740_993), msg.max_commitment_in_shares);
+    }
+
+    #[test]
+    fn initialization_with_withdrawal_fee() {
+        let instantiate_with_fee = |fee: &str| {
+            let msg: InstantiateMsg = from_slice(
+                format!(
+                    r#"{{
+                        "admins": ["admin"],
+                        "lp": "lp",
+                        "commitment_denom": "raise_1.commitment",
+                        "investment_denoms": ["raise_1.investment"],
+                        "like_capital_denoms": ["stable_coin"],
+                        "capital_per_share": 100,
+                        {}
+                    }}"#,
+                    fee
+                )
+                .as_bytes(),
+            )
+            .unwrap();
+            let mut deps = mock_dependencies(&[]);
+            instantiate(deps.as_mut(), mock_env(), mock_info("raise_1", &[]), msg)
+                .map(|_| state_storage_read(&deps.storage).load().unwrap())
+        };
+
+        let state =
+            instantiate_with_fee(r#""withdrawal_fee_bps": 25, "fee_recipient": "marketpalace""#)
+                .unwrap();
+        assert_eq!(Some(25), state.withdrawal_fee_bps);
+        assert_eq!(Some(Addr::unchecked("marketpalace")), state.fee_recipient);
+
+        let err = instantiate_with_fee(r#""withdrawal_fee_bps": 25"#).unwrap_err();
+        assert!(err.to_string().contains("requires a fee recipient"));
+        assert!(instantiate_with_fee(r#""withdrawal_fee_bps": 25, "fee_recipient": """#).is_err());
+        let err =
+            instantiate_with_fee(r#""withdrawal_fee_bps": 10001, "fee_recipient": "marketpalace""#)
+                .unwrap_err();
+        assert!(err.to_string().contains("exceeds 10000 bps"));
     }
 }
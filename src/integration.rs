@@ -0,0 +1,646 @@
+//! A small in-process harness that runs this contract alongside the mock raise contract and
+//! tracks bank balances, so flows can be exercised end-to-end. Rather than pull in `cw-multi-test`
+//! and a custom handler for provenance marker messages, the few messages we emit are dispatched
+//! here directly.
+
+use std::collections::BTreeMap;
+
+use cosmwasm_std::testing::{
+    mock_dependencies as mock_raise_dependencies, mock_env, mock_info, MockApi, MockQuerier,
+    MockStorage, MOCK_CONTRACT_ADDR,
+};
+use cosmwasm_std::{from_binary, Addr, BankMsg, Coin, CosmosMsg, Order, OwnedDeps, Storage};
+use cosmwasm_std::{Response, WasmMsg};
+use provwasm_mocks::{mock_dependencies, ProvenanceMockQuerier};
+use provwasm_std::{MarkerMsgParams, ProvenanceMsg, ProvenanceMsgParams, ProvenanceQuery};
+
+use crate::amount::Int128;
+use crate::contract::execute;
+use crate::instantiate::instantiate;
+use crate::mock_raise;
+use crate::mock_raise::ReceivedMsg;
+use crate::msg::{ExecuteMsg, InstantiateMsg};
+use crate::raise_msg::RaiseExecuteMsg;
+
+type Snapshot = Vec<(Vec<u8>, Vec<u8>)>;
+
+pub struct TestApp {
+    pub deps: OwnedDeps<MockStorage, MockApi, ProvenanceMockQuerier, ProvenanceQuery>,
+    pub contract: Addr,
+    pub raise: Addr,
+    pub raise_deps: OwnedDeps<MockStorage, MockApi, MockQuerier>,
+    balances: BTreeMap<(String, String), u128>,
+    investment_denom: String,
+    commitment_denom: String,
+    default_capital_denom: String,
+}
+
+impl TestApp {
+    pub fn new(msg: InstantiateMsg) -> TestApp {
+        let raise = Addr::unchecked("raise_1");
+        let mut deps = mock_dependencies(&[]);
+        let investment_denom = msg.investment_denoms.first().cloned().unwrap_or_default();
+        let commitment_denom = msg.commitment_denom.clone();
+        let default_capital_denom = msg.like_capital_denoms.first().cloned().unwrap_or_default();
+
+        instantiate(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(raise.as_str(), &[]),
+            msg,
+        )
+        .unwrap();
+
+        let mut raise_deps = mock_raise_dependencies();
+        mock_raise::instantiate(
+            raise_deps.as_mut(),
+            mock_env(),
+            mock_info("admin", &[]),
+            mock_raise::InstantiateMsg::default(),
+        )
+        .unwrap();
+
+        TestApp {
+            deps,
+            contract: Addr::unchecked(MOCK_CONTRACT_ADDR),
+            raise,
+            raise_deps,
+            balances: BTreeMap::new(),
+            investment_denom,
+            commitment_denom,
+            default_capital_denom,
+        }
+    }
+
+    pub fn set_balance(&mut self, address: &Addr, amount: u128, denom: &str) {
+        self.balances
+            .insert((address.to_string(), String::from(denom)), amount);
+    }
+
+    pub fn balance(&self, address: &Addr, denom: &str) -> u128 {
+        self.balances
+            .get(&(address.to_string(), String::from(denom)))
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// Makes the raise reject (or accept again) every message it receives.
+    pub fn set_raise_fails(&mut self, fail: bool) {
+        mock_raise::set_fail(&mut self.raise_deps.storage, fail).unwrap();
+    }
+
+    /// Messages accepted by the raise so far, with the funds sent along.
+    pub fn raise_received(&self) -> Vec<ReceivedMsg> {
+        mock_raise::received(&self.raise_deps.storage)
+    }
+
+    /// Executes a message against the subscription and dispatches the resulting messages. If
+    /// the contract or any dispatched message fails, storage and balances are rolled back.
+    pub fn execute(
+        &mut self,
+        sender: &str,
+        msg: ExecuteMsg,
+    ) -> Result<Response<ProvenanceMsg>, String> {
+        let storage_snapshot = snapshot(&self.deps.storage);
+        let raise_storage_snapshot = snapshot(&self.raise_deps.storage);
+        let balances_snapshot = self.balances.clone();
+
+        let result = execute(self.deps.as_mut(), mock_env(), mock_info(sender, &[]), msg)
+            .map_err(|error| error.to_string())
+            .and_then(|res| {
+                for msg in res.messages.iter() {
+                    self.dispatch(&msg.msg)?;
+                }
+                Ok(res)
+            });
+
+        if result.is_err() {
+            restore(&mut self.deps.storage, storage_snapshot);
+            restore(&mut self.raise_deps.storage, raise_storage_snapshot);
+            self.balances = balances_snapshot;
+        }
+
+        result
+    }
+
+    fn transfer(&mut self, from: &Addr, to: &Addr, coin: &Coin) -> Result<(), String> {
+        let amount = coin.amount.u128();
+        let available = self.balance(from, &coin.denom);
+        if available < amount {
+            return Err(format!(
+                "insufficient funds: {} has {}{}, needs {}",
+                from, available, coin.denom, amount
+            ));
+        }
+        self.set_balance(from, available - amount, &coin.denom);
+        let received = self.balance(to, &coin.denom);
+        self.set_balance(to, received + amount, &coin.denom);
+        Ok(())
+    }
+
+    fn mint(&mut self, to: &Addr, amount: u128, denom: &str) {
+        let balance = self.balance(to, denom);
+        self.set_balance(to, balance + amount, denom);
+    }
+
+    fn dispatch(&mut self, msg: &CosmosMsg<ProvenanceMsg>) -> Result<(), String> {
+        let contract = self.contract.clone();
+        match msg {
+            CosmosMsg::Bank(BankMsg::Send { to_address, amount }) => {
+                for coin in amount {
+                    self.transfer(&contract, &Addr::unchecked(to_address), coin)?;
+                }
+                Ok(())
+            }
+            CosmosMsg::Custom(ProvenanceMsg {
+                params:
+                    ProvenanceMsgParams::Marker(MarkerMsgParams::TransferMarkerCoins { coin, to, from }),
+                ..
+            }) => self.transfer(from, to, coin),
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr,
+                msg,
+                funds,
+            }) if contract_addr == self.raise.as_str() => {
+                let raise = self.raise.clone();
+                for coin in funds {
+                    self.transfer(&contract, &raise, coin)?;
+                }
+                let msg: RaiseExecuteMsg = from_binary(msg).map_err(|error| error.to_string())?;
+                mock_raise::execute(
+                    self.raise_deps.as_mut(),
+                    mock_env(),
+                    mock_info(contract.as_str(), funds),
+                    msg.clone(),
+                )
+                .map_err(|error| error.to_string())?;
+                self.pay_out_from_raise(msg)
+            }
+            _ => Err(format!("unsupported message: {:?}", msg)),
+        }
+    }
+
+    /// Pays out any positive investment, commitment and capital amounts of a completed
+    /// exchange, as the raise would. Investment and commitment go to the subscription, while
+    /// capital goes to the exchange's `to`, then the message's `to`, then the subscription.
+    fn pay_out_from_raise(&mut self, msg: RaiseExecuteMsg) -> Result<(), String> {
+        let contract = self.contract.clone();
+        let raise = self.raise.clone();
+        let (exchanges, to) = match &msg {
+            RaiseExecuteMsg::CompleteAssetExchange { exchanges, to, .. } => (exchanges, to),
+            RaiseExecuteMsg::ReturnCapital { .. } => return Ok(()),
+        };
+        for exchange in exchanges {
+            if let Some(investment) = exchange.investment.filter(Int128::is_positive) {
+                let denom = self.investment_denom.clone();
+                self.mint(&contract, investment.unsigned_abs().u128(), &denom);
+            }
+            if let Some(commitment) = exchange.commitment_in_shares.filter(Int128::is_positive) {
+                let denom = self.commitment_denom.clone();
+                self.mint(&contract, commitment.unsigned_abs().u128(), &denom);
+            }
+            if let Some(capital) = exchange.capital.filter(Int128::is_positive) {
+                let denom = exchange
+                    .capital_denom
+                    .clone()
+                    .unwrap_or_else(|| self.default_capital_denom.clone());
+                let coin = Coin::new(capital.unsigned_abs().u128(), denom);
+                let recipient = exchange
+                    .to
+                    .clone()
+                    .or_else(|| to.clone())
+                    .unwrap_or_else(|| contract.clone());
+                self.transfer(&raise, &recipient, &coin)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn snapshot(storage: &dyn Storage) -> Snapshot {
+    storage.range(None, None, Order::Ascending).collect()
+}
+
+fn restore(storage: &mut dyn Storage, snapshot: Snapshot) {
+    let keys: Vec<Vec<u8>> = storage
+        .range(None, None, Order::Ascending)
+        .map(|(key, _)| key)
+        .collect();
+    for key in keys {
+        storage.remove(&key);
+    }
+    for (key, value) in snapshot {
+        storage.set(&key, &value);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::ExchangeBuilder;
+    use crate::msg::AssetExchange;
+    use crate::state::asset_exchange_authorization_storage_read;
+    use cosmwasm_std::Uint128;
+
+    fn test_app() -> TestApp {
+        TestApp::new(InstantiateMsg {
+            admins: vec![Addr::unchecked("admin")],
+            lp: Addr::unchecked("lp"),
+            commitment_denom: String::from("raise_1.commitment"),
+            investment_denoms: vec![String::from("raise_1.investment")],
+            like_capital_denoms: vec![String::from("stable_coin")],
+            capital_per_share: 100,
+            capital_per_share_overrides: None,
+            initial_commitment: None,
+            required_capital_attributes: vec![],
+            require_distinct_roles: None,
+            max_commitment_in_shares: None,
+            admin_threshold: None,
+            admin_action_delay_seconds: None,
+            max_authorizations_per_block: None,
+            stale_after_seconds: None,
+            default_grace_period_seconds: None,
+            default_authorization_ttl_seconds: None,
+            metadata: None,
+            withdrawal_fee_bps: None,
+            fee_recipient: None,
+            withdrawal_approval_thresholds: None,
+        })
+    }
+
+    fn capital_call() -> Vec<AssetExchange> {
+        vec![AssetExchange {
+            investment: Some(Int128::new(1_000)),
+            investment_denom: None,
+            commitment_in_shares: Some(Int128::new(-10)),
+            capital: Some(Int128::new(-1_000)),
+            capital_denom: None,
+            date: None,
+            to: None,
+        }]
+    }
+
+    fn authorize(app: &mut TestApp, exchanges: Vec<AssetExchange>) {
+        app.execute(
+            "lp",
+            ExecuteMsg::AuthorizeAssetExchange {
+                exchanges,
+                to: None,
+                memo: None,
+                idempotency_key: None,
+                expires_at: None,
+                memo_hash: None,
+                raise: None,
+            },
+        )
+        .unwrap();
+    }
+
+    fn complete(app: &mut TestApp, exchanges: Vec<AssetExchange>) -> Result<(), String> {
+        app.execute(
+            "admin",
+            ExecuteMsg::CompleteAssetExchange {
+                exchanges,
+                to: None,
+                memo: None,
+                override_available_date: false,
+                raise: None,
+            },
+        )
+        .map(|_| ())
+    }
+
+    fn authorization_count(app: &TestApp) -> usize {
+        asset_exchange_authorization_storage_read(&app.deps.storage)
+            .may_load()
+            .unwrap()
+            .unwrap_or_default()
+            .len()
+    }
+
+    #[test]
+    fn authorize_and_complete_capital_call() {
+        let mut app = test_app();
+        let contract = app.contract.clone();
+        let raise = app.raise.clone();
+        app.set_balance(&contract, 1_000, "stable_coin");
+        app.set_balance(&contract, 10, "raise_1.commitment");
+
+        authorize(&mut app, capital_call());
+        complete(&mut app, capital_call()).unwrap();
+
+        assert_eq!(0, app.balance(&contract, "stable_coin"));
+        assert_eq!(0, app.balance(&contract, "raise_1.commitment"));
+        assert_eq!(1_000, app.balance(&contract, "raise_1.investment"));
+        assert_eq!(1_000, app.balance(&raise, "stable_coin"));
+        assert_eq!(10, app.balance(&raise, "raise_1.commitment"));
+        assert_eq!(1, app.raise_received().len());
+        assert_eq!(0, authorization_count(&app));
+    }
+
+    #[test]
+    fn complete_distribution_and_withdraw() {
+        let mut app = test_app();
+        let contract = app.contract.clone();
+        let raise = app.raise.clone();
+        let lp_side_account = Addr::unchecked("lp_side_account");
+        app.set_balance(&raise, 500, "stable_coin");
+
+        let distribution = vec![AssetExchange {
+            investment: None,
+            investment_denom: None,
+            commitment_in_shares: None,
+            capital: Some(Int128::new(500)),
+            capital_denom: None,
+            date: None,
+            to: None,
+        }];
+        authorize(&mut app, distribution.clone());
+        complete(&mut app, distribution).unwrap();
+        assert_eq!(500, app.balance(&contract, "stable_coin"));
+        assert_eq!(0, app.balance(&raise, "stable_coin"));
+
+        app.execute(
+            "lp",
+            ExecuteMsg::IssueWithdrawal {
+                to: Some(lp_side_account.clone()),
+                to_name: None,
+                amount: Uint128::new(300),
+                capital_denom: None,
+            },
+        )
+        .unwrap();
+        assert_eq!(200, app.balance(&contract, "stable_coin"));
+        assert_eq!(300, app.balance(&lp_side_account, "stable_coin"));
+    }
+
+    fn distribute(app: &mut TestApp, exchanges: Vec<AssetExchange>, to: Option<Addr>) {
+        app.execute(
+            "lp",
+            ExecuteMsg::AuthorizeAssetExchange {
+                exchanges: exchanges.clone(),
+                to: to.clone(),
+                memo: None,
+                idempotency_key: None,
+                expires_at: None,
+                memo_hash: None,
+                raise: None,
+            },
+        )
+        .unwrap();
+        app.execute(
+            "admin",
+            ExecuteMsg::CompleteAssetExchange {
+                exchanges,
+                to,
+                memo: None,
+                override_available_date: false,
+                raise: None,
+            },
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn distribution_paid_to_message_recipient() {
+        let mut app = test_app();
+        let contract = app.contract.clone();
+        let raise = app.raise.clone();
+        let lp_side_account = Addr::unchecked("lp_side_account");
+        app.set_balance(&raise, 500, "stable_coin");
+
+        distribute(
+            &mut app,
+            vec![ExchangeBuilder::new().capital(500).build()],
+            Some(lp_side_account.clone()),
+        );
+
+        assert_eq!(500, app.balance(&lp_side_account, "stable_coin"));
+        assert_eq!(0, app.balance(&contract, "stable_coin"));
+    }
+
+    #[test]
+    fn distribution_paid_to_exchange_recipient() {
+        let mut app = test_app();
+        let contract = app.contract.clone();
+        let raise = app.raise.clone();
+        let side_a = Addr::unchecked("side_a");
+        let side_b = Addr::unchecked("side_b");
+        app.set_balance(&raise, 500, "stable_coin");
+
+        distribute(
+            &mut app,
+            vec![
+                ExchangeBuilder::new().capital(200).to("side_a").build(),
+                ExchangeBuilder::new().capital(300).build(),
+            ],
+            Some(side_b.clone()),
+        );
+
+        assert_eq!(200, app.balance(&side_a, "stable_coin"));
+        assert_eq!(300, app.balance(&side_b, "stable_coin"));
+        assert_eq!(0, app.balance(&contract, "stable_coin"));
+    }
+
+    #[test]
+    fn distribution_paid_to_beneficiary() {
+        let mut app = test_app();
+        let contract = app.contract.clone();
+        let raise = app.raise.clone();
+        let beneficiary = Addr::unchecked("beneficiary");
+        app.set_balance(&raise, 500, "stable_coin");
+        app.execute(
+            "lp",
+            ExecuteMsg::SetBeneficiary {
+                beneficiary: Some(beneficiary.clone()),
+            },
+        )
+        .unwrap();
+
+        distribute(
+            &mut app,
+            vec![ExchangeBuilder::new().capital(500).build()],
+            None,
+        );
+
+        assert_eq!(500, app.balance(&beneficiary, "stable_coin"));
+        assert_eq!(0, app.balance(&contract, "stable_coin"));
+    }
+
+    #[test]
+    fn withdraw_more_than_balance_fails() {
+        let mut app = test_app();
+        let contract = app.contract.clone();
+        app.set_balance(&contract, 100, "stable_coin");
+
+        let res = app.execute(
+            "lp",
+            ExecuteMsg::IssueWithdrawal {
+                to: Some(Addr::unchecked("lp")),
+                to_name: None,
+                amount: Uint128::new(300),
+                capital_denom: None,
+            },
+        );
+        assert!(res.is_err());
+        assert_eq!(100, app.balance(&contract, "stable_coin"));
+    }
+
+    #[test]
+    fn return_capital_to_raise() {
+        let mut app = test_app();
+        let contract = app.contract.clone();
+        let raise = app.raise.clone();
+        app.set_balance(&contract, 400, "stable_coin");
+
+        app.execute(
+            "lp",
+            ExecuteMsg::ReturnCapital {
+                amount: Uint128::new(250),
+                capital_denom: None,
+                memo: Some(String::from("excess")),
+            },
+        )
+        .unwrap();
+
+        assert_eq!(150, app.balance(&contract, "stable_coin"));
+        assert_eq!(250, app.balance(&raise, "stable_coin"));
+        assert_eq!(
+            vec![ReceivedMsg {
+                msg: RaiseExecuteMsg::ReturnCapital {
+                    capital: Coin::new(250, "stable_coin"),
+                    memo: Some(String::from("excess")),
+                },
+                funds: vec![Coin::new(250, "stable_coin")],
+            }],
+            app.raise_received()
+        );
+    }
+
+    #[test]
+    fn raise_rejection_rolls_back_completion() {
+        let mut app = test_app();
+        let contract = app.contract.clone();
+        let raise = app.raise.clone();
+        app.set_balance(&contract, 1_000, "stable_coin");
+        app.set_balance(&contract, 10, "raise_1.commitment");
+        app.set_raise_fails(true);
+
+        authorize(&mut app, capital_call());
+        let res = complete(&mut app, capital_call());
+        assert_eq!(
+            Err(String::from("Generic error: raise rejected asset exchange")),
+            res
+        );
+
+        assert_eq!(1_000, app.balance(&contract, "stable_coin"));
+        assert_eq!(10, app.balance(&contract, "raise_1.commitment"));
+        assert_eq!(0, app.balance(&raise, "stable_coin"));
+        assert_eq!(0, app.balance(&contract, "raise_1.investment"));
+        assert_eq!(1, authorization_count(&app));
+    }
+
+    #[test]
+    fn insufficient_capital_rolls_back_completion() {
+        let mut app = test_app();
+        let contract = app.contract.clone();
+        app.set_balance(&contract, 10, "raise_1.commitment");
+
+        authorize(&mut app, capital_call());
+        assert!(complete(&mut app, capital_call()).is_err());
+
+        assert_eq!(10, app.balance(&contract, "raise_1.commitment"));
+        assert_eq!(1, authorization_count(&app));
+        assert!(app.raise_received().is_empty());
+    }
+
+    #[test]
+    fn multi_denom_completion_payload() {
+        let mut app = TestApp::new(InstantiateMsg {
+            admins: vec![Addr::unchecked("admin")],
+            lp: Addr::unchecked("lp"),
+            commitment_denom: String::from("raise_1.commitment"),
+            investment_denoms: vec![String::from("raise_1.investment")],
+            like_capital_denoms: vec![String::from("stable_coin"), String::from("capital_coin")],
+            capital_per_share: 100,
+            capital_per_share_overrides: None,
+            initial_commitment: None,
+            required_capital_attributes: vec![],
+            require_distinct_roles: None,
+            max_commitment_in_shares: None,
+            admin_threshold: None,
+            admin_action_delay_seconds: None,
+            max_authorizations_per_block: None,
+            stale_after_seconds: None,
+            default_grace_period_seconds: None,
+            default_authorization_ttl_seconds: None,
+            metadata: None,
+            withdrawal_fee_bps: None,
+            fee_recipient: None,
+            withdrawal_approval_thresholds: None,
+        });
+        let contract = app.contract.clone();
+        app.set_balance(&contract, 600, "stable_coin");
+        app.set_balance(&contract, 400, "capital_coin");
+        app.set_balance(&contract, 10, "raise_1.commitment");
+
+        let exchanges = vec![
+            ExchangeBuilder::new()
+                .investment(600)
+                .commitment(-6)
+                .capital(-600)
+                .denom("stable_coin")
+                .build(),
+            ExchangeBuilder::new()
+                .investment(400)
+                .commitment(-4)
+                .capital(-400)
+                .denom("capital_coin")
+                .build(),
+        ];
+        let to = Some(Addr::unchecked("lp_side_account"));
+        let memo = Some(String::from("memo"));
+        app.execute(
+            "lp",
+            ExecuteMsg::AuthorizeAssetExchange {
+                exchanges: exchanges.clone(),
+                to: to.clone(),
+                memo: memo.clone(),
+                idempotency_key: None,
+                expires_at: None,
+                memo_hash: None,
+                raise: None,
+            },
+        )
+        .unwrap();
+        app.execute(
+            "admin",
+            ExecuteMsg::CompleteAssetExchange {
+                exchanges: exchanges.clone(),
+                to: to.clone(),
+                memo: memo.clone(),
+                override_available_date: false,
+                raise: None,
+            },
+        )
+        .unwrap();
+
+        assert_eq!(
+            vec![ReceivedMsg {
+                msg: RaiseExecuteMsg::CompleteAssetExchange {
+                    exchanges,
+                    to,
+                    memo
+                },
+                funds: vec![
+                    Coin::new(400, "capital_coin"),
+                    Coin::new(10, "raise_1.commitment"),
+                    Coin::new(600, "stable_coin"),
+                ],
+            }],
+            app.raise_received()
+        );
+        assert_eq!(1_000, app.balance(&contract, "raise_1.investment"));
+    }
+}
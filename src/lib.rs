@@ -1,11 +1,27 @@
+pub mod address;
+pub mod amount;
 pub mod contract;
+pub mod denom;
 pub mod error;
 pub mod instantiate;
 pub mod migrate;
 pub mod msg;
 pub mod raise_msg;
 pub mod state;
+pub mod sudo;
 pub mod version;
 
 #[cfg(test)]
 pub mod mock;
+
+#[cfg(test)]
+pub mod integration;
+
+#[cfg(test)]
+pub mod mock_raise;
+
+#[cfg(test)]
+mod decode_fuzz;
+
+#[cfg(test)]
+mod storage_budget;
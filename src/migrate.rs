@@ -1,52 +1,379 @@
+use std::convert::TryInto;
 use std::hash::Hash;
 
+use crate::amount::Int128;
+use crate::denom::validate_like_capital_denoms;
 use crate::error::ContractError;
+use crate::msg::AssetExchange;
+use crate::msg::CapitalDenomRequirement;
 use crate::msg::MigrateMsg;
+use crate::msg::ValidateMigrationResponse;
+use crate::state::asset_exchange_authorization_storage;
+use crate::state::authorization_sequence_storage;
+use crate::state::authorization_sequence_storage_read;
+use crate::state::capital_denom_requirements;
+use crate::state::join_addresses;
+use crate::state::migration_history_storage;
+use crate::state::migration_history_storage_read;
+use crate::state::operation_stats_storage;
+use crate::state::operation_stats_storage_read;
 use crate::state::state_storage;
+use crate::state::state_storage_read;
+use crate::state::AssetExchangeAuthorization;
+use crate::state::AuthorizationStatus;
+use crate::state::MigrationRecord;
+use crate::state::OperationStats;
 use crate::state::State;
+use crate::state::ASSET_EXCHANGE_AUTHORIZATION_KEY;
 use crate::state::CONFIG_KEY;
+use crate::state::DEFAULT_MAX_AUTHORIZATIONS_PER_BLOCK;
+use crate::state::MAX_MIGRATION_HISTORY;
 use crate::version::CONTRACT_NAME;
 use crate::version::CONTRACT_VERSION;
+use crate::version::{is_upgrade, same_minor};
 use cosmwasm_std::entry_point;
+use cosmwasm_std::to_vec;
 use cosmwasm_std::Addr;
+use cosmwasm_std::Binary;
+use cosmwasm_std::Deps;
 use cosmwasm_std::DepsMut;
 use cosmwasm_std::Env;
 use cosmwasm_std::Response;
+use cosmwasm_std::Storage;
 use cosmwasm_storage::singleton_read;
+use cosmwasm_storage::to_length_prefixed;
 use cw2::set_contract_version;
+use cw2::CONTRACT;
 use provwasm_std::ProvenanceMsg;
 use provwasm_std::ProvenanceQuery;
 use serde::Deserialize;
 use serde::Serialize;
+use sha2::{Digest, Sha256};
 
 #[entry_point]
 pub fn migrate(
     deps: DepsMut<ProvenanceQuery>,
-    _: Env,
+    env: Env,
     migrate_msg: MigrateMsg,
 ) -> Result<Response<ProvenanceMsg>, ContractError> {
+    let msg_hash = Binary::from(Sha256::digest(&to_vec(&migrate_msg)?).as_slice());
+    let purge_legacy_storage = migrate_msg.purge_legacy_storage;
+    let plan = plan_migration(deps.as_ref(), migrate_msg)?;
+
     set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
 
-    let old_state: StateV2_0_0 = singleton_read(deps.storage, CONFIG_KEY).load()?;
+    if let Some(authorizations) = plan.authorizations {
+        if let Some(last) = authorizations.last() {
+            authorization_sequence_storage(deps.storage).save(&last.id)?;
+        }
+        asset_exchange_authorization_storage(deps.storage).save(&authorizations)?;
+    }
+
+    let mut response = Response::new();
+    if let Some(old_admins) = plan.old_admins {
+        response = response
+            .add_attribute("old_admin", join_addresses(&old_admins))
+            .add_attribute("new_admin", join_addresses(&plan.state.admins));
+    }
+
+    state_storage(deps.storage).save(&plan.state)?;
+    if operation_stats_storage_read(deps.storage)
+        .may_load()?
+        .is_none()
+    {
+        operation_stats_storage(deps.storage).save(&OperationStats::default())?;
+    }
+
+    if purge_legacy_storage {
+        let removed = purge_legacy_storage_keys(deps.storage);
+        response = response.add_attribute("legacy_keys_removed", removed.to_string());
+    }
+
+    let mut history = migration_history_storage_read(deps.storage)
+        .may_load()?
+        .unwrap_or_default();
+    history.push(MigrationRecord {
+        from_version: plan.source_version,
+        to_version: CONTRACT_VERSION.to_string(),
+        height: env.block.height,
+        time: env.block.time,
+        msg_hash,
+    });
+    if history.len() > MAX_MIGRATION_HISTORY {
+        history.drain(..history.len() - MAX_MIGRATION_HISTORY);
+    }
+    migration_history_storage(deps.storage).save(&history)?;
+
+    Ok(response)
+}
 
-    let capital_denom = match migrate_msg.capital_denom {
-        None => old_state.capital_denom,
-        Some(capital_denom) => capital_denom,
+/// The outcome of a migration, computed without writing to storage.
+pub struct MigrationPlan {
+    pub source_version: String,
+    pub state: State,
+    /// Authorizations rewritten from a legacy layout, if any needed rewriting.
+    pub authorizations: Option<Vec<AssetExchangeAuthorization>>,
+    /// The replaced admins, when the migration changes them.
+    pub old_admins: Option<Vec<Addr>>,
+}
+
+pub fn plan_migration(
+    deps: Deps<ProvenanceQuery>,
+    migrate_msg: MigrateMsg,
+) -> Result<MigrationPlan, ContractError> {
+    let source_version = CONTRACT
+        .may_load(deps.storage)?
+        .map(|contract| contract.version)
+        .unwrap_or_default();
+
+    if !source_version.is_empty() && is_upgrade(CONTRACT_VERSION, &source_version)? {
+        return Err(ContractError::from(format!(
+            "cannot migrate from {} to older version {}",
+            source_version, CONTRACT_VERSION
+        )));
+    }
+
+    let (mut state, authorizations) = match load_legacy_state(deps.storage, &source_version)? {
+        Some(old_state) => (old_state.into(), load_legacy_authorizations(deps.storage)?),
+        None => (state_storage_read(deps.storage).load()?, None),
     };
-    let new_state = State {
-        admin: old_state.admin,
-        lp: old_state.lp,
-        raise: old_state.raise.clone(),
-        commitment_denom: old_state.commitment_denom,
-        investment_denom: old_state.investment_denom,
-        capital_denom,
-        capital_per_share: old_state.capital_per_share,
-        required_capital_attribute: migrate_msg.required_capital_attribute,
+
+    if let Some(like_capital_denoms) = migrate_msg.like_capital_denoms {
+        validate_like_capital_denoms(&like_capital_denoms)?;
+        state.like_capital_denoms = like_capital_denoms;
+    }
+    if let Some(capital_per_share_overrides) = migrate_msg.capital_per_share_overrides {
+        state.capital_per_share_overrides = capital_per_share_overrides;
+    }
+    state.validate_capital_per_share()?;
+    if let Some(stale_after_seconds) = migrate_msg.stale_after_seconds {
+        state.stale_after_seconds = Some(stale_after_seconds);
+    }
+    if let Some(default_grace_period_seconds) = migrate_msg.default_grace_period_seconds {
+        state.default_grace_period_seconds = default_grace_period_seconds;
+    }
+    if let Some(default_authorization_ttl_seconds) = migrate_msg.default_authorization_ttl_seconds {
+        state.default_authorization_ttl_seconds = Some(default_authorization_ttl_seconds);
+    }
+    if let Some(withdrawal_fee_bps) = migrate_msg.withdrawal_fee_bps {
+        state.withdrawal_fee_bps = Some(withdrawal_fee_bps);
+    }
+    if let Some(fee_recipient) = migrate_msg.fee_recipient {
+        state.fee_recipient = Some(deps.api.addr_validate(fee_recipient.as_str())?);
+    }
+    if let Some(withdrawal_approval_thresholds) = migrate_msg.withdrawal_approval_thresholds {
+        state.withdrawal_approval_thresholds = withdrawal_approval_thresholds;
+    }
+    state.validate_withdrawal_fee()?;
+    state.validate_withdrawal_approval_thresholds()?;
+    let required_capital_attributes = if migrate_msg.clear_required_capital_attributes {
+        if !migrate_msg.required_capital_attributes.is_empty() {
+            return Err(ContractError::from(
+                "cannot both clear and replace required capital attributes",
+            ));
+        }
+        vec![]
+    } else if migrate_msg.required_capital_attributes.is_empty() {
+        state.required_capital_attributes
+    } else {
+        migrate_msg.required_capital_attributes
+    };
+    state.required_capital_attributes =
+        capital_denom_requirements(&state.like_capital_denoms, required_capital_attributes)?;
+
+    // a new admin replaces the whole admin set, so the threshold falls back to a single approval
+    let mut old_admins = None;
+    if let Some(new_admin) = migrate_msg.new_admin {
+        let new_admin = deps.api.addr_validate(new_admin.as_str())?;
+        old_admins = Some(std::mem::replace(&mut state.admins, vec![new_admin]));
+        state.admin_threshold = 1;
+        state.validate_admins()?;
+    }
+
+    Ok(MigrationPlan {
+        source_version,
+        state,
+        authorizations,
+        old_admins,
+    })
+}
+
+/// Runs a migration with the given configuration against the stored state without writing
+/// anything, reporting the detected source version and any validation errors.
+pub fn validate_migration(
+    deps: Deps<ProvenanceQuery>,
+    like_capital_denoms: Vec<String>,
+    required_capital_attributes: Vec<CapitalDenomRequirement>,
+) -> ValidateMigrationResponse {
+    let source_version = CONTRACT
+        .may_load(deps.storage)
+        .ok()
+        .flatten()
+        .map(|contract| contract.version)
+        .unwrap_or_default();
+
+    let migrate_msg = MigrateMsg {
+        like_capital_denoms: Some(like_capital_denoms),
+        required_capital_attributes,
+        ..Default::default()
+    };
+    let errors = match plan_migration(deps, migrate_msg) {
+        Ok(_) => vec![],
+        Err(error) => vec![error.to_string()],
     };
 
-    state_storage(deps.storage).save(&new_state)?;
+    ValidateMigrationResponse {
+        source_version,
+        valid: errors.is_empty(),
+        errors,
+    }
+}
+
+/// Singleton keys written by releases prior to 2.0 that are no longer read. Must never include
+/// `CONFIG_KEY` or `ASSET_EXCHANGE_AUTHORIZATION_KEY`.
+pub const LEGACY_STORAGE_KEYS: &[&[u8]] = &[
+    b"status",
+    LEGACY_CAPITAL_CALLS_KEY,
+    LEGACY_REDEMPTIONS_KEY,
+    LEGACY_DISTRIBUTIONS_KEY,
+    b"withdrawals",
+];
+
+pub const LEGACY_CAPITAL_CALLS_KEY: &[u8] = b"capital_calls";
+pub const LEGACY_REDEMPTIONS_KEY: &[u8] = b"redemptions";
+pub const LEGACY_DISTRIBUTIONS_KEY: &[u8] = b"distributions";
+
+/// Removes any legacy keys that are present, returning how many were removed.
+fn purge_legacy_storage_keys(storage: &mut dyn Storage) -> usize {
+    let mut removed = 0;
+    for key in LEGACY_STORAGE_KEYS {
+        if *key == CONFIG_KEY || *key == ASSET_EXCHANGE_AUTHORIZATION_KEY {
+            continue;
+        }
+        let key = to_length_prefixed(key);
+        if storage.get(&key).is_some() {
+            storage.remove(&key);
+            removed += 1;
+        }
+    }
+    removed
+}
 
-    Ok(Response::default())
+/// Loads the stored state using the layout of the given version, upgrading it to the most
+/// recent single capital denom layout. Returns `None` when the state is already in the current
+/// layout.
+fn load_legacy_state(
+    storage: &dyn Storage,
+    version: &str,
+) -> Result<Option<StateV2_2_0>, ContractError> {
+    if version.is_empty() || is_upgrade(version, "2.2.0-0")? {
+        let old_state: StateV2_0_0 = singleton_read(storage, CONFIG_KEY).load()?;
+        Ok(Some(old_state.into()))
+    } else if same_minor(version, "2.2.0")? {
+        Ok(Some(singleton_read(storage, CONFIG_KEY).load()?))
+    } else {
+        Ok(None)
+    }
+}
+
+/// Loads authorizations stored before they carried ids, assigning sequential ids in their
+/// existing order, followed by any pending activity from releases prior to 2.0 converted into
+/// equivalent authorizations.
+fn load_legacy_authorizations(
+    storage: &dyn Storage,
+) -> Result<Option<Vec<AssetExchangeAuthorization>>, ContractError> {
+    let legacy_authorizations: Option<Vec<AssetExchangeAuthorizationV2_2_0>> =
+        singleton_read(storage, ASSET_EXCHANGE_AUTHORIZATION_KEY).may_load()?;
+    let capital_calls: Option<Vec<CapitalCall>> =
+        singleton_read(storage, LEGACY_CAPITAL_CALLS_KEY).may_load()?;
+    let redemptions: Option<Vec<Redemption>> =
+        singleton_read(storage, LEGACY_REDEMPTIONS_KEY).may_load()?;
+    let distributions: Option<Vec<Distribution>> =
+        singleton_read(storage, LEGACY_DISTRIBUTIONS_KEY).may_load()?;
+
+    if legacy_authorizations.is_none()
+        && capital_calls.is_none()
+        && redemptions.is_none()
+        && distributions.is_none()
+    {
+        return Ok(None);
+    }
+
+    let mut converted = legacy_authorizations.unwrap_or_default();
+    for call in capital_calls.unwrap_or_default() {
+        let amount: i64 = call.amount.try_into()?;
+        converted.push(legacy_activity(
+            Some(amount),
+            Some(-amount),
+            format!("legacy capital call {}", call.sequence),
+        ));
+    }
+    for redemption in redemptions.unwrap_or_default() {
+        let asset: i64 = redemption.asset.try_into()?;
+        converted.push(legacy_activity(
+            Some(-asset),
+            Some(redemption.capital.try_into()?),
+            format!("legacy redemption {}", redemption.sequence),
+        ));
+    }
+    for distribution in distributions.unwrap_or_default() {
+        converted.push(legacy_activity(
+            None,
+            Some(distribution.amount.try_into()?),
+            format!("legacy distribution {}", distribution.sequence),
+        ));
+    }
+
+    let sequence = authorization_sequence_storage_read(storage)
+        .may_load()?
+        .unwrap_or_default();
+
+    Ok(Some(
+        converted
+            .into_iter()
+            .zip(sequence + 1..)
+            .map(|(authorization, id)| AssetExchangeAuthorization {
+                id,
+                raise: None,
+                exchanges: authorization.exchanges,
+                to: authorization.to,
+                memo: authorization.memo,
+                created_at: None,
+                expires_at: None,
+                memo_hash: None,
+                status: AuthorizationStatus::Pending,
+                resolved_at: None,
+                resolved_by: None,
+            })
+            .collect(),
+    ))
+}
+
+fn legacy_activity(
+    investment: Option<i64>,
+    capital: Option<i64>,
+    memo: String,
+) -> AssetExchangeAuthorizationV2_2_0 {
+    AssetExchangeAuthorizationV2_2_0 {
+        exchanges: vec![AssetExchange {
+            investment: investment.map(Int128::from),
+            investment_denom: None,
+            commitment_in_shares: None,
+            capital: capital.map(Int128::from),
+            capital_denom: None,
+            date: None,
+            to: None,
+        }],
+        to: None,
+        memo: Some(memo),
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct AssetExchangeAuthorizationV2_2_0 {
+    pub exchanges: Vec<AssetExchange>,
+    pub to: Option<Addr>,
+    pub memo: Option<String>,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
@@ -60,6 +387,76 @@ pub struct StateV2_0_0 {
     pub capital_per_share: u64,
 }
 
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct StateV2_2_0 {
+    pub admin: Addr,
+    pub lp: Addr,
+    pub raise: Addr,
+    pub commitment_denom: String,
+    pub investment_denom: String,
+    pub capital_denom: String,
+    pub capital_per_share: u64,
+    pub required_capital_attribute: Option<String>,
+}
+
+impl From<StateV2_0_0> for StateV2_2_0 {
+    fn from(state: StateV2_0_0) -> Self {
+        StateV2_2_0 {
+            admin: state.admin,
+            lp: state.lp,
+            raise: state.raise,
+            commitment_denom: state.commitment_denom,
+            investment_denom: state.investment_denom,
+            capital_denom: state.capital_denom,
+            capital_per_share: state.capital_per_share,
+            required_capital_attribute: None,
+        }
+    }
+}
+
+impl From<StateV2_2_0> for State {
+    fn from(state: StateV2_2_0) -> Self {
+        let required_capital_attributes = match state.required_capital_attribute {
+            None => vec![],
+            Some(required_attribute) => vec![CapitalDenomRequirement {
+                capital_denom: state.capital_denom.clone(),
+                required_attributes: vec![required_attribute],
+                enforce_attribute_on_bank_send: false,
+            }],
+        };
+        State {
+            admins: vec![state.admin],
+            admin_threshold: 1,
+            lp: state.lp,
+            raises: vec![state.raise],
+            commitment_denom: state.commitment_denom,
+            investment_denoms: vec![state.investment_denom],
+            like_capital_denoms: vec![state.capital_denom],
+            capital_per_share: state.capital_per_share,
+            capital_per_share_overrides: vec![],
+            required_capital_attributes,
+            require_distinct_roles: false,
+            max_commitment_in_shares: None,
+            total_commitment_in_shares: 0,
+            operators: vec![],
+            admin_action_delay_seconds: 0,
+            max_authorizations_per_block: DEFAULT_MAX_AUTHORIZATIONS_PER_BLOCK,
+            paused: None,
+            stale_after_seconds: None,
+            default_grace_period_seconds: 0,
+            default_authorization_ttl_seconds: None,
+            default_distribution_address: None,
+            lp_note: None,
+            last_lp_activity: None,
+            last_admin_activity: None,
+            withdrawal_fee_bps: None,
+            fee_recipient: None,
+            withdrawal_approval_thresholds: vec![],
+            beneficiary: None,
+        }
+    }
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
 pub enum Status {
     Draft,
@@ -156,7 +553,20 @@ impl Hash for Withdrawal {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use cosmwasm_std::testing::mock_env;
+    use crate::contract::execute;
+    use crate::contract::query;
+    use crate::mock::restricted_capital_coin_deps;
+    use crate::mock::{execute_args, msg_at_index};
+    use crate::msg::ExecuteMsg;
+    use crate::msg::MigrationHistoryResponse;
+    use crate::msg::QueryMsg;
+    use crate::raise_msg::RaiseExecuteMsg;
+    use crate::state::asset_exchange_authorization_storage_read;
+    use cosmwasm_std::attr;
+    use cosmwasm_std::coin;
+    use cosmwasm_std::from_binary;
+    use cosmwasm_std::testing::{mock_env, mock_info};
+    use cosmwasm_std::Order;
     use cosmwasm_storage::singleton;
     use provwasm_mocks::mock_dependencies;
 
@@ -177,26 +587,38 @@ mod tests {
             })
             .unwrap();
 
-        migrate(
-            deps.as_mut(),
-            mock_env(),
-            MigrateMsg {
-                capital_denom: None,
-                required_capital_attribute: None,
-            },
-        )
-        .unwrap();
+        migrate(deps.as_mut(), mock_env(), MigrateMsg::default()).unwrap();
 
         assert_eq!(
             State {
-                admin: Addr::unchecked("marketpalace"),
+                admins: vec![Addr::unchecked("marketpalace")],
+                admin_threshold: 1,
                 lp: Addr::unchecked("lp"),
-                raise: Addr::unchecked("raise_1"),
+                raises: vec![Addr::unchecked("raise_1")],
                 commitment_denom: String::from("commitment"),
-                investment_denom: String::from("investment"),
-                capital_denom: String::from("stable_coin"),
+                investment_denoms: vec![String::from("investment")],
+                like_capital_denoms: vec![String::from("stable_coin")],
                 capital_per_share: 100,
-                required_capital_attribute: None,
+                capital_per_share_overrides: vec![],
+                required_capital_attributes: vec![],
+                require_distinct_roles: false,
+                max_commitment_in_shares: None,
+                total_commitment_in_shares: 0,
+                operators: vec![],
+                admin_action_delay_seconds: 0,
+                max_authorizations_per_block: DEFAULT_MAX_AUTHORIZATIONS_PER_BLOCK,
+                paused: None,
+                stale_after_seconds: None,
+                default_grace_period_seconds: 0,
+                default_authorization_ttl_seconds: None,
+                default_distribution_address: None,
+                lp_note: None,
+                last_lp_activity: None,
+                last_admin_activity: None,
+                withdrawal_fee_bps: None,
+                fee_recipient: None,
+                withdrawal_approval_thresholds: vec![],
+                beneficiary: None,
             },
             singleton_read(&deps.storage, CONFIG_KEY).load().unwrap()
         );
@@ -218,23 +640,726 @@ mod tests {
             .unwrap();
 
         let migration_msg = MigrateMsg {
-            capital_denom: Some(String::from("new_denom")),
-            required_capital_attribute: Some(String::from("attr")),
+            like_capital_denoms: Some(vec![String::from("new_denom")]),
+            required_capital_attributes: vec![CapitalDenomRequirement {
+                capital_denom: String::from("new_denom"),
+                required_attributes: vec![String::from("attr")],
+                enforce_attribute_on_bank_send: false,
+            }],
+            ..Default::default()
         };
         migrate(deps.as_mut(), mock_env(), migration_msg).unwrap();
 
         assert_eq!(
             State {
+                admins: vec![Addr::unchecked("marketpalace")],
+                admin_threshold: 1,
+                lp: Addr::unchecked("lp"),
+                raises: vec![Addr::unchecked("raise_1")],
+                commitment_denom: String::from("commitment"),
+                investment_denoms: vec![String::from("investment")],
+                like_capital_denoms: vec![String::from("new_denom")],
+                capital_per_share: 100,
+                capital_per_share_overrides: vec![],
+                required_capital_attributes: vec![CapitalDenomRequirement {
+                    capital_denom: String::from("new_denom"),
+                    required_attributes: vec![String::from("attr")],
+                    enforce_attribute_on_bank_send: false,
+                }],
+                require_distinct_roles: false,
+                max_commitment_in_shares: None,
+                total_commitment_in_shares: 0,
+                operators: vec![],
+                admin_action_delay_seconds: 0,
+                max_authorizations_per_block: DEFAULT_MAX_AUTHORIZATIONS_PER_BLOCK,
+                paused: None,
+                stale_after_seconds: None,
+                default_grace_period_seconds: 0,
+                default_authorization_ttl_seconds: None,
+                default_distribution_address: None,
+                lp_note: None,
+                last_lp_activity: None,
+                last_admin_activity: None,
+                withdrawal_fee_bps: None,
+                fee_recipient: None,
+                withdrawal_approval_thresholds: vec![],
+                beneficiary: None,
+            },
+            singleton_read(&deps.storage, CONFIG_KEY).load().unwrap()
+        );
+    }
+
+    #[test]
+    fn migration_with_legacy_msg() {
+        let mut deps = mock_dependencies(&[]);
+        singleton(&mut deps.storage, CONFIG_KEY)
+            .save(&StateV2_0_0 {
                 admin: Addr::unchecked("marketpalace"),
                 lp: Addr::unchecked("lp"),
                 raise: Addr::unchecked("raise_1"),
-                commitment_denom: String::from("commitment"),
-                investment_denom: String::from("investment"),
-                capital_denom: String::from("new_denom"),
+                commitment_denom: "commitment".to_string(),
+                investment_denom: "investment".to_string(),
+                capital_denom: String::from("stable_coin"),
                 capital_per_share: 100,
-                required_capital_attribute: Some(String::from("attr")),
+            })
+            .unwrap();
+
+        let migration_msg: MigrateMsg = cosmwasm_std::from_slice(
+            br#"{"capital_denom": "new_denom", "required_capital_attribute": "attr"}"#,
+        )
+        .unwrap();
+        migrate(deps.as_mut(), mock_env(), migration_msg).unwrap();
+
+        let state: State = singleton_read(&deps.storage, CONFIG_KEY).load().unwrap();
+        assert_eq!(vec![String::from("new_denom")], state.like_capital_denoms);
+        assert_eq!(
+            Some(&[String::from("attr")][..]),
+            state.required_capital_attributes("new_denom")
+        );
+    }
+
+    #[test]
+    fn migration_sets_stale_window() {
+        let mut deps = mock_dependencies(&[]);
+        save_v2_2_0_state(&mut deps.storage);
+
+        let migration_msg: MigrateMsg =
+            cosmwasm_std::from_slice(br#"{"stale_after_seconds": 2592000}"#).unwrap();
+        migrate(deps.as_mut(), mock_env(), migration_msg).unwrap();
+
+        let state: State = singleton_read(&deps.storage, CONFIG_KEY).load().unwrap();
+        assert_eq!(Some(2_592_000), state.stale_after_seconds);
+    }
+
+    #[test]
+    fn migration_sets_withdrawal_fee() {
+        let mut deps = mock_dependencies(&[]);
+        save_v2_2_0_state(&mut deps.storage);
+
+        let with_recipient = |fee_recipient: &str| MigrateMsg {
+            withdrawal_fee_bps: Some(25),
+            fee_recipient: Some(Addr::unchecked(fee_recipient)),
+            ..Default::default()
+        };
+        assert!(migrate(deps.as_mut(), mock_env(), with_recipient("")).is_err());
+
+        migrate(deps.as_mut(), mock_env(), with_recipient("marketpalace")).unwrap();
+        let state = state_storage_read(&deps.storage).load().unwrap();
+        assert_eq!(Some(25), state.withdrawal_fee_bps);
+        assert_eq!(Some(Addr::unchecked("marketpalace")), state.fee_recipient);
+    }
+
+    fn empty_migrate_msg() -> MigrateMsg {
+        cosmwasm_std::from_slice(b"{}").unwrap()
+    }
+
+    #[test]
+    fn migration_initializes_operation_stats() {
+        let mut deps = mock_dependencies(&[]);
+        save_v2_2_0_state(&mut deps.storage);
+
+        migrate(deps.as_mut(), mock_env(), empty_migrate_msg()).unwrap();
+        assert_eq!(
+            OperationStats::default(),
+            operation_stats_storage_read(&deps.storage).load().unwrap()
+        );
+
+        // counters survive later migrations
+        let counted = OperationStats {
+            authorizations: 2,
+            last_activity: Some(mock_env().block.time),
+            ..OperationStats::default()
+        };
+        operation_stats_storage(&mut deps.storage)
+            .save(&counted)
+            .unwrap();
+        migrate(deps.as_mut(), mock_env(), empty_migrate_msg()).unwrap();
+        assert_eq!(
+            counted,
+            operation_stats_storage_read(&deps.storage).load().unwrap()
+        );
+    }
+
+    fn save_v2_2_0_state(storage: &mut dyn Storage) {
+        set_contract_version(storage, CONTRACT_NAME, "2.2.0").unwrap();
+        singleton(storage, CONFIG_KEY)
+            .save(&StateV2_2_0 {
+                admin: Addr::unchecked("marketpalace"),
+                lp: Addr::unchecked("lp"),
+                raise: Addr::unchecked("raise_1"),
+                commitment_denom: "commitment".to_string(),
+                investment_denom: "investment".to_string(),
+                capital_denom: String::from("restricted_capital_coin"),
+                capital_per_share: 100,
+                required_capital_attribute: Some(String::from("capital.test")),
+            })
+            .unwrap();
+    }
+
+    #[test]
+    fn migration_from_v2_2_0() {
+        let mut deps = mock_dependencies(&[]);
+        save_v2_2_0_state(&mut deps.storage);
+
+        migrate(deps.as_mut(), mock_env(), MigrateMsg::default()).unwrap();
+
+        let state: State = singleton_read(&deps.storage, CONFIG_KEY).load().unwrap();
+        assert_eq!(
+            vec![String::from("restricted_capital_coin")],
+            state.like_capital_denoms
+        );
+        assert_eq!(
+            vec![CapitalDenomRequirement {
+                capital_denom: String::from("restricted_capital_coin"),
+                required_attributes: vec![String::from("capital.test")],
+                enforce_attribute_on_bank_send: false,
+            }],
+            state.required_capital_attributes
+        );
+    }
+
+    #[test]
+    fn migration_of_single_required_attribute() {
+        let mut deps = mock_dependencies(&[]);
+        set_contract_version(&mut deps.storage, CONTRACT_NAME, CONTRACT_VERSION).unwrap();
+        let stored = String::from_utf8(to_vec(&State::test_restricted_capital_coin()).unwrap())
+            .unwrap()
+            .replace(
+                r#""required_attributes":["capital.test"]"#,
+                r#""required_attribute":"capital.test""#,
+            );
+        assert!(stored.contains(r#""required_attribute":"capital.test""#));
+        deps.storage
+            .set(&to_length_prefixed(CONFIG_KEY), stored.as_bytes());
+
+        migrate(deps.as_mut(), mock_env(), empty_migrate_msg()).unwrap();
+
+        let state: State = singleton_read(&deps.storage, CONFIG_KEY).load().unwrap();
+        assert_eq!(
+            vec![CapitalDenomRequirement {
+                capital_denom: String::from("restricted_capital_coin"),
+                required_attributes: vec![String::from("capital.test")],
+                enforce_attribute_on_bank_send: false,
+            }],
+            state.required_capital_attributes
+        );
+    }
+
+    #[test]
+    fn migration_from_v2_2_0_with_requirement_override() {
+        let mut deps = mock_dependencies(&[]);
+        save_v2_2_0_state(&mut deps.storage);
+
+        migrate(
+            deps.as_mut(),
+            mock_env(),
+            MigrateMsg {
+                like_capital_denoms: Some(vec![
+                    String::from("restricted_capital_coin"),
+                    String::from("stable_coin"),
+                ]),
+                required_capital_attributes: vec![CapitalDenomRequirement {
+                    capital_denom: String::from("restricted_capital_coin"),
+                    required_attributes: vec![String::from("capital.other")],
+                    enforce_attribute_on_bank_send: false,
+                }],
+                ..Default::default()
+            },
+        )
+        .unwrap();
+
+        let state: State = singleton_read(&deps.storage, CONFIG_KEY).load().unwrap();
+        assert_eq!(
+            vec![CapitalDenomRequirement {
+                capital_denom: String::from("restricted_capital_coin"),
+                required_attributes: vec![String::from("capital.other")],
+                enforce_attribute_on_bank_send: false,
+            }],
+            state.required_capital_attributes
+        );
+    }
+
+    #[test]
+    fn migration_twice() {
+        let mut deps = mock_dependencies(&[]);
+        save_v2_2_0_state(&mut deps.storage);
+
+        migrate(deps.as_mut(), mock_env(), MigrateMsg::default()).unwrap();
+
+        // re-running the same version applies only the overrides
+        migrate(
+            deps.as_mut(),
+            mock_env(),
+            MigrateMsg {
+                like_capital_denoms: Some(vec![
+                    String::from("restricted_capital_coin"),
+                    String::from("stable_coin"),
+                ]),
+                ..Default::default()
             },
+        )
+        .unwrap();
+
+        let state: State = singleton_read(&deps.storage, CONFIG_KEY).load().unwrap();
+        assert_eq!(
+            vec![
+                String::from("restricted_capital_coin"),
+                String::from("stable_coin")
+            ],
+            state.like_capital_denoms
+        );
+        assert_eq!(
+            Some(&[String::from("capital.test")][..]),
+            state.required_capital_attributes("restricted_capital_coin")
+        );
+        assert_eq!(
+            CONTRACT_VERSION,
+            CONTRACT.load(&deps.storage).unwrap().version
+        );
+    }
+
+    #[test]
+    fn migration_twice_without_overrides() {
+        let mut deps = mock_dependencies(&[]);
+        save_v2_2_0_state(&mut deps.storage);
+
+        let msg = MigrateMsg::default();
+        migrate(deps.as_mut(), mock_env(), msg.clone()).unwrap();
+        let state: State = singleton_read(&deps.storage, CONFIG_KEY).load().unwrap();
+
+        migrate(deps.as_mut(), mock_env(), msg).unwrap();
+        assert_eq!(
+            state,
             singleton_read(&deps.storage, CONFIG_KEY).load().unwrap()
         );
     }
+
+    #[test]
+    fn migration_of_current_state() {
+        let mut deps =
+            restricted_capital_coin_deps(Some(|state| state.max_commitment_in_shares = Some(500)));
+        set_contract_version(&mut deps.storage, CONTRACT_NAME, CONTRACT_VERSION).unwrap();
+
+        migrate(
+            deps.as_mut(),
+            mock_env(),
+            MigrateMsg {
+                new_admin: Some(Addr::unchecked("admin_2")),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+
+        let mut expected = State::test_restricted_capital_coin();
+        expected.max_commitment_in_shares = Some(500);
+        expected.admins = vec![Addr::unchecked("admin_2")];
+        assert_eq!(expected, state_storage_read(&deps.storage).load().unwrap());
+    }
+
+    #[test]
+    fn migration_of_legacy_authorizations() {
+        let mut deps = mock_dependencies(&[]);
+        save_v2_2_0_state(&mut deps.storage);
+        let exchange = AssetExchange {
+            investment: None,
+            investment_denom: None,
+            commitment_in_shares: Some(Int128::new(1_000)),
+            capital: None,
+            capital_denom: None,
+            date: None,
+            to: None,
+        };
+        singleton(&mut deps.storage, ASSET_EXCHANGE_AUTHORIZATION_KEY)
+            .save(&vec![
+                AssetExchangeAuthorizationV2_2_0 {
+                    exchanges: vec![exchange.clone()],
+                    to: None,
+                    memo: None,
+                },
+                AssetExchangeAuthorizationV2_2_0 {
+                    exchanges: vec![exchange.clone()],
+                    to: None,
+                    memo: Some(String::from("memo")),
+                },
+            ])
+            .unwrap();
+
+        migrate(deps.as_mut(), mock_env(), MigrateMsg::default()).unwrap();
+
+        let authorizations = asset_exchange_authorization_storage_read(&deps.storage)
+            .load()
+            .unwrap();
+        assert_eq!(
+            vec![1, 2],
+            authorizations.iter().map(|a| a.id).collect::<Vec<u64>>()
+        );
+        assert_eq!(None, authorizations[0].created_at);
+
+        // verify a migrated authorization can be completed
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("lp", &[]),
+            ExecuteMsg::CompleteAssetExchange {
+                exchanges: vec![exchange],
+                to: None,
+                memo: Some(String::from("memo")),
+                override_available_date: false,
+                raise: None,
+            },
+        )
+        .unwrap();
+        let authorizations = asset_exchange_authorization_storage_read(&deps.storage)
+            .load()
+            .unwrap();
+        assert_eq!(
+            vec![1],
+            authorizations.iter().map(|a| a.id).collect::<Vec<u64>>()
+        );
+    }
+
+    #[test]
+    fn migration_with_new_admin() {
+        let mut deps = mock_dependencies(&[]);
+        save_v2_2_0_state(&mut deps.storage);
+
+        let res = migrate(
+            deps.as_mut(),
+            mock_env(),
+            MigrateMsg {
+                new_admin: Some(Addr::unchecked("new_admin")),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+
+        assert_eq!(
+            vec![
+                attr("old_admin", "marketpalace"),
+                attr("new_admin", "new_admin")
+            ],
+            res.attributes
+        );
+        let state: State = singleton_read(&deps.storage, CONFIG_KEY).load().unwrap();
+        assert_eq!(vec![Addr::unchecked("new_admin")], state.admins);
+        assert_eq!(1, state.admin_threshold);
+    }
+
+    #[test]
+    fn migration_without_new_admin() {
+        let mut deps = mock_dependencies(&[]);
+        save_v2_2_0_state(&mut deps.storage);
+
+        let res = migrate(deps.as_mut(), mock_env(), MigrateMsg::default()).unwrap();
+
+        assert!(res.attributes.is_empty());
+        let state: State = singleton_read(&deps.storage, CONFIG_KEY).load().unwrap();
+        assert_eq!(vec![Addr::unchecked("marketpalace")], state.admins);
+    }
+
+    #[test]
+    fn migration_clearing_required_capital_attributes() {
+        let mut deps = mock_dependencies(&[]);
+        save_v2_2_0_state(&mut deps.storage);
+
+        migrate(
+            deps.as_mut(),
+            mock_env(),
+            MigrateMsg {
+                clear_required_capital_attributes: true,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+
+        let state: State = singleton_read(&deps.storage, CONFIG_KEY).load().unwrap();
+        assert!(state.required_capital_attributes.is_empty());
+    }
+
+    #[test]
+    fn migration_clearing_and_replacing_required_capital_attributes() {
+        let mut deps = mock_dependencies(&[]);
+        save_v2_2_0_state(&mut deps.storage);
+
+        let res = migrate(
+            deps.as_mut(),
+            mock_env(),
+            MigrateMsg {
+                required_capital_attributes: vec![CapitalDenomRequirement {
+                    capital_denom: String::from("restricted_capital_coin"),
+                    required_attributes: vec![String::from("capital.other")],
+                    enforce_attribute_on_bank_send: false,
+                }],
+                clear_required_capital_attributes: true,
+                ..Default::default()
+            },
+        );
+        assert!(res.is_err());
+    }
+
+    #[test]
+    fn migration_dry_run() {
+        let mut deps = mock_dependencies(&[]);
+        save_v2_2_0_state(&mut deps.storage);
+        let before: Vec<(Vec<u8>, Vec<u8>)> =
+            deps.storage.range(None, None, Order::Ascending).collect();
+
+        let res: ValidateMigrationResponse = from_binary(
+            &query(
+                deps.as_ref(),
+                mock_env(),
+                QueryMsg::ValidateMigration {
+                    like_capital_denoms: vec![String::from("stable_coin")],
+                    required_capital_attributes: vec![],
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap();
+
+        // the stored requirement references a denom that is no longer supported
+        assert_eq!("2.2.0", res.source_version);
+        assert!(!res.valid);
+        assert_eq!(
+            vec![String::from(
+                "unsupported capital denom: restricted_capital_coin"
+            )],
+            res.errors
+        );
+
+        let res: ValidateMigrationResponse = from_binary(
+            &query(
+                deps.as_ref(),
+                mock_env(),
+                QueryMsg::ValidateMigration {
+                    like_capital_denoms: vec![
+                        String::from("restricted_capital_coin"),
+                        String::from("stable_coin"),
+                    ],
+                    required_capital_attributes: vec![],
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        assert!(res.valid);
+
+        // verify storage unchanged
+        let after: Vec<(Vec<u8>, Vec<u8>)> =
+            deps.storage.range(None, None, Order::Ascending).collect();
+        assert_eq!(before, after);
+    }
+
+    #[test]
+    fn migration_history() {
+        let mut deps = mock_dependencies(&[]);
+        save_v2_2_0_state(&mut deps.storage);
+        let msg = MigrateMsg::default();
+
+        let mut env = mock_env();
+        migrate(deps.as_mut(), env.clone(), msg.clone()).unwrap();
+        env.block.height += 1;
+        migrate(
+            deps.as_mut(),
+            env.clone(),
+            MigrateMsg {
+                new_admin: Some(Addr::unchecked("new_admin")),
+                ..msg.clone()
+            },
+        )
+        .unwrap();
+
+        let history = from_binary::<MigrationHistoryResponse>(
+            &query(deps.as_ref(), mock_env(), QueryMsg::GetMigrationHistory {}).unwrap(),
+        )
+        .unwrap()
+        .migrations;
+        assert_eq!(2, history.len());
+        assert_eq!("2.2.0", history[0].from_version);
+        assert_eq!(CONTRACT_VERSION, history[0].to_version);
+        assert_eq!(mock_env().block.height, history[0].height);
+        assert_eq!(
+            Binary::from(Sha256::digest(&to_vec(&msg).unwrap()).as_slice()),
+            history[0].msg_hash
+        );
+        assert_eq!(CONTRACT_VERSION, history[1].from_version);
+        assert_eq!(env.block.height, history[1].height);
+        assert_ne!(history[0].msg_hash, history[1].msg_hash);
+    }
+
+    #[test]
+    fn migration_history_capped() {
+        let mut deps = mock_dependencies(&[]);
+        save_v2_2_0_state(&mut deps.storage);
+
+        let mut env = mock_env();
+        for _ in 0..MAX_MIGRATION_HISTORY + 2 {
+            env.block.height += 1;
+            migrate(deps.as_mut(), env.clone(), MigrateMsg::default()).unwrap();
+        }
+
+        let history = migration_history_storage_read(&deps.storage)
+            .load()
+            .unwrap();
+        assert_eq!(MAX_MIGRATION_HISTORY, history.len());
+        assert_eq!(env.block.height, history.last().unwrap().height);
+    }
+
+    fn save_legacy_keys(storage: &mut dyn Storage) {
+        for key in LEGACY_STORAGE_KEYS {
+            singleton(storage, key).save(&Vec::<u8>::new()).unwrap();
+        }
+    }
+
+    #[test]
+    fn migration_purging_legacy_storage() {
+        let mut deps = mock_dependencies(&[]);
+        save_v2_2_0_state(&mut deps.storage);
+        save_legacy_keys(&mut deps.storage);
+
+        let res = migrate(
+            deps.as_mut(),
+            mock_env(),
+            MigrateMsg {
+                purge_legacy_storage: true,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+
+        assert_eq!(
+            vec![attr(
+                "legacy_keys_removed",
+                LEGACY_STORAGE_KEYS.len().to_string()
+            )],
+            res.attributes
+        );
+        for key in LEGACY_STORAGE_KEYS {
+            assert_eq!(None, deps.storage.get(&to_length_prefixed(key)));
+        }
+        assert!(state_storage_read(&deps.storage).load().is_ok());
+    }
+
+    #[test]
+    fn migration_keeping_legacy_storage() {
+        let mut deps = mock_dependencies(&[]);
+        save_v2_2_0_state(&mut deps.storage);
+        save_legacy_keys(&mut deps.storage);
+
+        migrate(deps.as_mut(), mock_env(), MigrateMsg::default()).unwrap();
+
+        for key in LEGACY_STORAGE_KEYS {
+            assert!(deps.storage.get(&to_length_prefixed(key)).is_some());
+        }
+    }
+
+    #[test]
+    fn legacy_storage_keys_exclude_live_keys() {
+        assert!(!LEGACY_STORAGE_KEYS.contains(&CONFIG_KEY));
+        assert!(!LEGACY_STORAGE_KEYS.contains(&ASSET_EXCHANGE_AUTHORIZATION_KEY));
+    }
+
+    #[test]
+    fn migration_of_legacy_activity() {
+        let mut deps = mock_dependencies(&[]);
+        singleton(&mut deps.storage, CONFIG_KEY)
+            .save(&StateV2_0_0 {
+                admin: Addr::unchecked("marketpalace"),
+                lp: Addr::unchecked("lp"),
+                raise: Addr::unchecked("raise_1"),
+                commitment_denom: "commitment".to_string(),
+                investment_denom: "investment".to_string(),
+                capital_denom: String::from("stable_coin"),
+                capital_per_share: 100,
+            })
+            .unwrap();
+        singleton(&mut deps.storage, LEGACY_CAPITAL_CALLS_KEY)
+            .save(&vec![CapitalCall {
+                sequence: 1,
+                amount: 10_000,
+                days_of_notice: None,
+            }])
+            .unwrap();
+        singleton(&mut deps.storage, LEGACY_REDEMPTIONS_KEY)
+            .save(&vec![Redemption {
+                sequence: 2,
+                asset: 5_000,
+                capital: 6_000,
+            }])
+            .unwrap();
+        singleton(&mut deps.storage, LEGACY_DISTRIBUTIONS_KEY)
+            .save(&vec![Distribution {
+                sequence: 3,
+                amount: 1_000,
+            }])
+            .unwrap();
+
+        migrate(
+            deps.as_mut(),
+            mock_env(),
+            MigrateMsg {
+                purge_legacy_storage: true,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+
+        let authorizations = asset_exchange_authorization_storage_read(&deps.storage)
+            .load()
+            .unwrap();
+        assert_eq!(3, authorizations.len());
+        assert_eq!(
+            vec![
+                Some(String::from("legacy capital call 1")),
+                Some(String::from("legacy redemption 2")),
+                Some(String::from("legacy distribution 3")),
+            ],
+            authorizations
+                .iter()
+                .map(|a| a.memo.clone())
+                .collect::<Vec<Option<String>>>()
+        );
+
+        // verify each converted authorization completes with the expected funds
+        let mut funds_sent = vec![];
+        for authorization in authorizations {
+            let res = execute(
+                deps.as_mut(),
+                mock_env(),
+                mock_info("marketpalace", &[]),
+                ExecuteMsg::CompleteAssetExchange {
+                    exchanges: authorization.exchanges,
+                    to: authorization.to,
+                    memo: authorization.memo,
+                    override_available_date: false,
+                    raise: None,
+                },
+            )
+            .unwrap();
+            let (_, _, funds) = execute_args::<RaiseExecuteMsg>(msg_at_index(&res, 0));
+            funds_sent.push(funds.clone());
+        }
+        assert_eq!(
+            vec![
+                vec![coin(10_000, "stable_coin")],
+                vec![coin(5_000, "investment")],
+                vec![],
+            ],
+            funds_sent
+        );
+        assert!(asset_exchange_authorization_storage_read(&deps.storage)
+            .load()
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn migration_downgrade_rejected() {
+        let mut deps = mock_dependencies(&[]);
+        save_v2_2_0_state(&mut deps.storage);
+        set_contract_version(&mut deps.storage, CONTRACT_NAME, "99.0.0").unwrap();
+
+        let res = migrate(deps.as_mut(), mock_env(), MigrateMsg::default());
+        assert!(res.is_err());
+    }
 }
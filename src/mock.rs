@@ -1,12 +1,25 @@
+use crate::msg::{AssetExchange, CapitalDenomRequirement, ExchangeDate};
+use crate::state::{state_storage, State, DEFAULT_MAX_AUTHORIZATIONS_PER_BLOCK};
+use cosmwasm_std::coin;
 use cosmwasm_std::from_binary;
+use cosmwasm_std::from_slice;
+use cosmwasm_std::testing::{MockApi, MockQuerier, MockStorage, MOCK_CONTRACT_ADDR};
+use cosmwasm_std::Addr;
 use cosmwasm_std::BankMsg;
 use cosmwasm_std::Coin;
 use cosmwasm_std::CosmosMsg;
+use cosmwasm_std::DepsMut;
+use cosmwasm_std::OwnedDeps;
 use cosmwasm_std::Response;
 use cosmwasm_std::WasmMsg;
-use provwasm_mocks::{must_read_binary_file, ProvenanceMockQuerier};
-use provwasm_std::{Marker, MarkerMsgParams, ProvenanceMsg, ProvenanceMsgParams};
+use cosmwasm_std::{Order, Querier, QuerierResult, QuerierWrapper, QueryRequest, Record, Storage};
+use provwasm_mocks::{mock_dependencies, must_read_binary_file, ProvenanceMockQuerier};
+use provwasm_std::{
+    Marker, MarkerMsgParams, ProvenanceMsg, ProvenanceMsgParams, ProvenanceQuery,
+    ProvenanceQueryParams,
+};
 use serde::de::DeserializeOwned;
+use std::cell::Cell;
 
 pub fn msg_at_index(res: &Response<ProvenanceMsg>, i: usize) -> &CosmosMsg<ProvenanceMsg> {
     &res.messages.get(i).unwrap().msg
@@ -72,14 +85,640 @@ pub fn execute_args<T: DeserializeOwned>(
     }
 }
 
+pub fn attr_value<'a, T>(res: &'a Response<T>, key: &str) -> Option<&'a str> {
+    res.attributes
+        .iter()
+        .find(|attr| attr.key == key)
+        .map(|attr| attr.value.as_str())
+}
+
+pub fn assert_attr<T>(res: &Response<T>, key: &str, value: &str) {
+    assert_eq!(
+        Some(value),
+        attr_value(res, key),
+        "unexpected value for attribute {}",
+        key
+    );
+}
+
+pub fn assert_no_attr<T>(res: &Response<T>, key: &str) {
+    assert_eq!(None, attr_value(res, key), "unexpected attribute {}", key);
+}
+
+pub fn response_data<T: DeserializeOwned, U>(res: &Response<U>) -> T {
+    from_binary(res.data.as_ref().expect("no response data")).unwrap()
+}
+
+pub type MockDeps = OwnedDeps<MockStorage, MockApi, ProvenanceMockQuerier, ProvenanceQuery>;
+
+impl State {
+    pub fn test_default() -> State {
+        State {
+            admins: vec![Addr::unchecked("admin")],
+            admin_threshold: 1,
+            lp: Addr::unchecked("lp"),
+            raises: vec![Addr::unchecked("raise_1")],
+            commitment_denom: String::from("raise_1.commitment"),
+            investment_denoms: vec![String::from("raise_1.investment")],
+            like_capital_denoms: vec![String::from("stable_coin")],
+            capital_per_share: 100,
+            capital_per_share_overrides: vec![],
+            required_capital_attributes: vec![],
+            require_distinct_roles: true,
+            max_commitment_in_shares: None,
+            total_commitment_in_shares: 0,
+            operators: vec![],
+            admin_action_delay_seconds: 0,
+            max_authorizations_per_block: DEFAULT_MAX_AUTHORIZATIONS_PER_BLOCK,
+            paused: None,
+            stale_after_seconds: None,
+            default_grace_period_seconds: 0,
+            default_authorization_ttl_seconds: None,
+            default_distribution_address: None,
+            lp_note: None,
+            last_lp_activity: None,
+            last_admin_activity: None,
+            withdrawal_fee_bps: None,
+            fee_recipient: None,
+            withdrawal_approval_thresholds: vec![],
+            beneficiary: None,
+        }
+    }
+
+    pub fn test_capital_coin() -> State {
+        State {
+            admins: vec![Addr::unchecked("admin")],
+            admin_threshold: 1,
+            lp: Addr::unchecked("lp"),
+            raises: vec![Addr::unchecked("raise_1")],
+            commitment_denom: String::from("raise_1.commitment"),
+            investment_denoms: vec![String::from("raise_1.investment")],
+            like_capital_denoms: vec![String::from("capital_coin")],
+            capital_per_share: 100,
+            capital_per_share_overrides: vec![],
+            required_capital_attributes: vec![],
+            require_distinct_roles: true,
+            max_commitment_in_shares: None,
+            total_commitment_in_shares: 0,
+            operators: vec![],
+            admin_action_delay_seconds: 0,
+            max_authorizations_per_block: DEFAULT_MAX_AUTHORIZATIONS_PER_BLOCK,
+            paused: None,
+            stale_after_seconds: None,
+            default_grace_period_seconds: 0,
+            default_authorization_ttl_seconds: None,
+            default_distribution_address: None,
+            lp_note: None,
+            last_lp_activity: None,
+            last_admin_activity: None,
+            withdrawal_fee_bps: None,
+            fee_recipient: None,
+            withdrawal_approval_thresholds: vec![],
+            beneficiary: None,
+        }
+    }
+
+    pub fn test_restricted_capital_coin() -> State {
+        State {
+            admins: vec![Addr::unchecked("admin")],
+            admin_threshold: 1,
+            lp: Addr::unchecked("lp"),
+            raises: vec![Addr::unchecked("raise_1")],
+            commitment_denom: String::from("raise_1.commitment"),
+            investment_denoms: vec![String::from("raise_1.investment")],
+            like_capital_denoms: vec![String::from("restricted_capital_coin")],
+            capital_per_share: 100,
+            capital_per_share_overrides: vec![],
+            required_capital_attributes: vec![CapitalDenomRequirement {
+                capital_denom: String::from("restricted_capital_coin"),
+                required_attributes: vec![String::from("capital.test")],
+                enforce_attribute_on_bank_send: false,
+            }],
+            require_distinct_roles: true,
+            max_commitment_in_shares: None,
+            total_commitment_in_shares: 0,
+            operators: vec![],
+            admin_action_delay_seconds: 0,
+            max_authorizations_per_block: DEFAULT_MAX_AUTHORIZATIONS_PER_BLOCK,
+            paused: None,
+            stale_after_seconds: None,
+            default_grace_period_seconds: 0,
+            default_authorization_ttl_seconds: None,
+            default_distribution_address: None,
+            lp_note: None,
+            last_lp_activity: None,
+            last_admin_activity: None,
+            withdrawal_fee_bps: None,
+            fee_recipient: None,
+            withdrawal_approval_thresholds: vec![],
+            beneficiary: None,
+        }
+    }
+}
+
+/// Builds deps with the given state saved and no contract balances.
+pub fn deps_with_state(state: State) -> MockDeps {
+    let mut deps = mock_dependencies(&[]);
+    state_storage(&mut deps.storage).save(&state).unwrap();
+    deps
+}
+
+fn deps_with_updated_state(mut state: State, update_state: Option<fn(&mut State)>) -> MockDeps {
+    if let Some(update) = update_state {
+        update(&mut state);
+    }
+    deps_with_state(state)
+}
+
+pub fn default_deps(update_state: Option<fn(&mut State)>) -> MockDeps {
+    deps_with_updated_state(State::test_default(), update_state)
+}
+
+pub fn capital_coin_deps(update_state: Option<fn(&mut State)>) -> MockDeps {
+    deps_with_updated_state(State::test_capital_coin(), update_state)
+}
+
+pub fn restricted_capital_coin_deps(update_state: Option<fn(&mut State)>) -> MockDeps {
+    deps_with_updated_state(State::test_restricted_capital_coin(), update_state)
+}
+
+/// Builds deps with the default test state and the given contract balances.
+pub fn deps_with_balances(balances: &[(&str, u128)]) -> MockDeps {
+    let coins: Vec<Coin> = balances
+        .iter()
+        .map(|(denom, amount)| coin(*amount, *denom))
+        .collect();
+    let mut deps = mock_dependencies(&coins);
+    state_storage(&mut deps.storage)
+        .save(&State::test_default())
+        .unwrap();
+    deps
+}
+
+/// Replaces the contract balance of a single denom, leaving other denoms untouched.
+pub fn set_balance(deps: &mut MockDeps, denom: &str, amount: u128) {
+    let mut balances: Vec<Coin> = deps
+        .as_ref()
+        .querier
+        .query_all_balances(MOCK_CONTRACT_ADDR)
+        .unwrap()
+        .into_iter()
+        .filter(|balance| balance.denom != denom)
+        .collect();
+    if amount > 0 {
+        balances.push(coin(amount, denom));
+    }
+    deps.querier
+        .base
+        .update_balance(MOCK_CONTRACT_ADDR, balances);
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct StorageCounts {
+    pub reads: u64,
+    pub writes: u64,
+    pub removes: u64,
+    pub bytes_read: u64,
+    pub bytes_written: u64,
+}
+
+/// Wraps mock storage and tallies every access, so storage-heavy flows can be held to a budget.
+/// Each record yielded by a range counts as a read.
+#[derive(Default)]
+pub struct CountingStorage {
+    inner: MockStorage,
+    counts: Cell<StorageCounts>,
+}
+
+impl CountingStorage {
+    pub fn new(inner: MockStorage) -> CountingStorage {
+        CountingStorage {
+            inner,
+            counts: Cell::new(StorageCounts::default()),
+        }
+    }
+
+    pub fn counts(&self) -> StorageCounts {
+        self.counts.get()
+    }
+
+    pub fn reset(&self) {
+        self.counts.set(StorageCounts::default());
+    }
+
+    fn record_read(&self, bytes: usize) {
+        let mut counts = self.counts.get();
+        counts.reads += 1;
+        counts.bytes_read += bytes as u64;
+        self.counts.set(counts);
+    }
+}
+
+impl Storage for CountingStorage {
+    fn get(&self, key: &[u8]) -> Option<Vec<u8>> {
+        let value = self.inner.get(key);
+        self.record_read(value.as_ref().map(|value| value.len()).unwrap_or_default());
+        value
+    }
+
+    fn range<'a>(
+        &'a self,
+        start: Option<&[u8]>,
+        end: Option<&[u8]>,
+        order: Order,
+    ) -> Box<dyn Iterator<Item = Record> + 'a> {
+        Box::new(
+            self.inner
+                .range(start, end, order)
+                .inspect(move |(key, value)| self.record_read(key.len() + value.len())),
+        )
+    }
+
+    fn set(&mut self, key: &[u8], value: &[u8]) {
+        let mut counts = self.counts.get();
+        counts.writes += 1;
+        counts.bytes_written += value.len() as u64;
+        self.counts.set(counts);
+        self.inner.set(key, value);
+    }
+
+    fn remove(&mut self, key: &[u8]) {
+        let mut counts = self.counts.get();
+        counts.removes += 1;
+        self.counts.set(counts);
+        self.inner.remove(key);
+    }
+}
+
+/// Borrows mock deps with their storage swapped for the given counting storage.
+pub fn counting_deps_mut<'a>(
+    deps: &'a MockDeps,
+    storage: &'a mut CountingStorage,
+) -> DepsMut<'a, ProvenanceQuery> {
+    DepsMut {
+        storage,
+        api: &deps.api,
+        querier: QuerierWrapper::new(&deps.querier),
+    }
+}
+
+/// Wraps a querier and tallies the attribute queries made through it, so flows that check
+/// attributes can be held to a query count.
+pub struct CountingQuerier<'a> {
+    inner: &'a dyn Querier,
+    attribute_queries: Cell<u64>,
+}
+
+impl<'a> CountingQuerier<'a> {
+    pub fn new(inner: &'a dyn Querier) -> CountingQuerier<'a> {
+        CountingQuerier {
+            inner,
+            attribute_queries: Cell::new(0),
+        }
+    }
+
+    pub fn attribute_queries(&self) -> u64 {
+        self.attribute_queries.get()
+    }
+}
+
+impl Querier for CountingQuerier<'_> {
+    fn raw_query(&self, bin_request: &[u8]) -> QuerierResult {
+        if let Ok(QueryRequest::Custom(ProvenanceQuery {
+            params: ProvenanceQueryParams::Attribute(_),
+            ..
+        })) = from_slice::<QueryRequest<ProvenanceQuery>>(bin_request)
+        {
+            self.attribute_queries.set(self.attribute_queries.get() + 1);
+        }
+        self.inner.raw_query(bin_request)
+    }
+}
+
+/// Marker fixtures with distinct denoms, so they can all be loaded at once. Variants of a marker,
+/// like `restricted_capital_no_grant`, are left out.
+pub const ALL_MARKER_FIXTURES: &[&str] = &[
+    "capital",
+    "restricted_capital",
+    "restricted_investment",
+    "commitment",
+    "investment",
+    "multi_access_capital",
+];
+
 pub fn load_markers(querier: &mut ProvenanceMockQuerier) {
-    let get_marker = |name: &str| -> Marker {
-        let bin = must_read_binary_file(&format!("testdata/{}_marker.json", name));
-        from_binary(&bin).unwrap()
+    load_marker_fixtures(querier, &["capital", "restricted_capital"]);
+}
+
+/// A querier with every marker fixture in `ALL_MARKER_FIXTURES` loaded.
+pub fn querier_with_all_fixtures() -> ProvenanceMockQuerier {
+    let mut querier = ProvenanceMockQuerier::new(MockQuerier::new(&[]));
+    load_marker_fixtures(&mut querier, ALL_MARKER_FIXTURES);
+    querier
+}
+
+/// Gives an account the named attributes, replacing any attributes already loaded. The mock
+/// attribute querier only holds one account, so every entry must name the same address.
+pub fn load_attributes(querier: &mut ProvenanceMockQuerier, attributes: &[(&str, &str)]) {
+    let address = match attributes.first() {
+        Some((address, _)) => *address,
+        None => return querier.with_attributes("", &[]),
+    };
+    assert!(
+        attributes.iter().all(|(other, _)| *other == address),
+        "mock attributes can only be loaded for a single account"
+    );
+    let attributes: Vec<(&str, &str, &str)> =
+        attributes.iter().map(|(_, name)| (*name, "", "")).collect();
+    querier.with_attributes(address, &attributes);
+}
+
+/// Loads `testdata/<name>_marker.json` for each name, replacing any markers already loaded.
+pub fn load_marker_fixtures(querier: &mut ProvenanceMockQuerier, names: &[&str]) {
+    let get_marker = |name: &&str| -> Marker {
+        let path = format!("testdata/{}_marker.json", name);
+        from_binary(&must_read_binary_file(&path))
+            .unwrap_or_else(|err| panic!("invalid marker fixture {}: {}", path, err))
     };
 
-    querier.with_markers(vec![
-        get_marker("capital"),
-        get_marker("restricted_capital"),
-    ]);
+    querier.with_markers(names.iter().map(get_marker).collect());
+}
+
+#[derive(Clone, Debug)]
+pub struct ExchangeBuilder {
+    exchange: AssetExchange,
+}
+
+impl Default for ExchangeBuilder {
+    fn default() -> Self {
+        ExchangeBuilder::new()
+    }
+}
+
+impl ExchangeBuilder {
+    pub fn new() -> ExchangeBuilder {
+        ExchangeBuilder {
+            exchange: AssetExchange {
+                investment: None,
+                investment_denom: None,
+                commitment_in_shares: None,
+                capital: None,
+                capital_denom: None,
+                date: None,
+                to: None,
+            },
+        }
+    }
+
+    pub fn investment(mut self, investment: i64) -> ExchangeBuilder {
+        self.exchange.investment = Some(investment.into());
+        self
+    }
+
+    pub fn investment_denom(mut self, investment_denom: &str) -> ExchangeBuilder {
+        self.exchange.investment_denom = Some(String::from(investment_denom));
+        self
+    }
+
+    pub fn commitment(mut self, commitment_in_shares: i64) -> ExchangeBuilder {
+        self.exchange.commitment_in_shares = Some(commitment_in_shares.into());
+        self
+    }
+
+    pub fn capital(mut self, capital: i64) -> ExchangeBuilder {
+        self.exchange.capital = Some(capital.into());
+        self
+    }
+
+    pub fn denom(mut self, capital_denom: &str) -> ExchangeBuilder {
+        self.exchange.capital_denom = Some(String::from(capital_denom));
+        self
+    }
+
+    pub fn due(mut self, time: u64) -> ExchangeBuilder {
+        self.exchange.date = Some(ExchangeDate::Due(time));
+        self
+    }
+
+    pub fn available(mut self, time: u64) -> ExchangeBuilder {
+        self.exchange.date = Some(ExchangeDate::Available(time));
+        self
+    }
+
+    pub fn to(mut self, to: &str) -> ExchangeBuilder {
+        self.exchange.to = Some(Addr::unchecked(to));
+        self
+    }
+
+    pub fn build(self) -> AssetExchange {
+        self.exchange
+    }
+}
+
+/// The raise granting commitment to the subscription when it is accepted.
+pub fn accept_exchange() -> AssetExchange {
+    ExchangeBuilder::new().commitment(1_000).build()
+}
+
+/// The raise paying capital out to the subscription.
+pub fn distribution_exchange() -> AssetExchange {
+    ExchangeBuilder::new().capital(1_000).build()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::amount::Int128;
+    use cosmwasm_std::to_binary;
+    use provwasm_std::{MarkerAccess, MarkerType, ProvenanceQuerier};
+
+    #[test]
+    fn exchange_builder_defaults() {
+        assert_eq!(
+            AssetExchange {
+                investment: None,
+                investment_denom: None,
+                commitment_in_shares: None,
+                capital: None,
+                capital_denom: None,
+                date: None,
+                to: None,
+            },
+            ExchangeBuilder::new().build()
+        );
+    }
+
+    #[test]
+    fn exchange_builder() {
+        assert_eq!(
+            AssetExchange {
+                investment: Some(Int128::new(1_000)),
+                investment_denom: None,
+                commitment_in_shares: Some(Int128::new(-10)),
+                capital: Some(Int128::new(-1_000)),
+                capital_denom: Some(String::from("stable_coin")),
+                date: Some(ExchangeDate::Due(1_700_000_000)),
+                to: None,
+            },
+            ExchangeBuilder::new()
+                .investment(1_000)
+                .commitment(-10)
+                .capital(-1_000)
+                .denom("stable_coin")
+                .due(1_700_000_000)
+                .build()
+        );
+        assert_eq!(
+            Some(ExchangeDate::Available(1)),
+            ExchangeBuilder::new().due(0).available(1).build().date
+        );
+    }
+
+    #[test]
+    fn attribute_helpers() {
+        let res: Response<ProvenanceMsg> = Response::new()
+            .add_attribute("action", "issue_withdrawal")
+            .set_data(to_binary(&accept_exchange()).unwrap());
+
+        assert_attr(&res, "action", "issue_withdrawal");
+        assert_eq!(Some("issue_withdrawal"), attr_value(&res, "action"));
+        assert_no_attr(&res, "amount");
+        assert_eq!(accept_exchange(), response_data::<AssetExchange, _>(&res));
+    }
+
+    #[test]
+    fn balance_helpers() {
+        let mut deps = deps_with_balances(&[("stable_coin", 10_000), ("raise_1.commitment", 500)]);
+        let balance = |deps: &MockDeps, denom: &str| {
+            deps.as_ref()
+                .querier
+                .query_balance(MOCK_CONTRACT_ADDR, denom)
+                .unwrap()
+                .amount
+                .u128()
+        };
+        assert_eq!(10_000, balance(&deps, "stable_coin"));
+        assert_eq!(500, balance(&deps, "raise_1.commitment"));
+        assert_eq!(
+            State::test_default(),
+            crate::state::state_storage_read(&deps.storage)
+                .load()
+                .unwrap()
+        );
+
+        set_balance(&mut deps, "stable_coin", 2_500);
+        set_balance(&mut deps, "raise_1.investment", 100);
+        assert_eq!(2_500, balance(&deps, "stable_coin"));
+        assert_eq!(500, balance(&deps, "raise_1.commitment"));
+        assert_eq!(100, balance(&deps, "raise_1.investment"));
+    }
+
+    #[test]
+    fn marker_fixtures_load() {
+        let mut names = ALL_MARKER_FIXTURES.to_vec();
+        names.push("restricted_capital_no_grant");
+        for name in names {
+            let mut querier = ProvenanceMockQuerier::new(MockQuerier::new(&[]));
+            load_marker_fixtures(&mut querier, &[name]);
+        }
+    }
+
+    #[test]
+    fn all_fixtures_queryable() {
+        let querier = querier_with_all_fixtures();
+        let wrapper = QuerierWrapper::<ProvenanceQuery>::new(&querier);
+        let provenance = ProvenanceQuerier::new(&wrapper);
+        for denom in [
+            "capital_coin",
+            "restricted_capital_coin",
+            "raise_1.investment",
+            "raise_2.commitment",
+            "raise_2.investment",
+        ] {
+            assert_eq!(denom, provenance.get_marker_by_denom(denom).unwrap().denom);
+        }
+
+        let marker = provenance
+            .get_marker_by_denom("multi_access_capital_coin")
+            .unwrap();
+        assert_eq!(MarkerType::Restricted, marker.marker_type);
+        assert_eq!(4, marker.permissions.len());
+        assert!(marker
+            .permissions
+            .iter()
+            .any(|grant| grant.address == Addr::unchecked(MOCK_CONTRACT_ADDR)
+                && grant.permissions == vec![MarkerAccess::Transfer]));
+    }
+
+    #[test]
+    fn attribute_fixtures() {
+        let mut deps = default_deps(None);
+        load_attributes(
+            &mut deps.querier,
+            &[
+                ("lp_side_account", "capital.test"),
+                ("lp_side_account", "capital.kyc"),
+            ],
+        );
+        let wrapper = QuerierWrapper::<ProvenanceQuery>::new(&deps.querier);
+        let attributes = ProvenanceQuerier::new(&wrapper)
+            .get_attributes(Addr::unchecked("lp_side_account"), None as Option<String>)
+            .unwrap()
+            .attributes;
+        assert_eq!(
+            vec!["capital.test", "capital.kyc"],
+            attributes
+                .iter()
+                .map(|attribute| attribute.name.as_str())
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "a single account")]
+    fn attribute_fixtures_single_account() {
+        let mut deps = default_deps(None);
+        load_attributes(
+            &mut deps.querier,
+            &[
+                ("lp_side_account", "capital.test"),
+                ("raise_1", "capital.test"),
+            ],
+        );
+    }
+
+    #[test]
+    fn counting_storage() {
+        let mut storage = CountingStorage::default();
+        storage.set(b"key", b"value");
+        assert_eq!(Some(b"value".to_vec()), storage.get(b"key"));
+        assert_eq!(None, storage.get(b"missing"));
+        assert_eq!(1, storage.range(None, None, Order::Ascending).count());
+        storage.remove(b"key");
+
+        assert_eq!(
+            StorageCounts {
+                reads: 3,
+                writes: 1,
+                removes: 1,
+                bytes_read: 13,
+                bytes_written: 5,
+            },
+            storage.counts()
+        );
+
+        storage.reset();
+        assert_eq!(StorageCounts::default(), storage.counts());
+    }
+
+    #[test]
+    fn common_exchanges() {
+        assert_eq!(
+            Some(Int128::new(1_000)),
+            accept_exchange().commitment_in_shares
+        );
+        assert_eq!(None, accept_exchange().capital);
+        assert_eq!(Some(Int128::new(1_000)), distribution_exchange().capital);
+        assert_eq!(None, distribution_exchange().investment);
+    }
 }
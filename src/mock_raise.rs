@@ -0,0 +1,151 @@
+//! A stand-in for the raise contract, exposing the same `RaiseExecuteMsg` interface. Every
+//! message it accepts is recorded in its own storage along with the funds sent, and it can be
+//! configured to reject messages so failure propagation can be tested.
+
+use cosmwasm_std::{Coin, DepsMut, Env, MessageInfo, Response, StdError, StdResult, Storage};
+use cosmwasm_storage::{singleton, singleton_read};
+use serde::{Deserialize, Serialize};
+
+use crate::raise_msg::RaiseExecuteMsg;
+
+pub static CONFIG_KEY: &[u8] = b"mock_raise_config";
+pub static RECEIVED_KEY: &[u8] = b"mock_raise_received";
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Default)]
+pub struct InstantiateMsg {
+    pub fail: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct ReceivedMsg {
+    pub msg: RaiseExecuteMsg,
+    pub funds: Vec<Coin>,
+}
+
+pub fn instantiate(
+    deps: DepsMut,
+    _env: Env,
+    _info: MessageInfo,
+    msg: InstantiateMsg,
+) -> StdResult<Response> {
+    set_fail(deps.storage, msg.fail)?;
+    singleton::<Vec<ReceivedMsg>>(deps.storage, RECEIVED_KEY).save(&vec![])?;
+    Ok(Response::default())
+}
+
+pub fn execute(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    msg: RaiseExecuteMsg,
+) -> StdResult<Response> {
+    if singleton_read::<InstantiateMsg>(deps.storage, CONFIG_KEY)
+        .load()?
+        .fail
+    {
+        return Err(StdError::generic_err("raise rejected asset exchange"));
+    }
+
+    let action = match &msg {
+        RaiseExecuteMsg::CompleteAssetExchange { .. } => "complete_asset_exchange",
+        RaiseExecuteMsg::ReturnCapital { .. } => "return_capital",
+    };
+
+    singleton::<Vec<ReceivedMsg>>(deps.storage, RECEIVED_KEY).update(|mut received| {
+        received.push(ReceivedMsg {
+            msg,
+            funds: info.funds,
+        });
+        Ok(received) as StdResult<_>
+    })?;
+
+    Ok(Response::new().add_attribute("action", action))
+}
+
+pub fn set_fail(storage: &mut dyn Storage, fail: bool) -> StdResult<()> {
+    singleton(storage, CONFIG_KEY).save(&InstantiateMsg { fail })
+}
+
+pub fn received(storage: &dyn Storage) -> Vec<ReceivedMsg> {
+    singleton_read(storage, RECEIVED_KEY)
+        .may_load()
+        .unwrap()
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::accept_exchange;
+    use cosmwasm_std::coins;
+    use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info};
+
+    #[test]
+    fn records_received_messages() {
+        let mut deps = mock_dependencies();
+        instantiate(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("admin", &[]),
+            InstantiateMsg::default(),
+        )
+        .unwrap();
+
+        let msg = RaiseExecuteMsg::CompleteAssetExchange {
+            exchanges: vec![accept_exchange()],
+            to: None,
+            memo: None,
+        };
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("subscription", &coins(100, "stable_coin")),
+            msg.clone(),
+        )
+        .unwrap();
+
+        assert_eq!(
+            vec![ReceivedMsg {
+                msg,
+                funds: coins(100, "stable_coin"),
+            }],
+            received(&deps.storage)
+        );
+    }
+
+    #[test]
+    fn fails_on_demand() {
+        let mut deps = mock_dependencies();
+        instantiate(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("admin", &[]),
+            InstantiateMsg { fail: true },
+        )
+        .unwrap();
+
+        let msg = RaiseExecuteMsg::CompleteAssetExchange {
+            exchanges: vec![accept_exchange()],
+            to: None,
+            memo: None,
+        };
+        assert!(execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("subscription", &[]),
+            msg.clone()
+        )
+        .is_err());
+        assert!(received(&deps.storage).is_empty());
+
+        set_fail(&mut deps.storage, false).unwrap();
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("subscription", &[]),
+            msg,
+        )
+        .unwrap();
+        assert_eq!(1, received(&deps.storage).len());
+    }
+}
@@ -1,71 +1,472 @@
+#[cfg(not(target_arch = "wasm32"))]
+use std::collections::BTreeMap;
+use std::fmt;
+
+#[cfg(not(target_arch = "wasm32"))]
+use schemars::schema::RootSchema;
+#[cfg(not(target_arch = "wasm32"))]
+use schemars::schema_for;
 use schemars::JsonSchema;
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize};
+
+use cosmwasm_std::{Addr, Binary, Coin, Timestamp, Uint128};
 
-use cosmwasm_std::Addr;
+use crate::amount::{
+    option_u64_string, option_u64_string_pairs, u64_string, u64_string_pairs, uint128_or_number,
+    Int128,
+};
+use crate::raise_msg::SubscriptionHoldings;
+use crate::state::{
+    AbortedAuthorization, AssetExchangeAuthorization, AuthorizationStatus, Deposit,
+    MigrationRecord, PauseSource, PendingAdminAction, ScheduledAdminAction, State,
+    SubscriptionMetadata,
+};
 
+/// Incoming messages reject unknown fields, so a misspelled option fails instead of quietly
+/// falling back to its default.
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(deny_unknown_fields)]
 pub struct InstantiateMsg {
-    pub admin: Addr,
+    #[serde(alias = "admin")]
+    #[serde(deserialize_with = "one_or_many")]
+    pub admins: Vec<Addr>,
     pub lp: Addr,
     pub commitment_denom: String,
-    pub investment_denom: String,
-    pub capital_denom: String,
+    #[serde(alias = "investment_denom")]
+    #[serde(deserialize_with = "one_or_many")]
+    pub investment_denoms: Vec<String>,
+    #[serde(alias = "capital_denom")]
+    #[serde(deserialize_with = "one_or_many")]
+    pub like_capital_denoms: Vec<String>,
+    #[serde(deserialize_with = "u64_string::deserialize")]
     pub capital_per_share: u64,
+    #[serde(deserialize_with = "option_u64_string_pairs::deserialize")]
+    #[serde(default)]
+    pub capital_per_share_overrides: Option<Vec<(String, u64)>>,
+    #[serde(deserialize_with = "option_u64_string::deserialize")]
+    #[serde(default)]
     pub initial_commitment: Option<u64>,
-    pub required_capital_attribute: Option<String>,
+    #[serde(alias = "required_capital_attribute")]
+    #[serde(deserialize_with = "capital_denom_requirements")]
+    #[serde(default)]
+    pub required_capital_attributes: Vec<CapitalDenomRequirement>,
+    pub require_distinct_roles: Option<bool>,
+    #[serde(deserialize_with = "option_u64_string::deserialize")]
+    #[serde(default)]
+    pub max_commitment_in_shares: Option<u64>,
+    pub admin_threshold: Option<u8>,
+    pub admin_action_delay_seconds: Option<u64>,
+    pub max_authorizations_per_block: Option<u32>,
+    pub stale_after_seconds: Option<u64>,
+    pub default_grace_period_seconds: Option<u64>,
+    pub default_authorization_ttl_seconds: Option<u64>,
+    #[serde(default)]
+    pub metadata: Option<SubscriptionMetadata>,
+    /// Basis points of each withdrawal paid to `fee_recipient`, rounded down.
+    #[serde(default)]
+    pub withdrawal_fee_bps: Option<u16>,
+    #[serde(default)]
+    pub fee_recipient: Option<Addr>,
+    /// Withdrawals of a denom above its threshold need an unexpired admin approval.
+    #[serde(default)]
+    pub withdrawal_approval_thresholds: Option<Vec<Coin>>,
 }
 
-#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
-#[serde(rename_all = "snake_case")]
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case", deny_unknown_fields)]
 pub struct MigrateMsg {
-    pub capital_denom: Option<String>,
-    pub required_capital_attribute: Option<String>,
+    #[serde(alias = "capital_denom")]
+    #[serde(deserialize_with = "optional_one_or_many_denoms")]
+    #[serde(default)]
+    pub like_capital_denoms: Option<Vec<String>>,
+    #[serde(alias = "required_capital_attribute")]
+    #[serde(deserialize_with = "capital_denom_requirements")]
+    #[serde(default)]
+    pub required_capital_attributes: Vec<CapitalDenomRequirement>,
+    #[serde(default)]
+    pub clear_required_capital_attributes: bool,
+    pub new_admin: Option<Addr>,
+    #[serde(default)]
+    pub purge_legacy_storage: bool,
+    #[serde(deserialize_with = "option_u64_string_pairs::deserialize")]
+    #[serde(default)]
+    pub capital_per_share_overrides: Option<Vec<(String, u64)>>,
+    #[serde(default)]
+    pub stale_after_seconds: Option<u64>,
+    #[serde(default)]
+    pub default_grace_period_seconds: Option<u64>,
+    #[serde(default)]
+    pub default_authorization_ttl_seconds: Option<u64>,
+    #[serde(default)]
+    pub withdrawal_fee_bps: Option<u16>,
+    #[serde(default)]
+    pub fee_recipient: Option<Addr>,
+    #[serde(default)]
+    pub withdrawal_approval_thresholds: Option<Vec<Coin>>,
+}
+
+/// Attributes of which any account receiving the given capital denom must hold at least one.
+///
+/// An empty `capital_denom` refers to the sole like capital denom, which is how the
+/// legacy singular `required_capital_attribute` field is represented. The legacy singular
+/// `required_attribute` is read as a list of one. An attribute ending in `.*` is satisfied by any
+/// attribute under that prefix.
+///
+/// Restricted markers always enforce the attributes. A denom that moves by bank send only does
+/// when `enforce_attribute_on_bank_send` is set, which also lets the requirement name a denom
+/// that is not a restricted marker.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct CapitalDenomRequirement {
+    #[serde(default)]
+    pub capital_denom: String,
+    #[serde(alias = "required_attribute")]
+    #[serde(deserialize_with = "one_or_many")]
+    pub required_attributes: Vec<String>,
+    #[serde(default)]
+    pub enforce_attribute_on_bank_send: bool,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum OneOrMany<T> {
+    One(T),
+    Many(Vec<T>),
+}
+
+pub(crate) fn one_or_many<'de, D, T>(deserializer: D) -> Result<Vec<T>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    Ok(match OneOrMany::deserialize(deserializer)? {
+        OneOrMany::One(value) => vec![value],
+        OneOrMany::Many(values) => values,
+    })
+}
+
+fn optional_one_or_many_denoms<'de, D>(deserializer: D) -> Result<Option<Vec<String>>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    Ok(
+        match Option::<OneOrMany<String>>::deserialize(deserializer)? {
+            None => None,
+            Some(OneOrMany::One(denom)) => Some(vec![denom]),
+            Some(OneOrMany::Many(denoms)) => Some(denoms),
+        },
+    )
+}
+
+fn capital_denom_requirements<'de, D>(
+    deserializer: D,
+) -> Result<Vec<CapitalDenomRequirement>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Requirements {
+        Legacy(String),
+        PerDenom(Vec<CapitalDenomRequirement>),
+    }
+
+    Ok(match Option::<Requirements>::deserialize(deserializer)? {
+        None => vec![],
+        Some(Requirements::Legacy(required_attribute)) => vec![CapitalDenomRequirement {
+            capital_denom: String::new(),
+            required_attributes: vec![required_attribute],
+            enforce_attribute_on_bank_send: false,
+        }],
+        Some(Requirements::PerDenom(requirements)) => requirements,
+    })
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
-#[serde(rename_all = "snake_case")]
-pub enum HandleMsg {
+#[serde(rename_all = "snake_case", deny_unknown_fields)]
+pub enum ExecuteMsg {
+    SetOperators {
+        operators: Vec<Addr>,
+    },
+    SetPaused {
+        paused: bool,
+    },
     Recover {
         lp: Addr,
     },
+    UpdateRaise {
+        raise: Addr,
+    },
+    /// Adds a raise the subscription can exchange assets with, alongside the primary raise.
+    /// Goes through admin approval and the timelock.
+    RegisterRaise {
+        raise: Addr,
+    },
+    UpdateRequiredCapitalAttribute {
+        #[serde(alias = "required_capital_attribute")]
+        #[serde(deserialize_with = "capital_denom_requirements")]
+        #[serde(default)]
+        required_capital_attributes: Vec<CapitalDenomRequirement>,
+    },
+    ExecuteScheduledAdminAction {
+        id: u64,
+    },
     AuthorizeAssetExchange {
         exchanges: Vec<AssetExchange>,
         to: Option<Addr>,
         memo: Option<String>,
+        #[serde(default)]
+        idempotency_key: Option<String>,
+        /// sha256 of a memo to keep off chain, given instead of `memo`. The plaintext is revealed
+        /// as the memo when completing, and only then passed on to the raise.
+        #[serde(default)]
+        memo_hash: Option<Binary>,
+        /// When the authorization stops being completable. Defaults to the subscription's
+        /// default ttl, and may not exceed it.
+        #[serde(default)]
+        expires_at: Option<Timestamp>,
+        /// The raise the exchanges are with, when not the primary raise.
+        #[serde(default)]
+        raise: Option<Addr>,
+    },
+    /// Sets the ttl stamped onto authorizations that don't carry their own expiration, or
+    /// clears it. Goes through admin approval and the timelock.
+    UpdateDefaultAuthorizationTtl {
+        default_authorization_ttl_seconds: Option<u64>,
+    },
+    /// Points the subscription at a re-minted commitment marker. Refused while an authorization
+    /// with a commitment leg is pending. Goes through admin approval and the timelock.
+    UpdateCommitmentDenom {
+        denom: String,
+    },
+    /// Points the subscription at a re-minted investment marker, replacing `replaces`, which may
+    /// be left out when there is only one investment denom. Refused while an authorization with
+    /// an investment leg is pending. Goes through admin approval and the timelock.
+    UpdateInvestmentDenom {
+        denom: String,
+        #[serde(default)]
+        replaces: Option<String>,
+    },
+    SubmitSignedAuthorization {
+        payload: Binary,
+        signature: Binary,
+        pubkey: Binary,
     },
     CancelAssetExchangeAuthorization {
         exchanges: Vec<AssetExchange>,
         to: Option<Addr>,
         memo: Option<String>,
+        #[serde(default)]
+        raise: Option<Addr>,
+    },
+    /// Replaces a pending authorization in one step, so it can't be completed in between. The
+    /// replacement is for the same raise.
+    AmendAssetExchangeAuthorization {
+        old: AuthorizationRef,
+        new_exchanges: Vec<AssetExchange>,
+        new_to: Option<Addr>,
+        new_memo: Option<String>,
+    },
+    /// Backs out some of a pending authorization's exchanges, keeping the rest authorized. Each
+    /// exchange to remove must match one of the authorization's; removing them all cancels it.
+    CancelAuthorizedExchanges {
+        authorization: AuthorizationRef,
+        exchanges_to_remove: Vec<AssetExchange>,
+    },
+    /// Removes a pending authorization like `CancelAssetExchangeAuthorization`, recording who
+    /// removed it and why.
+    AbortAssetExchangeAuthorization {
+        exchanges: Vec<AssetExchange>,
+        to: Option<Addr>,
+        memo: Option<String>,
+        reason: String,
+        #[serde(default)]
+        raise: Option<Addr>,
     },
+    /// Removes every pending authorization that has expired or gone stale, recording each as
+    /// cancelled. The lp, an admin or an operator may send it.
+    PruneExpiredAuthorizations {},
+    /// `override_available_date` lets an admin settle before the exchanges' available date, e.g.
+    /// during a wind-down; it needs a memo saying why and is refused for anyone else.
     CompleteAssetExchange {
         exchanges: Vec<AssetExchange>,
         to: Option<Addr>,
         memo: Option<String>,
+        #[serde(default)]
+        override_available_date: bool,
+        /// The raise the exchanges are with, when not the primary raise.
+        #[serde(default)]
+        raise: Option<Addr>,
+    },
+    /// Completes part of an authorized exchange now. `fraction_exchanges` pairs up with
+    /// `exchanges`, and the authorization is kept with whatever remains.
+    CompleteAssetExchangePartial {
+        exchanges: Vec<AssetExchange>,
+        to: Option<Addr>,
+        memo: Option<String>,
+        fraction_exchanges: Vec<AssetExchange>,
+        #[serde(default)]
+        raise: Option<Addr>,
+    },
+    /// Caps how much of `capital_denom` may be withdrawn to `to` within each `period_seconds`,
+    /// replacing any allowance for that destination and denom. Only the lp may send it.
+    SetWithdrawalAllowance {
+        to: Addr,
+        #[serde(deserialize_with = "uint128_or_number")]
+        amount: Uint128,
+        #[serde(default)]
+        capital_denom: Option<String>,
+        period_seconds: u64,
+    },
+    /// Approves one withdrawal of exactly `amount` of `capital_denom` to `to`, for withdrawals
+    /// above that denom's approval threshold. The approval lapses after `ttl_seconds` or at
+    /// `expires_at`, whichever is given. Only an admin may send it.
+    ApproveWithdrawal {
+        to: Addr,
+        #[serde(deserialize_with = "uint128_or_number")]
+        amount: Uint128,
+        capital_denom: Option<String>,
+        #[serde(default)]
+        ttl_seconds: Option<u64>,
+        #[serde(default)]
+        expires_at: Option<Timestamp>,
     },
+    /// Revokes the outstanding approvals for withdrawals to `to`, or only those for `amount`
+    /// and/or `capital_denom`.
+    RevokeWithdrawalApproval {
+        to: Addr,
+        #[serde(default)]
+        amount: Option<Uint128>,
+        #[serde(default)]
+        capital_denom: Option<String>,
+    },
+    /// Sets where proceeds go when a completion names no recipient, or clears it to leave that to
+    /// the raise. Only the lp may send it.
+    UpdateDefaultDistributionAddress {
+        default_distribution_address: Option<Addr>,
+    },
+    /// Sets or clears the account that receives every distribution naming no recipient, ahead of
+    /// the default distribution address. Only the lp may send it.
+    SetBeneficiary {
+        beneficiary: Option<Addr>,
+    },
+    /// Replaces or clears the subscription's metadata. Only an admin may send it.
+    UpdateMetadata {
+        metadata: Option<SubscriptionMetadata>,
+    },
+    /// Sets or clears the lp's note on the subscription. Only the lp may send it.
+    SetLpNote {
+        note: Option<String>,
+    },
+    /// Sends capital to `to`, or to the address the Provenance name `to_name` resolves to; exactly
+    /// one of the two must be given.
     IssueWithdrawal {
+        #[serde(default)]
+        to: Option<Addr>,
+        #[serde(default)]
+        to_name: Option<String>,
+        #[serde(deserialize_with = "uint128_or_number")]
+        amount: Uint128,
+        capital_denom: Option<String>,
+    },
+    /// Hands excess drawn capital back to the raise, which credits the subscription for it.
+    ReturnCapital {
+        #[serde(deserialize_with = "uint128_or_number")]
+        amount: Uint128,
+        capital_denom: Option<String>,
+        memo: Option<String>,
+    },
+    /// Withdraws several like capital denoms to one destination at once, given as for
+    /// `IssueWithdrawal`. Unrestricted denoms share a single bank send; restricted denoms each get
+    /// their own marker transfer.
+    IssueMultiCoinWithdrawal {
+        #[serde(default)]
+        to: Option<Addr>,
+        #[serde(default)]
+        to_name: Option<String>,
+        amounts: Vec<Coin>,
+    },
+    /// Funds the subscription with the attached like capital coins. Every other message rejects
+    /// attached funds.
+    Deposit {
+        memo: Option<String>,
+    },
+    /// Sends the remainder of each like capital balance too small to make up a whole share to
+    /// `to`.
+    SweepDust {
+        to: Addr,
+    },
+    /// Sends the contract's whole balance of a stray denom, one the subscription never uses, to
+    /// `to`.
+    RefundUnexpectedFunds {
+        denom: String,
         to: Addr,
-        amount: u64,
     },
 }
 
+/// Identifies a pending authorization by its content, as cancel and complete do.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct AuthorizationRef {
+    pub exchanges: Vec<AssetExchange>,
+    pub to: Option<Addr>,
+    pub memo: Option<String>,
+    #[serde(default)]
+    pub raise: Option<Addr>,
+}
+
+/// The pre-1.0 name of `ExecuteMsg`. Only the variant names appear on the wire, so the rename
+/// doesn't affect clients.
+#[deprecated(note = "renamed to ExecuteMsg")]
+pub type HandleMsg = ExecuteMsg;
+
+/// An authorization signed offline by the lp and submitted on its behalf. The signature covers
+/// the sha256 hash of this struct's canonical JSON encoding.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct SignedAuthorization {
+    pub exchanges: Vec<AssetExchange>,
+    pub to: Option<Addr>,
+    pub memo: Option<String>,
+    pub nonce: u64,
+    pub contract: Addr,
+    /// Left out of the encoding when unset, so payloads signed before multiple raises still
+    /// verify.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default)]
+    pub raise: Option<Addr>,
+}
+
+/// Serialized with abbreviated field names to keep authorizations small. The full field names
+/// are accepted on input, and unknown fields are rejected so a misspelled leg can't silently
+/// deserialize to `None`.
 #[derive(Deserialize, Serialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+#[serde(deny_unknown_fields)]
 pub struct AssetExchange {
-    #[serde(rename = "inv")]
+    #[serde(rename = "inv", alias = "investment")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default)]
+    pub investment: Option<Int128>,
+    #[serde(rename = "inv_d", alias = "investment_denom")]
     #[serde(skip_serializing_if = "Option::is_none")]
     #[serde(default)]
-    pub investment: Option<i64>,
-    #[serde(rename = "com")]
+    pub investment_denom: Option<String>,
+    #[serde(rename = "com", alias = "commitment_in_shares")]
     #[serde(skip_serializing_if = "Option::is_none")]
     #[serde(default)]
-    pub commitment_in_shares: Option<i64>,
-    #[serde(rename = "cap")]
+    pub commitment_in_shares: Option<Int128>,
+    #[serde(rename = "cap", alias = "capital")]
     #[serde(skip_serializing_if = "Option::is_none")]
     #[serde(default)]
-    pub capital: Option<i64>,
+    pub capital: Option<Int128>,
+    #[serde(rename = "cap_d", alias = "capital_denom")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default)]
+    pub capital_denom: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     #[serde(default)]
     pub date: Option<ExchangeDate>,
+    /// Overrides the message-level `to` for this leg only.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default)]
+    pub to: Option<Addr>,
 }
 
 #[derive(Deserialize, Serialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
@@ -76,9 +477,1469 @@ pub enum ExchangeDate {
     Available(u64),
 }
 
+impl AssetExchange {
+    /// Whether the exchange moves anything; a date on its own is not an exchange.
+    pub fn has_amount(&self) -> bool {
+        self.investment.is_some() || self.commitment_in_shares.is_some() || self.capital.is_some()
+    }
+
+    /// Whether the exchange sends capital or investment out to the lp's side.
+    pub fn pays_out(&self) -> bool {
+        [self.investment, self.capital]
+            .iter()
+            .flatten()
+            .any(|amount| amount.is_positive())
+    }
+}
+
+/// Renders compactly for logs and error messages, e.g.
+/// `inv:+1000 com:+10 cap:-1000 stable_coin due:1700000000`. Dates are epoch seconds.
+impl fmt::Display for AssetExchange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut parts = Vec::new();
+        if let Some(investment) = self.investment {
+            parts.push(format!("inv:{:+}", investment));
+            parts.extend(self.investment_denom.clone());
+        }
+        if let Some(commitment_in_shares) = self.commitment_in_shares {
+            parts.push(format!("com:{:+}", commitment_in_shares));
+        }
+        if let Some(capital) = self.capital {
+            parts.push(format!("cap:{:+}", capital));
+            parts.extend(self.capital_denom.clone());
+        }
+        if let Some(date) = &self.date {
+            parts.push(date.to_string());
+        }
+        if let Some(to) = &self.to {
+            parts.push(format!("to:{}", to));
+        }
+        if parts.is_empty() {
+            f.write_str("empty")
+        } else {
+            f.write_str(&parts.join(" "))
+        }
+    }
+}
+
+impl fmt::Display for ExchangeDate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ExchangeDate::Due(time) => write!(f, "due:{}", time),
+            ExchangeDate::Available(time) => write!(f, "avl:{}", time),
+        }
+    }
+}
+
+pub fn display_exchanges(exchanges: &[AssetExchange]) -> String {
+    exchanges
+        .iter()
+        .map(AssetExchange::to_string)
+        .collect::<Vec<String>>()
+        .join("; ")
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
-#[serde(rename_all = "snake_case")]
+#[serde(rename_all = "snake_case", deny_unknown_fields)]
 pub enum QueryMsg {
     GetState {},
-    GetAssetExchangeAuthorizations {},
+    /// Pending authorizations, followed by the most recently cancelled or completed ones when
+    /// `include_history` is set.
+    GetAssetExchangeAuthorizations {
+        #[serde(default)]
+        include_history: bool,
+    },
+    ValidateMigration {
+        like_capital_denoms: Vec<String>,
+        required_capital_attributes: Vec<CapitalDenomRequirement>,
+    },
+    GetMigrationHistory {},
+    GetPendingAdminActions {},
+    GetScheduledActions {},
+    GetOverdueObligations {},
+    GetStats {},
+    GetDeposits {
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    GetAbortedAuthorizations {},
+    /// Pending, cancelled and completed authorizations in id order, optionally only those with
+    /// `status`. Cancelled and completed ones are kept up to a fixed number.
+    GetAuthorizationHistory {
+        status: Option<AuthorizationStatus>,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    GetMetadata {},
+    /// The like capital denoms along with what a recipient of each must hold.
+    GetCapitalDenoms {},
+    /// Reports what `CompleteAssetExchange` sent by `sender` would do, without doing it.
+    SimulateCompleteAssetExchange {
+        exchanges: Vec<AssetExchange>,
+        to: Option<Addr>,
+        memo: Option<String>,
+        sender: Addr,
+        #[serde(default)]
+        raise: Option<Addr>,
+    },
+    /// Reports which checks `IssueWithdrawal` sent by the lp would pass, without withdrawing.
+    SimulateWithdrawal {
+        to: Addr,
+        #[serde(deserialize_with = "uint128_or_number")]
+        amount: Uint128,
+        capital_denom: Option<String>,
+    },
+    /// Compares what the contract holds of the commitment denom against its own accounting, and
+    /// reports its investment denom balances alongside.
+    Reconcile {},
+    /// The contract name and version recorded at instantiation or migration, the message API
+    /// version, and the optional features this build supports.
+    GetVersion {},
+    /// Whether `CompleteAssetExchange` with these exchanges, `to` and `memo` would match a
+    /// pending authorization, and the position of the one it would match.
+    HasAuthorization {
+        exchanges: Vec<AssetExchange>,
+        to: Option<Addr>,
+        memo: Option<String>,
+        #[serde(default)]
+        raise: Option<Addr>,
+    },
+    /// Asks a raise, the primary raise unless another is named, what it holds on behalf of this
+    /// subscription.
+    GetRaiseHoldings {
+        #[serde(default)]
+        raise: Option<Addr>,
+    },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum SudoMsg {
+    GovRecover {
+        new_admin: Addr,
+        new_lp: Option<Addr>,
+    },
+    SetPaused {
+        paused: bool,
+    },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ValidateMigrationResponse {
+    pub source_version: String,
+    pub valid: bool,
+    pub errors: Vec<String>,
+}
+
+/// Ties each query to the shape of its response, mirroring `cosmwasm_schema::QueryResponses`
+/// from later cosmwasm releases. Keys are the snake case query names. Only schema generation
+/// needs it, so it is left out of the wasm build.
+#[cfg(not(target_arch = "wasm32"))]
+pub trait QueryResponses {
+    fn response_schemas() -> BTreeMap<String, RootSchema>;
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl QueryResponses for QueryMsg {
+    fn response_schemas() -> BTreeMap<String, RootSchema> {
+        BTreeMap::from([
+            (String::from("get_state"), schema_for!(StateResponse)),
+            (
+                String::from("get_asset_exchange_authorizations"),
+                schema_for!(AuthorizationsResponse),
+            ),
+            (
+                String::from("validate_migration"),
+                schema_for!(ValidateMigrationResponse),
+            ),
+            (
+                String::from("get_migration_history"),
+                schema_for!(MigrationHistoryResponse),
+            ),
+            (
+                String::from("get_pending_admin_actions"),
+                schema_for!(PendingAdminActionsResponse),
+            ),
+            (
+                String::from("get_scheduled_actions"),
+                schema_for!(ScheduledActionsResponse),
+            ),
+            (
+                String::from("get_overdue_obligations"),
+                schema_for!(OverdueObligationsResponse),
+            ),
+            (String::from("get_stats"), schema_for!(StatsResponse)),
+            (String::from("get_deposits"), schema_for!(DepositsResponse)),
+            (
+                String::from("get_aborted_authorizations"),
+                schema_for!(AbortedAuthorizationsResponse),
+            ),
+            (String::from("get_metadata"), schema_for!(MetadataResponse)),
+            (
+                String::from("get_authorization_history"),
+                schema_for!(AuthorizationHistoryResponse),
+            ),
+            (
+                String::from("get_capital_denoms"),
+                schema_for!(CapitalDenomsResponse),
+            ),
+            (String::from("reconcile"), schema_for!(ReconcileResponse)),
+            (String::from("get_version"), schema_for!(VersionResponse)),
+            (
+                String::from("has_authorization"),
+                schema_for!(HasAuthorizationResponse),
+            ),
+            (
+                String::from("get_raise_holdings"),
+                schema_for!(RaiseHoldingsResponse),
+            ),
+            (
+                String::from("simulate_complete_asset_exchange"),
+                schema_for!(SimulateCompleteAssetExchangeResponse),
+            ),
+            (
+                String::from("simulate_withdrawal"),
+                schema_for!(SimulateWithdrawalResponse),
+            ),
+        ])
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct StateResponse {
+    pub state: StateView,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct AuthorizationsResponse {
+    pub authorizations: Vec<AuthorizationView>,
+}
+
+/// The public shape of `State`. Fields added to `State` are not exposed until they are added
+/// here too.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct StateView {
+    pub admins: Vec<Addr>,
+    pub admin_threshold: u8,
+    pub lp: Addr,
+    /// The primary raise.
+    pub raise: Addr,
+    pub raises: Vec<Addr>,
+    pub commitment_denom: String,
+    pub investment_denoms: Vec<String>,
+    pub like_capital_denoms: Vec<String>,
+    #[serde(with = "u64_string")]
+    #[schemars(with = "String")]
+    pub capital_per_share: u64,
+    #[serde(with = "u64_string_pairs")]
+    #[schemars(with = "Vec<(String, String)>")]
+    pub capital_per_share_overrides: Vec<(String, u64)>,
+    pub required_capital_attributes: Vec<CapitalDenomRequirement>,
+    pub require_distinct_roles: bool,
+    #[serde(with = "option_u64_string")]
+    #[schemars(with = "Option<String>")]
+    pub max_commitment_in_shares: Option<u64>,
+    #[serde(with = "u64_string")]
+    #[schemars(with = "String")]
+    pub total_commitment_in_shares: u64,
+    pub operators: Vec<Addr>,
+    pub admin_action_delay_seconds: u64,
+    pub max_authorizations_per_block: u32,
+    pub paused: Option<PauseSource>,
+    pub stale_after_seconds: Option<u64>,
+    pub default_grace_period_seconds: u64,
+    pub default_authorization_ttl_seconds: Option<u64>,
+    pub default_distribution_address: Option<Addr>,
+    pub lp_note: Option<String>,
+    pub last_lp_activity: Option<Timestamp>,
+    pub last_admin_activity: Option<Timestamp>,
+    pub withdrawal_fee_bps: Option<u16>,
+    pub fee_recipient: Option<Addr>,
+    pub withdrawal_approval_thresholds: Vec<Coin>,
+    pub beneficiary: Option<Addr>,
+}
+
+impl From<State> for StateView {
+    fn from(state: State) -> Self {
+        // destructured so a new State field fails to compile until it's exposed or skipped here
+        let State {
+            admins,
+            admin_threshold,
+            lp,
+            raises,
+            commitment_denom,
+            investment_denoms,
+            like_capital_denoms,
+            capital_per_share,
+            capital_per_share_overrides,
+            required_capital_attributes,
+            require_distinct_roles,
+            max_commitment_in_shares,
+            total_commitment_in_shares,
+            operators,
+            admin_action_delay_seconds,
+            max_authorizations_per_block,
+            paused,
+            stale_after_seconds,
+            default_grace_period_seconds,
+            default_authorization_ttl_seconds,
+            default_distribution_address,
+            lp_note,
+            last_lp_activity,
+            last_admin_activity,
+            withdrawal_fee_bps,
+            fee_recipient,
+            withdrawal_approval_thresholds,
+            beneficiary,
+        } = state;
+        StateView {
+            admins,
+            admin_threshold,
+            lp,
+            raise: raises[0].clone(),
+            raises,
+            commitment_denom,
+            investment_denoms,
+            like_capital_denoms,
+            capital_per_share,
+            capital_per_share_overrides,
+            required_capital_attributes,
+            require_distinct_roles,
+            max_commitment_in_shares,
+            total_commitment_in_shares,
+            operators,
+            admin_action_delay_seconds,
+            max_authorizations_per_block,
+            paused,
+            stale_after_seconds,
+            default_grace_period_seconds,
+            default_authorization_ttl_seconds,
+            default_distribution_address,
+            lp_note,
+            last_lp_activity,
+            last_admin_activity,
+            withdrawal_fee_bps,
+            fee_recipient,
+            withdrawal_approval_thresholds,
+            beneficiary,
+        }
+    }
+}
+
+/// The public shape of an `AssetExchangeAuthorization`, without its bookkeeping fields.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct AuthorizationView {
+    pub id: u64,
+    /// The raise the exchanges are with, unless it is the primary raise.
+    pub raise: Option<Addr>,
+    pub exchanges: Vec<AssetExchange>,
+    pub to: Option<Addr>,
+    pub memo: Option<String>,
+    pub memo_hash: Option<Binary>,
+    pub expires_at: Option<Timestamp>,
+    /// Whether the authorization is past its stale window or expiration, and can no longer be
+    /// completed.
+    pub stale: bool,
+    pub status: AuthorizationStatus,
+}
+
+impl AuthorizationView {
+    pub fn new(authorization: AssetExchangeAuthorization, stale: bool) -> Self {
+        let AssetExchangeAuthorization {
+            id,
+            raise,
+            exchanges,
+            to,
+            memo,
+            created_at: _,
+            expires_at,
+            memo_hash,
+            status,
+            resolved_at: _,
+            resolved_by: _,
+        } = authorization;
+        AuthorizationView {
+            id,
+            raise,
+            exchanges,
+            to,
+            memo,
+            memo_hash,
+            expires_at,
+            stale,
+            status,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct MigrationHistoryResponse {
+    pub migrations: Vec<MigrationRecord>,
+}
+
+/// Pending admin actions that have not yet expired.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PendingAdminActionsResponse {
+    pub pending_admin_actions: Vec<PendingAdminAction>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ScheduledActionsResponse {
+    pub scheduled_actions: Vec<ScheduledAdminAction>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct OverdueObligationsResponse {
+    pub obligations: Vec<OverdueObligation>,
+}
+
+/// An authorized exchange whose due date has passed without it being completed.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct OverdueObligation {
+    pub authorization: AuthorizationView,
+    /// Seconds since the earliest passed due date of the authorization.
+    pub seconds_overdue: u64,
+    /// Net capital the lp owes per capital denom.
+    pub capital_owed: Vec<Coin>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct SimulateCompleteAssetExchangeResponse {
+    pub authorization_found: bool,
+    /// Coins that would be attached to the raise call.
+    pub funds: Vec<Coin>,
+    /// Restricted coins that would be moved to the raise by marker transfer.
+    pub marker_transfers: Vec<Coin>,
+    /// Why the completion would fail; empty when it would succeed.
+    pub errors: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct SimulateWithdrawalResponse {
+    /// The checks `IssueWithdrawal` runs, in the order it runs them. The attribute check is only
+    /// listed for restricted denoms.
+    pub checks: Vec<WithdrawalCheck>,
+    /// How the capital would move, when the denom resolves.
+    pub path: Option<WithdrawalPath>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct WithdrawalCheck {
+    pub name: String,
+    pub passed: bool,
+    /// The error the withdrawal would fail with, when the check does not pass.
+    pub error: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum WithdrawalPath {
+    BankSend,
+    MarkerTransfer,
+}
+
+/// The most recently aborted authorizations, oldest first.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct AbortedAuthorizationsResponse {
+    pub aborted_authorizations: Vec<AbortedAuthorization>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct AuthorizationHistoryResponse {
+    pub authorizations: Vec<AssetExchangeAuthorization>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct HasAuthorizationResponse {
+    pub found: bool,
+    pub index: Option<u32>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct RaiseHoldingsResponse {
+    pub raise: Addr,
+    pub holdings: SubscriptionHoldings,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct VersionResponse {
+    pub contract: String,
+    pub version: String,
+    pub api_version: u32,
+    pub features: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct MetadataResponse {
+    pub metadata: Option<SubscriptionMetadata>,
+}
+
+/// A like capital denom, the attributes of which a recipient must hold at least one, and whether
+/// it is the denom used when an exchange or withdrawal omits `capital_denom`.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct CapitalDenom {
+    pub denom: String,
+    pub required_attributes: Vec<String>,
+    pub is_default: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct CapitalDenomsResponse {
+    pub capital_denoms: Vec<CapitalDenom>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct DepositsResponse {
+    pub deposits: Vec<Deposit>,
+}
+
+/// Lifetime counts of successful operations, for telling whether a subscription was ever used.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct StatsResponse {
+    pub authorizations: u64,
+    pub cancellations: u64,
+    pub completions: u64,
+    pub withdrawals: u64,
+    /// Authorizations not yet completed or cancelled.
+    pub pending_authorizations: u64,
+    pub last_activity: Option<Timestamp>,
+    pub last_lp_activity: Option<Timestamp>,
+    pub last_admin_activity: Option<Timestamp>,
+}
+
+/// The contract's balance of a denom next to what its accounting expects it to hold. Only the
+/// commitment denom is tracked, through `total_commitment_in_shares`. Investment denoms carry no
+/// expectation: the raise sends investment in response to a completion without reporting back what
+/// it sent, and may send it to a completion's `to` rather than to the contract, so the contract
+/// never learns an amount it could hold itself to.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct DenomReconciliation {
+    pub denom: String,
+    pub held: Uint128,
+    pub expected: Option<Uint128>,
+    /// `held - expected`, when the denom is tracked.
+    pub delta: Option<Int128>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ReconcileResponse {
+    /// Whether the commitment denom's delta is zero. Investment denoms aren't tracked, so they
+    /// never make a response unbalanced, however much of them is held.
+    pub balanced: bool,
+    pub denoms: Vec<DenomReconciliation>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::raise_msg::RaiseExecuteMsg;
+    use crate::state::AdminAction;
+    use cosmwasm_schema::schema_for;
+
+    use crate::mock::ExchangeBuilder;
+    use cosmwasm_std::{from_slice, to_vec};
+
+    fn assert_round_trip<T>(json: &str, expected: T)
+    where
+        T: serde::Serialize + serde::de::DeserializeOwned + PartialEq + std::fmt::Debug,
+    {
+        assert_eq!(expected, from_slice::<T>(json.as_bytes()).unwrap());
+        assert_eq!(json, String::from_utf8(to_vec(&expected).unwrap()).unwrap());
+    }
+
+    #[test]
+    fn execute_msg_wire_format() {
+        let exchanges = || vec![ExchangeBuilder::new().commitment(10).build()];
+        assert_round_trip(
+            r#"{"set_operators":{"operators":["operator"]}}"#,
+            ExecuteMsg::SetOperators {
+                operators: vec![Addr::unchecked("operator")],
+            },
+        );
+        assert_round_trip(
+            r#"{"set_paused":{"paused":true}}"#,
+            ExecuteMsg::SetPaused { paused: true },
+        );
+        assert_round_trip(
+            r#"{"recover":{"lp":"lp_2"}}"#,
+            ExecuteMsg::Recover {
+                lp: Addr::unchecked("lp_2"),
+            },
+        );
+        assert_round_trip(
+            r#"{"update_raise":{"raise":"raise_2"}}"#,
+            ExecuteMsg::UpdateRaise {
+                raise: Addr::unchecked("raise_2"),
+            },
+        );
+        assert_round_trip(
+            r#"{"update_required_capital_attribute":{"required_capital_attributes":[{"capital_denom":"stable_coin","required_attributes":["capital.test"],"enforce_attribute_on_bank_send":false}]}}"#,
+            ExecuteMsg::UpdateRequiredCapitalAttribute {
+                required_capital_attributes: vec![CapitalDenomRequirement {
+                    capital_denom: String::from("stable_coin"),
+                    required_attributes: vec![String::from("capital.test")],
+                    enforce_attribute_on_bank_send: false,
+                }],
+            },
+        );
+        assert_round_trip(
+            r#"{"execute_scheduled_admin_action":{"id":1}}"#,
+            ExecuteMsg::ExecuteScheduledAdminAction { id: 1 },
+        );
+        assert_round_trip(
+            r#"{"authorize_asset_exchange":{"exchanges":[{"com":"10"}],"to":null,"memo":"memo","idempotency_key":"key-1","memo_hash":null,"expires_at":null,"raise":null}}"#,
+            ExecuteMsg::AuthorizeAssetExchange {
+                exchanges: exchanges(),
+                to: None,
+                memo: Some(String::from("memo")),
+                idempotency_key: Some(String::from("key-1")),
+                expires_at: None,
+                memo_hash: None,
+                raise: None,
+            },
+        );
+        // expires_at is optional on the wire
+        assert_eq!(
+            ExecuteMsg::AuthorizeAssetExchange {
+                exchanges: exchanges(),
+                to: None,
+                memo: None,
+                idempotency_key: None,
+                expires_at: None,
+                memo_hash: None,
+                raise: None,
+            },
+            from_slice::<ExecuteMsg>(
+                br#"{"authorize_asset_exchange":{"exchanges":[{"com":"10"}],"to":null,"memo":null}}"#
+            )
+            .unwrap()
+        );
+        assert_round_trip(
+            r#"{"update_metadata":{"metadata":{"name":"Fund III","description":null,"external_id":"ACME-0042"}}}"#,
+            ExecuteMsg::UpdateMetadata {
+                metadata: Some(SubscriptionMetadata {
+                    name: String::from("Fund III"),
+                    description: None,
+                    external_id: Some(String::from("ACME-0042")),
+                }),
+            },
+        );
+        assert_round_trip(
+            r#"{"set_lp_note":{"note":"acct-7731"}}"#,
+            ExecuteMsg::SetLpNote {
+                note: Some(String::from("acct-7731")),
+            },
+        );
+        assert_round_trip(
+            r#"{"update_default_distribution_address":{"default_distribution_address":"lp_side_account"}}"#,
+            ExecuteMsg::UpdateDefaultDistributionAddress {
+                default_distribution_address: Some(Addr::unchecked("lp_side_account")),
+            },
+        );
+        assert_round_trip(
+            r#"{"set_beneficiary":{"beneficiary":"custodian"}}"#,
+            ExecuteMsg::SetBeneficiary {
+                beneficiary: Some(Addr::unchecked("custodian")),
+            },
+        );
+        assert_round_trip(
+            r#"{"update_default_authorization_ttl":{"default_authorization_ttl_seconds":604800}}"#,
+            ExecuteMsg::UpdateDefaultAuthorizationTtl {
+                default_authorization_ttl_seconds: Some(604_800),
+            },
+        );
+        assert_round_trip(
+            r#"{"cancel_authorized_exchanges":{"authorization":{"exchanges":[{"com":"10"}],"to":null,"memo":null,"raise":null},"exchanges_to_remove":[{"com":"10"}]}}"#,
+            ExecuteMsg::CancelAuthorizedExchanges {
+                authorization: AuthorizationRef {
+                    exchanges: exchanges(),
+                    to: None,
+                    memo: None,
+                    raise: None,
+                },
+                exchanges_to_remove: exchanges(),
+            },
+        );
+        assert_round_trip(
+            r#"{"update_commitment_denom":{"denom":"raise_2.commitment"}}"#,
+            ExecuteMsg::UpdateCommitmentDenom {
+                denom: String::from("raise_2.commitment"),
+            },
+        );
+        assert_round_trip(
+            r#"{"update_investment_denom":{"denom":"raise_2.investment","replaces":null}}"#,
+            ExecuteMsg::UpdateInvestmentDenom {
+                denom: String::from("raise_2.investment"),
+                replaces: None,
+            },
+        );
+        assert_round_trip(
+            r#"{"submit_signed_authorization":{"payload":"e30=","signature":"AQI=","pubkey":"Aw=="}}"#,
+            ExecuteMsg::SubmitSignedAuthorization {
+                payload: Binary::from(b"{}".to_vec()),
+                signature: Binary::from(vec![1, 2]),
+                pubkey: Binary::from(vec![3]),
+            },
+        );
+        assert_round_trip(
+            r#"{"cancel_asset_exchange_authorization":{"exchanges":[{"com":"10"}],"to":"lp_side_account","memo":null,"raise":null}}"#,
+            ExecuteMsg::CancelAssetExchangeAuthorization {
+                exchanges: exchanges(),
+                to: Some(Addr::unchecked("lp_side_account")),
+                memo: None,
+                raise: None,
+            },
+        );
+        assert_round_trip(
+            r#"{"amend_asset_exchange_authorization":{"old":{"exchanges":[{"com":"10"}],"to":null,"memo":null,"raise":null},"new_exchanges":[{"com":"20"}],"new_to":null,"new_memo":"amended"}}"#,
+            ExecuteMsg::AmendAssetExchangeAuthorization {
+                old: AuthorizationRef {
+                    exchanges: exchanges(),
+                    to: None,
+                    memo: None,
+                    raise: None,
+                },
+                new_exchanges: vec![ExchangeBuilder::new().commitment(20).build()],
+                new_to: None,
+                new_memo: Some(String::from("amended")),
+            },
+        );
+        assert_round_trip(
+            r#"{"abort_asset_exchange_authorization":{"exchanges":[{"com":"10"}],"to":null,"memo":null,"reason":"kyc lapsed","raise":null}}"#,
+            ExecuteMsg::AbortAssetExchangeAuthorization {
+                exchanges: exchanges(),
+                to: None,
+                memo: None,
+                reason: String::from("kyc lapsed"),
+                raise: None,
+            },
+        );
+        assert_round_trip(
+            r#"{"prune_expired_authorizations":{}}"#,
+            ExecuteMsg::PruneExpiredAuthorizations {},
+        );
+        assert_round_trip(
+            r#"{"complete_asset_exchange":{"exchanges":[{"com":"10"}],"to":null,"memo":null,"override_available_date":false,"raise":null}}"#,
+            ExecuteMsg::CompleteAssetExchange {
+                exchanges: exchanges(),
+                to: None,
+                memo: None,
+                override_available_date: false,
+                raise: None,
+            },
+        );
+        assert_round_trip(
+            r#"{"complete_asset_exchange_partial":{"exchanges":[{"com":"10"}],"to":null,"memo":null,"fraction_exchanges":[{"com":"6"}],"raise":null}}"#,
+            ExecuteMsg::CompleteAssetExchangePartial {
+                exchanges: exchanges(),
+                to: None,
+                memo: None,
+                fraction_exchanges: vec![ExchangeBuilder::new().commitment(6).build()],
+                raise: None,
+            },
+        );
+        assert_round_trip(
+            r#"{"set_withdrawal_allowance":{"to":"ops","amount":"5000","capital_denom":"stable_coin","period_seconds":86400}}"#,
+            ExecuteMsg::SetWithdrawalAllowance {
+                to: Addr::unchecked("ops"),
+                amount: Uint128::new(5_000),
+                capital_denom: Some(String::from("stable_coin")),
+                period_seconds: 86_400,
+            },
+        );
+        assert_round_trip(
+            r#"{"approve_withdrawal":{"to":"lp","amount":"5000","capital_denom":"stable_coin","ttl_seconds":86400,"expires_at":null}}"#,
+            ExecuteMsg::ApproveWithdrawal {
+                to: Addr::unchecked("lp"),
+                amount: Uint128::new(5_000),
+                capital_denom: Some(String::from("stable_coin")),
+                ttl_seconds: Some(86_400),
+                expires_at: None,
+            },
+        );
+        assert_round_trip(
+            r#"{"revoke_withdrawal_approval":{"to":"lp","amount":null,"capital_denom":null}}"#,
+            ExecuteMsg::RevokeWithdrawalApproval {
+                to: Addr::unchecked("lp"),
+                amount: None,
+                capital_denom: None,
+            },
+        );
+        assert_round_trip(
+            r#"{"issue_withdrawal":{"to":"lp","to_name":null,"amount":"300","capital_denom":"stable_coin"}}"#,
+            ExecuteMsg::IssueWithdrawal {
+                to: Some(Addr::unchecked("lp")),
+                to_name: None,
+                amount: Uint128::new(300),
+                capital_denom: Some(String::from("stable_coin")),
+            },
+        );
+        assert_round_trip(
+            r#"{"return_capital":{"amount":"500","capital_denom":null,"memo":"excess"}}"#,
+            ExecuteMsg::ReturnCapital {
+                amount: Uint128::new(500),
+                capital_denom: None,
+                memo: Some(String::from("excess")),
+            },
+        );
+        assert_round_trip(
+            r#"{"issue_multi_coin_withdrawal":{"to":"lp","to_name":null,"amounts":[{"denom":"stable_coin","amount":"300"}]}}"#,
+            ExecuteMsg::IssueMultiCoinWithdrawal {
+                to: Some(Addr::unchecked("lp")),
+                to_name: None,
+                amounts: vec![Coin::new(300, "stable_coin")],
+            },
+        );
+        assert_round_trip(
+            r#"{"sweep_dust":{"to":"lp_side_account"}}"#,
+            ExecuteMsg::SweepDust {
+                to: Addr::unchecked("lp_side_account"),
+            },
+        );
+        assert_round_trip(
+            r#"{"refund_unexpected_funds":{"denom":"airdrop","to":"lp"}}"#,
+            ExecuteMsg::RefundUnexpectedFunds {
+                denom: String::from("airdrop"),
+                to: Addr::unchecked("lp"),
+            },
+        );
+        assert_round_trip(
+            r#"{"deposit":{"memo":"q3 call"}}"#,
+            ExecuteMsg::Deposit {
+                memo: Some(String::from("q3 call")),
+            },
+        );
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn handle_msg_alias() {
+        let msg: HandleMsg = from_slice(br#"{"set_paused":{"paused":false}}"#).unwrap();
+        assert_eq!(ExecuteMsg::SetPaused { paused: false }, msg);
+    }
+
+    #[test]
+    fn capital_denom_requirement_legacy_single_attribute() {
+        assert_eq!(
+            CapitalDenomRequirement {
+                capital_denom: String::from("stable_coin"),
+                required_attributes: vec![String::from("capital.test")],
+                enforce_attribute_on_bank_send: false,
+            },
+            from_slice::<CapitalDenomRequirement>(
+                br#"{"capital_denom":"stable_coin","required_attribute":"capital.test"}"#
+            )
+            .unwrap()
+        );
+        assert_eq!(
+            vec![String::from("capital.us"), String::from("capital.intl")],
+            from_slice::<CapitalDenomRequirement>(
+                br#"{"capital_denom":"stable_coin","required_attributes":["capital.us","capital.intl"]}"#
+            )
+            .unwrap()
+            .required_attributes
+        );
+    }
+
+    #[test]
+    fn asset_exchange_wire_format() {
+        assert_round_trip(
+            r#"{"inv":"1000","com":"-10","cap":"-1000","cap_d":"stable_coin","date":{"due":1700000000}}"#,
+            ExchangeBuilder::new()
+                .investment(1_000)
+                .commitment(-10)
+                .capital(-1_000)
+                .denom("stable_coin")
+                .due(1_700_000_000)
+                .build(),
+        );
+        assert_round_trip(
+            r#"{"cap":"500","date":{"avl":1700000000}}"#,
+            ExchangeBuilder::new()
+                .capital(500)
+                .available(1_700_000_000)
+                .build(),
+        );
+        assert_round_trip(
+            r#"{"com":"10"}"#,
+            ExchangeBuilder::new().commitment(10).build(),
+        );
+        assert_round_trip(
+            r#"{"inv":"-500","inv_d":"raise_1.investment_b"}"#,
+            ExchangeBuilder::new()
+                .investment(-500)
+                .investment_denom("raise_1.investment_b")
+                .build(),
+        );
+        assert_round_trip(
+            r#"{"cap":"500","to":"lp_side_account"}"#,
+            ExchangeBuilder::new()
+                .capital(500)
+                .to("lp_side_account")
+                .build(),
+        );
+        assert_round_trip(r#"{}"#, ExchangeBuilder::new().build());
+    }
+
+    #[test]
+    fn asset_exchange_long_field_names() {
+        let expected = ExchangeBuilder::new()
+            .investment(1_000)
+            .investment_denom("raise_1.investment_b")
+            .commitment(-10)
+            .capital(-1_000)
+            .denom("stable_coin")
+            .build();
+        let short = br#"{"inv":"1000","inv_d":"raise_1.investment_b","com":"-10","cap":"-1000","cap_d":"stable_coin"}"#;
+        let long = br#"{"investment":"1000","investment_denom":"raise_1.investment_b","commitment_in_shares":"-10","capital":"-1000","capital_denom":"stable_coin"}"#;
+        let mixed = br#"{"investment":"1000","inv_d":"raise_1.investment_b","com":"-10","capital":"-1000","cap_d":"stable_coin"}"#;
+
+        assert_eq!(expected, from_slice::<AssetExchange>(short).unwrap());
+        assert_eq!(expected, from_slice::<AssetExchange>(long).unwrap());
+        assert_eq!(expected, from_slice::<AssetExchange>(mixed).unwrap());
+
+        // output always uses the short names
+        assert_eq!(short.to_vec(), to_vec(&expected).unwrap());
+    }
+
+    #[test]
+    fn asset_exchange_rejects_unknown_fields() {
+        assert!(from_slice::<AssetExchange>(br#"{"investmnet":"1000"}"#).is_err());
+        assert!(from_slice::<AssetExchange>(br#"{"cap":"-1000","denom":"stable_coin"}"#).is_err());
+    }
+
+    #[test]
+    fn messages_reject_unknown_fields() {
+        let unknown = |err: cosmwasm_std::StdError, field: &str| {
+            assert!(
+                err.to_string()
+                    .contains(&format!("unknown field `{}`", field)),
+                "{}",
+                err
+            )
+        };
+
+        unknown(
+            from_slice::<ExecuteMsg>(
+                br#"{"issue_withdrawal":{"to":"lp","amount":"300","capital_denoms":"stable_coin"}}"#,
+            )
+            .unwrap_err(),
+            "capital_denoms",
+        );
+        unknown(
+            from_slice::<ExecuteMsg>(
+                br#"{"authorize_asset_exchange":{"exchanges":[{"com":"10"}],"to":null,"memo":null,"expires":1}}"#,
+            )
+            .unwrap_err(),
+            "expires",
+        );
+        unknown(
+            from_slice::<ExecuteMsg>(br#"{"set_paused":{"paused":true,"reason":"audit"}}"#)
+                .unwrap_err(),
+            "reason",
+        );
+        unknown(
+            from_slice::<QueryMsg>(br#"{"get_authorization_history":{"limt":5}}"#).unwrap_err(),
+            "limt",
+        );
+        unknown(
+            from_slice::<MigrateMsg>(br#"{"stale_after_second":60}"#).unwrap_err(),
+            "stale_after_second",
+        );
+        unknown(
+            from_slice::<InstantiateMsg>(
+                br#"{"admins":["admin"],"lp":"lp","commitment_denom":"raise_1.commitment","investment_denoms":["raise_1.investment"],"like_capital_denoms":["stable_coin"],"capital_per_share":100,"withdrawal_fee_bp":25}"#,
+            )
+            .unwrap_err(),
+            "withdrawal_fee_bp",
+        );
+    }
+
+    #[test]
+    fn messages_accept_long_and_legacy_names() {
+        let msg: InstantiateMsg = from_slice(
+            br#"{"admin":"admin","lp":"lp","commitment_denom":"raise_1.commitment","investment_denom":"raise_1.investment","capital_denom":"stable_coin","capital_per_share":100,"required_capital_attribute":"capital.test"}"#,
+        )
+        .unwrap();
+        assert_eq!(vec![Addr::unchecked("admin")], msg.admins);
+        assert_eq!(vec![String::from("stable_coin")], msg.like_capital_denoms);
+        assert_eq!(1, msg.required_capital_attributes.len());
+
+        let msg: MigrateMsg = from_slice(br#"{"capital_denom":"stable_coin"}"#).unwrap();
+        assert_eq!(
+            Some(vec![String::from("stable_coin")]),
+            msg.like_capital_denoms
+        );
+
+        let msg: ExecuteMsg = from_slice(
+            br#"{"cancel_asset_exchange_authorization":{"exchanges":[{"commitment_in_shares":"10","capital":"100","capital_denom":"stable_coin"}],"to":null,"memo":null}}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            ExecuteMsg::CancelAssetExchangeAuthorization {
+                exchanges: vec![ExchangeBuilder::new()
+                    .commitment(10)
+                    .capital(100)
+                    .denom("stable_coin")
+                    .build()],
+                to: None,
+                memo: None,
+                raise: None,
+            },
+            msg
+        );
+    }
+
+    #[test]
+    fn stored_state_tolerates_unknown_fields() {
+        // only incoming messages are strict; state written by another version still loads
+        let stored = String::from_utf8(to_vec(&State::test_default()).unwrap())
+            .unwrap()
+            .replacen('{', r#"{"retired_field":true,"#, 1);
+        assert_eq!(
+            State::test_default(),
+            from_slice::<State>(stored.as_bytes()).unwrap()
+        );
+    }
+
+    #[test]
+    fn stored_state_with_single_raise() {
+        // state stored before multiple raises names only the primary raise
+        let stored = String::from_utf8(to_vec(&State::test_default()).unwrap())
+            .unwrap()
+            .replacen(r#""raises":["raise_1"]"#, r#""raise":"raise_1""#, 1);
+        assert!(stored.contains(r#""raise":"raise_1""#));
+        assert_eq!(
+            State::test_default(),
+            from_slice::<State>(stored.as_bytes()).unwrap()
+        );
+    }
+
+    #[test]
+    fn asset_exchange_legacy_numbers() {
+        assert_eq!(
+            ExchangeBuilder::new()
+                .investment(1_000)
+                .commitment(-10)
+                .capital(-1_000)
+                .build(),
+            from_slice::<AssetExchange>(br#"{"inv":1000,"com":-10,"cap":-1000}"#).unwrap()
+        );
+    }
+
+    #[test]
+    fn asset_exchange_beyond_64_bits() {
+        let capital = Int128::new(-18_000_000_000_000_000_000_000);
+        assert_round_trip(
+            r#"{"cap":"-18000000000000000000000"}"#,
+            AssetExchange {
+                capital: Some(capital),
+                ..ExchangeBuilder::new().build()
+            },
+        );
+    }
+
+    #[test]
+    fn issue_withdrawal_wire_format() {
+        let expected = ExecuteMsg::IssueWithdrawal {
+            to: Some(cosmwasm_std::Addr::unchecked("lp")),
+            to_name: None,
+            amount: Uint128::new(300),
+            capital_denom: None,
+        };
+        assert_round_trip(
+            r#"{"issue_withdrawal":{"to":"lp","to_name":null,"amount":"300","capital_denom":null}}"#,
+            expected.clone(),
+        );
+        assert_eq!(
+            expected,
+            from_slice::<ExecuteMsg>(
+                br#"{"issue_withdrawal":{"to":"lp","amount":300,"capital_denom":null}}"#
+            )
+            .unwrap()
+        );
+    }
+
+    #[test]
+    fn asset_exchange_display() {
+        assert_eq!(
+            "inv:+1000 com:+1000 cap:-100000 stable_coin due:1719792000",
+            ExchangeBuilder::new()
+                .investment(1_000)
+                .commitment(1_000)
+                .capital(-100_000)
+                .denom("stable_coin")
+                .due(1_719_792_000)
+                .build()
+                .to_string()
+        );
+        assert_eq!(
+            "inv:-500 raise_1.investment_b avl:1700000000",
+            ExchangeBuilder::new()
+                .investment(-500)
+                .investment_denom("raise_1.investment_b")
+                .available(1_700_000_000)
+                .build()
+                .to_string()
+        );
+        assert_eq!(
+            "cap:+0",
+            ExchangeBuilder::new().capital(0).build().to_string()
+        );
+        assert_eq!(
+            "cap:+500 to:lp_side_account",
+            ExchangeBuilder::new()
+                .capital(500)
+                .to("lp_side_account")
+                .build()
+                .to_string()
+        );
+        assert_eq!("empty", ExchangeBuilder::new().build().to_string());
+        assert_eq!(
+            "com:-10; empty",
+            display_exchanges(&[
+                ExchangeBuilder::new().commitment(-10).build(),
+                ExchangeBuilder::new().build()
+            ])
+        );
+    }
+
+    #[test]
+    fn exchange_date_wire_format() {
+        assert_round_trip(r#"{"due":1}"#, ExchangeDate::Due(1));
+        assert_round_trip(r#"{"avl":2}"#, ExchangeDate::Available(2));
+    }
+
+    #[test]
+    fn complete_asset_exchange_wire_format() {
+        assert_round_trip(
+            r#"{"complete_asset_exchange":{"exchanges":[{"inv":"-1000","cap":"1000"}],"to":"lp_side_account","memo":"memo"}}"#,
+            RaiseExecuteMsg::CompleteAssetExchange {
+                exchanges: vec![ExchangeBuilder::new()
+                    .investment(-1_000)
+                    .capital(1_000)
+                    .build()],
+                to: Some(cosmwasm_std::Addr::unchecked("lp_side_account")),
+                memo: Some(String::from("memo")),
+            },
+        );
+        assert_round_trip(
+            r#"{"complete_asset_exchange":{"exchanges":[],"to":null,"memo":null}}"#,
+            RaiseExecuteMsg::CompleteAssetExchange {
+                exchanges: vec![],
+                to: None,
+                memo: None,
+            },
+        );
+    }
+
+    #[test]
+    fn authorizations_query_defaults_to_pending_only() {
+        assert_eq!(
+            QueryMsg::GetAssetExchangeAuthorizations {
+                include_history: false
+            },
+            from_slice(br#"{"get_asset_exchange_authorizations":{}}"#).unwrap()
+        );
+        assert_eq!(
+            QueryMsg::GetAssetExchangeAuthorizations {
+                include_history: true
+            },
+            from_slice(br#"{"get_asset_exchange_authorizations":{"include_history":true}}"#)
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn state_view_wire_format() {
+        assert_round_trip(
+            r#"{"admins":["admin"],"admin_threshold":1,"lp":"lp","raise":"raise_1","raises":["raise_1"],"commitment_denom":"raise_1.commitment","investment_denoms":["raise_1.investment"],"like_capital_denoms":["stable_coin"],"capital_per_share":"100","capital_per_share_overrides":[],"required_capital_attributes":[],"require_distinct_roles":true,"max_commitment_in_shares":null,"total_commitment_in_shares":"0","operators":[],"admin_action_delay_seconds":0,"max_authorizations_per_block":10,"paused":null,"stale_after_seconds":null,"default_grace_period_seconds":0,"default_authorization_ttl_seconds":null,"default_distribution_address":null,"lp_note":null,"last_lp_activity":null,"last_admin_activity":null,"withdrawal_fee_bps":null,"fee_recipient":null,"withdrawal_approval_thresholds":[],"beneficiary":null}"#,
+            StateView::from(State::test_default()),
+        );
+
+        let state = StateView::from(State {
+            capital_per_share_overrides: vec![(String::from("wei_coin"), 100_000_000_000_000)],
+            max_commitment_in_shares: Some(9_007_199_254_740_993),
+            total_commitment_in_shares: 9_007_199_254_740_993,
+            ..State::test_default()
+        });
+        let json = String::from_utf8(to_vec(&state).unwrap()).unwrap();
+        assert!(json.contains(r#""capital_per_share_overrides":[["wei_coin","100000000000000"]],"#));
+        assert!(json.contains(r#""max_commitment_in_shares":"9007199254740993","#));
+        assert!(json.contains(r#""total_commitment_in_shares":"9007199254740993","#));
+        assert_eq!(state, from_slice(json.as_bytes()).unwrap());
+    }
+
+    #[test]
+    fn authorization_view_wire_format() {
+        let authorization = AssetExchangeAuthorization {
+            id: 3,
+            raise: None,
+            exchanges: vec![ExchangeBuilder::new()
+                .commitment(1_000)
+                .due(1_700_000_000)
+                .build()],
+            to: None,
+            memo: Some(String::from("memo")),
+            created_at: Some(Timestamp::from_seconds(1_600_000_000)),
+            expires_at: None,
+            memo_hash: None,
+            status: AuthorizationStatus::Pending,
+            resolved_at: None,
+            resolved_by: None,
+        };
+
+        // created_at is bookkeeping and stays out of the public shape
+        assert_round_trip(
+            r#"{"id":3,"raise":null,"exchanges":[{"com":"1000","date":{"due":1700000000}}],"to":null,"memo":"memo","memo_hash":null,"expires_at":null,"stale":true,"status":"pending"}"#,
+            AuthorizationView::new(authorization, true),
+        );
+    }
+
+    #[test]
+    fn asset_exchange_authorization_wire_format() {
+        assert_round_trip(
+            r#"{"id":3,"exchanges":[{"com":"1000","date":{"due":1700000000}}],"to":"lp_side_account","memo":"memo","created_at":"1571797419879305533"}"#,
+            AssetExchangeAuthorization {
+                id: 3,
+                raise: None,
+                exchanges: vec![ExchangeBuilder::new()
+                    .commitment(1_000)
+                    .due(1_700_000_000)
+                    .build()],
+                to: Some(cosmwasm_std::Addr::unchecked("lp_side_account")),
+                memo: Some(String::from("memo")),
+                created_at: Some(cosmwasm_std::Timestamp::from_nanos(
+                    1_571_797_419_879_305_533,
+                )),
+                expires_at: None,
+                memo_hash: None,
+                status: AuthorizationStatus::Pending,
+                resolved_at: None,
+                resolved_by: None,
+            },
+        );
+        assert_round_trip(
+            r#"{"id":1,"exchanges":[{"inv":"1"}],"to":null,"memo":null,"created_at":null}"#,
+            AssetExchangeAuthorization {
+                id: 1,
+                raise: None,
+                exchanges: vec![ExchangeBuilder::new().investment(1).build()],
+                to: None,
+                memo: None,
+                created_at: None,
+                expires_at: None,
+                memo_hash: None,
+                status: AuthorizationStatus::Pending,
+                resolved_at: None,
+                resolved_by: None,
+            },
+        );
+    }
+
+    #[test]
+    fn asset_exchange_authorization_legacy_numbers() {
+        let stored = br#"{"id":1,"exchanges":[{"inv":1000,"com":-10,"cap":-1000}],"to":null,"memo":null,"created_at":null}"#;
+        assert_eq!(
+            vec![ExchangeBuilder::new()
+                .investment(1_000)
+                .commitment(-10)
+                .capital(-1_000)
+                .build()],
+            from_slice::<AssetExchangeAuthorization>(stored)
+                .unwrap()
+                .exchanges
+        );
+    }
+
+    #[test]
+    fn schemas_generate() {
+        schema_for!(InstantiateMsg);
+        schema_for!(ExecuteMsg);
+        schema_for!(QueryMsg);
+        schema_for!(MigrateMsg);
+        schema_for!(SudoMsg);
+        schema_for!(State);
+        schema_for!(AssetExchange);
+        schema_for!(ExchangeDate);
+        schema_for!(AssetExchangeAuthorization);
+        schema_for!(MigrationRecord);
+        schema_for!(ValidateMigrationResponse);
+        schema_for!(RaiseExecuteMsg);
+        schema_for!(SignedAuthorization);
+        QueryMsg::response_schemas();
+    }
+
+    #[test]
+    fn every_query_has_a_response_schema() {
+        let queries: Vec<String> = schema_for!(QueryMsg)
+            .schema
+            .subschemas
+            .unwrap()
+            .any_of
+            .unwrap()
+            .into_iter()
+            .map(|variant| {
+                variant
+                    .into_object()
+                    .object
+                    .unwrap()
+                    .required
+                    .into_iter()
+                    .next()
+                    .unwrap()
+            })
+            .collect();
+        assert_eq!(
+            queries.len(),
+            QueryMsg::response_schemas().len(),
+            "{:?}",
+            queries
+        );
+        for query in queries {
+            assert!(
+                QueryMsg::response_schemas().contains_key(&query),
+                "{} has no response schema",
+                query
+            );
+        }
+    }
+
+    fn assert_json_round_trip<T>(value: T)
+    where
+        T: serde::Serialize + serde::de::DeserializeOwned + PartialEq + std::fmt::Debug,
+    {
+        assert_eq!(value, from_slice::<T>(&to_vec(&value).unwrap()).unwrap());
+    }
+
+    #[test]
+    fn query_responses_round_trip() {
+        let authorization = AuthorizationView {
+            id: 1,
+            exchanges: vec![ExchangeBuilder::new()
+                .capital(-1_000)
+                .due(1_700_000_000)
+                .build()],
+            to: None,
+            memo: Some(String::from("memo")),
+            memo_hash: None,
+            expires_at: Some(Timestamp::from_seconds(1_700_086_400)),
+            stale: true,
+            status: AuthorizationStatus::Pending,
+            raise: None,
+        };
+        let action = AdminAction::Recover {
+            lp: Addr::unchecked("lp_2"),
+        };
+
+        assert_json_round_trip(StateResponse {
+            state: State::test_default().into(),
+        });
+        assert_json_round_trip(HasAuthorizationResponse {
+            found: true,
+            index: Some(0),
+        });
+        assert_json_round_trip(RaiseHoldingsResponse {
+            raise: Addr::unchecked("raise_1"),
+            holdings: SubscriptionHoldings {
+                capital_credited: vec![Coin::new(1_000, "stable_coin")],
+                commitment_outstanding: Uint128::new(10),
+            },
+        });
+        assert_json_round_trip(VersionResponse {
+            contract: String::from("marketpalace-subscription-contract"),
+            version: String::from("2.3.0"),
+            api_version: 1,
+            features: vec![String::from("reconcile")],
+        });
+        assert_json_round_trip(AuthorizationsResponse {
+            authorizations: vec![authorization.clone()],
+        });
+        assert_json_round_trip(ValidateMigrationResponse {
+            source_version: String::from("2.2.0"),
+            valid: false,
+            errors: vec![String::from("unsupported capital denom: other_coin")],
+        });
+        assert_json_round_trip(MigrationHistoryResponse {
+            migrations: vec![MigrationRecord {
+                from_version: String::from("2.2.0"),
+                to_version: String::from("2.3.0"),
+                height: 12_345,
+                time: Timestamp::from_seconds(1_600_000_000),
+                msg_hash: Binary::from(vec![1; 32]),
+            }],
+        });
+        assert_json_round_trip(PendingAdminActionsResponse {
+            pending_admin_actions: vec![PendingAdminAction {
+                action: action.clone(),
+                approvals: vec![Addr::unchecked("admin_1")],
+                proposed_at: Timestamp::from_seconds(1_600_000_000),
+            }],
+        });
+        assert_json_round_trip(ScheduledActionsResponse {
+            scheduled_actions: vec![ScheduledAdminAction {
+                id: 2,
+                action,
+                execute_after: Timestamp::from_seconds(1_600_086_400),
+            }],
+        });
+        assert_json_round_trip(OverdueObligationsResponse {
+            obligations: vec![OverdueObligation {
+                authorization,
+                seconds_overdue: 100,
+                capital_owed: vec![Coin::new(1_000, "stable_coin")],
+            }],
+        });
+        assert_json_round_trip(StatsResponse {
+            authorizations: 3,
+            cancellations: 1,
+            completions: 1,
+            withdrawals: 0,
+            pending_authorizations: 1,
+            last_activity: Some(Timestamp::from_seconds(1_600_000_000)),
+            last_lp_activity: Some(Timestamp::from_seconds(1_600_000_000)),
+            last_admin_activity: None,
+        });
+        assert_json_round_trip(SimulateCompleteAssetExchangeResponse {
+            authorization_found: true,
+            funds: vec![Coin::new(1_000, "stable_coin")],
+            marker_transfers: vec![Coin::new(10, "raise_1.commitment")],
+            errors: vec![],
+        });
+        assert_json_round_trip(SimulateWithdrawalResponse {
+            checks: vec![
+                WithdrawalCheck {
+                    name: String::from("not_paused"),
+                    passed: true,
+                    error: None,
+                },
+                WithdrawalCheck {
+                    name: String::from("attribute"),
+                    passed: false,
+                    error: Some(String::from(
+                        "Generic error: lp does not have required attribute of capital.test",
+                    )),
+                },
+            ],
+            path: Some(WithdrawalPath::MarkerTransfer),
+        });
+        assert_json_round_trip(AbortedAuthorizationsResponse {
+            aborted_authorizations: vec![AbortedAuthorization {
+                authorization_id: 1,
+                summary: String::from("#1 [cap:-1000 due:1700000000]"),
+                actor: Addr::unchecked("admin"),
+                reason: String::from("sanctions screening"),
+                time: Timestamp::from_seconds(1_600_000_000),
+            }],
+        });
+        assert_json_round_trip(DepositsResponse {
+            deposits: vec![Deposit {
+                id: 1,
+                sender: Addr::unchecked("lp"),
+                coins: vec![Coin::new(1_000, "stable_coin")],
+                memo: Some(String::from("q3 call")),
+                time: Timestamp::from_seconds(1_600_000_000),
+            }],
+        });
+    }
 }
@@ -1,9 +1,12 @@
-use cosmwasm_std::Addr;
+use cosmwasm_std::{Addr, Coin, Uint128};
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
 use crate::msg::AssetExchange;
 
-#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+/// The message sent to the raise contract. Its exported schema, `raise_execute_msg.json`, is the
+/// interface the raise implements against, so changes here must stay wire compatible.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum RaiseExecuteMsg {
     CompleteAssetExchange {
@@ -11,4 +14,94 @@ pub enum RaiseExecuteMsg {
         to: Option<Addr>,
         memo: Option<String>,
     },
+    /// Excess drawn capital handed back by the subscription, for the raise to credit. The coin
+    /// is attached as funds, or moved by a marker transfer just before when restricted.
+    ReturnCapital { capital: Coin, memo: Option<String> },
+}
+
+/// Queries sent to the raise contract, exported as `raise_query_msg.json`. Raises deployed before
+/// a query was added reject it as an unknown variant.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum RaiseQueryMsg {
+    /// What the raise holds on behalf of `subscription`.
+    GetSubscriptionHoldings { subscription: Addr },
+}
+
+/// The raise's answer to `GetSubscriptionHoldings`: capital it has credited to the subscription,
+/// and the commitment shares the subscription has yet to settle.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct SubscriptionHoldings {
+    pub capital_credited: Vec<Coin>,
+    pub commitment_outstanding: Uint128,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::ExchangeBuilder;
+    use cosmwasm_std::{coin, from_slice, to_vec};
+    use provwasm_mocks::must_read_binary_file;
+
+    /// A completion payload as captured on the raise side.
+    #[test]
+    fn raise_payload_fixture() {
+        let expected = RaiseExecuteMsg::CompleteAssetExchange {
+            exchanges: vec![
+                ExchangeBuilder::new()
+                    .investment(600)
+                    .commitment(-6)
+                    .capital(-600)
+                    .denom("stable_coin")
+                    .due(1_700_000_000)
+                    .build(),
+                ExchangeBuilder::new()
+                    .investment(-250)
+                    .investment_denom("raise_1.investment_b")
+                    .capital(250)
+                    .denom("capital_coin")
+                    .available(1_700_086_400)
+                    .build(),
+            ],
+            to: Some(Addr::unchecked("tp1u0t8uzuq3n9aqmznsle8f5nyx6ahq5xy7qwxhu")),
+            memo: Some(String::from("q3 capital call")),
+        };
+
+        let fixture = must_read_binary_file("testdata/raise_complete_asset_exchange.json");
+        let msg: RaiseExecuteMsg = from_slice(&fixture).unwrap();
+        assert_eq!(expected, msg);
+        assert_eq!(
+            expected,
+            from_slice::<RaiseExecuteMsg>(&to_vec(&msg).unwrap()).unwrap()
+        );
+    }
+
+    #[test]
+    fn return_capital_wire_format() {
+        let msg = RaiseExecuteMsg::ReturnCapital {
+            capital: coin(500, "stable_coin"),
+            memo: Some(String::from("excess q3 call")),
+        };
+        let json = br#"{"return_capital":{"capital":{"denom":"stable_coin","amount":"500"},"memo":"excess q3 call"}}"#;
+        assert_eq!(json.to_vec(), to_vec(&msg).unwrap());
+        assert_eq!(msg, from_slice::<RaiseExecuteMsg>(json).unwrap());
+    }
+
+    #[test]
+    fn subscription_holdings_wire_format() {
+        let msg = RaiseQueryMsg::GetSubscriptionHoldings {
+            subscription: Addr::unchecked("subscription"),
+        };
+        let json = br#"{"get_subscription_holdings":{"subscription":"subscription"}}"#;
+        assert_eq!(json.to_vec(), to_vec(&msg).unwrap());
+        assert_eq!(msg, from_slice::<RaiseQueryMsg>(json).unwrap());
+
+        let holdings = SubscriptionHoldings {
+            capital_credited: vec![coin(1_000, "stable_coin")],
+            commitment_outstanding: Uint128::new(10),
+        };
+        let json = br#"{"capital_credited":[{"denom":"stable_coin","amount":"1000"}],"commitment_outstanding":"10"}"#;
+        assert_eq!(json.to_vec(), to_vec(&holdings).unwrap());
+        assert_eq!(holdings, from_slice::<SubscriptionHoldings>(json).unwrap());
+    }
 }
@@ -1,115 +1,2022 @@
+use std::collections::BTreeMap;
+use std::convert::TryFrom;
+use std::fmt;
+
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 
-use cosmwasm_std::{Addr, Storage};
-use cosmwasm_storage::{singleton, singleton_read, ReadonlySingleton, Singleton};
+use cosmwasm_std::{Addr, Binary, Coin, Order, StdError, StdResult, Storage, Timestamp, Uint128};
+use cosmwasm_storage::{
+    bucket, bucket_read, singleton, singleton_read, Bucket, ReadonlyBucket, ReadonlySingleton,
+    Singleton,
+};
 
-use crate::msg::AssetExchange;
+use crate::amount::{uint128_or_number, Int128};
+use crate::denom::is_ibc_denom;
+use crate::error::ContractError;
+use crate::msg::{
+    display_exchanges, AssetExchange, CapitalDenom, CapitalDenomRequirement, ExchangeDate,
+};
 
 pub static CONFIG_KEY: &[u8] = b"config";
 pub static ASSET_EXCHANGE_AUTHORIZATION_KEY: &[u8] = b"asset_exchange_authorizations";
+pub static AUTHORIZATION_SEQUENCE_KEY: &[u8] = b"authorization_sequence";
+pub static MIGRATION_HISTORY_KEY: &[u8] = b"migration_history";
+pub static PENDING_ADMIN_ACTIONS_KEY: &[u8] = b"pending_admin_actions";
+pub static SCHEDULED_ADMIN_ACTIONS_KEY: &[u8] = b"scheduled_admin_actions";
+pub static SCHEDULED_ADMIN_ACTION_SEQUENCE_KEY: &[u8] = b"scheduled_admin_action_sequence";
+pub static AUTHORIZATION_RATE_KEY: &[u8] = b"authorization_rate";
+pub static IDEMPOTENCY_KEYS_KEY: &[u8] = b"idempotency_keys";
+pub static SIGNED_AUTHORIZATION_NONCE_KEY: &[u8] = b"signed_authorization_nonce";
+pub static WITHDRAWAL_ALLOWANCES_KEY: &[u8] = b"withdrawal_allowances";
+pub static WITHDRAWAL_APPROVALS_KEY: &[u8] = b"withdrawal_approvals";
+pub static OPERATION_STATS_KEY: &[u8] = b"operation_stats";
+pub static DEPOSITS_KEY: &[u8] = b"deposits";
+pub static DEPOSIT_SEQUENCE_KEY: &[u8] = b"deposit_sequence";
+pub static ABORTED_AUTHORIZATIONS_KEY: &[u8] = b"aborted_authorizations";
+pub static METADATA_KEY: &[u8] = b"metadata";
+pub static AUTHORIZATION_HISTORY_KEY: &[u8] = b"authorization_history";
+
+pub const MAX_MIGRATION_HISTORY: usize = 10;
+
+/// How long a proposed admin action collects approvals before it lapses.
+pub const ADMIN_ACTION_EXPIRY_SECONDS: u64 = 7 * 24 * 60 * 60;
+
+pub const DEFAULT_MAX_AUTHORIZATIONS_PER_BLOCK: u32 = 10;
+
+pub const MAX_IDEMPOTENCY_KEY_LENGTH: usize = 64;
+
+/// How many idempotency keys are remembered; the oldest are forgotten first.
+pub const MAX_IDEMPOTENCY_KEYS: usize = 100;
+
+/// How many aborted authorizations are remembered; the oldest are forgotten first.
+pub const MAX_ABORTED_AUTHORIZATIONS: usize = 50;
+
+/// How many cancelled or completed authorizations are remembered; the oldest are forgotten first.
+pub const MAX_AUTHORIZATION_HISTORY: usize = 50;
+
+pub const MAX_ABORT_REASON_LENGTH: usize = 256;
+
+pub const MAX_LP_NOTE_LENGTH: usize = 128;
+
+pub const MAX_METADATA_NAME_LENGTH: usize = 64;
+pub const MAX_METADATA_DESCRIPTION_LENGTH: usize = 512;
+pub const MAX_METADATA_EXTERNAL_ID_LENGTH: usize = 64;
+
+pub const DEFAULT_DEPOSITS_LIMIT: u32 = 10;
+pub const MAX_DEPOSITS_LIMIT: u32 = 30;
+
+pub const MAX_WITHDRAWAL_FEE_BPS: u16 = 10_000;
+
+pub const DEFAULT_AUTHORIZATION_HISTORY_LIMIT: u32 = 10;
+pub const MAX_AUTHORIZATION_HISTORY_LIMIT: u32 = 30;
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct State {
-    pub admin: Addr,
+    pub admins: Vec<Addr>,
+    pub admin_threshold: u8,
     pub lp: Addr,
-    pub raise: Addr,
+    /// The raises the subscription exchanges assets with. The first is the primary raise, which
+    /// instantiated the subscription and is used when a message names none. Stored state from
+    /// before multiple raises holds a single `raise`.
+    #[serde(alias = "raise", deserialize_with = "crate::msg::one_or_many")]
+    pub raises: Vec<Addr>,
     pub commitment_denom: String,
-    pub investment_denom: String,
-    pub capital_denom: String,
+    /// Investment classes issued to the lp; the first is used when an exchange names none.
+    pub investment_denoms: Vec<String>,
+    pub like_capital_denoms: Vec<String>,
     pub capital_per_share: u64,
-    pub required_capital_attribute: Option<String>,
+    /// Capital per share for like capital denoms that don't use `capital_per_share`, such as
+    /// stablecoins with a different number of decimals.
+    pub capital_per_share_overrides: Vec<(String, u64)>,
+    pub required_capital_attributes: Vec<CapitalDenomRequirement>,
+    pub require_distinct_roles: bool,
+    pub max_commitment_in_shares: Option<u64>,
+    pub total_commitment_in_shares: u64,
+    /// Accounts allowed to complete LP-authorized exchanges, and nothing else.
+    pub operators: Vec<Addr>,
+    /// How long approved sensitive admin actions wait before they can be executed. Zero applies
+    /// them immediately.
+    pub admin_action_delay_seconds: u64,
+    /// How many asset exchange authorizations the lp may submit within a single block.
+    pub max_authorizations_per_block: u32,
+    /// Set while the subscription is paused, recording who paused it so only they can unpause.
+    pub paused: Option<PauseSource>,
+    /// How long after a due date an uncompleted authorization can still be completed. Unset,
+    /// authorizations never go stale.
+    pub stale_after_seconds: Option<u64>,
+    /// How long after a due date an admin would wait before settling an exchange the lp never
+    /// authorized. Admin completions still need an authorization, so this is only recorded.
+    pub default_grace_period_seconds: u64,
+    /// How long a new authorization stays completable when the lp doesn't give it an expiration,
+    /// and the furthest out an expiration may be set. Unset, authorizations don't expire unless
+    /// given an expiration.
+    #[serde(default)]
+    pub default_authorization_ttl_seconds: Option<u64>,
+    /// Where proceeds go when a completion paying out capital names no recipient. Unset, the raise
+    /// decides.
+    #[serde(default)]
+    pub default_distribution_address: Option<Addr>,
+    /// Free-form reference the lp keeps on the subscription, such as a custodian's account id.
+    #[serde(default)]
+    pub lp_note: Option<String>,
+    /// When the lp last sent this subscription an execute message.
+    #[serde(default)]
+    pub last_lp_activity: Option<Timestamp>,
+    /// When an admin last sent this subscription an execute message.
+    #[serde(default)]
+    pub last_admin_activity: Option<Timestamp>,
+    /// Basis points of each withdrawal paid to `fee_recipient`, rounded down in the lp's favor.
+    /// Only instantiation and migration set it.
+    #[serde(default)]
+    pub withdrawal_fee_bps: Option<u16>,
+    #[serde(default)]
+    pub fee_recipient: Option<Addr>,
+    /// Withdrawals of a denom above its threshold need an unexpired admin approval for their
+    /// recipient, amount and denom. Denoms without a threshold need none. Only instantiation and
+    /// migration set them.
+    #[serde(default)]
+    pub withdrawal_approval_thresholds: Vec<Coin>,
+    /// Where the lp directs every distribution, such as a custody account. Takes the place of
+    /// `default_distribution_address` when set.
+    #[serde(default)]
+    pub beneficiary: Option<Addr>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum PauseSource {
+    Admin,
+    Governance,
+}
+
+impl PauseSource {
+    pub fn name(&self) -> &'static str {
+        match self {
+            PauseSource::Admin => "admin",
+            PauseSource::Governance => "governance",
+        }
+    }
 }
 
 impl State {
-    pub fn not_evenly_divisble(&self, amount: u64) -> bool {
-        amount % self.capital_per_share > 0
+    pub fn is_admin(&self, address: &Addr) -> bool {
+        self.admins.contains(address)
+    }
+
+    pub fn is_operator(&self, address: &Addr) -> bool {
+        self.operators.contains(address)
+    }
+
+    pub fn primary_raise(&self) -> &Addr {
+        &self.raises[0]
+    }
+
+    /// Resolves the raise a message names to the form authorizations record it in: `None` for
+    /// the primary raise, which a message may also leave out.
+    pub fn select_raise(&self, raise: Option<Addr>) -> Result<Option<Addr>, ContractError> {
+        match raise {
+            Some(raise) if &raise == self.primary_raise() => Ok(None),
+            Some(raise) if self.raises.contains(&raise) => Ok(Some(raise)),
+            Some(raise) => Err(ContractError::UnregisteredRaise {
+                raise: raise.to_string(),
+            }),
+            None => Ok(None),
+        }
+    }
+
+    /// The address of a raise selected by `select_raise`.
+    pub fn raise_address<'a>(&'a self, raise: &'a Option<Addr>) -> &'a Addr {
+        raise.as_ref().unwrap_or_else(|| self.primary_raise())
+    }
+
+    /// Pausing takes over a pause from another source only when governance pauses; unpausing
+    /// is reserved for whoever paused.
+    pub fn set_paused(&mut self, paused: bool, source: PauseSource) -> Result<(), ContractError> {
+        match (self.paused, paused) {
+            (Some(current), _)
+                if current != source && !(paused && source == PauseSource::Governance) =>
+            {
+                Err(ContractError::from(format!(
+                    "subscription was paused by {}, only {} can change it",
+                    current.name(),
+                    current.name()
+                )))
+            }
+            (_, true) => {
+                self.paused = Some(source);
+                Ok(())
+            }
+            (_, false) => {
+                self.paused = None;
+                Ok(())
+            }
+        }
+    }
+
+    pub fn check_not_paused(&self) -> Result<(), ContractError> {
+        match self.paused {
+            Some(source) => Err(ContractError::from(format!(
+                "subscription is paused by {}",
+                source.name()
+            ))),
+            None => Ok(()),
+        }
+    }
+
+    /// Checks the admin set: at least one admin, no duplicates, a threshold that can be met, and
+    /// no overlap with the lp when roles must be distinct.
+    pub fn validate_admins(&self) -> Result<(), ContractError> {
+        if self.admins.is_empty() {
+            return Err(ContractError::from("at least one admin is required"));
+        }
+
+        let mut admins = self.admins.clone();
+        admins.sort();
+        admins.dedup();
+        if admins.len() != self.admins.len() {
+            return Err(ContractError::from("admins must be distinct addresses"));
+        }
+
+        if self.admin_threshold == 0 || usize::from(self.admin_threshold) > self.admins.len() {
+            return Err(ContractError::from(
+                "admin threshold must be between 1 and the number of admins",
+            ));
+        }
+
+        if self.require_distinct_roles && self.is_admin(&self.lp) {
+            return Err(ContractError::from(
+                "admin and lp must be distinct addresses",
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Resolves the capital denom for an exchange or withdrawal, falling back to the sole like
+    /// capital denom when none is specified.
+    pub fn resolve_capital_denom(&self, denom: Option<&String>) -> Result<String, ContractError> {
+        match denom {
+            Some(denom) => {
+                if self.like_capital_denoms.contains(denom) {
+                    Ok(denom.clone())
+                } else {
+                    Err(ContractError::UnsupportedCapitalDenom {
+                        denom: denom.clone(),
+                    })
+                }
+            }
+            None => match self.like_capital_denoms.as_slice() {
+                [denom] => Ok(denom.clone()),
+                _ => Err(ContractError::CapitalDenomRequired {}),
+            },
+        }
+    }
+
+    pub fn resolve_investment_denom(
+        &self,
+        denom: Option<&String>,
+    ) -> Result<String, ContractError> {
+        match denom {
+            Some(denom) if self.investment_denoms.contains(denom) => Ok(denom.clone()),
+            Some(denom) => Err(ContractError::UnsupportedInvestmentDenom {
+                denom: denom.clone(),
+            }),
+            None => self
+                .investment_denoms
+                .first()
+                .cloned()
+                .ok_or_else(|| ContractError::from("no investment denom configured")),
+        }
+    }
+
+    /// Returns the net commitment of the exchanges, failing if a net increase would take the
+    /// running total above the commitment ceiling.
+    pub fn check_commitment_ceiling(
+        &self,
+        exchanges: &[AssetExchange],
+    ) -> Result<Int128, ContractError> {
+        let net_commitment = exchanges
+            .iter()
+            .filter_map(|e| e.commitment_in_shares)
+            .try_fold(Int128::zero(), Int128::checked_add)
+            .map_err(StdError::overflow)?;
+        if let Some(max_commitment_in_shares) = self.max_commitment_in_shares {
+            if net_commitment.is_positive()
+                && u128::from(self.total_commitment_in_shares)
+                    + net_commitment.unsigned_abs().u128()
+                    > u128::from(max_commitment_in_shares)
+            {
+                return Err(ContractError::CommitmentCeilingExceeded {
+                    headroom: max_commitment_in_shares
+                        .saturating_sub(self.total_commitment_in_shares),
+                });
+            }
+        }
+        Ok(net_commitment)
+    }
+
+    /// Stamps `now` as the last activity of whichever roles `sender` holds, returning whether
+    /// anything changed.
+    pub fn record_activity(&mut self, sender: &Addr, now: &Timestamp) -> bool {
+        let mut changed = false;
+        if sender == &self.lp && self.last_lp_activity != Some(*now) {
+            self.last_lp_activity = Some(*now);
+            changed = true;
+        }
+        if self.is_admin(sender) && self.last_admin_activity != Some(*now) {
+            self.last_admin_activity = Some(*now);
+            changed = true;
+        }
+        changed
+    }
+
+    /// The recipient a completion names to the raise: `to` when given, otherwise the beneficiary
+    /// or the default distribution address if any exchange pays out capital. Unset, the raise
+    /// decides.
+    pub fn distribution_address(
+        &self,
+        exchanges: &[AssetExchange],
+        to: Option<Addr>,
+    ) -> Option<Addr> {
+        to.or_else(|| {
+            exchanges
+                .iter()
+                .filter_map(|exchange| exchange.capital)
+                .any(|capital| capital.is_positive())
+                .then(|| {
+                    self.beneficiary
+                        .clone()
+                        .or_else(|| self.default_distribution_address.clone())
+                })
+                .flatten()
+        })
+    }
+
+    /// Checks that every exchange moves something and that its denoms are supported.
+    pub fn validate_exchanges(&self, exchanges: &[AssetExchange]) -> Result<(), ContractError> {
+        if let Some(exchange) = exchanges.iter().find(|exchange| !exchange.has_amount()) {
+            return Err(ContractError::InvalidExchange {
+                exchange: exchange.to_string(),
+            });
+        }
+        self.validate_exchange_denoms(exchanges)
+    }
+
+    /// Checks that every exchange moving capital or investment names a supported denom.
+    pub fn validate_exchange_denoms(
+        &self,
+        exchanges: &[AssetExchange],
+    ) -> Result<(), ContractError> {
+        for exchange in exchanges.iter().filter(|e| e.capital.is_some()) {
+            self.resolve_capital_denom(exchange.capital_denom.as_ref())?;
+        }
+        for exchange in exchanges.iter().filter(|e| e.investment.is_some()) {
+            self.resolve_investment_denom(exchange.investment_denom.as_ref())?;
+        }
+        Ok(())
+    }
+
+    /// Fills in the denom each capital and investment leg resolves to, so exchanges that name
+    /// the default denom and exchanges that leave it out compare equal.
+    pub fn normalize_exchanges(
+        &self,
+        exchanges: &[AssetExchange],
+    ) -> Result<Vec<AssetExchange>, ContractError> {
+        exchanges
+            .iter()
+            .map(|exchange| {
+                let mut exchange = exchange.clone();
+                if exchange.capital.is_some() {
+                    exchange.capital_denom =
+                        Some(self.resolve_capital_denom(exchange.capital_denom.as_ref())?);
+                }
+                if exchange.investment.is_some() {
+                    exchange.investment_denom =
+                        Some(self.resolve_investment_denom(exchange.investment_denom.as_ref())?);
+                }
+                Ok(exchange)
+            })
+            .collect()
+    }
+
+    /// Sums the capital legs of the exchanges per resolved capital denom. Negative totals are
+    /// owed by the lp, positive ones by the raise.
+    pub fn net_capital(
+        &self,
+        exchanges: &[AssetExchange],
+    ) -> Result<BTreeMap<String, Int128>, ContractError> {
+        let mut net_capital: BTreeMap<String, Int128> = BTreeMap::new();
+        for exchange in exchanges {
+            if let Some(capital) = exchange.capital {
+                let denom = self.resolve_capital_denom(exchange.capital_denom.as_ref())?;
+                let total = net_capital.entry(denom).or_default();
+                *total = total.checked_add(capital).map_err(StdError::overflow)?;
+            }
+        }
+        Ok(net_capital)
+    }
+
+    /// The expiration a new authorization gets: the one requested, which may not lie beyond the
+    /// default ttl, or else the default ttl from `now`.
+    pub fn authorization_expiration(
+        &self,
+        requested: Option<Timestamp>,
+        now: &Timestamp,
+    ) -> Result<Option<Timestamp>, ContractError> {
+        match (requested, self.default_authorization_ttl_seconds) {
+            (Some(requested), _) if requested <= *now => Err(ContractError::from(format!(
+                "authorization expiration {} is not in the future",
+                requested.seconds()
+            ))),
+            (Some(requested), Some(ttl)) if requested > now.plus_seconds(ttl) => {
+                Err(ContractError::from(format!(
+                    "authorization expiration {} is beyond the default ttl of {} seconds",
+                    requested.seconds(),
+                    ttl
+                )))
+            }
+            (Some(requested), _) => Ok(Some(requested)),
+            (None, ttl) => Ok(ttl.map(|ttl| now.plus_seconds(ttl))),
+        }
+    }
+
+    /// The attributes of which a recipient of the denom must hold at least one, if any.
+    pub fn required_capital_attributes(&self, denom: &str) -> Option<&[String]> {
+        self.required_capital_attributes
+            .iter()
+            .find(|requirement| requirement.capital_denom == denom)
+            .map(|requirement| requirement.required_attributes.as_slice())
+    }
+
+    /// The attributes a recipient must hold for a transfer of the denom to go ahead: always for a
+    /// restricted marker, and for a bank send only when the requirement asks for it.
+    pub fn enforced_capital_attributes(&self, denom: &str, restricted: bool) -> Option<&[String]> {
+        self.required_capital_attributes
+            .iter()
+            .find(|requirement| {
+                requirement.capital_denom == denom
+                    && (restricted || requirement.enforce_attribute_on_bank_send)
+            })
+            .map(|requirement| requirement.required_attributes.as_slice())
+    }
+
+    /// Describes each like capital denom, in configured order.
+    pub fn capital_denoms(&self) -> Vec<CapitalDenom> {
+        let default_denom = self.resolve_capital_denom(None).ok();
+        self.like_capital_denoms
+            .iter()
+            .map(|denom| CapitalDenom {
+                denom: denom.clone(),
+                required_attributes: self
+                    .required_capital_attributes(denom)
+                    .unwrap_or_default()
+                    .to_vec(),
+                is_default: default_denom.as_ref() == Some(denom),
+            })
+            .collect()
+    }
+
+    pub fn capital_per_share(&self, denom: &str) -> u64 {
+        self.capital_per_share_overrides
+            .iter()
+            .find(|(override_denom, _)| override_denom == denom)
+            .map(|(_, capital_per_share)| *capital_per_share)
+            .unwrap_or(self.capital_per_share)
+    }
+
+    /// A fee can't take more than the whole withdrawal, and needs somewhere to go.
+    pub fn validate_withdrawal_fee(&self) -> Result<(), ContractError> {
+        match (self.withdrawal_fee_bps, &self.fee_recipient) {
+            (Some(bps), _) if bps > MAX_WITHDRAWAL_FEE_BPS => Err(ContractError::from(format!(
+                "withdrawal fee of {} bps exceeds {} bps",
+                bps, MAX_WITHDRAWAL_FEE_BPS
+            ))),
+            (Some(bps), None) if bps > 0 => Err(ContractError::from(
+                "a withdrawal fee requires a fee recipient",
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    /// The fee on a withdrawal of `amount` and who it goes to, when one is configured. Rounds
+    /// down, so the lp never pays more than the configured rate.
+    pub fn withdrawal_fee(&self, amount: Uint128) -> Option<(Uint128, &Addr)> {
+        match (self.withdrawal_fee_bps, &self.fee_recipient) {
+            (Some(bps), Some(fee_recipient)) if bps > 0 => Some((
+                amount.multiply_ratio(bps, MAX_WITHDRAWAL_FEE_BPS),
+                fee_recipient,
+            )),
+            _ => None,
+        }
+    }
+
+    pub fn validate_capital_per_share(&self) -> Result<(), ContractError> {
+        if self.capital_per_share == 0 {
+            return Err(ContractError::from(
+                "capital per share must be greater than zero",
+            ));
+        }
+        for (i, (denom, capital_per_share)) in self.capital_per_share_overrides.iter().enumerate() {
+            if !self.like_capital_denoms.contains(denom) {
+                return Err(ContractError::UnsupportedCapitalDenom {
+                    denom: denom.clone(),
+                });
+            }
+            if *capital_per_share == 0 {
+                return Err(ContractError::from(format!(
+                    "capital per share for {} must be greater than zero",
+                    denom
+                )));
+            }
+            if self.capital_per_share_overrides[..i]
+                .iter()
+                .any(|(other, _)| other == denom)
+            {
+                return Err(ContractError::from(format!(
+                    "duplicate capital per share override for {}",
+                    denom
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// The amount of `denom` a withdrawal may move without an admin approval, if limited.
+    pub fn withdrawal_approval_threshold(&self, denom: &str) -> Option<Uint128> {
+        self.withdrawal_approval_thresholds
+            .iter()
+            .find(|threshold| threshold.denom == denom)
+            .map(|threshold| threshold.amount)
+    }
+
+    pub fn validate_withdrawal_approval_thresholds(&self) -> Result<(), ContractError> {
+        for (i, threshold) in self.withdrawal_approval_thresholds.iter().enumerate() {
+            self.resolve_capital_denom(Some(&threshold.denom))?;
+            if self.withdrawal_approval_thresholds[..i]
+                .iter()
+                .any(|other| other.denom == threshold.denom)
+            {
+                return Err(ContractError::from(format!(
+                    "duplicate withdrawal approval threshold for {}",
+                    threshold.denom
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// Splits an authorized exchange, returning what remains once `partial` has been completed.
+    /// Each amount of `partial` must have the sign of the authorized amount and a smaller
+    /// magnitude, and capital on both sides of the split must be a whole number of shares.
+    pub fn partial_remainder(
+        &self,
+        authorized: &AssetExchange,
+        partial: &AssetExchange,
+    ) -> Result<AssetExchange, ContractError> {
+        if partial.investment_denom != authorized.investment_denom
+            || partial.capital_denom != authorized.capital_denom
+            || partial.date != authorized.date
+            || partial.to != authorized.to
+        {
+            return Err(ContractError::from(
+                "a partial exchange must keep the denoms, date and recipient of the authorized exchange",
+            ));
+        }
+
+        let remainder = AssetExchange {
+            investment: split_amount("investment", authorized.investment, partial.investment)?,
+            commitment_in_shares: split_amount(
+                "commitment",
+                authorized.commitment_in_shares,
+                partial.commitment_in_shares,
+            )?,
+            capital: split_amount("capital", authorized.capital, partial.capital)?,
+            ..authorized.clone()
+        };
+        if let (Some(partial_capital), Some(remaining_capital)) =
+            (partial.capital, remainder.capital)
+        {
+            let denom = self.resolve_capital_denom(partial.capital_denom.as_ref())?;
+            for capital in [partial_capital, remaining_capital] {
+                let amount = u64::try_from(capital.unsigned_abs().u128()).map_err(|_| {
+                    ContractError::from(format!("capital {} out of range", capital))
+                })?;
+                if self.not_evenly_divisble(&denom, amount) {
+                    return Err(ContractError::from(format!(
+                        "a partial exchange must split capital into whole shares of {} {}",
+                        self.capital_per_share(&denom),
+                        denom
+                    )));
+                }
+            }
+        }
+        Ok(remainder)
     }
 
-    pub fn capital_to_shares(&self, amount: u64) -> u64 {
-        amount / self.capital_per_share
+    pub fn not_evenly_divisble(&self, denom: &str, amount: u64) -> bool {
+        amount % self.capital_per_share(denom) != 0
+    }
+
+    pub fn capital_to_shares(&self, denom: &str, amount: u64) -> u64 {
+        amount / self.capital_per_share(denom)
     }
 }
 
-pub fn state_storage(storage: &mut dyn Storage) -> Singleton<State> {
+fn split_amount(
+    field: &str,
+    authorized: Option<Int128>,
+    partial: Option<Int128>,
+) -> Result<Option<Int128>, ContractError> {
+    match (authorized, partial) {
+        (None, None) => Ok(None),
+        (Some(authorized), Some(partial))
+            if !partial.is_zero()
+                && partial.is_negative() == authorized.is_negative()
+                && partial.unsigned_abs() < authorized.unsigned_abs() =>
+        {
+            Ok(Some(
+                authorized
+                    .checked_sub(partial)
+                    .map_err(StdError::overflow)?,
+            ))
+        }
+        _ => Err(ContractError::from(format!(
+            "partial {} must lie strictly between zero and the authorized amount",
+            field
+        ))),
+    }
+}
+
+/// Converts requirements from a message into the form kept in state, keyed by denom with at
+/// most one entry each, resolving the legacy denom-less form against the sole like capital denom.
+pub fn capital_denom_requirements(
+    like_capital_denoms: &[String],
+    requirements: Vec<CapitalDenomRequirement>,
+) -> Result<Vec<CapitalDenomRequirement>, ContractError> {
+    let mut resolved: Vec<CapitalDenomRequirement> = Vec::new();
+    for requirement in requirements {
+        let denom = if requirement.capital_denom.is_empty() {
+            match like_capital_denoms {
+                [denom] => denom.clone(),
+                _ => return Err(ContractError::CapitalDenomRequired {}),
+            }
+        } else if like_capital_denoms.contains(&requirement.capital_denom) {
+            requirement.capital_denom
+        } else {
+            return Err(ContractError::UnsupportedCapitalDenom {
+                denom: requirement.capital_denom,
+            });
+        };
+        if is_ibc_denom(&denom) {
+            return Err(ContractError::from(format!(
+                "ibc denom {} cannot carry a required attribute",
+                denom
+            )));
+        }
+        if requirement.required_attributes.is_empty() {
+            return Err(ContractError::from(format!(
+                "capital denom {} requires at least one attribute",
+                denom
+            )));
+        }
+        resolved.retain(|existing| existing.capital_denom != denom);
+        resolved.push(CapitalDenomRequirement {
+            capital_denom: denom,
+            required_attributes: requirement.required_attributes,
+            enforce_attribute_on_bank_send: requirement.enforce_attribute_on_bank_send,
+        });
+    }
+    Ok(resolved)
+}
+
+pub fn state_storage(storage: &mut dyn Storage) -> Singleton<'_, State> {
     singleton(storage, CONFIG_KEY)
 }
 
-pub fn state_storage_read(storage: &dyn Storage) -> ReadonlySingleton<State> {
+pub fn state_storage_read(storage: &dyn Storage) -> ReadonlySingleton<'_, State> {
     singleton_read(storage, CONFIG_KEY)
 }
 
 #[derive(Deserialize, Serialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
 pub struct AssetExchangeAuthorization {
+    pub id: u64,
+    /// The raise the exchanges are with, unless it is the primary raise. An authorization only
+    /// matches messages for its own raise.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default)]
+    pub raise: Option<Addr>,
     pub exchanges: Vec<AssetExchange>,
     pub to: Option<Addr>,
     pub memo: Option<String>,
+    pub created_at: Option<Timestamp>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default)]
+    pub expires_at: Option<Timestamp>,
+    /// sha256 of a memo the lp keeps off chain until completion, in place of `memo`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default)]
+    pub memo_hash: Option<Binary>,
+    #[serde(skip_serializing_if = "AuthorizationStatus::is_pending")]
+    #[serde(default)]
+    pub status: AuthorizationStatus,
+    /// When and by whom the authorization was cancelled or completed.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default)]
+    pub resolved_at: Option<Timestamp>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default)]
+    pub resolved_by: Option<Addr>,
+}
+
+/// Only pending authorizations can be matched; the others are kept as history.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum AuthorizationStatus {
+    #[default]
+    Pending,
+    Cancelled,
+    Completed,
+}
+
+impl AuthorizationStatus {
+    pub fn is_pending(&self) -> bool {
+        self == &AuthorizationStatus::Pending
+    }
+}
+
+impl AssetExchangeAuthorization {
+    /// A hashed memo is matched by revealing the plaintext it was hashed from.
+    pub fn matches_memo(&self, memo: &Option<String>) -> bool {
+        match (&self.memo_hash, memo) {
+            (Some(memo_hash), Some(memo)) => memo_hash == &hash_memo(memo),
+            (Some(_), None) => false,
+            (None, memo) => &self.memo == memo,
+        }
+    }
+
+    /// Whether the authorization is past its expiration, or any of its due dates lies more than
+    /// `stale_after_seconds` before `now`.
+    pub fn is_stale(&self, stale_after_seconds: Option<u64>, now: &Timestamp) -> bool {
+        matches!(self.expires_at, Some(expires_at) if expires_at < *now)
+            || is_stale(&self.exchanges, stale_after_seconds, now)
+    }
+}
+
+impl fmt::Display for AssetExchangeAuthorization {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "#{} [{}]", self.id, display_exchanges(&self.exchanges))?;
+        if let Some(to) = &self.to {
+            write!(f, " to:{}", to)?;
+        }
+        if let Some(memo) = &self.memo {
+            write!(f, " memo:{:?}", memo)?;
+        }
+        if let Some(memo_hash) = &self.memo_hash {
+            write!(f, " memo_hash:{}", memo_hash)?;
+        }
+        if let Some(raise) = &self.raise {
+            write!(f, " raise:{}", raise)?;
+        }
+        Ok(())
+    }
+}
+
+pub fn hash_memo(memo: &str) -> Binary {
+    Binary::from(Sha256::digest(memo.as_bytes()).as_slice())
+}
+
+pub fn is_stale(
+    exchanges: &[AssetExchange],
+    stale_after_seconds: Option<u64>,
+    now: &Timestamp,
+) -> bool {
+    match stale_after_seconds {
+        Some(stale_after_seconds) => exchanges.iter().any(|exchange| {
+            matches!(exchange.date, Some(ExchangeDate::Due(due))
+                if due.saturating_add(stale_after_seconds) < now.seconds())
+        }),
+        None => false,
+    }
 }
 
 pub fn asset_exchange_authorization_storage(
     storage: &mut dyn Storage,
-) -> Singleton<Vec<AssetExchangeAuthorization>> {
+) -> Singleton<'_, Vec<AssetExchangeAuthorization>> {
     singleton(storage, ASSET_EXCHANGE_AUTHORIZATION_KEY)
 }
 
 pub fn asset_exchange_authorization_storage_read(
     storage: &dyn Storage,
-) -> ReadonlySingleton<Vec<AssetExchangeAuthorization>> {
+) -> ReadonlySingleton<'_, Vec<AssetExchangeAuthorization>> {
     singleton_read(storage, ASSET_EXCHANGE_AUTHORIZATION_KEY)
 }
 
+pub fn authorization_sequence_storage(storage: &mut dyn Storage) -> Singleton<'_, u64> {
+    singleton(storage, AUTHORIZATION_SEQUENCE_KEY)
+}
+
+pub fn authorization_sequence_storage_read(storage: &dyn Storage) -> ReadonlySingleton<'_, u64> {
+    singleton_read(storage, AUTHORIZATION_SEQUENCE_KEY)
+}
+
+pub fn next_authorization_id(storage: &mut dyn Storage) -> StdResult<u64> {
+    let id = authorization_sequence_storage_read(storage)
+        .may_load()?
+        .unwrap_or_default()
+        + 1;
+    authorization_sequence_storage(storage).save(&id)?;
+    Ok(id)
+}
+
+/// The number of authorizations submitted at the most recent block height that saw one.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Default)]
+pub struct AuthorizationRate {
+    pub height: u64,
+    pub count: u32,
+}
+
+pub fn authorization_rate_storage(storage: &mut dyn Storage) -> Singleton<'_, AuthorizationRate> {
+    singleton(storage, AUTHORIZATION_RATE_KEY)
+}
+
+pub fn authorization_rate_storage_read(
+    storage: &dyn Storage,
+) -> ReadonlySingleton<'_, AuthorizationRate> {
+    singleton_read(storage, AUTHORIZATION_RATE_KEY)
+}
+
+/// Counts an authorization against the limit for the current block, resetting the count when
+/// the height has moved on.
+pub fn record_authorization(
+    storage: &mut dyn Storage,
+    height: u64,
+    max_per_block: u32,
+) -> Result<(), ContractError> {
+    let mut rate = authorization_rate_storage_read(storage)
+        .may_load()?
+        .unwrap_or_default();
+    if rate.height != height {
+        rate = AuthorizationRate { height, count: 0 };
+    }
+    if rate.count >= max_per_block {
+        return Err(ContractError::from(format!(
+            "at most {} asset exchange authorizations are accepted per block, retry next block",
+            max_per_block
+        )));
+    }
+    rate.count += 1;
+    authorization_rate_storage(storage).save(&rate)?;
+    Ok(())
+}
+
+/// The highest nonce accepted on a signed authorization.
+pub fn signed_authorization_nonce_storage(storage: &mut dyn Storage) -> Singleton<'_, u64> {
+    singleton(storage, SIGNED_AUTHORIZATION_NONCE_KEY)
+}
+
+pub fn signed_authorization_nonce_storage_read(
+    storage: &dyn Storage,
+) -> ReadonlySingleton<'_, u64> {
+    singleton_read(storage, SIGNED_AUTHORIZATION_NONCE_KEY)
+}
+
+/// A client-supplied key and the authorization it created.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct IdempotencyKey {
+    pub key: String,
+    pub authorization_id: u64,
+}
+
+pub fn idempotency_keys_storage(storage: &mut dyn Storage) -> Singleton<'_, Vec<IdempotencyKey>> {
+    singleton(storage, IDEMPOTENCY_KEYS_KEY)
+}
+
+pub fn idempotency_keys_storage_read(
+    storage: &dyn Storage,
+) -> ReadonlySingleton<'_, Vec<IdempotencyKey>> {
+    singleton_read(storage, IDEMPOTENCY_KEYS_KEY)
+}
+
+pub fn check_idempotency_key(storage: &dyn Storage, key: &str) -> Result<(), ContractError> {
+    if key.is_empty() || key.len() > MAX_IDEMPOTENCY_KEY_LENGTH {
+        return Err(ContractError::from(format!(
+            "idempotency key must be between 1 and {} bytes",
+            MAX_IDEMPOTENCY_KEY_LENGTH
+        )));
+    }
+    match idempotency_keys_storage_read(storage)
+        .may_load()?
+        .unwrap_or_default()
+        .into_iter()
+        .find(|seen| seen.key == key)
+    {
+        Some(seen) => Err(ContractError::DuplicateRequest {
+            key: seen.key,
+            authorization_id: seen.authorization_id,
+        }),
+        None => Ok(()),
+    }
+}
+
+pub fn record_idempotency_key(
+    storage: &mut dyn Storage,
+    key: String,
+    authorization_id: u64,
+) -> StdResult<()> {
+    let mut seen = idempotency_keys_storage_read(storage)
+        .may_load()?
+        .unwrap_or_default();
+    seen.push(IdempotencyKey {
+        key,
+        authorization_id,
+    });
+    if seen.len() > MAX_IDEMPOTENCY_KEYS {
+        seen.drain(..seen.len() - MAX_IDEMPOTENCY_KEYS);
+    }
+    idempotency_keys_storage(storage).save(&seen)
+}
+
+/// An authorization removed with a recorded reason, rather than simply cancelled.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct AbortedAuthorization {
+    pub authorization_id: u64,
+    /// The authorization as rendered by its `Display` impl.
+    pub summary: String,
+    pub actor: Addr,
+    pub reason: String,
+    pub time: Timestamp,
+}
+
+pub fn aborted_authorizations_storage(
+    storage: &mut dyn Storage,
+) -> Singleton<'_, Vec<AbortedAuthorization>> {
+    singleton(storage, ABORTED_AUTHORIZATIONS_KEY)
+}
+
+pub fn aborted_authorizations_storage_read(
+    storage: &dyn Storage,
+) -> ReadonlySingleton<'_, Vec<AbortedAuthorization>> {
+    singleton_read(storage, ABORTED_AUTHORIZATIONS_KEY)
+}
+
+/// Human-readable identification of the fund and lp relationship a subscription represents.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct SubscriptionMetadata {
+    pub name: String,
+    pub description: Option<String>,
+    pub external_id: Option<String>,
+}
+
+impl SubscriptionMetadata {
+    pub fn validate(&self) -> Result<(), ContractError> {
+        validate_metadata_field("name", &self.name, MAX_METADATA_NAME_LENGTH)?;
+        if let Some(description) = &self.description {
+            validate_metadata_field("description", description, MAX_METADATA_DESCRIPTION_LENGTH)?;
+        }
+        if let Some(external_id) = &self.external_id {
+            validate_metadata_field("external id", external_id, MAX_METADATA_EXTERNAL_ID_LENGTH)?;
+        }
+        Ok(())
+    }
+}
+
+fn validate_metadata_field(
+    field: &str,
+    value: &str,
+    max_length: usize,
+) -> Result<(), ContractError> {
+    if value.trim().is_empty() || value.len() > max_length {
+        return Err(ContractError::from(format!(
+            "metadata {} must be between 1 and {} bytes",
+            field, max_length
+        )));
+    }
+    Ok(())
+}
+
+pub fn metadata_storage(storage: &mut dyn Storage) -> Singleton<'_, SubscriptionMetadata> {
+    singleton(storage, METADATA_KEY)
+}
+
+pub fn metadata_storage_read(storage: &dyn Storage) -> ReadonlySingleton<'_, SubscriptionMetadata> {
+    singleton_read(storage, METADATA_KEY)
+}
+
+pub fn validate_lp_note(note: &str) -> Result<(), ContractError> {
+    if note.trim().is_empty() || note.len() > MAX_LP_NOTE_LENGTH {
+        return Err(ContractError::from(format!(
+            "lp note must be between 1 and {} bytes",
+            MAX_LP_NOTE_LENGTH
+        )));
+    }
+    Ok(())
+}
+
+pub fn validate_abort_reason(reason: &str) -> Result<(), ContractError> {
+    if reason.trim().is_empty() || reason.len() > MAX_ABORT_REASON_LENGTH {
+        return Err(ContractError::from(format!(
+            "abort reason must be between 1 and {} bytes",
+            MAX_ABORT_REASON_LENGTH
+        )));
+    }
+    Ok(())
+}
+
+pub fn authorization_history_storage(
+    storage: &mut dyn Storage,
+) -> Singleton<'_, Vec<AssetExchangeAuthorization>> {
+    singleton(storage, AUTHORIZATION_HISTORY_KEY)
+}
+
+pub fn authorization_history_storage_read(
+    storage: &dyn Storage,
+) -> ReadonlySingleton<'_, Vec<AssetExchangeAuthorization>> {
+    singleton_read(storage, AUTHORIZATION_HISTORY_KEY)
+}
+
+/// Keeps an authorization leaving the pending list, marked with how, when and by whom it left.
+pub fn record_authorization_history(
+    storage: &mut dyn Storage,
+    mut authorization: AssetExchangeAuthorization,
+    status: AuthorizationStatus,
+    actor: &Addr,
+    time: Timestamp,
+) -> StdResult<()> {
+    authorization.status = status;
+    authorization.resolved_at = Some(time);
+    authorization.resolved_by = Some(actor.clone());
+    let mut history = authorization_history_storage_read(storage)
+        .may_load()?
+        .unwrap_or_default();
+    history.push(authorization);
+    if history.len() > MAX_AUTHORIZATION_HISTORY {
+        history.drain(..history.len() - MAX_AUTHORIZATION_HISTORY);
+    }
+    authorization_history_storage(storage).save(&history)
+}
+
+pub fn record_aborted_authorization(
+    storage: &mut dyn Storage,
+    aborted: AbortedAuthorization,
+) -> StdResult<()> {
+    let mut history = aborted_authorizations_storage_read(storage)
+        .may_load()?
+        .unwrap_or_default();
+    history.push(aborted);
+    if history.len() > MAX_ABORTED_AUTHORIZATIONS {
+        history.drain(..history.len() - MAX_ABORTED_AUTHORIZATIONS);
+    }
+    aborted_authorizations_storage(storage).save(&history)
+}
+
+/// A standing cap on how much of a denom may be withdrawn to a destination within a rolling
+/// window.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct WithdrawalAllowance {
+    pub to: Addr,
+    /// Empty for allowances set before they were tied to a denom, which apply to the sole like
+    /// capital denom.
+    #[serde(default)]
+    pub denom: String,
+    #[serde(deserialize_with = "uint128_or_number")]
+    pub amount: Uint128,
+    pub period_seconds: u64,
+    #[serde(deserialize_with = "uint128_or_number")]
+    pub consumed: Uint128,
+    pub window_start: Timestamp,
+}
+
+impl WithdrawalAllowance {
+    /// Whether the allowance caps withdrawals of `denom` to `to`.
+    pub fn applies_to(&self, state: &State, to: &Addr, denom: &str) -> bool {
+        &self.to == to
+            && match self.denom.as_str() {
+                "" => matches!(state.resolve_capital_denom(None), Ok(sole) if sole == denom),
+                own => own == denom,
+            }
+    }
+
+    pub fn remaining(&self, now: &Timestamp) -> Uint128 {
+        if self.window_expired(now) {
+            self.amount
+        } else {
+            self.amount.saturating_sub(self.consumed)
+        }
+    }
+
+    fn window_expired(&self, now: &Timestamp) -> bool {
+        now.seconds() >= self.window_start.seconds() + self.period_seconds
+    }
+
+    pub fn consume(&mut self, amount: Uint128, now: &Timestamp) -> Result<(), ContractError> {
+        let remaining = self.remaining(now);
+        if amount > remaining {
+            return Err(ContractError::WithdrawalAllowanceExceeded {
+                to: self.to.to_string(),
+                remaining,
+            });
+        }
+        if self.window_expired(now) {
+            self.window_start = *now;
+            self.consumed = Uint128::zero();
+        }
+        self.consumed += amount;
+        Ok(())
+    }
+}
+
+pub fn withdrawal_allowances_storage(
+    storage: &mut dyn Storage,
+) -> Singleton<'_, Vec<WithdrawalAllowance>> {
+    singleton(storage, WITHDRAWAL_ALLOWANCES_KEY)
+}
+
+pub fn withdrawal_allowances_storage_read(
+    storage: &dyn Storage,
+) -> ReadonlySingleton<'_, Vec<WithdrawalAllowance>> {
+    singleton_read(storage, WITHDRAWAL_ALLOWANCES_KEY)
+}
+
+/// Consumes `amount` from the allowance for its denom to `to`, if there is one.
+pub fn consume_withdrawal_allowance(
+    storage: &mut dyn Storage,
+    state: &State,
+    to: &Addr,
+    amount: &Coin,
+    now: &Timestamp,
+) -> Result<(), ContractError> {
+    let mut allowances = withdrawal_allowances_storage_read(storage)
+        .may_load()?
+        .unwrap_or_default();
+    if let Some(allowance) = allowances
+        .iter_mut()
+        .find(|allowance| allowance.applies_to(state, to, &amount.denom))
+    {
+        allowance.consume(amount.amount, now)?;
+        withdrawal_allowances_storage(storage).save(&allowances)?;
+    }
+    Ok(())
+}
+
+/// An admin's one-time approval of a withdrawal of exactly `amount` of `denom` to `to`, for
+/// withdrawals above the denom's approval threshold.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct WithdrawalApproval {
+    pub to: Addr,
+    pub amount: Uint128,
+    pub denom: String,
+    pub approved_by: Addr,
+    pub expires_at: Timestamp,
+}
+
+impl WithdrawalApproval {
+    pub fn is_expired(&self, now: &Timestamp) -> bool {
+        now >= &self.expires_at
+    }
+}
+
+pub fn withdrawal_approvals_storage(
+    storage: &mut dyn Storage,
+) -> Singleton<'_, Vec<WithdrawalApproval>> {
+    singleton(storage, WITHDRAWAL_APPROVALS_KEY)
+}
+
+pub fn withdrawal_approvals_storage_read(
+    storage: &dyn Storage,
+) -> ReadonlySingleton<'_, Vec<WithdrawalApproval>> {
+    singleton_read(storage, WITHDRAWAL_APPROVALS_KEY)
+}
+
+/// Loads the withdrawal approvals, dropping any that have expired.
+pub fn unexpired_withdrawal_approvals(
+    storage: &dyn Storage,
+    now: &Timestamp,
+) -> StdResult<Vec<WithdrawalApproval>> {
+    let mut approvals = withdrawal_approvals_storage_read(storage)
+        .may_load()?
+        .unwrap_or_default();
+    approvals.retain(|approval| !approval.is_expired(now));
+    Ok(approvals)
+}
+
+/// Uses up the approval for a withdrawal of `amount` to `to`, returning the admin who granted
+/// it. Expired approvals are pruned along the way.
+pub fn consume_withdrawal_approval(
+    storage: &mut dyn Storage,
+    to: &Addr,
+    amount: &Coin,
+    now: &Timestamp,
+) -> Result<Addr, ContractError> {
+    let (mut approvals, expired): (Vec<WithdrawalApproval>, Vec<WithdrawalApproval>) =
+        withdrawal_approvals_storage_read(storage)
+            .may_load()?
+            .unwrap_or_default()
+            .into_iter()
+            .partition(|approval| !approval.is_expired(now));
+    let matches = |approval: &WithdrawalApproval| {
+        &approval.to == to && approval.amount == amount.amount && approval.denom == amount.denom
+    };
+    match approvals.iter().position(matches) {
+        Some(index) => {
+            let approval = approvals.remove(index);
+            withdrawal_approvals_storage(storage).save(&approvals)?;
+            Ok(approval.approved_by)
+        }
+        None => Err(match expired.into_iter().find(matches) {
+            Some(approval) => ContractError::WithdrawalApprovalExpired {
+                to: to.to_string(),
+                amount: amount.clone(),
+                expires_at: approval.expires_at,
+            },
+            None => ContractError::WithdrawalApprovalRequired {
+                to: to.to_string(),
+                amount: amount.clone(),
+            },
+        }),
+    }
+}
+
+/// Lifetime counts of successful operations. They only ever increase.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Default)]
+pub struct OperationStats {
+    pub authorizations: u64,
+    pub cancellations: u64,
+    pub completions: u64,
+    pub withdrawals: u64,
+    pub last_activity: Option<Timestamp>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Operation {
+    Authorization,
+    Cancellation,
+    Completion,
+    Withdrawal,
+}
+
+pub fn operation_stats_storage(storage: &mut dyn Storage) -> Singleton<'_, OperationStats> {
+    singleton(storage, OPERATION_STATS_KEY)
+}
+
+pub fn operation_stats_storage_read(
+    storage: &dyn Storage,
+) -> ReadonlySingleton<'_, OperationStats> {
+    singleton_read(storage, OPERATION_STATS_KEY)
+}
+
+pub fn record_operation(
+    storage: &mut dyn Storage,
+    operation: Operation,
+    now: &Timestamp,
+) -> StdResult<()> {
+    let mut stats = operation_stats_storage_read(storage)
+        .may_load()?
+        .unwrap_or_default();
+    let counter = match operation {
+        Operation::Authorization => &mut stats.authorizations,
+        Operation::Cancellation => &mut stats.cancellations,
+        Operation::Completion => &mut stats.completions,
+        Operation::Withdrawal => &mut stats.withdrawals,
+    };
+    *counter += 1;
+    stats.last_activity = Some(*now);
+    operation_stats_storage(storage).save(&stats)
+}
+
+/// Capital sent to the subscription with an explicit deposit, as opposed to a bare bank send.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Deposit {
+    pub id: u64,
+    pub sender: Addr,
+    pub coins: Vec<Coin>,
+    pub memo: Option<String>,
+    pub time: Timestamp,
+}
+
+/// Unlike the other lists, deposits grow without bound, so each is stored under its big-endian
+/// id rather than in a single vector.
+pub fn deposits_storage(storage: &mut dyn Storage) -> Bucket<'_, Deposit> {
+    bucket(storage, DEPOSITS_KEY)
+}
+
+pub fn deposits_storage_read(storage: &dyn Storage) -> ReadonlyBucket<'_, Deposit> {
+    bucket_read(storage, DEPOSITS_KEY)
+}
+
+pub fn record_deposit(
+    storage: &mut dyn Storage,
+    sender: Addr,
+    coins: Vec<Coin>,
+    memo: Option<String>,
+    time: Timestamp,
+) -> StdResult<Deposit> {
+    let mut sequence = singleton(storage, DEPOSIT_SEQUENCE_KEY);
+    let id = sequence.may_load()?.unwrap_or_default() + 1;
+    sequence.save(&id)?;
+
+    let deposit = Deposit {
+        id,
+        sender,
+        coins,
+        memo,
+        time,
+    };
+    deposits_storage(storage).save(&id.to_be_bytes(), &deposit)?;
+    Ok(deposit)
+}
+
+/// Deposits in id order, starting after `start_after`.
+pub fn load_deposits(
+    storage: &dyn Storage,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<Vec<Deposit>> {
+    let limit = limit
+        .unwrap_or(DEFAULT_DEPOSITS_LIMIT)
+        .min(MAX_DEPOSITS_LIMIT) as usize;
+    let start = match start_after {
+        Some(id) => match id.checked_add(1) {
+            Some(next) => Some(next.to_be_bytes()),
+            None => return Ok(vec![]),
+        },
+        None => None,
+    };
+    deposits_storage_read(storage)
+        .range(start.as_ref().map(|key| &key[..]), None, Order::Ascending)
+        .take(limit)
+        .map(|item| item.map(|(_, deposit)| deposit))
+        .collect()
+}
+
+/// Pending and remembered authorizations with the given status, or any status, in id order
+/// starting after `start_after`. Ids only ever grow, so new authorizations never shift a page.
+pub fn load_authorization_history(
+    storage: &dyn Storage,
+    status: Option<AuthorizationStatus>,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<Vec<AssetExchangeAuthorization>> {
+    let limit = limit
+        .unwrap_or(DEFAULT_AUTHORIZATION_HISTORY_LIMIT)
+        .min(MAX_AUTHORIZATION_HISTORY_LIMIT) as usize;
+    let mut authorizations = asset_exchange_authorization_storage_read(storage)
+        .may_load()?
+        .unwrap_or_default();
+    authorizations.extend(
+        authorization_history_storage_read(storage)
+            .may_load()?
+            .unwrap_or_default(),
+    );
+    authorizations.sort_by_key(|authorization| authorization.id);
+    Ok(authorizations
+        .into_iter()
+        .filter(|authorization| !matches!(start_after, Some(id) if authorization.id <= id))
+        .filter(|authorization| match status {
+            Some(status) => authorization.status == status,
+            None => true,
+        })
+        .take(limit)
+        .collect())
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct MigrationRecord {
+    pub from_version: String,
+    pub to_version: String,
+    pub height: u64,
+    pub time: Timestamp,
+    /// sha256 of the JSON encoded migrate message
+    pub msg_hash: Binary,
+}
+
+pub fn migration_history_storage(storage: &mut dyn Storage) -> Singleton<'_, Vec<MigrationRecord>> {
+    singleton(storage, MIGRATION_HISTORY_KEY)
+}
+
+pub fn migration_history_storage_read(
+    storage: &dyn Storage,
+) -> ReadonlySingleton<'_, Vec<MigrationRecord>> {
+    singleton_read(storage, MIGRATION_HISTORY_KEY)
+}
+
+pub fn join_addresses(addresses: &[Addr]) -> String {
+    addresses
+        .iter()
+        .map(Addr::as_str)
+        .collect::<Vec<&str>>()
+        .join(",")
+}
+
+pub fn join_coins(coins: &[Coin]) -> String {
+    coins
+        .iter()
+        .map(Coin::to_string)
+        .collect::<Vec<String>>()
+        .join(",")
+}
+
+/// A sensitive admin action that needs `admin_threshold` distinct admin approvals.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum AdminAction {
+    Recover {
+        lp: Addr,
+    },
+    UpdateRaise {
+        raise: Addr,
+    },
+    RegisterRaise {
+        raise: Addr,
+    },
+    UpdateRequiredCapitalAttribute {
+        required_capital_attributes: Vec<CapitalDenomRequirement>,
+    },
+    UpdateDefaultAuthorizationTtl {
+        default_authorization_ttl_seconds: Option<u64>,
+    },
+    UpdateCommitmentDenom {
+        denom: String,
+    },
+    UpdateInvestmentDenom {
+        denom: String,
+        replaces: Option<String>,
+    },
+}
+
+impl AdminAction {
+    pub fn name(&self) -> &'static str {
+        match self {
+            AdminAction::Recover { .. } => "recover",
+            AdminAction::UpdateRaise { .. } => "update_raise",
+            AdminAction::RegisterRaise { .. } => "register_raise",
+            AdminAction::UpdateRequiredCapitalAttribute { .. } => {
+                "update_required_capital_attribute"
+            }
+            AdminAction::UpdateDefaultAuthorizationTtl { .. } => "update_default_authorization_ttl",
+            AdminAction::UpdateCommitmentDenom { .. } => "update_commitment_denom",
+            AdminAction::UpdateInvestmentDenom { .. } => "update_investment_denom",
+        }
+    }
+
+    /// Denom updates would leave a pending authorization moving the old marker, so they wait
+    /// until no authorization with an affected leg is pending.
+    pub fn check_pending_authorizations(
+        &self,
+        authorizations: &[AssetExchangeAuthorization],
+    ) -> Result<(), ContractError> {
+        let (leg, affected): (&str, fn(&AssetExchange) -> bool) = match self {
+            AdminAction::UpdateCommitmentDenom { .. } => ("a commitment", |exchange| {
+                exchange.commitment_in_shares.is_some()
+            }),
+            AdminAction::UpdateInvestmentDenom { .. } => {
+                ("an investment", |exchange| exchange.investment.is_some())
+            }
+            _ => return Ok(()),
+        };
+        match authorizations
+            .iter()
+            .find(|authorization| authorization.exchanges.iter().any(affected))
+        {
+            Some(authorization) => Err(ContractError::from(format!(
+                "authorization {} with {} leg is still pending",
+                authorization.id, leg
+            ))),
+            None => Ok(()),
+        }
+    }
+
+    /// Applies the action to the state, validating it against the state as it is now.
+    pub fn apply(self, state: &mut State) -> Result<(), ContractError> {
+        match self {
+            AdminAction::Recover { lp } => {
+                if state.require_distinct_roles && state.is_admin(&lp) {
+                    return Err(ContractError::from(
+                        "admin and lp must be distinct addresses",
+                    ));
+                }
+                state.lp = lp;
+            }
+            AdminAction::UpdateRaise { raise } => {
+                if state.raises[1..].contains(&raise) {
+                    return Err(ContractError::from(format!(
+                        "{} is already registered as an additional raise",
+                        raise
+                    )));
+                }
+                state.raises[0] = raise
+            }
+            AdminAction::RegisterRaise { raise } => {
+                if state.raises.contains(&raise) {
+                    return Err(ContractError::from(format!(
+                        "{} is already a raise of this subscription",
+                        raise
+                    )));
+                }
+                state.raises.push(raise)
+            }
+            AdminAction::UpdateRequiredCapitalAttribute {
+                required_capital_attributes,
+            } => {
+                state.required_capital_attributes = capital_denom_requirements(
+                    &state.like_capital_denoms,
+                    required_capital_attributes,
+                )?
+            }
+            AdminAction::UpdateDefaultAuthorizationTtl {
+                default_authorization_ttl_seconds,
+            } => {
+                if default_authorization_ttl_seconds == Some(0) {
+                    return Err(ContractError::from(
+                        "default authorization ttl must be greater than zero",
+                    ));
+                }
+                state.default_authorization_ttl_seconds = default_authorization_ttl_seconds
+            }
+            AdminAction::UpdateCommitmentDenom { denom } => {
+                if denom == state.commitment_denom {
+                    return Err(ContractError::from(format!(
+                        "{} is already the commitment denom",
+                        denom
+                    )));
+                }
+                state.commitment_denom = denom
+            }
+            AdminAction::UpdateInvestmentDenom { denom, replaces } => {
+                if state.investment_denoms.contains(&denom) {
+                    return Err(ContractError::from(format!(
+                        "{} is already an investment denom",
+                        denom
+                    )));
+                }
+                let index = match (replaces, state.investment_denoms.len()) {
+                    (Some(replaces), _) => state
+                        .investment_denoms
+                        .iter()
+                        .position(|investment_denom| *investment_denom == replaces)
+                        .ok_or_else(|| {
+                            ContractError::from(format!("{} is not an investment denom", replaces))
+                        })?,
+                    (None, 1) => 0,
+                    (None, _) => {
+                        return Err(ContractError::from(
+                            "the investment denom to replace must be given when there are several",
+                        ))
+                    }
+                };
+                state.investment_denoms[index] = denom
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PendingAdminAction {
+    pub action: AdminAction,
+    pub approvals: Vec<Addr>,
+    pub proposed_at: Timestamp,
+}
+
+impl PendingAdminAction {
+    pub fn expired(&self, now: &Timestamp) -> bool {
+        self.proposed_at.plus_seconds(ADMIN_ACTION_EXPIRY_SECONDS) < *now
+    }
+}
+
+pub fn pending_admin_actions_storage(
+    storage: &mut dyn Storage,
+) -> Singleton<'_, Vec<PendingAdminAction>> {
+    singleton(storage, PENDING_ADMIN_ACTIONS_KEY)
+}
+
+pub fn pending_admin_actions_storage_read(
+    storage: &dyn Storage,
+) -> ReadonlySingleton<'_, Vec<PendingAdminAction>> {
+    singleton_read(storage, PENDING_ADMIN_ACTIONS_KEY)
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ScheduledAdminAction {
+    pub id: u64,
+    pub action: AdminAction,
+    pub execute_after: Timestamp,
+}
+
+pub fn scheduled_admin_actions_storage(
+    storage: &mut dyn Storage,
+) -> Singleton<'_, Vec<ScheduledAdminAction>> {
+    singleton(storage, SCHEDULED_ADMIN_ACTIONS_KEY)
+}
+
+pub fn scheduled_admin_actions_storage_read(
+    storage: &dyn Storage,
+) -> ReadonlySingleton<'_, Vec<ScheduledAdminAction>> {
+    singleton_read(storage, SCHEDULED_ADMIN_ACTIONS_KEY)
+}
+
+pub fn next_scheduled_admin_action_id(storage: &mut dyn Storage) -> StdResult<u64> {
+    let mut sequence = singleton(storage, SCHEDULED_ADMIN_ACTION_SEQUENCE_KEY);
+    let id = sequence.may_load()?.unwrap_or_default() + 1;
+    sequence.save(&id)?;
+    Ok(id)
+}
+
 #[cfg(test)]
 pub mod tests {
     use super::*;
+    use crate::mock::ExchangeBuilder;
 
-    impl State {
-        pub fn test_default() -> State {
-            State {
-                admin: Addr::unchecked("admin"),
-                lp: Addr::unchecked("lp"),
-                raise: Addr::unchecked("raise_1"),
-                commitment_denom: String::from("raise_1.commitment"),
-                investment_denom: String::from("raise_1.investment"),
-                capital_denom: String::from("stable_coin"),
-                capital_per_share: 100,
-                required_capital_attribute: None,
-            }
+    #[test]
+    fn resolve_capital_denom() {
+        let mut state = State::test_default();
+        assert_eq!("stable_coin", state.resolve_capital_denom(None).unwrap());
+        assert_eq!(
+            "stable_coin",
+            state
+                .resolve_capital_denom(Some(&String::from("stable_coin")))
+                .unwrap()
+        );
+        assert!(state
+            .resolve_capital_denom(Some(&String::from("other_coin")))
+            .is_err());
+
+        state.like_capital_denoms.push(String::from("other_coin"));
+        assert!(state.resolve_capital_denom(None).is_err());
+        assert_eq!(
+            "other_coin",
+            state
+                .resolve_capital_denom(Some(&String::from("other_coin")))
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn normalize_exchanges() {
+        let state = State::test_default();
+        let normalized = state
+            .normalize_exchanges(&[
+                ExchangeBuilder::new().investment(10).capital(100).build(),
+                ExchangeBuilder::new().commitment(10).build(),
+            ])
+            .unwrap();
+        assert_eq!(
+            vec![
+                ExchangeBuilder::new()
+                    .investment(10)
+                    .investment_denom("raise_1.investment")
+                    .capital(100)
+                    .denom("stable_coin")
+                    .build(),
+                ExchangeBuilder::new().commitment(10).build(),
+            ],
+            normalized
+        );
+        assert!(state
+            .normalize_exchanges(&[ExchangeBuilder::new()
+                .capital(100)
+                .denom("other_coin")
+                .build()])
+            .is_err());
+    }
+
+    #[test]
+    fn idempotency_keys_pruned() {
+        let mut storage = cosmwasm_std::testing::MockStorage::new();
+        for id in 1..=(MAX_IDEMPOTENCY_KEYS as u64 + 1) {
+            record_idempotency_key(&mut storage, format!("key-{}", id), id).unwrap();
         }
 
-        pub fn test_capital_coin() -> State {
-            State {
-                admin: Addr::unchecked("admin"),
-                lp: Addr::unchecked("lp"),
-                raise: Addr::unchecked("raise_1"),
-                commitment_denom: String::from("raise_1.commitment"),
-                investment_denom: String::from("raise_1.investment"),
-                capital_denom: String::from("capital_coin"),
-                capital_per_share: 100,
-                required_capital_attribute: None,
+        let seen = idempotency_keys_storage_read(&storage).load().unwrap();
+        assert_eq!(MAX_IDEMPOTENCY_KEYS, seen.len());
+        assert_eq!(2, seen[0].authorization_id);
+        assert!(check_idempotency_key(&storage, "key-1").is_ok());
+        assert!(check_idempotency_key(&storage, "key-2").is_err());
+    }
+
+    #[test]
+    fn authorization_display() {
+        let mut authorization = AssetExchangeAuthorization {
+            id: 3,
+            raise: None,
+            exchanges: vec![
+                ExchangeBuilder::new()
+                    .commitment(-10)
+                    .capital(-1_000)
+                    .build(),
+                ExchangeBuilder::new().capital(500).build(),
+            ],
+            to: None,
+            memo: None,
+            created_at: None,
+            expires_at: None,
+            memo_hash: None,
+            status: AuthorizationStatus::Pending,
+            resolved_at: None,
+            resolved_by: None,
+        };
+        assert_eq!(
+            "#3 [com:-10 cap:-1000; cap:+500]",
+            authorization.to_string()
+        );
+
+        authorization.to = Some(Addr::unchecked("lp_side_account"));
+        authorization.memo = Some(String::from("q3 call"));
+        assert_eq!(
+            r#"#3 [com:-10 cap:-1000; cap:+500] to:lp_side_account memo:"q3 call""#,
+            authorization.to_string()
+        );
+    }
+
+    #[test]
+    fn withdrawal_allowance_window() {
+        let start = Timestamp::from_seconds(1_000);
+        let mut allowance = WithdrawalAllowance {
+            to: Addr::unchecked("ops"),
+            denom: String::from("stable_coin"),
+            amount: Uint128::new(50_000),
+            period_seconds: 100,
+            consumed: Uint128::zero(),
+            window_start: start,
+        };
+
+        allowance.consume(Uint128::new(30_000), &start).unwrap();
+        allowance
+            .consume(Uint128::new(20_000), &start.plus_seconds(99))
+            .unwrap();
+        assert_eq!(
+            Uint128::new(0),
+            allowance.remaining(&start.plus_seconds(99))
+        );
+        match allowance
+            .consume(Uint128::new(1), &start.plus_seconds(99))
+            .unwrap_err()
+        {
+            ContractError::WithdrawalAllowanceExceeded { remaining, .. } => {
+                assert_eq!(Uint128::new(0), remaining)
             }
+            err => panic!("unexpected error: {}", err),
         }
 
-        pub fn test_restricted_capital_coin() -> State {
-            State {
-                admin: Addr::unchecked("admin"),
-                lp: Addr::unchecked("lp"),
-                raise: Addr::unchecked("raise_1"),
-                commitment_denom: String::from("raise_1.commitment"),
-                investment_denom: String::from("raise_1.investment"),
-                capital_denom: String::from("restricted_capital_coin"),
-                capital_per_share: 100,
-                required_capital_attribute: Some(String::from("capital.test")),
-            }
+        assert_eq!(
+            Uint128::new(50_000),
+            allowance.remaining(&start.plus_seconds(100))
+        );
+        allowance
+            .consume(Uint128::new(10_000), &start.plus_seconds(150))
+            .unwrap();
+        assert_eq!(start.plus_seconds(150), allowance.window_start);
+        assert_eq!(
+            Uint128::new(40_000),
+            allowance.remaining(&start.plus_seconds(249))
+        );
+    }
+
+    #[test]
+    fn withdrawal_allowance_denom() {
+        let mut state = State::test_default();
+        let ops = Addr::unchecked("ops");
+        let mut allowance: WithdrawalAllowance = cosmwasm_std::from_slice(
+            br#"{"to":"ops","amount":"500","period_seconds":100,"consumed":"0","window_start":"0"}"#,
+        )
+        .unwrap();
+
+        // stored before allowances had a denom, so it caps the sole like capital denom
+        assert_eq!("", allowance.denom);
+        assert!(allowance.applies_to(&state, &ops, "stable_coin"));
+        assert!(!allowance.applies_to(&state, &Addr::unchecked("treasury"), "stable_coin"));
+        state.like_capital_denoms.push(String::from("other_coin"));
+        assert!(!allowance.applies_to(&state, &ops, "stable_coin"));
+
+        allowance.denom = String::from("other_coin");
+        assert!(allowance.applies_to(&state, &ops, "other_coin"));
+        assert!(!allowance.applies_to(&state, &ops, "stable_coin"));
+    }
+
+    #[test]
+    fn pause_source_matching() {
+        let mut state = State::test_default();
+        state.check_not_paused().unwrap();
+
+        state.set_paused(true, PauseSource::Admin).unwrap();
+        assert!(state.check_not_paused().is_err());
+        state.set_paused(false, PauseSource::Admin).unwrap();
+        state.check_not_paused().unwrap();
+
+        state.set_paused(true, PauseSource::Governance).unwrap();
+        assert!(state.set_paused(false, PauseSource::Admin).is_err());
+        assert!(state.set_paused(true, PauseSource::Admin).is_err());
+        assert_eq!(Some(PauseSource::Governance), state.paused);
+        state.set_paused(false, PauseSource::Governance).unwrap();
+        assert_eq!(None, state.paused);
+
+        // governance takes over an admin pause, after which only governance can unpause
+        state.set_paused(true, PauseSource::Admin).unwrap();
+        assert!(state.set_paused(false, PauseSource::Governance).is_err());
+        state.set_paused(true, PauseSource::Governance).unwrap();
+        assert!(state.set_paused(false, PauseSource::Admin).is_err());
+    }
+
+    #[test]
+    fn resolve_investment_denom() {
+        let mut state = State::test_default();
+        state
+            .investment_denoms
+            .push(String::from("raise_1.investment_b"));
+        assert_eq!(
+            "raise_1.investment",
+            state.resolve_investment_denom(None).unwrap()
+        );
+        assert_eq!(
+            "raise_1.investment_b",
+            state
+                .resolve_investment_denom(Some(&String::from("raise_1.investment_b")))
+                .unwrap()
+        );
+        assert!(state
+            .resolve_investment_denom(Some(&String::from("other_investment")))
+            .is_err());
+    }
+
+    #[test]
+    fn validate_admins() {
+        let mut state = State::test_default();
+        assert!(state.validate_admins().is_ok());
+
+        state.admin_threshold = 2;
+        assert!(state.validate_admins().is_err());
+        state.admins.push(Addr::unchecked("admin_2"));
+        assert!(state.validate_admins().is_ok());
+
+        state.admin_threshold = 0;
+        assert!(state.validate_admins().is_err());
+
+        state.admin_threshold = 1;
+        state.admins.push(Addr::unchecked("admin"));
+        assert!(state.validate_admins().is_err());
+
+        state.admins = vec![Addr::unchecked("admin"), Addr::unchecked("lp")];
+        assert!(state.validate_admins().is_err());
+        state.require_distinct_roles = false;
+        assert!(state.validate_admins().is_ok());
+
+        state.admins = vec![];
+        assert!(state.validate_admins().is_err());
+    }
+
+    #[test]
+    fn partial_remainder() {
+        let state = State::test_default();
+        let authorized = ExchangeBuilder::new()
+            .investment(-1_000)
+            .commitment(-10)
+            .capital(1_000)
+            .due(1_700_000_000)
+            .build();
+
+        assert_eq!(
+            ExchangeBuilder::new()
+                .investment(-400)
+                .commitment(-4)
+                .capital(400)
+                .due(1_700_000_000)
+                .build(),
+            state
+                .partial_remainder(
+                    &authorized,
+                    &ExchangeBuilder::new()
+                        .investment(-600)
+                        .commitment(-6)
+                        .capital(600)
+                        .due(1_700_000_000)
+                        .build()
+                )
+                .unwrap()
+        );
+
+        // every authorized amount must be split, and nothing else
+        for partial in [
+            ExchangeBuilder::new()
+                .commitment(-6)
+                .capital(600)
+                .due(1_700_000_000)
+                .build(),
+            ExchangeBuilder::new()
+                .investment(-600)
+                .commitment(-6)
+                .capital(600)
+                .build(),
+            ExchangeBuilder::new()
+                .investment(-600)
+                .commitment(-6)
+                .capital(600)
+                .denom("stable_coin")
+                .due(1_700_000_000)
+                .build(),
+            ExchangeBuilder::new()
+                .investment(-600)
+                .commitment(-6)
+                .capital(600)
+                .due(1_700_000_000)
+                .to("bad_actor")
+                .build(),
+        ] {
+            assert!(state.partial_remainder(&authorized, &partial).is_err());
         }
     }
 
+    #[test]
+    fn overflowing_sums() {
+        let state = State::test_default();
+        let huge = AssetExchange {
+            commitment_in_shares: Some(Int128::new(i128::MAX)),
+            capital: Some(Int128::new(i128::MAX)),
+            ..ExchangeBuilder::new().build()
+        };
+        let exchanges = vec![huge.clone(), huge];
+
+        assert!(matches!(
+            state.net_capital(&exchanges),
+            Err(ContractError::Std(StdError::Overflow { .. }))
+        ));
+        assert!(matches!(
+            state.check_commitment_ceiling(&exchanges),
+            Err(ContractError::Std(StdError::Overflow { .. }))
+        ));
+    }
+
+    #[test]
+    fn withdrawal_approval_thresholds() {
+        let mut state = State::test_default();
+        state.withdrawal_approval_thresholds = vec![Coin::new(10_000, "stable_coin")];
+        state.validate_withdrawal_approval_thresholds().unwrap();
+        assert_eq!(
+            Some(Uint128::new(10_000)),
+            state.withdrawal_approval_threshold("stable_coin")
+        );
+        assert_eq!(None, state.withdrawal_approval_threshold("other_coin"));
+
+        state
+            .withdrawal_approval_thresholds
+            .push(Coin::new(20_000, "stable_coin"));
+        assert!(state
+            .validate_withdrawal_approval_thresholds()
+            .unwrap_err()
+            .to_string()
+            .contains("duplicate withdrawal approval threshold for stable_coin"));
+
+        state.withdrawal_approval_thresholds = vec![Coin::new(10_000, "other_coin")];
+        assert!(matches!(
+            state.validate_withdrawal_approval_thresholds(),
+            Err(ContractError::UnsupportedCapitalDenom { .. })
+        ));
+    }
+
     #[test]
     fn not_evenly_divisble() {
         let state = State::test_default();
 
-        assert_eq!(false, state.not_evenly_divisble(100));
-        assert_eq!(true, state.not_evenly_divisble(101));
-        assert_eq!(false, state.not_evenly_divisble(1_000));
-        assert_eq!(true, state.not_evenly_divisble(1_001));
+        assert!(!state.not_evenly_divisble("stable_coin", 100));
+        assert!(state.not_evenly_divisble("stable_coin", 101));
+        assert!(!state.not_evenly_divisble("stable_coin", 1_000));
+        assert!(state.not_evenly_divisble("stable_coin", 1_001));
+    }
+
+    fn two_rate_state() -> State {
+        let mut state = State::test_default();
+        state.like_capital_denoms.push(String::from("wei_coin"));
+        state.capital_per_share_overrides = vec![(String::from("wei_coin"), 100_000_000_000_000)];
+        state
+    }
+
+    #[test]
+    fn capital_to_shares_per_denom() {
+        let state = two_rate_state();
+
+        assert_eq!(10, state.capital_to_shares("stable_coin", 1_000));
+        assert_eq!(
+            10,
+            state.capital_to_shares("wei_coin", 1_000_000_000_000_000)
+        );
+        assert!(!state.not_evenly_divisble("wei_coin", 200_000_000_000_000));
+        assert!(state.not_evenly_divisble("wei_coin", 1_000));
+    }
+
+    #[test]
+    fn validate_capital_per_share() {
+        let mut state = two_rate_state();
+        state.validate_capital_per_share().unwrap();
+
+        state
+            .capital_per_share_overrides
+            .push((String::from("wei_coin"), 1));
+        assert!(state.validate_capital_per_share().is_err());
+
+        state.capital_per_share_overrides = vec![(String::from("other_coin"), 1)];
+        assert!(state.validate_capital_per_share().is_err());
+
+        state.capital_per_share_overrides = vec![(String::from("wei_coin"), 0)];
+        assert!(state.validate_capital_per_share().is_err());
     }
 }
@@ -0,0 +1,214 @@
+//! Storage access and query budgets for the main execute flows. The budgets are deliberately
+//! tight; when a change moves one of these numbers, update the budget in the same PR so reviewers
+//! can see the cost of the change.
+
+use cosmwasm_std::testing::{mock_env, mock_info};
+use cosmwasm_std::{Addr, DepsMut, QuerierWrapper, Uint128};
+
+use crate::contract::execute;
+use crate::mock::{
+    counting_deps_mut, default_deps, deps_with_balances, load_attributes,
+    restricted_capital_coin_deps, CountingQuerier, CountingStorage, ExchangeBuilder, StorageCounts,
+};
+use crate::msg::CapitalDenomRequirement;
+use crate::msg::{AssetExchange, ExecuteMsg};
+use crate::state::{
+    asset_exchange_authorization_storage, AssetExchangeAuthorization, AuthorizationStatus,
+};
+
+const PENDING_AUTHORIZATIONS: u64 = 50;
+
+/// Authorizing next to 50 pending authorizations: state, the per-block rate, authorizations, the
+/// id sequence and the operation stats are read and written back, state only to stamp the lp's
+/// activity.
+const AUTHORIZE_BUDGET: Budget = Budget {
+    reads: 5,
+    writes: 5,
+    bytes_read: 9_500,
+    bytes_written: 10_200,
+};
+
+/// Completing the last of 50 pending authorizations: state, authorizations and the operation
+/// stats are each read once and written back, state a second time to stamp the lp's activity.
+/// The completed authorization is appended to the (here empty) authorization history.
+const COMPLETE_BUDGET: Budget = Budget {
+    reads: 4,
+    writes: 5,
+    bytes_read: 9_500,
+    bytes_written: 10_500,
+};
+
+/// Withdrawing unrestricted capital to a destination without an allowance reads state and the
+/// allowances, updates the operation stats, and stamps the lp's activity on state.
+const WITHDRAW_BUDGET: Budget = Budget {
+    reads: 3,
+    writes: 2,
+    bytes_read: 800,
+    bytes_written: 900,
+};
+
+struct Budget {
+    reads: u64,
+    writes: u64,
+    bytes_read: u64,
+    bytes_written: u64,
+}
+
+fn assert_within(budget: Budget, counts: StorageCounts) {
+    assert!(counts.reads <= budget.reads, "{:?}", counts);
+    assert!(
+        counts.writes + counts.removes <= budget.writes,
+        "{:?}",
+        counts
+    );
+    assert!(counts.bytes_read <= budget.bytes_read, "{:?}", counts);
+    assert!(counts.bytes_written <= budget.bytes_written, "{:?}", counts);
+}
+
+fn exchange(i: u64) -> Vec<AssetExchange> {
+    vec![ExchangeBuilder::new()
+        .investment(1_000 + i as i64)
+        .commitment(-10)
+        .capital(-1_000)
+        .due(1_700_000_000)
+        .build()]
+}
+
+fn storage_with_pending_authorizations() -> CountingStorage {
+    let mut deps = default_deps(None);
+    asset_exchange_authorization_storage(&mut deps.storage)
+        .save(
+            &(1..=PENDING_AUTHORIZATIONS)
+                .map(|id| AssetExchangeAuthorization {
+                    id,
+                    raise: None,
+                    exchanges: exchange(id),
+                    to: Some(Addr::unchecked("lp_side_account")),
+                    memo: Some(format!("capital call {}", id)),
+                    created_at: Some(mock_env().block.time),
+                    expires_at: None,
+                    memo_hash: None,
+                    status: AuthorizationStatus::Pending,
+                    resolved_at: None,
+                    resolved_by: None,
+                })
+                .collect::<Vec<_>>(),
+        )
+        .unwrap();
+    CountingStorage::new(deps.storage)
+}
+
+#[test]
+fn authorize_budget() {
+    let deps = default_deps(None);
+    let mut storage = storage_with_pending_authorizations();
+
+    execute(
+        counting_deps_mut(&deps, &mut storage),
+        mock_env(),
+        mock_info("lp", &[]),
+        ExecuteMsg::AuthorizeAssetExchange {
+            exchanges: exchange(0),
+            to: Some(Addr::unchecked("lp_side_account")),
+            memo: Some(String::from("capital call 0")),
+            idempotency_key: None,
+            expires_at: None,
+            memo_hash: None,
+            raise: None,
+        },
+    )
+    .unwrap();
+
+    assert_within(AUTHORIZE_BUDGET, storage.counts());
+}
+
+#[test]
+fn complete_budget() {
+    let deps = default_deps(None);
+    let mut storage = storage_with_pending_authorizations();
+
+    execute(
+        counting_deps_mut(&deps, &mut storage),
+        mock_env(),
+        mock_info("lp", &[]),
+        ExecuteMsg::CompleteAssetExchange {
+            exchanges: exchange(PENDING_AUTHORIZATIONS),
+            to: Some(Addr::unchecked("lp_side_account")),
+            memo: Some(format!("capital call {}", PENDING_AUTHORIZATIONS)),
+            override_available_date: false,
+            raise: None,
+        },
+    )
+    .unwrap();
+
+    assert_within(COMPLETE_BUDGET, storage.counts());
+}
+
+#[test]
+fn withdraw_budget() {
+    let mut deps = deps_with_balances(&[("stable_coin", 10_000)]);
+    let mut storage = CountingStorage::new(std::mem::take(&mut deps.storage));
+
+    execute(
+        counting_deps_mut(&deps, &mut storage),
+        mock_env(),
+        mock_info("lp", &[]),
+        ExecuteMsg::IssueWithdrawal {
+            to: Some(Addr::unchecked("lp_side_account")),
+            to_name: None,
+            amount: Uint128::new(10_000),
+            capital_denom: None,
+        },
+    )
+    .unwrap();
+
+    assert_within(WITHDRAW_BUDGET, storage.counts());
+}
+
+/// A completion paying five attribute-gated denoms to one address checks its attributes once.
+#[test]
+fn complete_attribute_queries() {
+    const DENOMS: [&str; 5] = [
+        "restricted_a",
+        "restricted_b",
+        "restricted_c",
+        "restricted_d",
+        "restricted_e",
+    ];
+    let mut deps = restricted_capital_coin_deps(Some(|state| {
+        state.like_capital_denoms = DENOMS.iter().map(|denom| denom.to_string()).collect();
+        state.required_capital_attributes = DENOMS
+            .iter()
+            .map(|denom| CapitalDenomRequirement {
+                capital_denom: denom.to_string(),
+                required_attributes: vec![String::from("capital.test")],
+                enforce_attribute_on_bank_send: true,
+            })
+            .collect();
+    }));
+    load_attributes(&mut deps.querier, &[("lp_side_account", "capital.test")]);
+    let querier = CountingQuerier::new(&deps.querier);
+
+    execute(
+        DepsMut {
+            storage: &mut deps.storage,
+            api: &deps.api,
+            querier: QuerierWrapper::new(&querier),
+        },
+        mock_env(),
+        mock_info("lp", &[]),
+        ExecuteMsg::CompleteAssetExchange {
+            exchanges: DENOMS
+                .iter()
+                .map(|denom| ExchangeBuilder::new().capital(1_000).denom(denom).build())
+                .collect(),
+            to: Some(Addr::unchecked("lp_side_account")),
+            memo: None,
+            override_available_date: false,
+            raise: None,
+        },
+    )
+    .unwrap();
+
+    assert_eq!(1, querier.attribute_queries());
+}
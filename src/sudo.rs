@@ -0,0 +1,258 @@
+use crate::contract::ContractResponse;
+use crate::msg::SudoMsg;
+use crate::state::join_addresses;
+use crate::state::pending_admin_actions_storage;
+use crate::state::scheduled_admin_actions_storage;
+use crate::state::state_storage;
+use crate::state::PauseSource;
+use cosmwasm_std::entry_point;
+use cosmwasm_std::DepsMut;
+use cosmwasm_std::Env;
+use cosmwasm_std::Response;
+use provwasm_std::ProvenanceQuery;
+
+/// Only reachable by the chain itself, e.g. through a governance proposal, so no sender checks
+/// are made here.
+#[entry_point]
+pub fn sudo(deps: DepsMut<ProvenanceQuery>, _env: Env, msg: SudoMsg) -> ContractResponse {
+    match msg {
+        SudoMsg::GovRecover { new_admin, new_lp } => {
+            let mut state = state_storage(deps.storage).load()?;
+            let old_admins = join_addresses(&state.admins);
+            let old_lp = state.lp.clone();
+
+            state.admins = vec![deps.api.addr_validate(new_admin.as_str())?];
+            state.admin_threshold = 1;
+            if let Some(new_lp) = new_lp {
+                state.lp = deps.api.addr_validate(new_lp.as_str())?;
+            }
+            state.validate_admins()?;
+            state_storage(deps.storage).save(&state)?;
+
+            // approvals collected from the replaced admins no longer apply, nor do actions they
+            // already scheduled
+            pending_admin_actions_storage(deps.storage).save(&vec![])?;
+            scheduled_admin_actions_storage(deps.storage).save(&vec![])?;
+
+            Ok(Response::new()
+                .add_attribute("action", "gov_recover")
+                .add_attribute("old_admin", old_admins)
+                .add_attribute("new_admin", join_addresses(&state.admins))
+                .add_attribute("old_lp", old_lp)
+                .add_attribute("new_lp", state.lp))
+        }
+        SudoMsg::SetPaused { paused } => {
+            let mut state = state_storage(deps.storage).load()?;
+
+            state.set_paused(paused, PauseSource::Governance)?;
+            state_storage(deps.storage).save(&state)?;
+
+            Ok(Response::new()
+                .add_attribute("action", "set_paused")
+                .add_attribute("paused", paused.to_string())
+                .add_attribute("pause_source", PauseSource::Governance.name()))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::contract::execute;
+    use crate::mock::{assert_attr, default_deps};
+    use crate::msg::ExecuteMsg;
+    use crate::state::{pending_admin_actions_storage_read, state_storage_read};
+    use crate::state::{AdminAction, PendingAdminAction};
+    use cosmwasm_std::testing::{mock_env, mock_info};
+    use cosmwasm_std::Addr;
+
+    #[test]
+    fn gov_recover() {
+        let mut deps = default_deps(Some(|state| {
+            state.admins = vec![Addr::unchecked("admin_1"), Addr::unchecked("admin_2")];
+            state.admin_threshold = 2;
+        }));
+        pending_admin_actions_storage(&mut deps.storage)
+            .save(&vec![PendingAdminAction {
+                action: AdminAction::Recover {
+                    lp: Addr::unchecked("lp_2"),
+                },
+                approvals: vec![Addr::unchecked("admin_1")],
+                proposed_at: mock_env().block.time,
+            }])
+            .unwrap();
+
+        let res = sudo(
+            deps.as_mut(),
+            mock_env(),
+            SudoMsg::GovRecover {
+                new_admin: Addr::unchecked("gov_admin"),
+                new_lp: Some(Addr::unchecked("gov_lp")),
+            },
+        )
+        .unwrap();
+
+        let state = state_storage_read(&deps.storage).load().unwrap();
+        assert_eq!(vec![Addr::unchecked("gov_admin")], state.admins);
+        assert_eq!(1, state.admin_threshold);
+        assert_eq!("gov_lp", state.lp);
+        assert!(pending_admin_actions_storage_read(&deps.storage)
+            .load()
+            .unwrap()
+            .is_empty());
+
+        assert_attr(&res, "action", "gov_recover");
+        assert_attr(&res, "old_admin", "admin_1,admin_2");
+        assert_attr(&res, "new_admin", "gov_admin");
+        assert_attr(&res, "old_lp", "lp");
+        assert_attr(&res, "new_lp", "gov_lp");
+    }
+
+    #[test]
+    fn gov_recover_drops_scheduled_admin_actions() {
+        let mut deps = default_deps(Some(|state| {
+            state.admin_action_delay_seconds = 86_400;
+        }));
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("admin", &[]),
+            ExecuteMsg::Recover {
+                lp: Addr::unchecked("lp_2"),
+            },
+        )
+        .unwrap();
+        assert_attr(&res, "action", "schedule_recover");
+
+        sudo(
+            deps.as_mut(),
+            mock_env(),
+            SudoMsg::GovRecover {
+                new_admin: Addr::unchecked("gov_admin"),
+                new_lp: None,
+            },
+        )
+        .unwrap();
+
+        let mut later = mock_env();
+        later.block.time = later.block.time.plus_seconds(86_401);
+        let res = execute(
+            deps.as_mut(),
+            later,
+            mock_info("gov_admin", &[]),
+            ExecuteMsg::ExecuteScheduledAdminAction { id: 1 },
+        );
+        assert!(res
+            .unwrap_err()
+            .to_string()
+            .contains("no scheduled admin action with id 1"));
+        assert_eq!("lp", state_storage_read(&deps.storage).load().unwrap().lp);
+    }
+
+    #[test]
+    fn gov_recover_admin_only() {
+        let mut deps = default_deps(None);
+
+        let res = sudo(
+            deps.as_mut(),
+            mock_env(),
+            SudoMsg::GovRecover {
+                new_admin: Addr::unchecked("gov_admin"),
+                new_lp: None,
+            },
+        )
+        .unwrap();
+
+        let state = state_storage_read(&deps.storage).load().unwrap();
+        assert_eq!(vec![Addr::unchecked("gov_admin")], state.admins);
+        assert_eq!("lp", state.lp);
+        assert_attr(&res, "new_lp", "lp");
+    }
+
+    #[test]
+    fn governance_pause() {
+        let mut deps = default_deps(None);
+
+        let res = sudo(
+            deps.as_mut(),
+            mock_env(),
+            SudoMsg::SetPaused { paused: true },
+        )
+        .unwrap();
+        assert_attr(&res, "paused", "true");
+        assert_attr(&res, "pause_source", "governance");
+        assert_eq!(
+            Some(PauseSource::Governance),
+            state_storage_read(&deps.storage).load().unwrap().paused
+        );
+
+        // the admin cannot lift a governance pause
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("admin", &[]),
+            ExecuteMsg::SetPaused { paused: false },
+        );
+        assert!(res.is_err());
+
+        sudo(
+            deps.as_mut(),
+            mock_env(),
+            SudoMsg::SetPaused { paused: false },
+        )
+        .unwrap();
+        assert_eq!(
+            None,
+            state_storage_read(&deps.storage).load().unwrap().paused
+        );
+    }
+
+    #[test]
+    fn governance_cannot_unpause_admin_pause() {
+        let mut deps = default_deps(None);
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("admin", &[]),
+            ExecuteMsg::SetPaused { paused: true },
+        )
+        .unwrap();
+
+        assert!(sudo(
+            deps.as_mut(),
+            mock_env(),
+            SudoMsg::SetPaused { paused: false }
+        )
+        .is_err());
+        assert_eq!(
+            Some(PauseSource::Admin),
+            state_storage_read(&deps.storage).load().unwrap().paused
+        );
+    }
+
+    #[test]
+    fn gov_recover_invalid_address() {
+        let res = sudo(
+            default_deps(None).as_mut(),
+            mock_env(),
+            SudoMsg::GovRecover {
+                new_admin: Addr::unchecked("a"),
+                new_lp: None,
+            },
+        );
+        assert!(res.is_err());
+    }
+
+    #[test]
+    fn gov_recover_distinct_roles() {
+        let res = sudo(
+            default_deps(None).as_mut(),
+            mock_env(),
+            SudoMsg::GovRecover {
+                new_admin: Addr::unchecked("lp"),
+                new_lp: None,
+            },
+        );
+        assert!(res.is_err());
+    }
+}
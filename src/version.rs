@@ -1,2 +1,193 @@
+use std::cmp::Ordering;
+
+use cosmwasm_std::{StdError, StdResult};
+
 pub const CONTRACT_NAME: &str = env!("CARGO_PKG_NAME");
 pub const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// The version of the message surface, independent of the crate version. Bump it whenever an
+/// execute, query, instantiate or migrate message gains, loses or changes a field or variant.
+pub const API_VERSION: u32 = 4;
+
+/// Optional capabilities compiled into this build, reported by `GetVersion` so clients can
+/// check for a feature rather than mapping code ids to releases. Kept sorted.
+pub const FEATURES: &[&str] = &[
+    "amend_authorization",
+    "authorization_history",
+    "authorization_pruning",
+    "cancel_authorized_exchanges",
+    "deposits",
+    "multi_coin_withdrawals",
+    "multiple_raises",
+    "paginated_queries",
+    "partial_completion",
+    "raise_holdings",
+    "reconcile",
+    "signed_authorizations",
+    "simulation",
+    "withdrawal_allowances",
+    "withdrawal_approvals",
+    "withdrawal_fees",
+    "withdrawal_to_name",
+];
+
+/// A semantic version. Build metadata is ignored and pre-release identifiers are compared as
+/// plain strings, which is sufficient for ordering our own release tags.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Version {
+    pub major: u64,
+    pub minor: u64,
+    pub patch: u64,
+    pub pre: Option<String>,
+}
+
+impl Ord for Version {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (self.major, self.minor, self.patch)
+            .cmp(&(other.major, other.minor, other.patch))
+            .then_with(|| match (&self.pre, &other.pre) {
+                (None, None) => Ordering::Equal,
+                (None, Some(_)) => Ordering::Greater,
+                (Some(_), None) => Ordering::Less,
+                (Some(pre), Some(other_pre)) => pre.cmp(other_pre),
+            })
+    }
+}
+
+impl PartialOrd for Version {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+pub fn parse_version(version: &str) -> StdResult<Version> {
+    let invalid = || StdError::generic_err(format!("invalid version: {}", version));
+
+    let version_core = version.split('+').next().unwrap_or_default();
+    let (core, pre) = match version_core.split_once('-') {
+        None => (version_core, None),
+        Some((_, "")) => return Err(invalid()),
+        Some((core, pre)) => (core, Some(String::from(pre))),
+    };
+
+    let parts = core
+        .split('.')
+        .map(|part| {
+            if part.is_empty() || !part.chars().all(|c| c.is_ascii_digit()) {
+                return Err(invalid());
+            }
+            part.parse::<u64>().map_err(|_| invalid())
+        })
+        .collect::<StdResult<Vec<u64>>>()?;
+
+    match parts.as_slice() {
+        [major, minor, patch] => Ok(Version {
+            major: *major,
+            minor: *minor,
+            patch: *patch,
+            pre,
+        }),
+        _ => Err(invalid()),
+    }
+}
+
+/// Whether moving from one version to the other is strictly an upgrade.
+pub fn is_upgrade(from: &str, to: &str) -> StdResult<bool> {
+    Ok(parse_version(from)? < parse_version(to)?)
+}
+
+/// Whether both versions share the same major and minor version.
+pub fn same_minor(from: &str, to: &str) -> StdResult<bool> {
+    let from = parse_version(from)?;
+    let to = parse_version(to)?;
+    Ok(from.major == to.major && from.minor == to.minor)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn features() {
+        // adding or removing a feature should be deliberate, and come with an API_VERSION bump
+        assert_eq!(4, API_VERSION);
+        assert_eq!(
+            &[
+                "amend_authorization",
+                "authorization_history",
+                "authorization_pruning",
+                "cancel_authorized_exchanges",
+                "deposits",
+                "multi_coin_withdrawals",
+                "multiple_raises",
+                "paginated_queries",
+                "partial_completion",
+                "raise_holdings",
+                "reconcile",
+                "signed_authorizations",
+                "simulation",
+                "withdrawal_allowances",
+                "withdrawal_approvals",
+                "withdrawal_fees",
+                "withdrawal_to_name",
+            ],
+            FEATURES
+        );
+        assert!(FEATURES.windows(2).all(|pair| pair[0] < pair[1]));
+    }
+
+    #[test]
+    fn parse() {
+        assert_eq!(
+            Version {
+                major: 2,
+                minor: 3,
+                patch: 0,
+                pre: None
+            },
+            parse_version("2.3.0").unwrap()
+        );
+        assert_eq!(
+            Version {
+                major: 2,
+                minor: 3,
+                patch: 0,
+                pre: Some(String::from("rc.1"))
+            },
+            parse_version("2.3.0-rc.1+build.5").unwrap()
+        );
+        assert!(parse_version(CONTRACT_VERSION).is_ok());
+    }
+
+    #[test]
+    fn parse_malformed() {
+        for version in [
+            "", "2", "2.3", "2.3.0.1", "v2.3.0", "2.x.0", "2..0", "2.3.0-", "-2.3.0", " 2.3.0",
+        ] {
+            assert!(parse_version(version).is_err(), "{}", version);
+        }
+    }
+
+    #[test]
+    fn ordering() {
+        assert!(is_upgrade("2.2.0", "2.3.0").unwrap());
+        assert!(is_upgrade("2.3.0", "2.3.1").unwrap());
+        assert!(is_upgrade("2.9.0", "2.10.0").unwrap());
+        assert!(is_upgrade("1.9.9", "2.0.0").unwrap());
+        assert!(!is_upgrade("2.3.0", "2.3.0").unwrap());
+        assert!(!is_upgrade("2.3.0", "2.2.9").unwrap());
+        assert!(is_upgrade("2.3.0", "2.3.0-rc.1").is_ok());
+        assert!(is_upgrade("2.3.0-rc.1", "2.3.0").unwrap());
+        assert!(!is_upgrade("2.3.0", "2.3.0-rc.1").unwrap());
+        assert!(is_upgrade("2.3.0-rc.1", "2.3.0-rc.2").unwrap());
+        assert!(is_upgrade("2.3.0", "bogus").is_err());
+    }
+
+    #[test]
+    fn minor_matching() {
+        assert!(same_minor("2.2.0", "2.2.5").unwrap());
+        assert!(same_minor("2.2.0-rc.1", "2.2.0").unwrap());
+        assert!(!same_minor("2.2.0", "2.3.0").unwrap());
+        assert!(!same_minor("1.2.0", "2.2.0").unwrap());
+    }
+}